@@ -1,19 +1,80 @@
-use web_framework_markdown::{markdown_component, CowStr, MarkdownProps, MATH_STYLE_SHEET_LINK};
+use web_framework_markdown::{markdown_component, slugify, CowStr, MarkdownProps, StyleLink};
 
-use std::collections::BTreeMap;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 pub type MdComponentProps = web_framework_markdown::MdComponentProps<Element>;
 
-use core::ops::Range;
-
 pub use web_framework_markdown::{
-    ComponentCreationError, Context, ElementAttributes, HtmlElement, LinkDescription, Options,
+    apply_task_toggle, line_number, parse_markdown, BlockQuoteKind, BlockquoteDescription,
+    ClickedElement, CodeBlockDescription, CodeClickEvent, CommentDirective, ComponentCreationError,
+    Context, DebugEvent, Direction, DirectiveDescription, DocumentStats, ElementAttributes,
+    ElementOverride, Frontmatter, FrontmatterOptions, FrontmatterValue, Heading, HtmlAllowlist,
+    HtmlElement, HtmlElementKind, HtmlPolicy, ImageAttributes, ImageErrorEvent, ImageLoading,
+    LinkAttributes, LinkClickEvent, LinkDescription, LinkInfo, LinkRelPolicy, LinkTarget,
+    ListStyle, MarkdownClickEvent, MarkdownConfig, MarkdownHoverEvent, MarkdownOptions, MathConfig,
+    MathDescription, MentionConfig, MermaidConfig, Options, ParseConfig, RenderStats, RootWrapper,
+    SoftBreakMode, StatsConfig, StreamTransform, SyntectConfig, TaskState, TaskToggle,
+    TextTransform, TypographyPreset, UnknownComponentPolicy, WikilinkResolution,
 };
 
+#[cfg(feature = "cache")]
+pub use web_framework_markdown::{clear_markdown_cache, set_markdown_cache_size};
+
 use dioxus::prelude::*;
 
 pub type HtmlCallback<T> = Callback<T, Element>;
 
+/// the attributes of `attributes` that are not set one by one in `rsx!`
+fn extra_attributes<H>(attributes: &ElementAttributes<H>) -> Vec<Attribute> {
+    let source_attributes = attributes
+        .source_attributes
+        .iter()
+        .map(|(name, value)| (static_name(name), value.clone()));
+    attributes
+        .extra_attributes()
+        .into_iter()
+        .chain(source_attributes)
+        .map(|(name, value)| Attribute::new(name, value, None, false))
+        .collect()
+}
+
+/// `static_name(name)` returns the attribute name `name` with a `'static` lifetime,
+/// as dioxus needs it.
+/// Every distinct name is allocated once, and kept until the end of the program
+fn static_name(name: &str) -> &'static str {
+    thread_local! {
+        static NAMES: RefCell<HashSet<&'static str>> = RefCell::default();
+    }
+    NAMES.with_borrow_mut(|names| match names.get(name) {
+        Some(name) => *name,
+        None => {
+            let name: &'static str = Box::leak(name.into());
+            names.insert(name);
+            name
+        }
+    })
+}
+
+thread_local! {
+    /// the number of markdown components that use each link and script of the head of the page,
+    /// by href or source, so that only the first one of them adds it
+    static MOUNTED: RefCell<HashMap<String, usize>> = RefCell::default();
+}
+
+/// `mount(used, key)` adds the link or the script `key` to `used`,
+/// the ones used by a component.
+/// Returns wether the component has to add it to the head of the page,
+/// because no other component uses it
+fn mount(mut used: Signal<Vec<String>>, key: &str) -> bool {
+    used.write().push(key.to_string());
+    MOUNTED.with_borrow_mut(|mounted| {
+        let count = mounted.entry(key.to_string()).or_default();
+        *count += 1;
+        *count == 1
+    })
+}
+
 #[cfg(feature = "debug")]
 pub mod debug {
     use dioxus::signals::{GlobalMemo, GlobalSignal, Signal};
@@ -26,18 +87,380 @@ pub mod debug {
 pub struct MdProps {
     src: ReadSignal<String>,
 
+    /// the options set with a [`MarkdownConfig`].
+    /// When set, they replace the options passed as separate props
+    config: Option<MarkdownConfig>,
+
     /// The callback called when a component is clicked.
     /// If you want to control what happens when a link is clicked,
     /// use [`render_links`][render_links]
     on_click: Option<EventHandler<MarkdownMouseEvent>>,
 
+    /// the callback called when the mouse enters or leaves a text element
+    on_hover: Option<EventHandler<MarkdownHoverMouseEvent>>,
+
+    /// the callback called when an image fails to load
+    on_image_error: Option<EventHandler<ImageErrorEvent>>,
+
+    /// the callback called when a link is clicked on, before the navigation.
+    /// Call `prevent_default` on the mouse event to route the link inside the application
+    on_link_click: Option<EventHandler<LinkClickMouseEvent>>,
+
+    /// the callback called when inline code is clicked on, with the text of the code.
+    /// Clicks on code blocks are not reported
+    on_inline_code_click: Option<EventHandler<CodeClickMouseEvent>>,
+
+    /// the callback called once the document is rendered,
+    /// with the number of rendered elements and the time it took
+    on_render_complete: Option<EventHandler<RenderStats>>,
+
+    /// the callback called once the document is rendered,
+    /// with what happened while rendering it, like the events that were dropped.
+    /// It is only called with the `debug` feature
+    send_debug: Option<EventHandler<Vec<DebugEvent>>>,
+
     ///
     render_links: Option<HtmlCallback<LinkDescription<Element>>>,
 
+    /// renders inline and display maths, for example to produce MathML.
+    /// By default, maths are compiled with katex
+    render_math: Option<HtmlCallback<MathDescription>>,
+
+    /// renders code blocks, for example to draw diagrams
+    /// depending on the language of the block.
+    /// By default, code blocks are highlighted with syntect
+    render_code_block: Option<HtmlCallback<CodeBlockDescription>>,
+
+    /// renders the container directives like `:::warning{key=value} ... :::`,
+    /// for example to create admonitions.
+    /// By default, the content of a directive is rendered in a `<div>`
+    render_directive: Option<HtmlCallback<DirectiveDescription<Element>>>,
+
+    /// renders the blockquotes, and the callouts like `> [!NOTE]`,
+    /// for example to style quotes with their author.
+    /// By default, they are rendered as a `<blockquote>`, or as a callout
+    render_blockquote: Option<HtmlCallback<BlockquoteDescription<Element>>>,
+
+    /// renders the elements of some kinds instead of the default tags,
+    /// for example to replace every table with a virtualized one.
+    /// The callback gets the rendered children and the attributes of the element
+    element_overrides: Option<
+        &'static HashMap<
+            HtmlElementKind,
+            HtmlCallback<ElementOverride<Element, EventHandler<MouseEvent>>>,
+        >,
+    >,
+
+    /// finds the pages that `[[wikilinks]]` point to.
+    /// Links to missing pages get the `md-wikilink-broken` class.
+    /// By default, the target of a wikilink is used as its url
+    resolve_wikilink: Option<Callback<String, Option<WikilinkResolution>>>,
+
+    /// finds the urls of the users mentioned like `@alice`, when `mentions` is set.
+    /// Unresolved mentions get the `md-mention-broken` class
+    resolve_mention: Option<Callback<String, Option<String>>>,
+
+    /// finds the urls of the hashtags like `#topic`, when `mentions` is set.
+    /// Unresolved hashtags get the `md-hashtag-broken` class
+    resolve_hashtag: Option<Callback<String, Option<String>>>,
+
     /// the name of the theme used for syntax highlighting.
     /// Only the default themes of [syntect::Theme] are supported
     theme: Option<&'static str>,
 
+    /// how code blocks are highlighted.
+    /// If set, it replaces the theme
+    #[props(default)]
+    syntax_highlight: Option<SyntectConfig>,
+
+    /// the stylesheet used to display maths.
+    /// It is only loaded when the document contains maths.
+    /// If None, no stylesheet is loaded
+    math: Option<MathConfig>,
+
+    /// the mermaid script, that draws the ` ```mermaid ` diagrams.
+    /// It is only loaded when the document contains a diagram.
+    /// If None, no script is loaded
+    #[props(default)]
+    mermaid: Option<MermaidConfig>,
+
+    /// how images are loaded.
+    /// By default, they are loaded lazily
+    #[props(default)]
+    image_loading: ImageLoading,
+
+    /// the number of images at the start of the document
+    /// that are always loaded eagerly
+    #[props(default)]
+    eager_image_count: usize,
+
+    /// gives `role="presentation"` to the images with an empty alternative text,
+    /// like `![](divider.png)`, so that screen readers treat them as decorative
+    #[props(default)]
+    decorative_empty_alt: bool,
+
+    /// the url of the image displayed instead of images that fail to load
+    #[props(default)]
+    broken_image_placeholder: Option<&'static str>,
+
+    /// the markdown extensions to enable.
+    /// They are ignored if `parse_options` is set
+    #[props(default)]
+    options: MarkdownOptions,
+
+    /// the url that relative links and images are resolved against,
+    /// usually the url of the markdown document.
+    /// If None, relative urls are left unchanged
+    #[props(default)]
+    base_url: Option<&'static str>,
+
+    /// a function that rewrites the parsed events before they are rendered,
+    /// see [`StreamTransform`]
+    #[props(default)]
+    transform_stream: Option<StreamTransform>,
+
+    /// typographic replacements applied to the text, in order, like german quotes
+    #[props(default)]
+    typography: &'static [TypographyPreset],
+
+    /// a function that rewrites the text of the document, but not the code
+    #[props(default)]
+    text_transform: Option<TextTransform>,
+
+    /// replaces github shortcodes like `:tada:` by the corresponding emojis.
+    /// It only has an effect when the `emoji` feature is enabled
+    #[props(default)]
+    emoji: bool,
+
+    /// how line breaks inside paragraphs are rendered
+    #[props(default)]
+    soft_break: SoftBreakMode,
+
+    /// how the word count and reading time of the document are computed
+    #[props(default)]
+    stats_config: StatsConfig,
+
+    /// the prefix of the paragraphs that are used as the caption of a table,
+    /// when they are right before or right after it, like `Table:` in pandoc.
+    /// If None, tables have no caption
+    #[props(default)]
+    table_caption_prefix: Option<&'static str>,
+
+    /// wether the raw html tables are rendered like markdown tables,
+    /// with the same elements and alignment
+    #[props(default)]
+    normalize_html_tables: bool,
+
+    /// wether code blocks get a button to copy their content to the clipboard
+    #[props(default)]
+    code_block_copy_button: bool,
+
+    /// wether the lines of code blocks are numbered
+    #[props(default)]
+    code_block_line_numbers: bool,
+
+    /// what to do with the tags that look like custom components,
+    /// like `<MyComponent/>`, but that are not registered
+    #[props(default)]
+    unknown_components: UnknownComponentPolicy,
+
+    /// how the raw html of the source is rendered.
+    /// Use `HtmlPolicy::Sanitize` or `HtmlPolicy::Strip` for untrusted content
+    #[props(default)]
+    html_policy: HtmlPolicy,
+
+    /// the number of levels that every heading is shifted down by, up to `<h6>`.
+    /// For example, with an offset of 1, `# Title` is rendered as a `<h2>`
+    #[props(default)]
+    heading_offset: u8,
+
+    /// adds a `#` link to the anchor of every heading.
+    /// Clicking it copies the permalink of the heading
+    #[props(default)]
+    heading_anchor_links: bool,
+
+    /// prepends hierarchical numbers to the headings, like `1`, `1.1`, `1.2`, `1.2.1`,
+    /// following their levels once the heading offset is applied
+    #[props(default)]
+    heading_numbering: bool,
+
+    /// the lowest level of the headings in the table of content, like 2 to leave the `<h1>` out.
+    /// It doesn't change how the headings are rendered
+    #[props(default)]
+    toc_min_level: u8,
+
+    /// the highest level of the headings in the table of content, like 3 to leave the `<h4>` to `<h6>` out.
+    /// If 0, there is no limit
+    #[props(default)]
+    toc_max_level: u8,
+
+    /// reads a block like `{.lead #intro}` at the end of a paragraph
+    /// as its classes, id and attributes, and removes it from the text.
+    /// On its own line at the end of a blockquote, the block applies to the blockquote
+    #[props(default)]
+    inline_attributes: bool,
+
+    /// sets `data-source-start` and `data-source-end` on the top-level blocks,
+    /// to their byte offsets in the source, for example to synchronize the scrolling of an editor
+    #[props(default)]
+    source_positions: bool,
+
+    /// how the items of ordered lists are numbered, like `I.`, `II.` for [`ListStyle::UpperRoman`]
+    #[props(default)]
+    ordered_list_style: Option<ListStyle>,
+
+    /// wether lists get the `md-list-tight` class, or `md-list-loose`
+    /// if their items are separated by blank lines
+    #[props(default)]
+    list_tightness_classes: bool,
+
+    /// the number of elements that can be nested in each other, like blockquotes.
+    /// Deeper elements are replaced by a `<div class="md-depth-limit">`,
+    /// or a `<span>` inside a paragraph.
+    /// If None, 100 levels are allowed
+    #[props(default)]
+    max_nesting_depth: Option<usize>,
+
+    /// the number of elements that are rendered at most.
+    /// The rest of the document is replaced by a `<div class="md-truncated">`.
+    /// If None, there is no limit
+    #[props(default)]
+    max_elements: Option<usize>,
+
+    /// the number of images that are rendered at most.
+    /// The rest of the document is replaced by a `<div class="md-truncated">`.
+    /// If None, there is no limit
+    #[props(default)]
+    max_image_count: Option<usize>,
+
+    /// the direction of the text of the document, like [`Direction::Rtl`].
+    /// If it or `lang` is set, the document is wrapped in a `<div>`
+    #[props(default)]
+    dir: Option<Direction>,
+
+    /// the language of the document, like `ar` or `en-US`
+    #[props(default)]
+    lang: Option<&'static str>,
+
+    /// gives `dir="auto"` to the paragraphs, headings and list items,
+    /// for documents that mix left-to-right and right-to-left languages
+    #[props(default)]
+    auto_block_direction: bool,
+
+    /// abbreviations and their expansion, rendered as `<abbr>` wherever they appear.
+    /// The abbreviations defined in the document, like `*[HTML]: HyperText Markup Language`,
+    /// take precedence
+    #[props(default)]
+    abbreviations: Option<&'static HashMap<&'static str, &'static str>>,
+
+    /// renders the text between `==`, like `==this==`, in a `<mark>`
+    #[props(default)]
+    highlight_marks: bool,
+
+    /// renders the keys between `++`, like `++Ctrl+C++`, in a `<kbd>`
+    #[props(default)]
+    kbd: bool,
+
+    /// the text shown when the document has no content
+    #[props(default)]
+    empty_placeholder: Option<&'static str>,
+
+    /// if the document is a single paragraph,
+    /// its content is rendered without the `<p>` around it
+    #[props(default)]
+    unwrap_single_paragraph: bool,
+
+    /// renders the checkboxes of task lists disabled, so that they can't be toggled
+    #[props(default)]
+    read_only_task_lists: bool,
+
+    /// the extra states of the tasks of task lists, like `[/]`,
+    /// rendered as indeterminate checkboxes
+    #[props(default)]
+    task_list_states: Option<&'static HashMap<char, TaskState>>,
+
+    /// extra css classes of the horizontal rules, separated by spaces
+    #[props(default)]
+    rule_class: Option<&'static str>,
+
+    /// renders consecutive horizontal rules as a single one
+    #[props(default)]
+    collapse_rules: bool,
+
+    /// the names of the html comments that are directives, like `more` for `<!-- more -->`.
+    /// They are not rendered, and are written to `comment_directives`
+    #[props(default)]
+    comment_directive_names: Option<&'static [&'static str]>,
+
+    /// scrolls the heading that the fragment of the url points to, like `#usage`, into view
+    #[props(default)]
+    scroll_to_fragment: bool,
+
+    /// the names of the languages of code blocks, by alias, like `javascript` for `js`.
+    /// They are added to [`DEFAULT_LANGUAGE_ALIASES`][web_framework_markdown::DEFAULT_LANGUAGE_ALIASES],
+    /// and take precedence over them
+    #[props(default)]
+    language_aliases: Option<&'static HashMap<&'static str, &'static str>>,
+
+    /// gives the footnote references the text of their definition as a `title`, shown as a tooltip
+    #[props(default)]
+    footnote_popovers: bool,
+
+    /// removes the indentation that all the lines of a code block have in common
+    #[props(default)]
+    code_block_dedent: bool,
+
+    /// removes the blank lines at the end of code blocks
+    #[props(default)]
+    code_block_trim: bool,
+
+    /// the element around the whole document, like a `<div>` with an id.
+    /// If None, it is a `<div>` only if the direction or the language is set
+    #[props(default)]
+    root_wrapper: Option<RootWrapper>,
+
+    /// with the `frontmatter` feature, lets the options set by the frontmatter
+    /// of the document take precedence over the props
+    #[props(default)]
+    frontmatter_overrides: bool,
+
+    /// renders the images alone in their paragraph and with a title
+    /// as a `<figure>`, with the title as caption
+    #[props(default)]
+    figure_images: bool,
+
+    /// a prefix added to the css classes created by this crate,
+    /// like `Some("prose-")` to get `prose-language-rust` instead of `language-rust`
+    #[props(default)]
+    class_prefix: Option<&'static str>,
+
+    /// wether bare urls like `https://example.com` or `www.example.com`,
+    /// and email addresses are turned into links
+    #[props(default)]
+    autolink: bool,
+
+    /// turns the mentions like `@alice` and the hashtags like `#topic` into links,
+    /// with `resolve_mention` and `resolve_hashtag`
+    #[props(default)]
+    mentions: Option<MentionConfig>,
+
+    /// where links are opened when clicked.
+    /// By default, they are opened in the same tab
+    #[props(default)]
+    link_target: LinkTarget,
+
+    /// the `rel` keywords of the links to other websites, like `nofollow ugc`
+    #[props(default)]
+    link_rel_policy: LinkRelPolicy,
+
+    /// the url schemes allowed in links and images.
+    /// By default, [`DEFAULT_URL_SCHEMES`][web_framework_markdown::DEFAULT_URL_SCHEMES]
+    allowed_url_schemes: Option<&'static [&'static str]>,
+
+    /// the function used to compute the anchor id of headings from their text.
+    /// By default, github-style slugs are used
+    heading_slugger: Option<Callback<String, String>>,
+
     /// wether to enable wikilinks support.
     /// Wikilinks look like [[shortcut link]] or [[url|name]]
     #[props(default = false)]
@@ -56,26 +479,57 @@ pub struct MdProps {
 
     frontmatter: Option<Signal<String>>,
 
+    /// the frontmatter of the document, parsed into key-value pairs.
+    /// It is only set with the `frontmatter` feature,
+    /// when the frontmatter is valid yaml or toml
+    parsed_frontmatter: Option<Signal<Frontmatter>>,
+
+    /// the headings of the document, to build a table of content
+    headings: Option<Signal<Vec<Heading>>>,
+
+    /// the links and images of the document, for example to check them
+    document_links: Option<Signal<Vec<LinkInfo>>>,
+
+    /// the html comments of the document that are directives, like `<!-- more -->`
+    comment_directives: Option<Signal<Vec<CommentDirective>>>,
+
+    /// the word count and reading time of the document
+    stats: Option<Signal<DocumentStats>>,
+
+    /// the last task list checkbox toggled by the user.
+    /// Use [`apply_task_toggle`] to update the source accordingly
+    task_toggle: Option<Signal<TaskToggle>>,
+
     /// wether to preserve arbitrary html.
     /// If true, content may inject unsafe html, which could be a security or privacy risk if the input comes from an untrusted source.
-    /// TODO: supporting a sanitized subset of html might be a better approach in the future.
+    /// To only keep a safe subset of html, use `html_policy` instead.
     #[props(default = true)]
     preserve_html: bool,
 }
 
-#[derive(Clone, Debug)]
-pub struct MarkdownMouseEvent {
-    /// the original mouse event triggered when a text element was clicked on
-    pub mouse_event: MouseEvent,
+/// the event reported when a markdown element is clicked on
+pub type MarkdownMouseEvent = MarkdownClickEvent<MouseEvent>;
 
-    /// the corresponding range in the markdown source, as a slice of [`u8`][u8]
-    pub position: Range<usize>,
-    // TODO: add a clonable tag for the type of the element
-    // pub tag: pulldown_cmark::Tag<'a>,
-}
+/// the event reported when the mouse enters or leaves a markdown element
+pub type MarkdownHoverMouseEvent = MarkdownHoverEvent<MouseEvent>;
+
+/// the event reported when a link is clicked on
+pub type LinkClickMouseEvent = LinkClickEvent<MouseEvent>;
+
+/// the event reported when inline code is clicked on
+pub type CodeClickMouseEvent = CodeClickEvent<MouseEvent>;
 
 #[derive(Clone, Copy)]
-pub struct MdContext(ReadSignal<MdProps>);
+pub struct MdContext {
+    props: ReadSignal<MdProps>,
+    /// the links to add to the head of the page
+    links: Signal<Vec<StyleLink>>,
+    /// the scripts to add to the head of the page
+    scripts: Signal<Vec<String>>,
+    /// the hrefs of the links and the sources of the scripts used by the component,
+    /// including the ones added by another component
+    used: Signal<Vec<String>>,
+}
 
 /// component store.
 /// It is called when therer is a `<CustomComponent>` inside the markdown source.
@@ -113,7 +567,7 @@ impl<'src> Context<'src, 'static> for MdContext {
 
     type Handler<T: 'static> = EventHandler<T>;
 
-    type MouseEvent = MouseEvent;
+    type Event = MouseEvent;
 
     #[cfg(feature = "debug")]
     fn send_debug_info(self, info: Vec<String>) {
@@ -126,127 +580,229 @@ impl<'src> Context<'src, 'static> for MdContext {
         inside: Self::View,
         attributes: ElementAttributes<EventHandler<MouseEvent>>,
     ) -> Self::View {
+        if let Some(f) = (self.props)()
+            .element_overrides
+            .and_then(|o| o.get(&e.kind()))
+        {
+            return f.call(ElementOverride {
+                element: Some(e),
+                children: inside,
+                attributes,
+                src: None,
+            });
+        }
+        let extra = extra_attributes(&attributes);
         let class = attributes.classes.join(" ");
         let style = attributes.style.unwrap_or_default();
+        let id = attributes.id;
         let onclick = attributes.on_click.unwrap_or_default();
         let onclick = move |e| onclick.call(e);
+        let on_mouseenter = attributes.on_mouseenter;
+        let onmouseenter = move |e| {
+            if let Some(f) = &on_mouseenter {
+                f.call(e)
+            }
+        };
+        let on_mouseleave = attributes.on_mouseleave;
+        let onmouseleave = move |e| {
+            if let Some(f) = &on_mouseleave {
+                f.call(e)
+            }
+        };
 
         match e {
             HtmlElement::Div => {
                 rsx! {
-                    div { onclick, style: "{style}", class: "{class}", {inside} }
+                    div { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
                 }
             }
             HtmlElement::Span => {
                 rsx! {
-                    span { onclick, style: "{style}", class: "{class}", {inside} }
+                    span { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
                 }
             }
             HtmlElement::Paragraph => {
                 rsx! {
-                    p { onclick, style: "{style}", class: "{class}", {inside} }
+                    p { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
                 }
             }
             HtmlElement::BlockQuote => {
                 rsx! {
-                    blockquote { onclick, style: "{style}", class: "{class}", {inside} }
+                    blockquote { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
                 }
             }
             HtmlElement::Ul => {
                 rsx! {
-                    ul { onclick, style: "{style}", class: "{class}", {inside} }
+                    ul { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
                 }
             }
             HtmlElement::Ol(x) => {
                 rsx! {
                     ol {
                         onclick,
+                        id,
                         style: "{style}",
                         class: "{class}",
                         start: x as i64,
+                        ..extra,
                         {inside}
                     }
                 }
             }
             HtmlElement::Li => {
                 rsx! {
-                    li { onclick, style: "{style}", class: "{class}", {inside} }
+                    li { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
                 }
             }
             HtmlElement::Heading(1) => {
                 rsx! {
-                    h1 { onclick, style: "{style}", class: "{class}", {inside} }
+                    h1 { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
                 }
             }
             HtmlElement::Heading(2) => {
                 rsx! {
-                    h2 { onclick, style: "{style}", class: "{class}", {inside} }
+                    h2 { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
                 }
             }
             HtmlElement::Heading(3) => {
                 rsx! {
-                    h3 { onclick, style: "{style}", class: "{class}", {inside} }
+                    h3 { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
                 }
             }
             HtmlElement::Heading(4) => {
                 rsx! {
-                    h4 { onclick, style: "{style}", class: "{class}", {inside} }
+                    h4 { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
                 }
             }
             HtmlElement::Heading(5) => {
                 rsx! {
-                    h5 { onclick, style: "{style}", class: "{class}", {inside} }
+                    h5 { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
                 }
             }
             HtmlElement::Heading(6) => {
                 rsx! {
-                    h6 { onclick, style: "{style}", class: "{class}", {inside} }
+                    h6 { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
                 }
             }
             HtmlElement::Heading(_) => panic!(),
             HtmlElement::Table => {
                 rsx! {
-                    table { onclick, style: "{style}", class: "{class}", {inside} }
+                    table { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
                 }
             }
             HtmlElement::Thead => {
                 rsx! {
-                    thead { onclick, style: "{style}", class: "{class}", {inside} }
+                    thead { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
                 }
             }
             HtmlElement::Trow => {
                 rsx! {
-                    tr { onclick, style: "{style}", class: "{class}", {inside} }
+                    tr { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
                 }
             }
             HtmlElement::Tcell => {
                 rsx! {
-                    td { onclick, style: "{style}", class: "{class}", {inside} }
+                    td { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
+                }
+            }
+            HtmlElement::TheadCell => {
+                rsx! {
+                    th { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
+                }
+            }
+            HtmlElement::Caption => {
+                rsx! {
+                    caption { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
+                }
+            }
+            HtmlElement::Button => {
+                rsx! {
+                    button { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
                 }
             }
             HtmlElement::Italics => {
                 rsx! {
-                    i { onclick, style: "{style}", class: "{class}", {inside} }
+                    i { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
                 }
             }
             HtmlElement::Bold => {
                 rsx! {
-                    b { onclick, style: "{style}", class: "{class}", {inside} }
+                    b { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
                 }
             }
             HtmlElement::StrikeThrough => {
                 rsx! {
-                    s { onclick, style: "{style}", class: "{class}", {inside} }
+                    s { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
+                }
+            }
+            HtmlElement::Sup => {
+                rsx! {
+                    sup { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
+                }
+            }
+            HtmlElement::Sub => {
+                rsx! {
+                    sub { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
                 }
             }
             HtmlElement::Pre => {
                 rsx! {
-                    p { onclick, style: "{style}", class: "{class}", {inside} }
+                    pre { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
                 }
             }
             HtmlElement::Code => {
                 rsx! {
-                    code { onclick, style: "{style}", class: "{class}", {inside} }
+                    code { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
+                }
+            }
+            HtmlElement::Dl => {
+                rsx! {
+                    dl { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
+                }
+            }
+            HtmlElement::Dt => {
+                rsx! {
+                    dt { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
+                }
+            }
+            HtmlElement::Dd => {
+                rsx! {
+                    dd { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
+                }
+            }
+            HtmlElement::Details => {
+                rsx! {
+                    details { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
+                }
+            }
+            HtmlElement::Summary => {
+                rsx! {
+                    summary { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
+                }
+            }
+            HtmlElement::Abbr => {
+                rsx! {
+                    abbr { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
+                }
+            }
+            HtmlElement::Mark => {
+                rsx! {
+                    mark { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
+                }
+            }
+            HtmlElement::Kbd => {
+                rsx! {
+                    kbd { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
+                }
+            }
+            HtmlElement::Figure => {
+                rsx! {
+                    figure { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
+                }
+            }
+            HtmlElement::Figcaption => {
+                rsx! {
+                    figcaption { onclick, onmouseenter, onmouseleave, id, style: "{style}", class: "{class}", ..extra, {inside} }
                 }
             }
         }
@@ -264,7 +820,7 @@ impl<'src> Context<'src, 'static> for MdContext {
                 f.call(e)
             }
         };
-        let props = self.0();
+        let props = (self.props)();
         if props.preserve_html {
             rsx! {
                 span {
@@ -282,17 +838,28 @@ impl<'src> Context<'src, 'static> for MdContext {
     }
 
     fn el_hr(self, attributes: ElementAttributes<EventHandler<MouseEvent>>) -> Self::View {
+        let extra = extra_attributes(&attributes);
         let class = attributes.classes.join(" ");
-        let style = attributes.style.unwrap_or_default();
+        let style = attributes.style.clone().unwrap_or_default();
+        let on_click = attributes.on_click;
         let onclick = move |e| {
-            if let Some(f) = &attributes.on_click {
+            if let Some(f) = &on_click {
                 f.call(e)
             }
         };
+        let onkeydown = move |e: KeyboardEvent| {
+            if attributes.is_activated_by(&e.key().to_string()) {
+                e.prevent_default();
+                // the same handler runs as for a click
+                document::eval("document.activeElement?.click()");
+            }
+        };
         rsx!(hr {
             onclick,
+            onkeydown,
             style: "{style}",
-            class: "{class}"
+            class: "{class}",
+            ..extra
         })
     }
 
@@ -306,17 +873,54 @@ impl<'src> Context<'src, 'static> for MdContext {
         }
     }
 
-    fn el_a(self, children: Self::View, href: String) -> Self::View {
+    fn el_a(
+        self,
+        children: Self::View,
+        href: String,
+        attributes: LinkAttributes,
+        on_click: Option<EventHandler<MouseEvent>>,
+    ) -> Self::View {
+        let onclick = move |e| {
+            if let Some(f) = &on_click {
+                f.call(e)
+            }
+        };
         rsx! {
-            a { href: "{href}", {children} }
+            a {
+                onclick,
+                href: "{href}",
+                target: attributes.target,
+                rel: attributes.rel,
+                title: attributes.title,
+                class: attributes.classes.join(" "),
+                {children}
+            }
         }
     }
 
-    fn el_img(self, src: String, alt: String) -> Self::View {
-        rsx!(img {
-            src: "{src}",
-            alt: "{alt}"
-        })
+    fn el_img(self, src: String, alt: String, attributes: ImageAttributes) -> Self::View {
+        let props = (self.props)();
+        let on_error = props.on_image_error;
+        // the key resets the state of the image when its source changes
+        let img = rsx!(MdImage {
+            key: "{src}",
+            src: src.clone(),
+            alt,
+            attributes,
+            on_error
+        });
+        match props
+            .element_overrides
+            .and_then(|o| o.get(&HtmlElementKind::Img))
+        {
+            Some(f) => f.call(ElementOverride {
+                element: None,
+                children: img,
+                attributes: Default::default(),
+                src: Some(src),
+            }),
+            None => img,
+        }
     }
 
     fn el_text<'a>(self, text: CowStr<'a>) -> Self::View {
@@ -328,8 +932,14 @@ impl<'src> Context<'src, 'static> for MdContext {
     fn el_input_checkbox(
         self,
         checked: bool,
+        indeterminate: bool,
         attributes: ElementAttributes<EventHandler<MouseEvent>>,
     ) -> Self::View {
+        let mut extra = extra_attributes(&attributes);
+        // the `indeterminate` state is a property of the element, that rsx can't set
+        if indeterminate {
+            extra.push(Attribute::new("aria-checked", "mixed", None, false));
+        }
         let class = attributes.classes.join(" ");
         let style = attributes.style.unwrap_or_default();
         let onclick = move |e| {
@@ -343,17 +953,99 @@ impl<'src> Context<'src, 'static> for MdContext {
             style: "{style}",
             class: "{class}",
             onclick,
+            ..extra
         })
     }
 
     fn props(self) -> MarkdownProps {
-        let props = self.0();
+        let props = (self.props)();
+        if let Some(config) = props.config {
+            return config.build();
+        }
 
         MarkdownProps {
             hard_line_breaks: props.hard_line_breaks,
             wikilinks: props.wikilinks,
             parse_options: props.parse_options,
             theme: props.theme,
+            syntax_highlight: props.syntax_highlight,
+            math: props.math,
+            mermaid: props.mermaid,
+            image_loading: props.image_loading,
+            eager_image_count: props.eager_image_count,
+            decorative_empty_alt: props.decorative_empty_alt,
+            broken_image_placeholder: props.broken_image_placeholder,
+            options: props.options,
+            base_url: props.base_url,
+            transform_stream: props.transform_stream,
+            typography: props.typography,
+            text_transform: props.text_transform,
+            emoji: props.emoji,
+            soft_break: props.soft_break,
+            stats_config: props.stats_config,
+            table_caption_prefix: props.table_caption_prefix,
+            normalize_html_tables: props.normalize_html_tables,
+            code_block_copy_button: props.code_block_copy_button,
+            code_block_line_numbers: props.code_block_line_numbers,
+            unknown_components: props.unknown_components,
+            html_policy: props.html_policy,
+            heading_offset: props.heading_offset,
+            heading_anchor_links: props.heading_anchor_links,
+            heading_numbering: props.heading_numbering,
+            toc_min_level: props.toc_min_level,
+            toc_max_level: props.toc_max_level,
+            inline_attributes: props.inline_attributes,
+            source_positions: props.source_positions,
+            ordered_list_style: props.ordered_list_style,
+            list_tightness_classes: props.list_tightness_classes,
+            max_nesting_depth: props.max_nesting_depth,
+            max_elements: props.max_elements,
+            max_image_count: props.max_image_count,
+            dir: props.dir,
+            lang: props.lang,
+            auto_block_direction: props.auto_block_direction,
+            abbreviations: props.abbreviations,
+            highlight_marks: props.highlight_marks,
+            kbd: props.kbd,
+            empty_placeholder: props.empty_placeholder,
+            unwrap_single_paragraph: props.unwrap_single_paragraph,
+            read_only_task_lists: props.read_only_task_lists,
+            task_list_states: props.task_list_states,
+            rule_class: props.rule_class,
+            collapse_rules: props.collapse_rules,
+            comment_directive_names: props.comment_directive_names,
+            scroll_to_fragment: props.scroll_to_fragment,
+            language_aliases: props.language_aliases,
+            footnote_popovers: props.footnote_popovers,
+            code_block_dedent: props.code_block_dedent,
+            code_block_trim: props.code_block_trim,
+            root_wrapper: props.root_wrapper,
+            frontmatter_overrides: props.frontmatter_overrides,
+            figure_images: props.figure_images,
+            class_prefix: props.class_prefix,
+            autolink: props.autolink,
+            mentions: props.mentions,
+            link_target: props.link_target,
+            link_rel_policy: props.link_rel_policy,
+            allowed_url_schemes: props.allowed_url_schemes,
+        }
+    }
+
+    fn mount_dynamic_link(self, link: StyleLink) {
+        if mount(self.used, link.href) {
+            let mut links = self.links;
+            links.write().push(link);
+        }
+    }
+
+    fn is_link_mounted(self, link: &StyleLink) -> bool {
+        self.used.peek().iter().any(|used| used == link.href)
+    }
+
+    fn mount_dynamic_script(self, src: String) {
+        if !self.used.peek().contains(&src) && mount(self.used, &src) {
+            let mut scripts = self.scripts;
+            scripts.write().push(src);
         }
     }
 
@@ -364,9 +1056,10 @@ impl<'src> Context<'src, 'static> for MdContext {
     fn make_md_handler(
         self,
         position: std::ops::Range<usize>,
+        element: ClickedElement,
         stop_propagation: bool,
     ) -> Self::Handler<MouseEvent> {
-        let on_click = self.0().on_click.as_ref().cloned();
+        let on_click = (self.props)().on_click.as_ref().cloned();
 
         EventHandler::new(move |e: MouseEvent| {
             if stop_propagation {
@@ -375,28 +1068,195 @@ impl<'src> Context<'src, 'static> for MdContext {
 
             let report = MarkdownMouseEvent {
                 position: position.clone(),
-                mouse_event: e,
+                event: e,
+                element: element.clone(),
             };
 
             on_click.map(|x| x.call(report));
         })
     }
 
+    fn make_md_hover_handler(
+        self,
+        position: std::ops::Range<usize>,
+        entering: bool,
+    ) -> Option<Self::Handler<MouseEvent>> {
+        let on_hover = (self.props)().on_hover?;
+        Some(EventHandler::new(move |e: MouseEvent| {
+            on_hover.call(MarkdownHoverMouseEvent {
+                event: e,
+                position: position.clone(),
+                entering,
+            })
+        }))
+    }
+
+    fn make_task_toggle_handler(
+        self,
+        position: std::ops::Range<usize>,
+        toggle: TaskToggle,
+    ) -> Self::Handler<MouseEvent> {
+        let on_click = self.make_md_handler(position, ClickedElement::TaskItem, true);
+        let task_toggle = (self.props)().task_toggle;
+        EventHandler::new(move |e: MouseEvent| {
+            on_click.call(e);
+            if let Some(mut setter) = task_toggle {
+                setter.set(toggle.clone())
+            }
+        })
+    }
+
+    fn make_link_click_handler(
+        self,
+        url: String,
+        position: std::ops::Range<usize>,
+    ) -> Option<Self::Handler<MouseEvent>> {
+        let on_link_click = (self.props)().on_link_click?;
+        Some(EventHandler::new(move |e: MouseEvent| {
+            on_link_click.call(LinkClickMouseEvent {
+                url: url.clone(),
+                mouse_event: e,
+                range: position.clone(),
+            })
+        }))
+    }
+
+    fn make_inline_code_click_handler(
+        self,
+        code: String,
+        position: std::ops::Range<usize>,
+    ) -> Option<Self::Handler<MouseEvent>> {
+        let on_inline_code_click = (self.props)().on_inline_code_click?;
+        Some(EventHandler::new(move |e: MouseEvent| {
+            on_inline_code_click.call(CodeClickMouseEvent {
+                code: code.clone(),
+                mouse_event: e,
+                range: position.clone(),
+            })
+        }))
+    }
+
+    fn make_copy_handler(self, code: String) -> Option<Self::Handler<MouseEvent>> {
+        Some(EventHandler::new(move |_| {
+            let eval = document::eval("navigator.clipboard.writeText(await dioxus.recv())");
+            let _ = eval.send(code.clone());
+        }))
+    }
+
+    fn make_permalink_copy_handler(self, id: String) -> Option<Self::Handler<MouseEvent>> {
+        Some(EventHandler::new(move |_| {
+            let eval = document::eval(
+                "navigator.clipboard.writeText(location.href.split('#')[0] + '#' + await dioxus.recv())",
+            );
+            let _ = eval.send(id.clone());
+        }))
+    }
+
     fn set_frontmatter(&mut self, frontmatter: String) {
-        self.0().frontmatter.as_mut().map(|x| x.set(frontmatter));
+        (self.props)()
+            .frontmatter
+            .as_mut()
+            .map(|x| x.set(frontmatter));
+    }
+
+    fn set_parsed_frontmatter(&mut self, frontmatter: Frontmatter) {
+        if let Some(mut setter) = (self.props)().parsed_frontmatter {
+            setter.set(frontmatter)
+        }
+    }
+
+    fn set_headings(&mut self, headings: Vec<Heading>) {
+        if let Some(mut setter) = (self.props)().headings {
+            setter.set(headings)
+        }
+    }
+
+    fn set_document_links(&mut self, links: Vec<LinkInfo>) {
+        if let Some(mut setter) = (self.props)().document_links {
+            setter.set(links)
+        }
+    }
+
+    fn set_comment_directives(&mut self, directives: Vec<CommentDirective>) {
+        if let Some(mut setter) = (self.props)().comment_directives {
+            setter.set(directives)
+        }
+    }
+
+    fn set_stats(&mut self, stats: DocumentStats) {
+        if let Some(mut setter) = (self.props)().stats {
+            setter.set(stats)
+        }
+    }
+
+    fn on_render_complete(self) -> Option<Self::Handler<RenderStats>> {
+        (self.props)().on_render_complete
+    }
+
+    #[cfg(feature = "debug")]
+    fn send_debug(self, events: Vec<DebugEvent>) {
+        if let Some(f) = (self.props)().send_debug {
+            f.call(events)
+        }
     }
 
     fn has_custom_links(self) -> bool {
-        self.0().render_links.is_some()
+        (self.props)().render_links.is_some()
+    }
+
+    fn heading_slug(self, text: &str) -> String {
+        match (self.props)().heading_slugger {
+            Some(f) => f(text.to_string()),
+            None => slugify(text),
+        }
+    }
+
+    fn render_code_block(self, code_block: CodeBlockDescription) -> Option<Self::View> {
+        (self.props)().render_code_block.map(|f| f(code_block))
+    }
+
+    fn render_directive(self, directive: DirectiveDescription<Element>) -> Option<Self::View> {
+        (self.props)().render_directive.map(|f| f(directive))
+    }
+
+    fn render_blockquote(self, blockquote: BlockquoteDescription<Element>) -> Option<Self::View> {
+        (self.props)().render_blockquote.map(|f| f(blockquote))
+    }
+
+    fn render_math(self, math: MathDescription) -> Option<Self::View> {
+        (self.props)().render_math.map(|f| f(math))
+    }
+
+    fn resolve_wikilink(self, target: &str) -> Option<WikilinkResolution> {
+        match (self.props)().resolve_wikilink {
+            Some(f) => f(target.to_string()),
+            None => Some(WikilinkResolution::identity(target)),
+        }
+    }
+
+    fn resolve_mention(self, name: &str) -> Option<String> {
+        (self.props)()
+            .resolve_mention
+            .and_then(|f| f(name.to_string()))
+    }
+
+    fn resolve_hashtag(self, tag: &str) -> Option<String> {
+        (self.props)()
+            .resolve_hashtag
+            .and_then(|f| f(tag.to_string()))
     }
 
     fn render_links(self, link: LinkDescription<Self::View>) -> Result<Self::View, String> {
         // TODO: remove the unwrap call
-        Ok(self.0().render_links.as_ref().unwrap()(link))
+        Ok((self.props)().render_links.as_ref().unwrap()(link))
     }
 
     fn has_custom_component(self, name: &str) -> bool {
-        self.0().components.read().get_callback(name).is_some()
+        (self.props)()
+            .components
+            .read()
+            .get_callback(name)
+            .is_some()
     }
 
     fn render_custom_component(
@@ -404,7 +1264,7 @@ impl<'src> Context<'src, 'static> for MdContext {
         name: &str,
         input: MdComponentProps,
     ) -> Result<Self::View, ComponentCreationError> {
-        let f: Callback<_, _> = self.0()
+        let f: Callback<_, _> = (self.props)()
             .components
             .read()
             .get_callback(name)
@@ -414,13 +1274,100 @@ impl<'src> Context<'src, 'static> for MdContext {
     }
 }
 
+/// an image that can be replaced by a placeholder if it fails to load
+#[component]
+fn MdImage(
+    src: String,
+    alt: String,
+    attributes: ImageAttributes,
+    on_error: Option<EventHandler<ImageErrorEvent>>,
+) -> Element {
+    let mut current_src = use_signal(|| src.clone());
+    let ImageAttributes {
+        title,
+        width,
+        height,
+        loading,
+        decoding,
+        role,
+        ..
+    } = attributes.clone();
+    let onerror = move |_| {
+        let failed_src = current_src();
+        if let Some(f) = on_error {
+            f.call(attributes.error_event(&failed_src))
+        }
+        if let Some(fallback) = attributes.fallback_src(&failed_src) {
+            current_src.set(fallback.to_string())
+        }
+    };
+    rsx!(img {
+        src: "{current_src}",
+        alt: "{alt}",
+        title,
+        width,
+        height,
+        loading,
+        decoding,
+        role,
+        onerror
+    })
+}
+
 #[allow(non_snake_case)]
 pub fn Markdown(props: MdProps) -> Element {
     let src: String = props.src.to_string();
+    let scroll_to_fragment = props.scroll_to_fragment;
     let signal: Signal<MdProps> = Signal::new(props);
-    let child = markdown_component(MdContext(signal.into()), &src);
+    let links: Signal<Vec<StyleLink>> = use_signal(Vec::new);
+    let scripts: Signal<Vec<String>> = use_signal(Vec::new);
+    let used: Signal<Vec<String>> = use_signal(Vec::new);
+    dioxus::core::use_drop(move || {
+        // once no component uses a link or a script,
+        // the next component that needs it adds it again
+        let Ok(used) = used.try_peek() else {
+            return;
+        };
+        MOUNTED.with_borrow_mut(|mounted| {
+            for key in used.iter() {
+                if let Some(count) = mounted.get_mut(key) {
+                    *count -= 1;
+                    if *count == 0 {
+                        mounted.remove(key);
+                    }
+                }
+            }
+        })
+    });
+    let context = MdContext {
+        props: signal.into(),
+        links,
+        scripts,
+        used,
+    };
+    let child = markdown_component(context, &src);
+    if scroll_to_fragment {
+        // the url is only known in javascript, so the heading is looked up there,
+        // once the document is mounted
+        document::eval(
+            "requestAnimationFrame(() => {
+                const id = decodeURIComponent(location.hash.slice(1));
+                if (id) document.getElementById(id)?.scrollIntoView();
+            })",
+        );
+    }
     rsx! {
-        document::Style { href: MATH_STYLE_SHEET_LINK.href }
+        for link in links() {
+            document::Link {
+                rel: link.rel,
+                href: link.href,
+                integrity: link.integrity,
+                crossorigin: link.crossorigin,
+            }
+        }
+        for src in scripts() {
+            document::Script { src }
+        }
         {child}
     }
 }
@@ -1,5 +1,5 @@
 use leptos::*;
-use leptos_markdown::Markdown;
+use leptos_markdown::{Markdown, MathConfig};
 
 static MARKDOWN_SOURCE: &str = r#"
 ## Code
@@ -51,7 +51,7 @@ Even todo lists:
 #[component]
 fn App() -> impl IntoView {
     view! {
-        <Markdown src=MARKDOWN_SOURCE wikilinks=true/>
+        <Markdown src=MARKDOWN_SOURCE wikilinks=true math=MathConfig::default()/>
     }
 }
 
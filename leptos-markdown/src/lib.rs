@@ -1,11 +1,25 @@
 use web_framework_markdown::{
-    markdown_component, Context, CowStr, ElementAttributes, HtmlElement, MarkdownProps, StyleLink,
-    MATH_STYLE_SHEET_LINK,
+    is_activation_key, markdown_component, slugify, Context, CowStr, ElementAttributes,
+    HtmlElement, MarkdownProps, StyleLink,
 };
 
 pub type MdComponentProps = web_framework_markdown::MdComponentProps<View>;
 
-pub use web_framework_markdown::{ComponentCreationError, LinkDescription, Options};
+pub use web_framework_markdown::{
+    apply_task_toggle, line_number, parse_markdown, BlockQuoteKind, BlockquoteDescription,
+    ClickedElement, CodeBlockDescription, CodeClickEvent, CommentDirective, ComponentCreationError,
+    DebugEvent, Direction, DirectiveDescription, DocumentStats, ElementOverride, Frontmatter,
+    FrontmatterOptions, FrontmatterValue, Heading, HtmlAllowlist, HtmlElementKind, HtmlPolicy,
+    ImageAttributes, ImageErrorEvent, ImageLoading, LinkAttributes, LinkClickEvent,
+    LinkDescription, LinkInfo, LinkRelPolicy, LinkTarget, ListStyle, MarkdownClickEvent,
+    MarkdownConfig, MarkdownHoverEvent, MarkdownOptions, MathConfig, MathDescription,
+    MentionConfig, MermaidConfig, Options, ParseConfig, RenderStats, RootWrapper, SoftBreakMode,
+    StatsConfig, StreamTransform, SyntectConfig, TaskState, TaskToggle, TextTransform,
+    TypographyPreset, UnknownComponentPolicy, WikilinkResolution,
+};
+
+#[cfg(feature = "cache")]
+pub use web_framework_markdown::{clear_markdown_cache, set_markdown_cache_size};
 
 use web_sys::MouseEvent;
 
@@ -13,7 +27,7 @@ use leptos::html::AnyElement;
 use leptos::*;
 
 use core::ops::Range;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 
 #[cfg(feature = "debug")]
 pub mod debug {
@@ -22,16 +36,17 @@ pub mod debug {
     pub struct EventInfo(pub WriteSignal<Vec<String>>);
 }
 
-#[derive(Clone, Debug)]
-pub struct MarkdownMouseEvent {
-    /// the original mouse event triggered when a text element was clicked on
-    pub mouse_event: MouseEvent,
+/// the event reported when a markdown element is clicked on
+pub type MarkdownMouseEvent = MarkdownClickEvent<MouseEvent>;
 
-    /// the corresponding range in the markdown source, as a slice of [`u8`][u8]
-    pub position: Range<usize>,
-    // TODO: add a clonable tag for the type of the element
-    // pub tag: pulldown_cmark::Tag<'a>,
-}
+/// the event reported when the mouse enters or leaves a markdown element
+pub type MarkdownHoverMouseEvent = MarkdownHoverEvent<MouseEvent>;
+
+/// the event reported when a link is clicked on
+pub type LinkClickMouseEvent = LinkClickEvent<MouseEvent>;
+
+/// the event reported when inline code is clicked on
+pub type CodeClickMouseEvent = CodeClickEvent<MouseEvent>;
 
 /// component store.
 /// It is called when therer is a `<CustomComponent>` inside the markdown source.
@@ -69,14 +84,77 @@ impl<'a> Context<'a, 'static> for &'a __MdProps {
 
     type Handler<T: 'static> = Callback<T, ()>;
 
-    type MouseEvent = MouseEvent;
+    type Event = MouseEvent;
 
     fn props(self) -> MarkdownProps {
+        if let Some(config) = &self.config {
+            return config.clone().build();
+        }
         MarkdownProps {
             hard_line_breaks: self.hard_line_breaks.get(),
             wikilinks: self.wikilinks.get(),
             parse_options: self.parse_options,
             theme: self.theme,
+            syntax_highlight: self.syntax_highlight,
+            math: self.math,
+            mermaid: self.mermaid,
+            image_loading: self.image_loading,
+            eager_image_count: self.eager_image_count,
+            decorative_empty_alt: self.decorative_empty_alt,
+            broken_image_placeholder: self.broken_image_placeholder,
+            options: self.options,
+            base_url: self.base_url,
+            transform_stream: self.transform_stream,
+            typography: self.typography,
+            text_transform: self.text_transform,
+            emoji: self.emoji,
+            soft_break: self.soft_break,
+            stats_config: self.stats_config,
+            table_caption_prefix: self.table_caption_prefix,
+            normalize_html_tables: self.normalize_html_tables,
+            code_block_copy_button: self.code_block_copy_button,
+            code_block_line_numbers: self.code_block_line_numbers,
+            unknown_components: self.unknown_components,
+            html_policy: self.html_policy,
+            heading_offset: self.heading_offset,
+            heading_anchor_links: self.heading_anchor_links,
+            heading_numbering: self.heading_numbering,
+            toc_min_level: self.toc_min_level,
+            toc_max_level: self.toc_max_level,
+            inline_attributes: self.inline_attributes,
+            source_positions: self.source_positions,
+            ordered_list_style: self.ordered_list_style,
+            list_tightness_classes: self.list_tightness_classes,
+            max_nesting_depth: self.max_nesting_depth,
+            max_elements: self.max_elements,
+            max_image_count: self.max_image_count,
+            dir: self.dir,
+            lang: self.lang,
+            auto_block_direction: self.auto_block_direction,
+            abbreviations: self.abbreviations,
+            highlight_marks: self.highlight_marks,
+            kbd: self.kbd,
+            empty_placeholder: self.empty_placeholder,
+            unwrap_single_paragraph: self.unwrap_single_paragraph,
+            read_only_task_lists: self.read_only_task_lists,
+            task_list_states: self.task_list_states,
+            rule_class: self.rule_class,
+            collapse_rules: self.collapse_rules,
+            comment_directive_names: self.comment_directive_names,
+            scroll_to_fragment: self.scroll_to_fragment,
+            language_aliases: self.language_aliases,
+            footnote_popovers: self.footnote_popovers,
+            code_block_dedent: self.code_block_dedent,
+            code_block_trim: self.code_block_trim,
+            root_wrapper: self.root_wrapper,
+            frontmatter_overrides: self.frontmatter_overrides,
+            figure_images: self.figure_images,
+            class_prefix: self.class_prefix,
+            autolink: self.autolink,
+            mentions: self.mentions,
+            link_target: self.link_target,
+            link_rel_policy: self.link_rel_policy,
+            allowed_url_schemes: self.allowed_url_schemes,
         }
     }
 
@@ -95,6 +173,15 @@ impl<'a> Context<'a, 'static> for &'a __MdProps {
         inside: Self::View,
         attributes: ElementAttributes<Callback<MouseEvent>>,
     ) -> Self::View {
+        if let Some(f) = self.element_overrides.and_then(|o| o.get(&e.kind())) {
+            let element = ElementOverride {
+                element: Some(e),
+                children: inside,
+                attributes,
+                src: None,
+            };
+            return Callable::call(f, element);
+        }
         let mut r: leptos::HtmlElement<AnyElement> = match e {
             HtmlElement::Div => html::div().into_any(),
             HtmlElement::Span => html::span().into_any(),
@@ -114,20 +201,50 @@ impl<'a> Context<'a, 'static> for &'a __MdProps {
             HtmlElement::Thead => html::thead().into_any(),
             HtmlElement::Trow => html::tr().into_any(),
             HtmlElement::Tcell => html::td().into_any(),
+            HtmlElement::TheadCell => html::th().into_any(),
+            HtmlElement::Caption => html::caption().into_any(),
+            HtmlElement::Button => html::button().into_any(),
             HtmlElement::Italics => html::i().into_any(),
             HtmlElement::Bold => html::b().into_any(),
             HtmlElement::StrikeThrough => html::s().into_any(),
+            HtmlElement::Sup => html::sup().into_any(),
+            HtmlElement::Sub => html::sub().into_any(),
             HtmlElement::Pre => html::pre().into_any(),
             HtmlElement::Code => html::code().into_any(),
+            HtmlElement::Dl => html::dl().into_any(),
+            HtmlElement::Dt => html::dt().into_any(),
+            HtmlElement::Dd => html::dd().into_any(),
+            HtmlElement::Details => html::details().into_any(),
+            HtmlElement::Summary => html::summary().into_any(),
+            HtmlElement::Abbr => html::abbr().into_any(),
+            HtmlElement::Mark => html::mark().into_any(),
+            HtmlElement::Kbd => html::kbd().into_any(),
+            HtmlElement::Figure => html::figure().into_any(),
+            HtmlElement::Figcaption => html::figcaption().into_any(),
         };
 
         r = r.child(inside);
+        for (name, value) in attributes.extra_attributes() {
+            r = r.attr(name, value)
+        }
+        for (name, value) in attributes.source_attributes {
+            r = r.attr(name, value)
+        }
         if let Some(s) = attributes.style {
             r = r.attr("style", s.to_string())
         }
+        if let Some(id) = attributes.id {
+            r = r.attr("id", id)
+        }
         if let Some(c) = attributes.on_click {
             r = r.on(ev::click, move |e| Callable::call(&c, e));
         }
+        if let Some(c) = attributes.on_mouseenter {
+            r = r.on(ev::mouseenter, move |e| Callable::call(&c, e));
+        }
+        if let Some(c) = attributes.on_mouseleave {
+            r = r.on(ev::mouseleave, move |e| Callable::call(&c, e));
+        }
         r = r.classes(attributes.classes.join(" "));
         r.into_view()
     }
@@ -154,6 +271,9 @@ impl<'a> Context<'a, 'static> for &'a __MdProps {
 
     fn el_hr(self, attributes: ElementAttributes<Callback<MouseEvent>>) -> Self::View {
         let mut r = html::hr();
+        for (name, value) in attributes.extra_attributes() {
+            r = r.attr(name, value)
+        }
 
         if let Some(s) = attributes.style {
             r = r.attr("style", s.to_string())
@@ -161,6 +281,15 @@ impl<'a> Context<'a, 'static> for &'a __MdProps {
         if let Some(c) = attributes.on_click {
             r = r.on(ev::click, move |e| Callable::call(&c, e));
         }
+        if attributes.keyboard_activation {
+            // the same handler runs as for a click
+            r = r.on(ev::keydown, |e| {
+                if is_activation_key(&e.key()) {
+                    e.prevent_default();
+                    event_target::<web_sys::HtmlElement>(&e).click()
+                }
+            });
+        }
         r = r.classes(attributes.classes.join(" "));
         r.into_view()
     }
@@ -173,12 +302,74 @@ impl<'a> Context<'a, 'static> for &'a __MdProps {
         children.into_iter().collect()
     }
 
-    fn el_a(self, children: Self::View, href: String) -> Self::View {
-        view! {<a href={href.to_string()}>{children}</a>}.into_view()
+    fn el_a(
+        self,
+        children: Self::View,
+        href: String,
+        attributes: LinkAttributes,
+        on_click: Option<Callback<MouseEvent>>,
+    ) -> Self::View {
+        let on_click = move |e| {
+            if let Some(f) = on_click {
+                Callable::call(&f, e)
+            }
+        };
+        view! {
+            <a
+                on:click=on_click
+                href={href}
+                target={attributes.target}
+                rel={attributes.rel}
+                title={attributes.title}
+                class={attributes.classes.join(" ")}
+            >
+                {children}
+            </a>
+        }
+        .into_view()
     }
 
-    fn el_img(self, src: String, alt: String) -> Self::View {
-        view! {<img src={src.to_string()} alt={alt.to_string()}/>}.into_view()
+    fn el_img(self, src: String, alt: String, attributes: ImageAttributes) -> Self::View {
+        let ImageAttributes {
+            title,
+            width,
+            height,
+            loading,
+            decoding,
+            role,
+            ..
+        } = attributes.clone();
+        let (current_src, set_current_src) = create_signal(src.clone());
+        let on_image_error = self.on_image_error;
+        let on_error = move |_| {
+            let failed_src = current_src.get_untracked();
+            if let Some(f) = on_image_error {
+                f.call(attributes.error_event(&failed_src))
+            }
+            if let Some(fallback) = attributes.fallback_src(&failed_src) {
+                set_current_src.set(fallback.to_string())
+            }
+        };
+        let img = view! {
+            <img src=current_src alt={alt} title={title} width={width} height={height}
+                loading={loading} decoding={decoding} role={role} on:error=on_error/>
+        }
+        .into_view();
+        match self
+            .element_overrides
+            .and_then(|o| o.get(&HtmlElementKind::Img))
+        {
+            Some(f) => {
+                let image = ElementOverride {
+                    element: None,
+                    children: img,
+                    attributes: Default::default(),
+                    src: Some(src),
+                };
+                Callable::call(f, image)
+            }
+            None => img,
+        }
     }
 
     fn el_text(self, text: CowStr<'a>) -> Self::View {
@@ -188,11 +379,19 @@ impl<'a> Context<'a, 'static> for &'a __MdProps {
     fn el_input_checkbox(
         self,
         checked: bool,
+        indeterminate: bool,
         attributes: ElementAttributes<Callback<MouseEvent>>,
     ) -> Self::View {
         let mut r = html::input()
             .attr("type", "checkbox")
-            .attr("checked", checked);
+            .attr("checked", checked)
+            .prop("indeterminate", indeterminate);
+        if indeterminate {
+            r = r.attr("aria-checked", "mixed")
+        }
+        for (name, value) in attributes.extra_attributes() {
+            r = r.attr(name, value)
+        }
         if let Some(c) = attributes.on_click {
             r = r.on(ev::click, move |e| Callable::call(&c, e));
         }
@@ -203,6 +402,55 @@ impl<'a> Context<'a, 'static> for &'a __MdProps {
         r.into_view()
     }
 
+    fn mount_dynamic_link(self, link: StyleLink) {
+        let StyleLink {
+            rel,
+            href,
+            integrity,
+            crossorigin,
+        } = link;
+
+        let document = document();
+
+        let link = document.create_element("link").unwrap();
+
+        link.set_attribute("rel", rel).unwrap();
+        link.set_attribute("href", href).unwrap();
+        link.set_attribute("integrity", integrity).unwrap();
+        link.set_attribute("crossorigin", crossorigin).unwrap();
+
+        document.head().unwrap().append_child(&link).unwrap();
+    }
+
+    fn mount_dynamic_script(self, src: String) {
+        let document = document();
+        let selector = format!("script[src=\"{src}\"]");
+        if let Ok(Some(_)) = document.query_selector(&selector) {
+            return;
+        }
+        let script = document.create_element("script").unwrap();
+        script.set_attribute("src", &src).unwrap();
+        document.head().unwrap().append_child(&script).unwrap();
+    }
+
+    fn location_fragment(self) -> Option<String> {
+        window().location().hash().ok()
+    }
+
+    fn scroll_into_view(self, id: String) {
+        // the document is not mounted yet, so the scroll waits for the next frame
+        request_animation_frame(move || {
+            if let Some(element) = document().get_element_by_id(&id) {
+                element.scroll_into_view()
+            }
+        })
+    }
+
+    fn is_link_mounted(self, link: &StyleLink) -> bool {
+        let selector = format!("link[href=\"{}\"]", link.href);
+        matches!(document().query_selector(&selector), Ok(Some(_)))
+    }
+
     fn call_handler<T: 'static>(callback: &Self::Handler<T>, input: T) {
         Callable::call(callback, input)
     }
@@ -210,6 +458,7 @@ impl<'a> Context<'a, 'static> for &'a __MdProps {
     fn make_md_handler(
         self,
         position: Range<usize>,
+        element: ClickedElement,
         stop_propagation: bool,
     ) -> Self::Handler<MouseEvent> {
         match self.on_click {
@@ -221,7 +470,8 @@ impl<'a> Context<'a, 'static> for &'a __MdProps {
                     }
                     let report = MarkdownMouseEvent {
                         position: position.clone(),
-                        mouse_event: e,
+                        event: e,
+                        element: element.clone(),
                     };
                     Callable::call(&f, report)
                 })
@@ -230,16 +480,179 @@ impl<'a> Context<'a, 'static> for &'a __MdProps {
         }
     }
 
+    fn make_md_hover_handler(
+        self,
+        position: Range<usize>,
+        entering: bool,
+    ) -> Option<Self::Handler<MouseEvent>> {
+        let f = self.on_hover?;
+        Some(Callback::new(move |e: MouseEvent| {
+            let report = MarkdownHoverMouseEvent {
+                event: e,
+                position: position.clone(),
+                entering,
+            };
+            Callable::call(&f, report)
+        }))
+    }
+
+    fn make_link_click_handler(
+        self,
+        url: String,
+        position: Range<usize>,
+    ) -> Option<Self::Handler<MouseEvent>> {
+        let f = self.on_link_click?;
+        Some(Callback::new(move |e: MouseEvent| {
+            let report = LinkClickMouseEvent {
+                url: url.clone(),
+                mouse_event: e,
+                range: position.clone(),
+            };
+            Callable::call(&f, report)
+        }))
+    }
+
+    fn make_inline_code_click_handler(
+        self,
+        code: String,
+        position: Range<usize>,
+    ) -> Option<Self::Handler<MouseEvent>> {
+        let f = self.on_inline_code_click?;
+        Some(Callback::new(move |e: MouseEvent| {
+            let report = CodeClickMouseEvent {
+                code: code.clone(),
+                mouse_event: e,
+                range: position.clone(),
+            };
+            Callable::call(&f, report)
+        }))
+    }
+
+    fn make_task_toggle_handler(
+        self,
+        position: Range<usize>,
+        toggle: TaskToggle,
+    ) -> Self::Handler<MouseEvent> {
+        let on_click = self.make_md_handler(position, ClickedElement::TaskItem, true);
+        let task_toggle = self.task_toggle;
+        Callback::new(move |e: MouseEvent| {
+            Callable::call(&on_click, e);
+            if let Some(setter) = task_toggle {
+                setter.set(toggle.clone())
+            }
+        })
+    }
+
+    fn make_copy_handler(self, code: String) -> Option<Self::Handler<MouseEvent>> {
+        Some(Callback::new(move |_| {
+            let _ = window().navigator().clipboard().write_text(&code);
+        }))
+    }
+
+    fn make_permalink_copy_handler(self, id: String) -> Option<Self::Handler<MouseEvent>> {
+        Some(Callback::new(move |_| {
+            let href = window().location().href().unwrap_or_default();
+            let page = href.split('#').next().unwrap_or_default();
+            let _ = window()
+                .navigator()
+                .clipboard()
+                .write_text(&format!("{page}#{id}"));
+        }))
+    }
+
     fn set_frontmatter(&mut self, frontmatter: String) {
         if let Some(setter) = self.frontmatter {
             setter.set(frontmatter)
         }
     }
 
+    fn set_parsed_frontmatter(&mut self, frontmatter: Frontmatter) {
+        if let Some(setter) = self.parsed_frontmatter {
+            setter.set(frontmatter)
+        }
+    }
+
+    fn set_headings(&mut self, headings: Vec<Heading>) {
+        if let Some(setter) = self.headings {
+            setter.set(headings)
+        }
+    }
+
+    fn set_document_links(&mut self, links: Vec<LinkInfo>) {
+        if let Some(setter) = self.document_links {
+            setter.set(links)
+        }
+    }
+
+    fn set_comment_directives(&mut self, directives: Vec<CommentDirective>) {
+        if let Some(setter) = self.comment_directives {
+            setter.set(directives)
+        }
+    }
+
+    fn set_stats(&mut self, stats: DocumentStats) {
+        if let Some(setter) = self.stats {
+            setter.set(stats)
+        }
+    }
+
+    fn on_render_complete(self) -> Option<Self::Handler<RenderStats>> {
+        self.on_render_complete
+    }
+
+    #[cfg(feature = "debug")]
+    fn send_debug(self, events: Vec<DebugEvent>) {
+        if let Some(f) = self.send_debug {
+            Callable::call(&f, events)
+        }
+    }
+
     fn has_custom_links(self) -> bool {
         self.render_links.is_some()
     }
 
+    fn heading_slug(self, text: &str) -> String {
+        match self.heading_slugger {
+            Some(f) => Callable::call(&f, text.to_string()),
+            None => slugify(text),
+        }
+    }
+
+    fn render_code_block(self, code_block: CodeBlockDescription) -> Option<Self::View> {
+        self.render_code_block
+            .map(|f| Callable::call(&f, code_block))
+    }
+
+    fn render_directive(self, directive: DirectiveDescription<View>) -> Option<Self::View> {
+        self.render_directive.map(|f| Callable::call(&f, directive))
+    }
+
+    fn render_blockquote(self, blockquote: BlockquoteDescription<View>) -> Option<Self::View> {
+        self.render_blockquote
+            .map(|f| Callable::call(&f, blockquote))
+    }
+
+    fn render_math(self, math: MathDescription) -> Option<Self::View> {
+        self.render_math.map(|f| Callable::call(&f, math))
+    }
+
+    fn resolve_wikilink(self, target: &str) -> Option<WikilinkResolution> {
+        match self.resolve_wikilink {
+            Some(f) => Callable::call(&f, target.to_string()),
+            None => Some(WikilinkResolution::identity(target)),
+        }
+    }
+
+    fn resolve_mention(self, name: &str) -> Option<String> {
+        self.resolve_mention
+            .and_then(|f| Callable::call(&f, name.to_string()))
+    }
+
+    fn resolve_hashtag(self, tag: &str) -> Option<String> {
+        self.resolve_hashtag
+            .and_then(|f| Callable::call(&f, tag.to_string()))
+    }
+
     fn render_links(self, link: LinkDescription<Self::View>) -> Result<Self::View, String> {
         Ok(Callable::call(&self.render_links.unwrap(), link))
     }
@@ -265,21 +678,401 @@ pub fn __Md(
     #[prop(into)]
     src: MaybeSignal<String>,
 
+    /// the options set with a [`MarkdownConfig`].
+    /// When set, they replace the options passed as separate props
+    #[prop(optional, into)]
+    config: Option<MarkdownConfig>,
+
     /// the callback called when a component is clicked.
     /// if you want to controll what happens when a link is clicked,
     /// use [`render_links`][render_links]
     #[prop(optional, into)]
     on_click: Option<Callback<MarkdownMouseEvent>>,
 
+    /// the callback called when the mouse enters or leaves a text element
+    #[prop(optional, into)]
+    on_hover: Option<Callback<MarkdownHoverMouseEvent>>,
+
+    /// the callback called when an image fails to load
+    #[prop(optional, into)]
+    on_image_error: Option<Callback<ImageErrorEvent>>,
+
+    /// the callback called when a link is clicked on, before the navigation.
+    /// Call `prevent_default` on the mouse event to route the link inside the application
+    #[prop(optional, into)]
+    on_link_click: Option<Callback<LinkClickMouseEvent>>,
+
+    /// the callback called when inline code is clicked on, with the text of the code.
+    /// Clicks on code blocks are not reported
+    #[prop(optional, into)]
+    on_inline_code_click: Option<Callback<CodeClickMouseEvent>>,
+
+    /// the callback called once the document is rendered,
+    /// with the number of rendered elements and the time it took
+    #[prop(optional, into)]
+    on_render_complete: Option<Callback<RenderStats>>,
+
+    /// the callback called once the document is rendered,
+    /// with what happened while rendering it, like the events that were dropped.
+    /// It is only called with the `debug` feature
+    #[prop(optional, into)]
+    send_debug: Option<Callback<Vec<DebugEvent>>>,
+
     ///
     #[prop(optional, into)]
     render_links: Option<Callback<LinkDescription<View>, leptos::View>>,
 
+    /// renders inline and display maths, for example to produce MathML.
+    /// By default, maths are compiled with katex
+    #[prop(optional, into)]
+    render_math: Option<Callback<MathDescription, leptos::View>>,
+
+    /// renders code blocks, for example to draw diagrams
+    /// depending on the language of the block.
+    /// By default, code blocks are highlighted with syntect
+    #[prop(optional, into)]
+    render_code_block: Option<Callback<CodeBlockDescription, leptos::View>>,
+
+    /// renders the container directives like `:::warning{key=value} ... :::`,
+    /// for example to create admonitions.
+    /// By default, the content of a directive is rendered in a `<div>`
+    #[prop(optional, into)]
+    render_directive: Option<Callback<DirectiveDescription<View>, leptos::View>>,
+
+    /// renders the blockquotes, and the callouts like `> [!NOTE]`,
+    /// for example to style quotes with their author.
+    /// By default, they are rendered as a `<blockquote>`, or as a callout
+    #[prop(optional, into)]
+    render_blockquote: Option<Callback<BlockquoteDescription<View>, leptos::View>>,
+
+    /// renders the elements of some kinds instead of the default tags,
+    /// for example to replace every table with a virtualized one.
+    /// The callback gets the rendered children and the attributes of the element
+    #[prop(optional, into)]
+    element_overrides: Option<
+        &'static HashMap<
+            HtmlElementKind,
+            Callback<ElementOverride<View, Callback<MouseEvent>>, leptos::View>,
+        >,
+    >,
+
+    /// finds the pages that `[[wikilinks]]` point to.
+    /// Links to missing pages get the `md-wikilink-broken` class.
+    /// By default, the target of a wikilink is used as its url
+    #[prop(optional, into)]
+    resolve_wikilink: Option<Callback<String, Option<WikilinkResolution>>>,
+
+    /// finds the urls of the users mentioned like `@alice`, when `mentions` is set.
+    /// Unresolved mentions get the `md-mention-broken` class
+    #[prop(optional, into)]
+    resolve_mention: Option<Callback<String, Option<String>>>,
+
+    /// finds the urls of the hashtags like `#topic`, when `mentions` is set.
+    /// Unresolved hashtags get the `md-hashtag-broken` class
+    #[prop(optional, into)]
+    resolve_hashtag: Option<Callback<String, Option<String>>>,
+
     /// the name of the theme used for syntax highlighting.
     /// Only the default themes of [syntect::Theme] are supported
     #[prop(optional, into)]
     theme: Option<&'static str>,
 
+    /// how code blocks are highlighted.
+    /// If set, it replaces the theme
+    #[prop(optional, into)]
+    syntax_highlight: Option<SyntectConfig>,
+
+    /// the stylesheet used to display maths.
+    /// It is only loaded when the document contains maths.
+    /// If None, no stylesheet is loaded
+    #[prop(optional, into)]
+    math: Option<MathConfig>,
+
+    /// the mermaid script, that draws the ` ```mermaid ` diagrams.
+    /// It is only loaded when the document contains a diagram.
+    /// If None, no script is loaded
+    #[prop(optional, into)]
+    mermaid: Option<MermaidConfig>,
+
+    /// how images are loaded.
+    /// By default, they are loaded lazily
+    #[prop(optional, into)]
+    image_loading: ImageLoading,
+
+    /// the number of images at the start of the document
+    /// that are always loaded eagerly
+    #[prop(optional, into)]
+    eager_image_count: usize,
+
+    /// gives `role="presentation"` to the images with an empty alternative text,
+    /// like `![](divider.png)`, so that screen readers treat them as decorative
+    #[prop(optional, into)]
+    decorative_empty_alt: bool,
+
+    /// the url of the image displayed instead of images that fail to load
+    #[prop(optional, into)]
+    broken_image_placeholder: Option<&'static str>,
+
+    /// the markdown extensions to enable.
+    /// They are ignored if `parse_options` is set
+    #[prop(optional, into)]
+    options: MarkdownOptions,
+
+    /// the url that relative links and images are resolved against,
+    /// usually the url of the markdown document.
+    /// If None, relative urls are left unchanged
+    #[prop(optional, into)]
+    base_url: Option<&'static str>,
+
+    /// a function that rewrites the parsed events before they are rendered,
+    /// see [`StreamTransform`]
+    #[prop(optional, into)]
+    transform_stream: Option<StreamTransform>,
+
+    /// typographic replacements applied to the text, in order, like german quotes
+    #[prop(optional, into)]
+    typography: &'static [TypographyPreset],
+
+    /// a function that rewrites the text of the document, but not the code
+    #[prop(optional, into)]
+    text_transform: Option<TextTransform>,
+
+    /// replaces github shortcodes like `:tada:` by the corresponding emojis.
+    /// It only has an effect when the `emoji` feature is enabled
+    #[prop(optional, into)]
+    emoji: bool,
+
+    /// how line breaks inside paragraphs are rendered
+    #[prop(optional, into)]
+    soft_break: SoftBreakMode,
+
+    /// how the word count and reading time of the document are computed
+    #[prop(optional, into)]
+    stats_config: StatsConfig,
+
+    /// the prefix of the paragraphs that are used as the caption of a table,
+    /// when they are right before or right after it, like `Table:` in pandoc.
+    /// If None, tables have no caption
+    #[prop(optional, into)]
+    table_caption_prefix: Option<&'static str>,
+
+    /// wether the raw html tables are rendered like markdown tables,
+    /// with the same elements and alignment
+    #[prop(optional, into)]
+    normalize_html_tables: bool,
+
+    /// wether code blocks get a button to copy their content to the clipboard
+    #[prop(optional, into)]
+    code_block_copy_button: bool,
+
+    /// wether the lines of code blocks are numbered
+    #[prop(optional, into)]
+    code_block_line_numbers: bool,
+
+    /// what to do with the tags that look like custom components,
+    /// like `<MyComponent/>`, but that are not registered
+    #[prop(optional, into)]
+    unknown_components: UnknownComponentPolicy,
+
+    /// how the raw html of the source is rendered.
+    /// Use `HtmlPolicy::Sanitize` or `HtmlPolicy::Strip` for untrusted content
+    #[prop(optional, into)]
+    html_policy: HtmlPolicy,
+
+    /// the number of levels that every heading is shifted down by, up to `<h6>`.
+    /// For example, with an offset of 1, `# Title` is rendered as a `<h2>`
+    #[prop(optional, into)]
+    heading_offset: u8,
+
+    /// adds a `#` link to the anchor of every heading.
+    /// Clicking it copies the permalink of the heading
+    #[prop(optional, into)]
+    heading_anchor_links: bool,
+
+    /// prepends hierarchical numbers to the headings, like `1`, `1.1`, `1.2`, `1.2.1`,
+    /// following their levels once the heading offset is applied
+    #[prop(optional, into)]
+    heading_numbering: bool,
+
+    /// the lowest level of the headings in the table of content, like 2 to leave the `<h1>` out.
+    /// It doesn't change how the headings are rendered
+    #[prop(optional, into)]
+    toc_min_level: u8,
+
+    /// the highest level of the headings in the table of content, like 3 to leave the `<h4>` to `<h6>` out.
+    /// If 0, there is no limit
+    #[prop(optional, into)]
+    toc_max_level: u8,
+
+    /// reads a block like `{.lead #intro}` at the end of a paragraph
+    /// as its classes, id and attributes, and removes it from the text.
+    /// On its own line at the end of a blockquote, the block applies to the blockquote
+    #[prop(optional, into)]
+    inline_attributes: bool,
+
+    /// sets `data-source-start` and `data-source-end` on the top-level blocks,
+    /// to their byte offsets in the source, for example to synchronize the scrolling of an editor
+    #[prop(optional, into)]
+    source_positions: bool,
+
+    /// how the items of ordered lists are numbered, like `I.`, `II.` for [`ListStyle::UpperRoman`]
+    #[prop(optional, into)]
+    ordered_list_style: Option<ListStyle>,
+
+    /// wether lists get the `md-list-tight` class, or `md-list-loose`
+    /// if their items are separated by blank lines
+    #[prop(optional, into)]
+    list_tightness_classes: bool,
+
+    /// the number of elements that can be nested in each other, like blockquotes.
+    /// Deeper elements are replaced by a `<div class="md-depth-limit">`,
+    /// or a `<span>` inside a paragraph.
+    /// If None, 100 levels are allowed
+    #[prop(optional, into)]
+    max_nesting_depth: Option<usize>,
+
+    /// the number of elements that are rendered at most.
+    /// The rest of the document is replaced by a `<div class="md-truncated">`.
+    /// If None, there is no limit
+    #[prop(optional, into)]
+    max_elements: Option<usize>,
+
+    /// the number of images that are rendered at most.
+    /// The rest of the document is replaced by a `<div class="md-truncated">`.
+    /// If None, there is no limit
+    #[prop(optional, into)]
+    max_image_count: Option<usize>,
+
+    /// the direction of the text of the document, like [`Direction::Rtl`].
+    /// If it or `lang` is set, the document is wrapped in a `<div>`
+    #[prop(optional, into)]
+    dir: Option<Direction>,
+
+    /// the language of the document, like `ar` or `en-US`
+    #[prop(optional, into)]
+    lang: Option<&'static str>,
+
+    /// gives `dir="auto"` to the paragraphs, headings and list items,
+    /// for documents that mix left-to-right and right-to-left languages
+    #[prop(optional, into)]
+    auto_block_direction: bool,
+
+    /// abbreviations and their expansion, rendered as `<abbr>` wherever they appear.
+    /// The abbreviations defined in the document, like `*[HTML]: HyperText Markup Language`,
+    /// take precedence
+    #[prop(optional, into)]
+    abbreviations: Option<&'static HashMap<&'static str, &'static str>>,
+
+    /// renders the text between `==`, like `==this==`, in a `<mark>`
+    #[prop(optional, into)]
+    highlight_marks: bool,
+
+    /// renders the keys between `++`, like `++Ctrl+C++`, in a `<kbd>`
+    #[prop(optional, into)]
+    kbd: bool,
+
+    /// the text shown when the document has no content
+    #[prop(optional, into)]
+    empty_placeholder: Option<&'static str>,
+
+    /// if the document is a single paragraph,
+    /// its content is rendered without the `<p>` around it
+    #[prop(optional, into)]
+    unwrap_single_paragraph: bool,
+
+    /// renders the checkboxes of task lists disabled, so that they can't be toggled
+    #[prop(optional, into)]
+    read_only_task_lists: bool,
+
+    /// the extra states of the tasks of task lists, like `[/]`,
+    /// rendered as indeterminate checkboxes
+    #[prop(optional, into)]
+    task_list_states: Option<&'static HashMap<char, TaskState>>,
+
+    /// extra css classes of the horizontal rules, separated by spaces
+    #[prop(optional, into)]
+    rule_class: Option<&'static str>,
+
+    /// renders consecutive horizontal rules as a single one
+    #[prop(optional, into)]
+    collapse_rules: bool,
+
+    /// the names of the html comments that are directives, like `more` for `<!-- more -->`.
+    /// They are not rendered, and are written to `comment_directives`
+    #[prop(optional, into)]
+    comment_directive_names: Option<&'static [&'static str]>,
+
+    /// scrolls the heading that the fragment of the url points to, like `#usage`, into view
+    #[prop(optional, into)]
+    scroll_to_fragment: bool,
+
+    /// the names of the languages of code blocks, by alias, like `javascript` for `js`.
+    /// They are added to [`DEFAULT_LANGUAGE_ALIASES`][web_framework_markdown::DEFAULT_LANGUAGE_ALIASES],
+    /// and take precedence over them
+    #[prop(optional, into)]
+    language_aliases: Option<&'static HashMap<&'static str, &'static str>>,
+
+    /// gives the footnote references the text of their definition as a `title`, shown as a tooltip
+    #[prop(optional, into)]
+    footnote_popovers: bool,
+
+    /// removes the indentation that all the lines of a code block have in common
+    #[prop(optional, into)]
+    code_block_dedent: bool,
+
+    /// removes the blank lines at the end of code blocks
+    #[prop(optional, into)]
+    code_block_trim: bool,
+
+    /// the element around the whole document, like a `<div>` with an id.
+    /// If None, it is a `<div>` only if the direction or the language is set
+    #[prop(optional, into)]
+    root_wrapper: Option<RootWrapper>,
+
+    /// with the `frontmatter` feature, lets the options set by the frontmatter
+    /// of the document take precedence over the props
+    #[prop(optional, into)]
+    frontmatter_overrides: bool,
+
+    /// renders the images alone in their paragraph and with a title
+    /// as a `<figure>`, with the title as caption
+    #[prop(optional, into)]
+    figure_images: bool,
+
+    /// a prefix added to the css classes created by this crate,
+    /// like `Some("prose-")` to get `prose-language-rust` instead of `language-rust`
+    #[prop(optional, into)]
+    class_prefix: Option<&'static str>,
+
+    /// wether bare urls like `https://example.com` or `www.example.com`,
+    /// and email addresses are turned into links
+    #[prop(optional, into)]
+    autolink: bool,
+
+    /// turns the mentions like `@alice` and the hashtags like `#topic` into links,
+    /// with `resolve_mention` and `resolve_hashtag`
+    #[prop(optional, into)]
+    mentions: Option<MentionConfig>,
+
+    /// where links are opened when clicked.
+    /// By default, they are opened in the same tab
+    #[prop(optional, into)]
+    link_target: LinkTarget,
+
+    /// the `rel` keywords of the links to other websites, like `nofollow ugc`
+    #[prop(optional, into)]
+    link_rel_policy: LinkRelPolicy,
+
+    /// the url schemes allowed in links and images.
+    /// By default, [`DEFAULT_URL_SCHEMES`][web_framework_markdown::DEFAULT_URL_SCHEMES]
+    #[prop(optional, into)]
+    allowed_url_schemes: Option<&'static [&'static str]>,
+
+    /// the function used to compute the anchor id of headings from their text.
+    /// By default, github-style slugs are used
+    #[prop(optional, into)]
+    heading_slugger: Option<Callback<String, String>>,
+
     /// wether to enable wikilinks support.
     /// Wikilinks look like [[shortcut link]] or [[url|name]]
     #[prop(optional, into)]
@@ -297,28 +1090,38 @@ pub fn __Md(
     #[prop(optional, into)] components: CustomComponents,
 
     #[prop(optional, into)] frontmatter: Option<WriteSignal<String>>,
+
+    /// the frontmatter of the document, parsed into key-value pairs.
+    /// It is only set with the `frontmatter` feature,
+    /// when the frontmatter is valid yaml or toml
+    #[prop(optional, into)]
+    parsed_frontmatter: Option<WriteSignal<Frontmatter>>,
+
+    /// the headings of the document, to build a table of content
+    #[prop(optional, into)]
+    headings: Option<WriteSignal<Vec<Heading>>>,
+
+    /// the links and images of the document, for example to check them
+    #[prop(optional, into)]
+    document_links: Option<WriteSignal<Vec<LinkInfo>>>,
+
+    /// the html comments of the document that are directives, like `<!-- more -->`
+    #[prop(optional, into)]
+    comment_directives: Option<WriteSignal<Vec<CommentDirective>>>,
+
+    /// the word count and reading time of the document
+    #[prop(optional, into)]
+    stats: Option<WriteSignal<DocumentStats>>,
+
+    /// the last task list checkbox toggled by the user.
+    /// Use [`apply_task_toggle`] to update the source accordingly
+    #[prop(optional, into)]
+    task_toggle: Option<WriteSignal<TaskToggle>>,
 ) -> impl IntoView {
     ()
 }
 
 #[allow(non_snake_case)]
 pub fn Markdown(props: __MdProps) -> impl IntoView {
-    let StyleLink {
-        rel,
-        href,
-        integrity,
-        crossorigin,
-    } = MATH_STYLE_SHEET_LINK;
-
-    let document = document();
-
-    let link = document.create_element("link").unwrap();
-
-    link.set_attribute("rel", rel).unwrap();
-    link.set_attribute("href", href).unwrap();
-    link.set_attribute("integrity", integrity).unwrap();
-    link.set_attribute("crossorigin", crossorigin).unwrap();
-
-    document.head().unwrap().append_child(&link).unwrap();
     move || markdown_component(&props, &props.src.get())
 }
@@ -0,0 +1,146 @@
+//! A minimal [`Context`] outside of the browser, that renders markdown to plain text,
+//! like a terminal application would.
+//! It only needs the core, without the optional features:
+//!
+//! `cargo run --example plain_text --no-default-features`
+
+use core::ops::Range;
+
+use web_framework_markdown::{
+    markdown_component, ClickedElement, CodeBlockDescription, ComponentCreationError, Context,
+    CowStr, ElementAttributes, HtmlElement, ImageAttributes, LinkAttributes, LinkDescription,
+    MarkdownProps, MdComponentProps,
+};
+
+#[derive(Clone, Copy)]
+struct PlainText<'p> {
+    props: &'p MarkdownProps,
+}
+
+impl<'a, 'p: 'a> Context<'a, 'static> for PlainText<'p> {
+    type View = String;
+
+    // there are no clicks in plain text
+    type Handler<T: 'static> = ();
+
+    type Event = ();
+
+    fn props(self) -> MarkdownProps {
+        self.props.clone()
+    }
+
+    fn set_frontmatter(&mut self, _frontmatter: String) {}
+
+    fn render_links(self, _link: LinkDescription<String>) -> Result<String, String> {
+        Err("no custom links".into())
+    }
+
+    fn render_code_block(self, code_block: CodeBlockDescription) -> Option<String> {
+        Some(format!("{}\n", code_block.content))
+    }
+
+    fn call_handler<T: 'static>(_callback: &(), _input: T) {}
+
+    fn make_md_handler(
+        self,
+        _position: Range<usize>,
+        _element: ClickedElement,
+        _stop_propagation: bool,
+    ) {
+    }
+
+    #[cfg(feature = "debug")]
+    fn send_debug_info(self, _info: Vec<String>) {}
+
+    fn el_with_attributes(
+        self,
+        e: HtmlElement,
+        inside: String,
+        _attributes: ElementAttributes<()>,
+    ) -> String {
+        match e {
+            HtmlElement::Heading(level) => {
+                format!("{} {inside}\n\n", "#".repeat(level as usize))
+            }
+            HtmlElement::Paragraph | HtmlElement::Ul | HtmlElement::Ol(_) => {
+                format!("{inside}\n")
+            }
+            HtmlElement::Li => format!("- {inside}\n"),
+            HtmlElement::Bold => inside.to_uppercase(),
+            _ => inside,
+        }
+    }
+
+    fn el_span_with_inner_html(self, html: String, _attributes: ElementAttributes<()>) -> String {
+        html
+    }
+
+    fn el_hr(self, _attributes: ElementAttributes<()>) -> String {
+        "----\n".into()
+    }
+
+    fn el_br(self) -> String {
+        "\n".into()
+    }
+
+    fn el_fragment(self, children: Vec<String>) -> String {
+        children.concat()
+    }
+
+    fn el_a(
+        self,
+        children: String,
+        href: String,
+        _attributes: LinkAttributes,
+        _on_click: Option<()>,
+    ) -> String {
+        format!("{children} <{href}>")
+    }
+
+    fn el_img(self, src: String, alt: String, _attributes: ImageAttributes) -> String {
+        format!("[{alt}]({src})")
+    }
+
+    fn el_text(self, text: CowStr<'a>) -> String {
+        text.to_string()
+    }
+
+    fn el_input_checkbox(
+        self,
+        checked: bool,
+        indeterminate: bool,
+        _attributes: ElementAttributes<()>,
+    ) -> String {
+        match (checked, indeterminate) {
+            (_, true) => "[-]",
+            (true, false) => "[x]",
+            (false, false) => "[ ]",
+        }
+        .into()
+    }
+
+    fn has_custom_component(self, _name: &str) -> bool {
+        false
+    }
+
+    fn render_custom_component(
+        self,
+        name: &str,
+        _input: MdComponentProps<String>,
+    ) -> Result<String, ComponentCreationError> {
+        Err(format!("no component named `{name}`").into())
+    }
+
+    fn has_custom_links(self) -> bool {
+        false
+    }
+}
+
+fn main() {
+    let props = MarkdownProps::default();
+    let source = "# Hello\n\nsome **plain** text, see [the docs](/docs)\n\n- [x] done\n- [ ] todo";
+    print!(
+        "{}",
+        markdown_component(PlainText { props: &props }, source)
+    );
+}
@@ -0,0 +1,137 @@
+use core::ops::Range;
+
+use pulldown_cmark::{Event, Tag, TagEnd};
+
+/// `parse_definition(line)` parses a line like `*[HTML]: HyperText Markup Language`
+/// into the abbreviation and its expansion
+fn parse_definition(line: &str) -> Option<(&str, &str)> {
+    let rest = line.trim().strip_prefix("*[")?;
+    let (abbreviation, rest) = rest.split_once("]:")?;
+    let expansion = rest.trim();
+    if abbreviation.trim().is_empty() || abbreviation.contains(']') || expansion.is_empty() {
+        return None;
+    }
+    Some((abbreviation.trim(), expansion))
+}
+
+/// `definitions(paragraph, source)` returns the definitions of `paragraph`,
+/// the events of a paragraph of `source`,
+/// if all its lines are abbreviation definitions
+fn definitions<'s>(
+    paragraph: &[(Event<'_>, Range<usize>)],
+    source: &'s str,
+) -> Option<Vec<(&'s str, &'s str)>> {
+    let text = source.get(paragraph.first()?.1.clone())?;
+    let lines: Vec<_> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(parse_definition)
+        .collect::<Option<_>>()?;
+    (!lines.is_empty()).then_some(lines)
+}
+
+/// `extract_definitions(stream, source)` removes the top-level paragraphs of `stream`
+/// that only contain abbreviation definitions, like `*[HTML]: HyperText Markup Language`,
+/// and returns the definitions, in order
+pub fn extract_definitions(
+    stream: &mut Vec<(Event<'_>, Range<usize>)>,
+    source: &str,
+) -> Vec<(String, String)> {
+    let mut result = Vec::new();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < stream.len() {
+        if depth == 0 && stream[i].0 == Event::Start(Tag::Paragraph) {
+            let len = stream[i..]
+                .iter()
+                .position(|(e, _)| *e == Event::End(TagEnd::Paragraph));
+            if let (Some(len), Some(lines)) = (len, definitions(&stream[i..], source)) {
+                let lines = lines
+                    .into_iter()
+                    .map(|(a, e)| (a.to_string(), e.to_string()));
+                result.extend(lines);
+                stream.drain(i..=i + len);
+                continue;
+            }
+        }
+        match stream[i].0 {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            _ => (),
+        }
+        i += 1;
+    }
+    result
+}
+
+/// `split_abbreviations(text, abbreviations)` splits `text` into the parts that are
+/// abbreviations, with their expansion, and the parts in between.
+/// An abbreviation only matches a whole word, and the longest one wins
+pub fn split_abbreviations<'t>(
+    text: &'t str,
+    abbreviations: &'t [(String, String)],
+) -> Vec<(Range<usize>, Option<&'t str>)> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let mut parts = Vec::new();
+    let mut last = 0;
+    let mut i = 0;
+    while i < text.len() {
+        let at_word_start = !text[..i].ends_with(is_word);
+        let found = abbreviations
+            .iter()
+            .filter(|(a, _)| at_word_start && text[i..].starts_with(a.as_str()))
+            .filter(|(a, _)| !text[i + a.len()..].starts_with(is_word))
+            .max_by_key(|(a, _)| a.len());
+        match found {
+            Some((abbreviation, expansion)) => {
+                if last < i {
+                    parts.push((last..i, None));
+                }
+                last = i + abbreviation.len();
+                parts.push((i..last, Some(expansion.as_str())));
+                i = last;
+            }
+            None => i += text[i..].chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    if last < text.len() {
+        parts.push((last..text.len(), None));
+    }
+    parts
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn definition() {
+        assert_eq!(
+            parse_definition("*[HTML]: HyperText Markup Language"),
+            Some(("HTML", "HyperText Markup Language"))
+        );
+        assert_eq!(parse_definition("*[HTML]:"), None);
+        assert_eq!(parse_definition("[HTML]: https://example.com"), None);
+    }
+
+    #[test]
+    fn split() {
+        let abbreviations = vec![
+            ("HTML".to_string(), "HyperText Markup Language".to_string()),
+            ("HTML5".to_string(), "HTML version 5".to_string()),
+        ];
+        assert_eq!(
+            split_abbreviations("HTML and HTML5, not XHTML", &abbreviations),
+            vec![
+                (0..4, Some("HyperText Markup Language")),
+                (4..9, None),
+                (9..14, Some("HTML version 5")),
+                (14..25, None),
+            ]
+        );
+        assert_eq!(
+            split_abbreviations("none", &abbreviations),
+            vec![(0..4, None)]
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use core::ops::Range;
+use std::collections::HashMap;
+
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+
+/// the attributes set on a paragraph or a blockquote
+/// by a block like `{.lead #intro data-level=2}` at its end
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BlockAttributes {
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+    /// the `key=value` attributes, like the ones of the headings
+    pub attributes: Vec<(CowStr<'static>, Option<CowStr<'static>>)>,
+}
+
+/// wether `name` can be used as a class, an id or the name of an attribute
+fn is_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+/// `split_token(text)` splits `text` at the first whitespace that is not quoted,
+/// or returns None if a quote is not closed
+fn split_token(text: &str) -> Option<(&str, &str)> {
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, _) if c.is_whitespace() => return Some((&text[..i], text[i..].trim_start())),
+            _ => (),
+        }
+    }
+    quote.is_none().then_some((text, ""))
+}
+
+/// `parse_attributes(block)` parses the inside of a block like `{.lead #intro data-level=2}`,
+/// with an optional leading `:`, or returns None if it is not only made of attributes
+fn parse_attributes(block: &str) -> Option<BlockAttributes> {
+    let block = block.trim();
+    let mut rest = block.strip_prefix(':').unwrap_or(block).trim_start();
+    if rest.is_empty() {
+        return None;
+    }
+    let mut attributes = BlockAttributes::default();
+    while !rest.is_empty() {
+        let (token, remaining) = split_token(rest)?;
+        rest = remaining;
+        if let Some(class) = token.strip_prefix('.').filter(|c| is_name(c)) {
+            attributes.classes.push(class.to_string());
+        } else if let Some(id) = token.strip_prefix('#').filter(|id| is_name(id)) {
+            attributes.id = Some(id.to_string());
+        } else {
+            let (key, value) = token.split_once('=').filter(|(key, _)| is_name(key))?;
+            let value = ['"', '\'']
+                .iter()
+                .find_map(|q| value.strip_prefix(*q)?.strip_suffix(*q))
+                .unwrap_or(value);
+            attributes
+                .attributes
+                .push((key.to_string().into(), Some(value.to_string().into())));
+        }
+    }
+    Some(attributes)
+}
+
+/// `fold_paragraph(stream, end, blockquote)` removes the attribute block
+/// at the end of the paragraph whose end is at `end`.
+/// A block on its own line at the end of a blockquote, whose position is `blockquote`,
+/// applies to the blockquote, any other block to the paragraph.
+/// Returns the position of the element the attributes apply to, the attributes,
+/// and the new index of the end of the paragraph
+fn fold_paragraph(
+    stream: &mut Vec<(Event<'_>, Range<usize>)>,
+    end: usize,
+    blockquote: Option<usize>,
+) -> Option<(usize, BlockAttributes, usize)> {
+    let start = stream[..end]
+        .iter()
+        .rposition(|(e, _)| !matches!(e, Event::Text(_)))?
+        + 1;
+    let text: String = stream[start..end]
+        .iter()
+        .filter_map(|(e, _)| match e {
+            Event::Text(t) => Some(t.as_ref()),
+            _ => None,
+        })
+        .collect();
+    let trimmed = text.trim_end();
+    let open = trimmed.strip_suffix('}')?.rfind('{')?;
+    let attributes = parse_attributes(&trimmed[open + 1..trimmed.len() - 1])?;
+
+    let before = text[..open].trim_end();
+    if before.is_empty() && stream[start - 1].0 == Event::Start(Tag::Paragraph) {
+        // a paragraph with only an attribute block is left as is
+        return None;
+    }
+    let own_line =
+        before.is_empty() && matches!(stream[start - 1].0, Event::SoftBreak | Event::HardBreak);
+    let paragraph = stream[..start]
+        .iter()
+        .rposition(|(e, _)| *e == Event::Start(Tag::Paragraph))?;
+    let position = match blockquote {
+        Some(position)
+            if own_line
+                && matches!(
+                    stream.get(end + 1),
+                    Some((Event::End(TagEnd::BlockQuote(_)), _))
+                ) =>
+        {
+            position
+        }
+        _ => stream[paragraph].1.start,
+    };
+
+    let range = stream[start].1.start..stream[end - 1].1.end;
+    let replacement = (!before.is_empty()).then(|| {
+        let end = (range.start + before.len()).min(range.end);
+        (Event::Text(before.to_string().into()), range.start..end)
+    });
+    let first = if own_line { start - 1 } else { start };
+    let new_end = first + replacement.is_some() as usize;
+    stream.splice(first..end, replacement);
+    Some((position, attributes, new_end))
+}
+
+/// `fold_block_attributes(stream)` removes the attribute blocks like `{.lead #intro}`
+/// at the end of the paragraphs of `stream`,
+/// and returns their attributes by position of the paragraph or blockquote they apply to.
+/// A block on its own line, at the end of the last paragraph of a blockquote,
+/// applies to the blockquote
+pub fn fold_block_attributes(
+    stream: &mut Vec<(Event<'_>, Range<usize>)>,
+) -> HashMap<usize, BlockAttributes> {
+    let mut result = HashMap::new();
+    let mut blockquotes = Vec::new();
+    let mut i = 0;
+    while i < stream.len() {
+        match stream[i].0 {
+            Event::Start(Tag::BlockQuote(_)) => blockquotes.push(stream[i].1.start),
+            Event::End(TagEnd::BlockQuote(_)) => {
+                blockquotes.pop();
+            }
+            Event::End(TagEnd::Paragraph) => {
+                let blockquote = blockquotes.last().copied();
+                if let Some((position, attributes, end)) = fold_paragraph(stream, i, blockquote) {
+                    result.insert(position, attributes);
+                    i = end;
+                }
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use pulldown_cmark::Parser;
+
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            parse_attributes(".lead #intro data-level=2 title=\"a title\""),
+            Some(BlockAttributes {
+                id: Some("intro".into()),
+                classes: vec!["lead".into()],
+                attributes: vec![
+                    ("data-level".into(), Some("2".into())),
+                    ("title".into(), Some("a title".into())),
+                ],
+            })
+        );
+        assert_eq!(
+            parse_attributes(": .note").map(|a| a.classes),
+            Some(vec!["note".into()])
+        );
+        assert_eq!(parse_attributes(""), None);
+        assert_eq!(parse_attributes("name"), None);
+        assert_eq!(parse_attributes(".lead title=\"open"), None);
+    }
+
+    #[test]
+    fn fold() {
+        let source = "Intro {.lead}\n\n> quote\n> {#note}\n\n{.alone}";
+        let mut stream: Vec<_> = Parser::new(source).into_offset_iter().collect();
+        let attributes = fold_block_attributes(&mut stream);
+        assert_eq!(attributes[&0].classes, vec!["lead".to_string()]);
+        assert_eq!(attributes[&15].id.as_deref(), Some("note"));
+        assert_eq!(attributes.len(), 2);
+
+        let texts: Vec<_> = stream
+            .iter()
+            .filter_map(|(e, _)| match e {
+                Event::Text(t) => Some(t.to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(texts, vec!["Intro", "quote", "{.alone}"]);
+        assert!(!stream.iter().any(|(e, _)| *e == Event::SoftBreak));
+    }
+}
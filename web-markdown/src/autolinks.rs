@@ -0,0 +1,255 @@
+use core::ops::Range;
+
+use pulldown_cmark::{CowStr, Event, LinkType, Tag, TagEnd};
+
+use crate::html::parse_tag;
+use crate::scripts::merge_text;
+
+/// pulldown_cmark only turns urls into links when they are wrapped in `<` and `>`.
+/// `linkify(stream)` finds the bare urls like `https://example.com` or `www.example.com`,
+/// and the email addresses in the text of the document, and turns them into links.
+/// Text inside links, including raw html `<a>` tags, images and code is never rewritten
+pub fn linkify(stream: Vec<(Event<'_>, Range<usize>)>) -> Vec<(Event<'_>, Range<usize>)> {
+    let mut result = Vec::with_capacity(stream.len());
+    let mut verbatim_depth: usize = 0;
+    for (event, range) in merge_text(stream) {
+        match &event {
+            Event::Start(
+                Tag::CodeBlock(_) | Tag::MetadataBlock(_) | Tag::Link { .. } | Tag::Image { .. },
+            ) => verbatim_depth += 1,
+            Event::End(
+                TagEnd::CodeBlock | TagEnd::MetadataBlock(_) | TagEnd::Link | TagEnd::Image,
+            ) => verbatim_depth -= 1,
+            Event::InlineHtml(html) => match raw_link_tag(html) {
+                Some(true) => verbatim_depth += 1,
+                Some(false) => verbatim_depth = verbatim_depth.saturating_sub(1),
+                None => (),
+            },
+            Event::Text(text) if verbatim_depth == 0 && text.len() == range.len() => {
+                linkify_text(text, range.start, &mut result);
+                continue;
+            }
+            _ => (),
+        }
+        result.push((event, range))
+    }
+    result
+}
+
+/// `raw_link_tag(html)` returns wether `html` opens a link, like `<a href="x">`,
+/// or closes it, like `</a>`, or None if it is not a link tag
+fn raw_link_tag(html: &str) -> Option<bool> {
+    let tag = html.trim().strip_prefix('<')?.strip_suffix('>')?;
+    let tag = parse_tag(tag).filter(|tag| tag.name == "a" && !tag.self_closing)?;
+    Some(!tag.end)
+}
+
+/// the characters that can't be right before a url or an email address
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '.' | '+' | '-' | '_' | '@' | '/' | ':')
+}
+
+/// wether `domain` is a valid domain for an autolink, like in github flavored markdown:
+/// segments of alphanumeric characters, `_` and `-`, separated by periods.
+/// There must be at least one period, and no `_` in the last two segments
+fn is_valid_domain(domain: &str) -> bool {
+    let segments: Vec<_> = domain.split('.').collect();
+    segments.len() > 1
+        && segments.iter().all(|s| {
+            s.chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-'))
+        })
+        && !segments[segments.len() - 2..]
+            .iter()
+            .any(|s| s.contains('_'))
+}
+
+/// `url_len(text)` returns the length of the url at the start of `text`,
+/// without the trailing punctuation, following the rules of github flavored markdown.
+/// Returns None if `text` doesn't start with `http://`, `https://` or `www.`
+/// followed by a valid domain
+fn url_len(text: &str) -> Option<usize> {
+    let prefix = ["https://", "http://", "www."].into_iter().find(|prefix| {
+        text.get(..prefix.len())
+            .is_some_and(|x| x.eq_ignore_ascii_case(prefix))
+    })?;
+
+    let end = text
+        .find(|c: char| c.is_whitespace() || c == '<')
+        .unwrap_or(text.len());
+    let mut url = &text[..end];
+    loop {
+        // a trailing `;` is removed, along with what looks like an entity before it,
+        // like `&hl;`
+        if let Some(x) = url.strip_suffix(';') {
+            let letters = x.len() - x.trim_end_matches(|c: char| c.is_ascii_alphabetic()).len();
+            url = match x[..x.len() - letters].strip_suffix('&') {
+                Some(before) if letters > 0 => before,
+                _ => x,
+            };
+            continue;
+        }
+        let trimmed = url.trim_end_matches(['.', ',', ':', '!', '?', '"', '\'', '*', '_', '~']);
+        // a closing parenthesis is only part of the url if it has a matching opening one
+        let trimmed = match trimmed.strip_suffix(')') {
+            Some(x) if trimmed.matches(')').count() > trimmed.matches('(').count() => x,
+            _ => trimmed,
+        };
+        if trimmed.len() == url.len() {
+            break;
+        }
+        url = trimmed;
+    }
+
+    // the domain of `www.` links includes the `www.`
+    let domain_start = if prefix == "www." { 0 } else { prefix.len() };
+    let domain = &url[domain_start..];
+    let domain_len = domain
+        .find(|c: char| !(c.is_alphanumeric() || matches!(c, '.' | '_' | '-')))
+        .unwrap_or(domain.len());
+    is_valid_domain(&domain[..domain_len]).then_some(url.len())
+}
+
+/// `email_len(text)` returns the length of the email address at the start of `text`.
+/// Returns None if `text` doesn't start with an email address
+fn email_len(text: &str) -> Option<usize> {
+    let local = text
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-' | '_')))
+        .unwrap_or(text.len());
+    if local == 0 || !text[local..].starts_with('@') {
+        return None;
+    }
+
+    let domain = &text[local + 1..];
+    let len = domain
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_')))
+        .unwrap_or(domain.len());
+    let domain = domain[..len].trim_end_matches('.');
+    let valid = domain.contains('.')
+        && domain.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && !domain.ends_with(['-', '_']);
+    valid.then_some(local + 1 + domain.len())
+}
+
+/// `find_link(text)` finds the first bare url or email address of `text`.
+/// Returns its position in `text`, the url it points to, and its type
+fn find_link(text: &str) -> Option<(Range<usize>, String, LinkType)> {
+    let mut previous = None;
+    for (i, c) in text.char_indices() {
+        let boundary = !previous.is_some_and(is_word_char);
+        previous = Some(c);
+        if !boundary {
+            continue;
+        }
+        let rest = &text[i..];
+        if let Some(len) = url_len(rest) {
+            let url = &rest[..len];
+            let url = if url.contains("://") {
+                url.to_string()
+            } else {
+                format!("http://{url}")
+            };
+            return Some((i..i + len, url, LinkType::Autolink));
+        }
+        if let Some(len) = email_len(rest) {
+            let url = format!("mailto:{}", &rest[..len]);
+            return Some((i..i + len, url, LinkType::Email));
+        }
+    }
+    None
+}
+
+fn linkify_text<'a>(text: &str, offset: usize, result: &mut Vec<(Event<'a>, Range<usize>)>) {
+    let mut start = 0;
+    while let Some((link, url, link_type)) = find_link(&text[start..]) {
+        let link = start + link.start..start + link.end;
+        if start < link.start {
+            let before = text[start..link.start].to_string();
+            result.push((
+                Event::Text(before.into()),
+                offset + start..offset + link.start,
+            ));
+        }
+        let range = offset + link.start..offset + link.end;
+        let tag = Tag::Link {
+            link_type,
+            dest_url: url.into(),
+            title: CowStr::Borrowed(""),
+            id: CowStr::Borrowed(""),
+        };
+        result.push((Event::Start(tag), range.clone()));
+        let content = text[link.clone()].to_string();
+        result.push((Event::Text(content.into()), range.clone()));
+        result.push((Event::End(TagEnd::Link), range));
+        start = link.end;
+    }
+
+    if start < text.len() {
+        let after = text[start..].to_string();
+        result.push((
+            Event::Text(after.into()),
+            offset + start..offset + text.len(),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn urls() {
+        assert_eq!(url_len("https://a.b/c d"), Some(13));
+        assert_eq!(url_len("www.a.b, and"), Some(7));
+        assert_eq!(url_len("HTTP://a.b"), Some(10));
+        assert_eq!(url_len("https://"), None);
+        assert_eq!(url_len("ftp://a.b"), None);
+    }
+
+    #[test]
+    fn url_punctuation() {
+        assert_eq!(url_len("https://a.b/c."), Some(13));
+        assert_eq!(url_len("https://a.b/(c))."), Some(15));
+        assert_eq!(url_len("www.a.b/?x=1!"), Some(12));
+    }
+
+    #[test]
+    fn gfm_literals() {
+        assert_eq!(url_len("www.x.com."), Some(9));
+        assert_eq!(url_len("www.x.com/a?b=c&hl;"), Some(15));
+        assert_eq!(url_len("https://x.com/a;"), Some(15));
+        assert_eq!(url_len("www.x_y.com"), None);
+        assert_eq!(url_len("www.a_b.x.com"), Some(13));
+        assert_eq!(url_len("https://localhost"), None);
+        assert_eq!(
+            find_link("mail a@b.com."),
+            Some((5..12, "mailto:a@b.com".into(), LinkType::Email))
+        );
+    }
+
+    #[test]
+    fn emails() {
+        assert_eq!(email_len("me.too+x@a.b."), Some(12));
+        assert_eq!(email_len("me@localhost"), None);
+        assert_eq!(email_len("me@a-.b-"), None);
+        assert_eq!(email_len("@a.b"), None);
+    }
+
+    #[test]
+    fn raw_link_tags() {
+        assert_eq!(raw_link_tag("<a href=\"https://a.b\">"), Some(true));
+        assert_eq!(raw_link_tag("<A>"), Some(true));
+        assert_eq!(raw_link_tag("</a>"), Some(false));
+        assert_eq!(raw_link_tag("<abbr>"), None);
+        assert_eq!(raw_link_tag("<a/>"), None);
+    }
+
+    #[test]
+    fn inside_words() {
+        assert_eq!(find_link("xhttps://a.b"), None);
+        assert_eq!(
+            find_link("(see www.a.b)"),
+            Some((5..12, "http://www.a.b".into(), LinkType::Autolink))
+        );
+    }
+}
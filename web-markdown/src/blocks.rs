@@ -0,0 +1,87 @@
+use core::marker::PhantomData;
+use core::ops::Range;
+
+use pulldown_cmark::Event;
+
+use crate::render::{DocumentState, Renderer};
+use crate::{finish_document, prepare_document, Context, PreparedDocument, RenderStats};
+
+/// Renders the top-level blocks of a document one at a time, as an iterator of views,
+/// so that a very long document can be rendered lazily,
+/// for example to paginate it or to feed a virtual list.
+///
+/// The source is parsed entirely when the renderer is created,
+/// and every call to `next` renders the next top-level block, like a paragraph or a list.
+/// Once the blocks are exhausted, the notice of a truncated document
+/// and the footnotes are yielded, if there are some.
+///
+/// Unlike [`crate::markdown_component`], the blocks are not wrapped in a `<div>`
+/// for the direction or the language of the document,
+/// and the placeholder of an empty document is not rendered.
+/// [`Context::on_render_complete`] is called when the last block is rendered,
+/// without a duration
+pub struct BlockRenderer<'a, 'callback, F: Context<'a, 'callback>> {
+    __marker: PhantomData<&'callback ()>,
+    cx: F,
+    source: &'a str,
+    events: std::vec::IntoIter<(Event<'a>, Range<usize>)>,
+    /// the state of the rendering, until all the blocks are rendered
+    document: Option<DocumentState<F::View>>,
+    /// what is rendered after the blocks, like the footnotes
+    end: std::vec::IntoIter<F::View>,
+}
+
+impl<'a, 'callback, F: Context<'a, 'callback>> BlockRenderer<'a, 'callback, F> {
+    /// `BlockRenderer::new(cx, source)` parses `source`, ready to render its blocks
+    pub fn new(cx: F, source: &'a str) -> Self {
+        let PreparedDocument {
+            stream, document, ..
+        } = prepare_document(cx, source, None);
+        Self {
+            __marker: PhantomData,
+            cx,
+            source,
+            events: stream.into_iter(),
+            document: Some(document),
+            end: Vec::new().into_iter(),
+        }
+    }
+
+    /// forgets the wikilinks and mentions resolved so far,
+    /// for example after the pages they point to changed,
+    /// so that the next blocks resolve them again with the context
+    pub fn clear_resolutions(&mut self) {
+        if let Some(document) = &mut self.document {
+            document.clear_resolutions()
+        }
+    }
+}
+
+impl<'a, 'callback, F: Context<'a, 'callback>> Iterator for BlockRenderer<'a, 'callback, F> {
+    type Item = F::View;
+
+    fn next(&mut self) -> Option<F::View> {
+        if let Some(document) = &mut self.document {
+            let block = Renderer::new(self.cx, self.source, &mut self.events, document).next();
+            if block.is_some() {
+                return block;
+            }
+
+            let document = self.document.take()?;
+            let (end, finished) = finish_document(self.cx, document);
+            self.end = end.into_iter();
+            #[cfg(feature = "debug")]
+            self.cx.send_debug(finished.debug_events);
+            if let Some(handler) = self.cx.on_render_complete() {
+                F::call_handler(
+                    &handler,
+                    RenderStats {
+                        element_count: finished.element_count,
+                        duration: None,
+                    },
+                )
+            }
+        }
+        self.end.next()
+    }
+}
@@ -0,0 +1,140 @@
+use core::cell::RefCell;
+use core::ops::Range;
+use std::collections::VecDeque;
+
+use pulldown_cmark::Event;
+
+use crate::ParseConfig;
+
+/// the number of documents kept by default
+const DEFAULT_CAPACITY: usize = 16;
+
+/// A parsed document
+struct Entry {
+    source: String,
+    config: ParseConfig,
+    stream: Vec<(Event<'static>, Range<usize>)>,
+}
+
+/// The last parsed documents, the most recently used first
+struct ParseCache {
+    entries: VecDeque<Entry>,
+    capacity: usize,
+}
+
+thread_local! {
+    static CACHE: RefCell<ParseCache> = const {
+        RefCell::new(ParseCache {
+            entries: VecDeque::new(),
+            capacity: DEFAULT_CAPACITY,
+        })
+    };
+}
+
+#[cfg(test)]
+thread_local! {
+    /// the number of times a document was actually parsed on this thread
+    pub static PARSE_COUNT: core::cell::Cell<usize> = const { core::cell::Cell::new(0) };
+}
+
+/// `get(source, config)` returns the events of `source`
+/// if it was already parsed with `config`
+pub fn get<'a>(source: &str, config: &ParseConfig) -> Option<Vec<(Event<'a>, Range<usize>)>> {
+    CACHE.with_borrow_mut(|cache| {
+        let i = cache
+            .entries
+            .iter()
+            .position(|e| e.source == source && e.config == *config)?;
+        let entry = cache.entries.remove(i)?;
+        let stream = entry.stream.clone();
+        cache.entries.push_front(entry);
+        Some(stream)
+    })
+}
+
+/// `insert(source, config, stream)` keeps the events `stream`
+/// of `source` parsed with `config`,
+/// and forgets the least recently used document if the cache is full
+pub fn insert(source: &str, config: &ParseConfig, stream: &[(Event<'_>, Range<usize>)]) {
+    CACHE.with_borrow_mut(|cache| {
+        if cache.capacity == 0 {
+            return;
+        }
+        let stream = stream
+            .iter()
+            .map(|(e, r)| (e.clone().into_static(), r.clone()))
+            .collect();
+        cache.entries.push_front(Entry {
+            source: source.to_string(),
+            config: *config,
+            stream,
+        });
+        let capacity = cache.capacity;
+        cache.entries.truncate(capacity);
+    })
+}
+
+/// counts the documents that are actually parsed, for the tests
+pub fn count_parse() {
+    #[cfg(test)]
+    PARSE_COUNT.set(PARSE_COUNT.get() + 1)
+}
+
+/// Forgets all the documents parsed on the current thread.
+/// Only available with the `cache` feature
+pub fn clear_markdown_cache() {
+    CACHE.with_borrow_mut(|cache| cache.entries.clear())
+}
+
+/// Sets the number of parsed documents kept on the current thread,
+/// 16 by default. A size of 0 disables the cache.
+/// Only available with the `cache` feature
+pub fn set_markdown_cache_size(size: usize) {
+    CACHE.with_borrow_mut(|cache| {
+        cache.capacity = size;
+        cache.entries.truncate(size);
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse_markdown;
+
+    #[test]
+    fn second_parse_is_cached() {
+        clear_markdown_cache();
+        let config = ParseConfig::default();
+        let count = PARSE_COUNT.get();
+
+        let first = parse_markdown("# cached *text*", &config);
+        assert_eq!(PARSE_COUNT.get(), count + 1);
+        let second = parse_markdown("# cached *text*", &config);
+        assert_eq!(PARSE_COUNT.get(), count + 1);
+        assert_eq!(first, second);
+
+        let config = ParseConfig {
+            hard_line_breaks: true,
+            ..Default::default()
+        };
+        parse_markdown("# cached *text*", &config);
+        assert_eq!(PARSE_COUNT.get(), count + 2);
+    }
+
+    #[test]
+    fn cache_size() {
+        clear_markdown_cache();
+        set_markdown_cache_size(1);
+        let config = ParseConfig::default();
+        let count = PARSE_COUNT.get();
+
+        parse_markdown("a", &config);
+        parse_markdown("b", &config);
+        parse_markdown("a", &config);
+        assert_eq!(PARSE_COUNT.get(), count + 3);
+        parse_markdown("a", &config);
+        assert_eq!(PARSE_COUNT.get(), count + 3);
+
+        set_markdown_cache_size(DEFAULT_CAPACITY);
+    }
+}
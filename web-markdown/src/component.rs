@@ -22,6 +22,18 @@ pub enum CustomHtmlTag {
     End(String),
 }
 
+/// What to do with a tag that looks like a custom component,
+/// ie its name starts with an uppercase letter like `<MyComponent/>`,
+/// but that is not registered
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnknownComponentPolicy {
+    /// render the tag as raw html
+    #[default]
+    Passthrough,
+    /// remove the tag. The children of the component are still rendered
+    Drop,
+}
+
 type ParseError = String;
 
 fn parse_attribute_value(stream: &mut Peekable<std::str::Chars>) -> Result<String, ParseError> {
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+
+use crate::{
+    Direction, HtmlPolicy, ImageLoading, LinkRelPolicy, LinkTarget, ListStyle, MarkdownOptions,
+    MarkdownProps, MathConfig, MentionConfig, MermaidConfig, Options, RootWrapper, SoftBreakMode,
+    StatsConfig, StreamTransform, SyntectConfig, TaskState, TextTransform, TypographyPreset,
+    UnknownComponentPolicy,
+};
+
+/// A builder of [`MarkdownProps`], to set some options without listing the others,
+/// like `MarkdownConfig::new().hard_line_breaks(true).wikilinks(true).build()`.
+///
+/// The props don't borrow anything, so the builder only holds them
+/// until [`MarkdownConfig::build`] is called.
+/// Like in the props, the strings, lists and maps are `&'static`, usually constants.
+/// The options that are optional in the props are set without `Some`,
+/// like `.lang("ar")`.
+///
+/// The markdown components of the frameworks take it as their `config` prop,
+/// which replaces the options passed as separate props
+#[derive(Clone, Default, PartialEq)]
+pub struct MarkdownConfig(MarkdownProps);
+
+/// defines the methods of [`MarkdownConfig`] that set the fields of the props,
+/// and the methods that set the optional fields to `Some`
+macro_rules! setters {
+    ($($name:ident: $t:ty),* $(,)?) => {
+        $(
+            #[doc = concat!("sets [`MarkdownProps::", stringify!($name), "`]")]
+            pub fn $name(mut self, $name: $t) -> Self {
+                self.0.$name = $name;
+                self
+            }
+        )*
+    };
+    (optional $($name:ident: $t:ty),* $(,)?) => {
+        $(
+            #[doc = concat!("sets [`MarkdownProps::", stringify!($name), "`]")]
+            pub fn $name(mut self, $name: $t) -> Self {
+                self.0.$name = Some($name);
+                self
+            }
+        )*
+    };
+}
+
+impl MarkdownConfig {
+    /// the default configuration, like [`MarkdownProps::default`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// the props with the options set so far
+    pub fn build(self) -> MarkdownProps {
+        self.0
+    }
+
+    setters! {
+        hard_line_breaks: bool,
+        soft_break: SoftBreakMode,
+        wikilinks: bool,
+        options: MarkdownOptions,
+        link_target: LinkTarget,
+        link_rel_policy: LinkRelPolicy,
+        image_loading: ImageLoading,
+        eager_image_count: usize,
+        decorative_empty_alt: bool,
+        normalize_html_tables: bool,
+        code_block_copy_button: bool,
+        code_block_line_numbers: bool,
+        code_block_dedent: bool,
+        code_block_trim: bool,
+        unknown_components: UnknownComponentPolicy,
+        html_policy: HtmlPolicy,
+        heading_offset: u8,
+        heading_anchor_links: bool,
+        heading_numbering: bool,
+        toc_min_level: u8,
+        toc_max_level: u8,
+        inline_attributes: bool,
+        source_positions: bool,
+        list_tightness_classes: bool,
+        auto_block_direction: bool,
+        highlight_marks: bool,
+        kbd: bool,
+        unwrap_single_paragraph: bool,
+        read_only_task_lists: bool,
+        frontmatter_overrides: bool,
+        scroll_to_fragment: bool,
+        collapse_rules: bool,
+        footnote_popovers: bool,
+        figure_images: bool,
+        stats_config: StatsConfig,
+        emoji: bool,
+        autolink: bool,
+        typography: &'static [TypographyPreset],
+    }
+
+    setters! {
+        optional
+        parse_options: Options,
+        theme: &'static str,
+        syntax_highlight: SyntectConfig,
+        broken_image_placeholder: &'static str,
+        table_caption_prefix: &'static str,
+        language_aliases: &'static HashMap<&'static str, &'static str>,
+        ordered_list_style: ListStyle,
+        max_nesting_depth: usize,
+        max_elements: usize,
+        max_image_count: usize,
+        dir: Direction,
+        lang: &'static str,
+        root_wrapper: RootWrapper,
+        abbreviations: &'static HashMap<&'static str, &'static str>,
+        empty_placeholder: &'static str,
+        task_list_states: &'static HashMap<char, TaskState>,
+        comment_directive_names: &'static [&'static str],
+        rule_class: &'static str,
+        class_prefix: &'static str,
+        base_url: &'static str,
+        math: MathConfig,
+        mermaid: MermaidConfig,
+        mentions: MentionConfig,
+        allowed_url_schemes: &'static [&'static str],
+        text_transform: TextTransform,
+        transform_stream: StreamTransform,
+    }
+}
+
+impl From<MarkdownConfig> for MarkdownProps {
+    fn from(config: MarkdownConfig) -> Self {
+        config.build()
+    }
+}
+
+impl From<MarkdownProps> for MarkdownConfig {
+    fn from(props: MarkdownProps) -> Self {
+        Self(props)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn builder() {
+        let props = MarkdownConfig::new()
+            .hard_line_breaks(true)
+            .wikilinks(true)
+            .lang("ar")
+            .build();
+        assert!(props.hard_line_breaks);
+        assert!(props.wikilinks);
+        assert_eq!(props.lang, Some("ar"));
+        assert_eq!(props.class_prefix, None);
+
+        let props = MarkdownConfig::from(props).wikilinks(false).build();
+        assert!(props.hard_line_breaks);
+        assert!(!props.wikilinks);
+    }
+}
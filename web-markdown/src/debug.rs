@@ -0,0 +1,85 @@
+use core::fmt;
+use core::ops::Range;
+use core::time::Duration;
+
+/// Something worth knowing about the rendering of a document,
+/// passed to [`crate::Context::send_debug`],
+/// for example to understand why some content is missing
+#[derive(Clone, Debug, PartialEq)]
+pub enum DebugEvent {
+    /// a part of the source that is not what it seems to be,
+    /// like a `:::` directive that is never closed
+    ParseWarning {
+        message: String,
+        range: Range<usize>,
+    },
+    /// an event of the markdown stream that was dropped without being rendered,
+    /// for example because it is nested too deeply, or because the html is stripped
+    UnhandledEvent {
+        /// the event, formatted with [`Debug`]
+        event: String,
+        range: Range<usize>,
+    },
+    /// a tag that looks like a custom component, like `<Chart/>`,
+    /// but that is not registered
+    ComponentNotFound { name: String },
+    /// the rendering stopped before the end of the document,
+    /// because it has more elements or images than the props allow
+    Truncated {
+        /// the limit that was reached, `max_elements` or `max_image_count`
+        limit: &'static str,
+        /// the position in the source of the first event that was not rendered
+        position: usize,
+    },
+    /// the time spent in a phase of the rendering, like `parse` or `render`.
+    /// It is not reported on targets without a clock, like `wasm32`
+    Timing {
+        phase: &'static str,
+        duration: Duration,
+    },
+}
+
+/// A problem of a document that makes [`crate::try_markdown_component`] fail
+#[derive(Clone, Debug, PartialEq)]
+pub enum RenderIssue {
+    /// a tag that looks like a custom component, like `<Chart/>`,
+    /// but that is not registered
+    UnknownComponent(String),
+    /// a frontmatter that is not a valid yaml or toml map.
+    /// It is only detected with the `frontmatter` feature
+    MalformedFrontmatter,
+    /// elements nested deeper than [`crate::MarkdownProps::max_nesting_depth`],
+    /// that were not rendered
+    DepthLimitExceeded,
+    /// more elements than [`crate::MarkdownProps::max_elements`],
+    /// or more images than [`crate::MarkdownProps::max_image_count`].
+    /// The rest of the document was not rendered
+    Truncated,
+}
+
+impl fmt::Display for RenderIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderIssue::UnknownComponent(name) => write!(f, "unknown component `{name}`"),
+            RenderIssue::MalformedFrontmatter => write!(f, "malformed frontmatter"),
+            RenderIssue::DepthLimitExceeded => write!(f, "elements nested too deeply"),
+            RenderIssue::Truncated => write!(f, "too many elements"),
+        }
+    }
+}
+
+/// The problems of a document rendered in strict mode,
+/// in the order they were found
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderError {
+    pub issues: Vec<RenderIssue>,
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let issues: Vec<_> = self.issues.iter().map(ToString::to_string).collect();
+        write!(f, "invalid markdown: {}", issues.join(", "))
+    }
+}
+
+impl std::error::Error for RenderError {}
@@ -0,0 +1,352 @@
+use core::ops::Range;
+
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+
+/// A line of a fenced directive, like `:::details Title` or `:::name{key=value}`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Directive<'a> {
+    /// the start of a collapsible section, with its title
+    Details(Option<&'a str>),
+    /// the start of any other container, with its name and attributes
+    Container {
+        name: &'a str,
+        attributes: Vec<(String, String)>,
+    },
+    /// the `:::` line that closes the last directive
+    End,
+}
+
+impl<'a> Directive<'a> {
+    /// `Directive::parse(line)` parses a line like `:::details Title`,
+    /// `:::name{#id .class key="value"}` or `:::`.
+    /// Returns None if `line` is not a directive
+    pub fn parse(line: &'a str) -> Option<Self> {
+        let rest = line.trim().strip_prefix(":::")?;
+        if rest.is_empty() {
+            return Some(Directive::End);
+        }
+        let rest = rest.trim_start();
+        let len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+            .unwrap_or(rest.len());
+        let (name, rest) = rest.split_at(len);
+        if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return None;
+        }
+
+        if name == "details" && (rest.is_empty() || rest.starts_with(char::is_whitespace)) {
+            let title = rest.trim();
+            return Some(Directive::Details((!title.is_empty()).then_some(title)));
+        }
+        let attributes = match rest {
+            "" => Vec::new(),
+            _ => parse_attributes(rest)?,
+        };
+        Some(Directive::Container { name, attributes })
+    }
+}
+
+/// `parse_attributes(text)` parses an attribute block like `{#id .class key="value" flag}`,
+/// that must be all of `text`.
+/// `#id` is the same as `id=id`, `.class` as `class=class`,
+/// and an attribute without a value has an empty value
+fn parse_attributes(text: &str) -> Option<Vec<(String, String)>> {
+    let word_len = |s: &str| {
+        s.find(|c: char| c.is_whitespace() || matches!(c, '=' | '"' | '\''))
+            .unwrap_or(s.len())
+    };
+
+    let mut rest = text.strip_prefix('{')?.strip_suffix('}')?.trim_start();
+    let mut attributes = Vec::new();
+    while !rest.is_empty() {
+        let (key, tail) = if let Some(tail) = rest.strip_prefix('#') {
+            (Some("id"), tail)
+        } else if let Some(tail) = rest.strip_prefix('.') {
+            (Some("class"), tail)
+        } else {
+            (None, rest)
+        };
+        let len = word_len(tail);
+        if len == 0 {
+            return None;
+        }
+        let (word, tail) = tail.split_at(len);
+
+        let (attribute, tail) = match (key, tail.strip_prefix('=')) {
+            (Some(key), _) => ((key, word), tail),
+            (None, None) => ((word, ""), tail),
+            (None, Some(tail)) => {
+                let (value, tail) = match tail.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let end = tail[1..].find(quote)? + 1;
+                        (&tail[1..end], &tail[end + 1..])
+                    }
+                    _ => tail.split_at(word_len(tail)),
+                };
+                ((word, value), tail)
+            }
+        };
+        attributes.push((attribute.0.to_string(), attribute.1.to_string()));
+        rest = tail.trim_start();
+    }
+    Some(attributes)
+}
+
+/// `fold_directives(stream)` finds the lines of the paragraphs that are directives,
+/// like `:::details Title` or `:::name`, and the `:::` line that closes them,
+/// and moves them out of their paragraph, as [`Event::Html`] events.
+/// They can't be confused with real html, that always starts with `<`.
+/// A `:::` line that doesn't close a directive is left unchanged
+pub fn fold_directives(stream: Vec<(Event<'_>, Range<usize>)>) -> Vec<(Event<'_>, Range<usize>)> {
+    let mut result = Vec::with_capacity(stream.len());
+    let mut open = 0;
+    let mut stream = stream.into_iter();
+    while let Some((event, range)) = stream.next() {
+        if event != Event::Start(Tag::Paragraph) {
+            result.push((event, range));
+            continue;
+        }
+        let content: Vec<_> = stream
+            .by_ref()
+            .take_while(|(e, _)| *e != Event::End(TagEnd::Paragraph))
+            .collect();
+        fold_paragraph(content, range, &mut open, &mut result);
+    }
+    result
+}
+
+/// splits the paragraph made of `content`, found at `range`,
+/// at the lines that are directives.
+/// `open` is the number of directives that are not closed yet
+fn fold_paragraph<'a>(
+    content: Vec<(Event<'a>, Range<usize>)>,
+    range: Range<usize>,
+    open: &mut usize,
+    result: &mut Vec<(Event<'a>, Range<usize>)>,
+) {
+    // the lines of the paragraph, with the line break that ends them
+    let mut lines = vec![Vec::new()];
+    let mut depth = 0;
+    for (event, r) in content {
+        match event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            _ => (),
+        }
+        let is_break = depth == 0 && matches!(event, Event::SoftBreak | Event::HardBreak);
+        lines.last_mut().unwrap().push((event, r));
+        if is_break {
+            lines.push(Vec::new())
+        }
+    }
+
+    let mut folded = false;
+    let mut pending = Vec::new();
+    for line in lines {
+        let text = line_text(&line);
+        let is_directive = match text.as_ref().and_then(|(text, _)| Directive::parse(text)) {
+            Some(Directive::Details(_) | Directive::Container { .. }) => {
+                *open += 1;
+                true
+            }
+            Some(Directive::End) if *open > 0 => {
+                *open -= 1;
+                true
+            }
+            _ => false,
+        };
+        if !is_directive {
+            pending.extend(line);
+            continue;
+        }
+        folded = true;
+        close_paragraph(&mut pending, result);
+        if let Some((text, r)) = text {
+            result.push((Event::Html(CowStr::from(text)), r))
+        }
+    }
+
+    if folded {
+        close_paragraph(&mut pending, result);
+    } else {
+        result.push((Event::Start(Tag::Paragraph), range.clone()));
+        result.append(&mut pending);
+        result.push((Event::End(TagEnd::Paragraph), range));
+    }
+}
+
+/// returns the text of `line`, and its position in the source,
+/// if it is only made of text that maps exactly to the source.
+/// pulldown_cmark can split a line in several text events
+fn line_text(line: &[(Event<'_>, Range<usize>)]) -> Option<(String, Range<usize>)> {
+    let line = match line.last() {
+        Some((Event::SoftBreak, _)) => &line[..line.len() - 1],
+        _ => line,
+    };
+    let mut text = String::new();
+    let mut range: Option<Range<usize>> = None;
+    for (event, r) in line {
+        match event {
+            Event::Text(t) if t.len() == r.len() => text.push_str(t),
+            _ => return None,
+        }
+        match &mut range {
+            Some(range) if range.end == r.start => range.end = r.end,
+            Some(_) => return None,
+            None => range = Some(r.clone()),
+        }
+    }
+    Some((text, range?))
+}
+
+/// moves the events of `pending` to `result`, as a paragraph.
+/// Does nothing if `pending` is empty
+fn close_paragraph<'a>(
+    pending: &mut Vec<(Event<'a>, Range<usize>)>,
+    result: &mut Vec<(Event<'a>, Range<usize>)>,
+) {
+    // the line break before a directive is not part of the paragraph
+    if matches!(
+        pending.last(),
+        Some((Event::SoftBreak | Event::HardBreak, _))
+    ) {
+        pending.pop();
+    }
+    let (Some((_, first)), Some((_, last))) = (pending.first(), pending.last()) else {
+        return;
+    };
+    let range = first.start..last.end;
+    result.push((Event::Start(Tag::Paragraph), range.clone()));
+    result.append(pending);
+    result.push((Event::End(TagEnd::Paragraph), range));
+}
+
+/// An html comment used as a directive, like `<!-- more -->`,
+/// see [`crate::MarkdownProps::comment_directive_names`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommentDirective {
+    /// the first word of the comment, like `more`
+    pub name: String,
+    /// the rest of the comment, trimmed. It is empty if there is nothing after the name
+    pub args: String,
+    /// the position of the comment in the markdown source
+    pub range: Range<usize>,
+}
+
+/// `comment_directive(raw_html, range, names)` parses the html `raw_html`, found at `range`
+/// in the markdown source, as a comment whose first word is one of `names`.
+/// Returns None if it is something else, like a comment with another name
+pub fn comment_directive(
+    raw_html: &str,
+    range: &Range<usize>,
+    names: &[&str],
+) -> Option<CommentDirective> {
+    let trimmed = raw_html.trim();
+    let content = trimmed.strip_prefix("<!--")?.strip_suffix("-->")?.trim();
+    if content.contains("-->") {
+        return None;
+    }
+    let (name, args) = content
+        .split_once(char::is_whitespace)
+        .unwrap_or((content, ""));
+    if !names.contains(&name) {
+        return None;
+    }
+    let start = range.start + (raw_html.len() - raw_html.trim_start().len());
+    Some(CommentDirective {
+        name: name.to_string(),
+        args: args.trim().to_string(),
+        range: start..start + trimmed.len(),
+    })
+}
+
+/// `collect_comment_directives(stream, names)` lists the html comments of `stream`
+/// whose first word is one of `names`, in the order of the document
+pub fn collect_comment_directives(
+    stream: &[(Event<'_>, Range<usize>)],
+    names: &[&str],
+) -> Vec<CommentDirective> {
+    stream
+        .iter()
+        .filter_map(|(event, range)| match event {
+            Event::Html(html) | Event::InlineHtml(html) => comment_directive(html, range, names),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn comments() {
+        let names = &["more", "toc"];
+        let parse = |html| comment_directive(html, &(10..10 + html.len()), names);
+        assert_eq!(
+            parse("<!-- toc depth=2 -->\n"),
+            Some(CommentDirective {
+                name: "toc".into(),
+                args: "depth=2".into(),
+                range: 10..30,
+            })
+        );
+        assert_eq!(parse("<!--more-->").map(|d| d.args), Some(String::new()));
+        assert_eq!(parse("<!-- a comment -->"), None);
+        assert_eq!(parse("<!-- more --> <!-- toc -->"), None);
+        assert_eq!(parse("<p>more</p>"), None);
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            Directive::parse(":::details Read more"),
+            Some(Directive::Details(Some("Read more")))
+        );
+        assert_eq!(
+            Directive::parse("::: details "),
+            Some(Directive::Details(None))
+        );
+        assert_eq!(Directive::parse(":::"), Some(Directive::End));
+        assert_eq!(Directive::parse("::::"), None);
+        assert_eq!(Directive::parse("a ::: b"), None);
+        assert_eq!(Directive::parse(":::1"), None);
+        assert_eq!(Directive::parse(":::note that"), None);
+    }
+
+    #[test]
+    fn parse_container() {
+        let container = |name, attributes: &[(&str, &str)]| {
+            Some(Directive::Container {
+                name,
+                attributes: attributes
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            })
+        };
+        assert_eq!(Directive::parse(":::warning"), container("warning", &[]));
+        assert_eq!(Directive::parse(":::detailed"), container("detailed", &[]));
+        assert_eq!(
+            Directive::parse(r#":::grid{#main .wide .dark cols=3 title="a b" open}"#),
+            container(
+                "grid",
+                &[
+                    ("id", "main"),
+                    ("class", "wide"),
+                    ("class", "dark"),
+                    ("cols", "3"),
+                    ("title", "a b"),
+                    ("open", ""),
+                ]
+            )
+        );
+        assert_eq!(
+            Directive::parse(":::grid{cols=}"),
+            container("grid", &[("cols", "")])
+        );
+        assert_eq!(Directive::parse(":::grid{title=\"a}"), None);
+        assert_eq!(Directive::parse(":::grid{#}"), None);
+        assert_eq!(Directive::parse(":::grid{a} b"), None);
+    }
+}
@@ -0,0 +1,291 @@
+use core::ops::Range;
+use std::collections::{BTreeMap, HashMap};
+
+use pulldown_cmark::{Event, MetadataBlockKind, Tag, TagEnd};
+
+use crate::{Direction, MarkdownProps, SoftBreakMode};
+
+/// A value of the frontmatter of a document
+#[derive(Clone, Debug, PartialEq)]
+pub enum FrontmatterValue {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    /// a string. Toml dates are also converted to strings
+    String(String),
+    List(Vec<FrontmatterValue>),
+    Map(BTreeMap<String, FrontmatterValue>),
+}
+
+/// The frontmatter of a document, parsed into key-value pairs
+pub type Frontmatter = HashMap<String, FrontmatterValue>;
+
+/// `parse_frontmatter(raw, kind)` parses the `raw` frontmatter,
+/// in yaml for `---` blocks or in toml for `+++` blocks.
+/// Returns None if it is malformed or if it is not a map
+#[cfg(feature = "frontmatter")]
+pub fn parse_frontmatter(raw: &str, kind: MetadataBlockKind) -> Option<Frontmatter> {
+    let value = match kind {
+        MetadataBlockKind::YamlStyle => {
+            let mut documents = yaml_rust::YamlLoader::load_from_str(raw).ok()?;
+            if documents.is_empty() {
+                return Some(Frontmatter::new());
+            }
+            from_yaml(documents.swap_remove(0))
+        }
+        MetadataBlockKind::PlusesStyle => from_toml(raw.parse::<toml::Table>().ok()?.into()),
+    };
+    match value {
+        FrontmatterValue::Map(map) => Some(map.into_iter().collect()),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "frontmatter")]
+fn from_yaml(yaml: yaml_rust::Yaml) -> FrontmatterValue {
+    use yaml_rust::Yaml;
+    match yaml {
+        Yaml::Boolean(b) => FrontmatterValue::Bool(b),
+        Yaml::Integer(i) => FrontmatterValue::Integer(i),
+        Yaml::Real(x) => match x.parse() {
+            Ok(x) => FrontmatterValue::Float(x),
+            Err(_) => FrontmatterValue::String(x),
+        },
+        Yaml::String(s) => FrontmatterValue::String(s),
+        Yaml::Array(items) => FrontmatterValue::List(items.into_iter().map(from_yaml).collect()),
+        Yaml::Hash(hash) => FrontmatterValue::Map(
+            hash.into_iter()
+                .filter_map(|(key, value)| {
+                    let key = match key {
+                        Yaml::String(s) | Yaml::Real(s) => s,
+                        Yaml::Integer(i) => i.to_string(),
+                        Yaml::Boolean(b) => b.to_string(),
+                        _ => return None,
+                    };
+                    Some((key, from_yaml(value)))
+                })
+                .collect(),
+        ),
+        Yaml::Null | Yaml::Alias(_) | Yaml::BadValue => FrontmatterValue::Null,
+    }
+}
+
+#[cfg(feature = "frontmatter")]
+fn from_toml(value: toml::Value) -> FrontmatterValue {
+    use toml::Value;
+    match value {
+        Value::String(s) => FrontmatterValue::String(s),
+        Value::Integer(i) => FrontmatterValue::Integer(i),
+        Value::Float(x) => FrontmatterValue::Float(x),
+        Value::Boolean(b) => FrontmatterValue::Bool(b),
+        Value::Datetime(d) => FrontmatterValue::String(d.to_string()),
+        Value::Array(items) => FrontmatterValue::List(items.into_iter().map(from_toml).collect()),
+        Value::Table(table) => FrontmatterValue::Map(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, from_toml(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// The rendering options that a document can set in its own frontmatter,
+/// with the `frontmatter` feature. The recognized keys are
+/// - `hard_breaks: true`, to render the line breaks inside paragraphs as `<br>`,
+///   like [`SoftBreakMode::HardBreak`]
+/// - `lang: <language>`, like `lang: fr`, the language of the document
+/// - `dir: ltr`, `dir: rtl` or `dir: auto`, the direction of the text of the document
+///
+/// Other keys, and values of the wrong type, are ignored.
+/// See [`MarkdownProps::frontmatter_overrides`] for how they are merged with the props
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrontmatterOptions {
+    pub hard_breaks: Option<bool>,
+    pub lang: Option<String>,
+    pub dir: Option<Direction>,
+}
+
+impl FrontmatterOptions {
+    /// reads the recognized keys of `frontmatter`
+    pub fn from_frontmatter(frontmatter: &Frontmatter) -> Self {
+        let string = |key| match frontmatter.get(key) {
+            Some(FrontmatterValue::String(s)) => Some(s.as_str()),
+            _ => None,
+        };
+        FrontmatterOptions {
+            hard_breaks: match frontmatter.get("hard_breaks") {
+                Some(FrontmatterValue::Bool(b)) => Some(*b),
+                _ => None,
+            },
+            lang: string("lang").map(String::from),
+            dir: match string("dir") {
+                Some("ltr") => Some(Direction::Ltr),
+                Some("rtl") => Some(Direction::Rtl),
+                Some("auto") => Some(Direction::Auto),
+                _ => None,
+            },
+        }
+    }
+
+    /// the way line breaks are rendered, from the props and the document
+    pub(crate) fn soft_break(&self, props: &MarkdownProps) -> SoftBreakMode {
+        let document = self.hard_breaks.map(|hard| {
+            if hard {
+                SoftBreakMode::HardBreak
+            } else {
+                SoftBreakMode::default()
+            }
+        });
+        merge(props.soft_break, document, props.frontmatter_overrides)
+    }
+
+    /// the direction of the document, from the props and the document
+    pub(crate) fn dir(&self, props: &MarkdownProps) -> Option<Direction> {
+        merge(props.dir, self.dir.map(Some), props.frontmatter_overrides)
+    }
+
+    /// the language of the document, from the props and the document
+    pub(crate) fn lang(&self, props: &MarkdownProps) -> Option<String> {
+        let document = self.lang.clone().map(Some);
+        merge(
+            props.lang.map(String::from),
+            document,
+            props.frontmatter_overrides,
+        )
+    }
+}
+
+/// the value set by the `document` if there is one and if it `overrides` the props,
+/// or if the `prop` has its default value. Otherwise, the `prop`
+fn merge<T: Default + PartialEq>(prop: T, document: Option<T>, overrides: bool) -> T {
+    match document {
+        Some(value) if overrides || prop == T::default() => value,
+        _ => prop,
+    }
+}
+
+/// `take_frontmatter(stream)` removes the metadata blocks of `stream`,
+/// like `---\ntitle: x\n---`, wherever they are in the document,
+/// so that they are never rendered.
+/// Returns the text and the kind of the first one, which is the frontmatter of the document
+pub(crate) fn take_frontmatter(
+    stream: &mut Vec<(Event<'_>, Range<usize>)>,
+) -> Option<(String, MetadataBlockKind)> {
+    let mut frontmatter = None;
+    let mut i = 0;
+    while i < stream.len() {
+        let Event::Start(Tag::MetadataBlock(kind)) = stream[i].0 else {
+            i += 1;
+            continue;
+        };
+        let end = stream[i..]
+            .iter()
+            .position(|(e, _)| *e == Event::End(TagEnd::MetadataBlock(kind)))
+            .map_or(stream.len(), |len| i + len + 1);
+        let raw = stream[i..end]
+            .iter()
+            .filter_map(|(event, _)| match event {
+                Event::Text(text) => Some(text.as_ref()),
+                _ => None,
+            })
+            .collect();
+        frontmatter.get_or_insert((raw, kind));
+        stream.drain(i..end);
+    }
+    frontmatter
+}
+
+/// `document_options(frontmatter)` finds the [`FrontmatterOptions`] set by the `frontmatter`
+/// of a document, see [`take_frontmatter`].
+/// Without the `frontmatter` feature, or without a valid frontmatter, there are none
+#[cfg_attr(not(feature = "frontmatter"), allow(unused_variables))]
+pub(crate) fn document_options(
+    frontmatter: Option<&(String, MetadataBlockKind)>,
+) -> FrontmatterOptions {
+    #[cfg(feature = "frontmatter")]
+    if let Some(frontmatter) = frontmatter.and_then(|(raw, kind)| parse_frontmatter(raw, *kind)) {
+        return FrontmatterOptions::from_frontmatter(&frontmatter);
+    }
+    FrontmatterOptions::default()
+}
+
+#[cfg(all(test, feature = "frontmatter"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn yaml() {
+        let raw = "title: Hello\ndraft: false\ntags:\n  - a\n  - b\nversion: 1.5\n";
+        let frontmatter = parse_frontmatter(raw, MetadataBlockKind::YamlStyle).unwrap();
+        assert_eq!(
+            frontmatter["title"],
+            FrontmatterValue::String("Hello".into())
+        );
+        assert_eq!(frontmatter["draft"], FrontmatterValue::Bool(false));
+        assert_eq!(
+            frontmatter["tags"],
+            FrontmatterValue::List(vec![
+                FrontmatterValue::String("a".into()),
+                FrontmatterValue::String("b".into()),
+            ])
+        );
+        assert_eq!(frontmatter["version"], FrontmatterValue::Float(1.5));
+    }
+
+    #[test]
+    fn toml() {
+        let raw = "title = \"Hello\"\nweight = 3\n\n[author]\nname = \"me\"\n";
+        let frontmatter = parse_frontmatter(raw, MetadataBlockKind::PlusesStyle).unwrap();
+        assert_eq!(
+            frontmatter["title"],
+            FrontmatterValue::String("Hello".into())
+        );
+        assert_eq!(frontmatter["weight"], FrontmatterValue::Integer(3));
+        assert_eq!(
+            frontmatter["author"],
+            FrontmatterValue::Map(BTreeMap::from([(
+                "name".to_string(),
+                FrontmatterValue::String("me".into())
+            )]))
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(
+            parse_frontmatter("title: [unclosed", MetadataBlockKind::YamlStyle),
+            None
+        );
+        assert_eq!(
+            parse_frontmatter("just a string", MetadataBlockKind::YamlStyle),
+            None
+        );
+        assert_eq!(
+            parse_frontmatter("title = ", MetadataBlockKind::PlusesStyle),
+            None
+        );
+    }
+
+    #[test]
+    fn take_metadata_blocks() {
+        use MetadataBlockKind::*;
+        let mut stream = vec![
+            (Event::Start(Tag::MetadataBlock(YamlStyle)), 0..17),
+            (Event::Text("title: x\n".into()), 4..13),
+            (Event::End(TagEnd::MetadataBlock(YamlStyle)), 0..17),
+            (Event::Start(Tag::Paragraph), 18..22),
+            (Event::Text("text".into()), 18..22),
+            (Event::End(TagEnd::Paragraph), 18..22),
+            (Event::Start(Tag::MetadataBlock(PlusesStyle)), 24..38),
+            (Event::Text("a = 1\n".into()), 28..34),
+            (Event::End(TagEnd::MetadataBlock(PlusesStyle)), 24..38),
+        ];
+        assert_eq!(
+            take_frontmatter(&mut stream),
+            Some(("title: x\n".to_string(), YamlStyle))
+        );
+        assert_eq!(stream.len(), 3);
+        assert_eq!(stream[0].0, Event::Start(Tag::Paragraph));
+    }
+}
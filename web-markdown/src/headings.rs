@@ -0,0 +1,282 @@
+use core::ops::Range;
+use std::collections::{HashMap, HashSet};
+
+use pulldown_cmark::{Event, Tag, TagEnd};
+
+/// `slugify(text)` computes a github-style anchor for a heading:
+/// the text is lowercased, punctuation is removed,
+/// and spaces are replaced with hyphens.
+/// Non-ascii letters and digits are preserved.
+pub fn slugify(text: &str) -> String {
+    text.trim()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Makes sure that every id is unique inside a document,
+/// by appending `-1`, `-2`, ... to repeated ids, like github does.
+#[derive(Default)]
+struct UniqueIds {
+    used: HashSet<String>,
+}
+
+impl UniqueIds {
+    fn make_unique(&mut self, id: String) -> String {
+        let mut candidate = id.clone();
+        let mut n = 0;
+        while self.used.contains(&candidate) {
+            n += 1;
+            candidate = format!("{id}-{n}");
+        }
+        self.used.insert(candidate.clone());
+        candidate
+    }
+}
+
+/// A heading of the document, used to create a table of content
+#[derive(Clone, Debug, PartialEq)]
+pub struct Heading {
+    /// the level of the heading, from 1 to 6
+    pub level: u8,
+    /// the text content of the heading, without formatting
+    pub text: String,
+    /// the anchor id of the heading.
+    /// It is empty if the text of the heading has no slug
+    pub id: String,
+    /// the position of the heading in the markdown source
+    pub range: Range<usize>,
+}
+
+/// `collect_headings(stream, slugify)` lists every heading in `stream`,
+/// in the order of the document, and computes their anchor id.
+/// The ids written in the source, like `# Title {#my-id}`, are kept as is,
+/// and the computed ids avoid them
+pub fn collect_headings<'a>(
+    stream: &[(Event<'a>, Range<usize>)],
+    slugify: impl Fn(&str) -> String,
+) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    let mut unique = UniqueIds::default();
+    for (event, _) in stream {
+        if let Event::Start(Tag::Heading { id: Some(id), .. }) = event {
+            unique.used.insert(id.to_string());
+        }
+    }
+
+    let mut current: Option<Heading> = None;
+    for (event, range) in stream {
+        match event {
+            Event::Start(Tag::Heading { level, id, .. }) => {
+                current = Some(Heading {
+                    level: *level as u8,
+                    text: String::new(),
+                    id: id.as_deref().unwrap_or_default().to_string(),
+                    range: range.clone(),
+                })
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some(mut heading) = current.take() {
+                    let slug = slugify(&heading.text);
+                    if heading.id.is_empty() && !slug.is_empty() {
+                        heading.id = unique.make_unique(slug);
+                    }
+                    headings.push(heading)
+                }
+            }
+            Event::Text(s) | Event::Code(s) => {
+                if let Some(heading) = &mut current {
+                    heading.text.push_str(s)
+                }
+            }
+            _ => (),
+        }
+    }
+    headings
+}
+
+/// `heading_id(headings, start)` returns the anchor id of the heading
+/// starting at `start` in the markdown source, if it has one
+pub fn heading_id(headings: &[Heading], start: usize) -> Option<String> {
+    let i = headings
+        .binary_search_by_key(&start, |h| h.range.start)
+        .ok()?;
+    Some(headings[i].id.clone()).filter(|id| !id.is_empty())
+}
+
+/// `number_headings(headings, offset)` numbers `headings` hierarchically,
+/// like `1`, `1.1`, `1.2`, `1.2.1`, by their level once shifted by `offset`,
+/// see [`crate::MarkdownProps::heading_offset`].
+/// The numbers start at the level of the shallowest heading,
+/// and every heading resets the numbering of the deeper levels.
+/// Returns the numbers by position of the heading
+pub fn number_headings(headings: &[Heading], offset: u8) -> HashMap<usize, String> {
+    let level = |h: &Heading| h.level.saturating_add(offset).min(6) as usize;
+    let Some(top) = headings.iter().map(level).min() else {
+        return HashMap::new();
+    };
+    let mut counters = [0; 7];
+    headings
+        .iter()
+        .map(|h| {
+            let level = level(h);
+            counters[level] += 1;
+            counters[level + 1..].fill(0);
+            let number: Vec<_> = counters[top..=level]
+                .iter()
+                .map(|n| n.to_string())
+                .collect();
+            (h.range.start, number.join("."))
+        })
+        .collect()
+}
+
+/// `fragment_heading(headings, fragment)` returns the id of the heading
+/// that the url fragment `fragment`, like `#usage`, points to.
+/// The fragment can be percent-encoded, like `#%C3%BCberblick`.
+/// Returns None if no heading has this id
+pub fn fragment_heading(headings: &[Heading], fragment: &str) -> Option<String> {
+    let id = percent_decode(fragment.strip_prefix('#').unwrap_or(fragment));
+    headings
+        .iter()
+        .any(|h| !h.id.is_empty() && h.id == id)
+        .then_some(id)
+}
+
+/// `percent_decode(text)` decodes the `%XX` escapes of `text`, like in urls.
+/// Invalid escapes are kept as is
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pulldown_cmark::Parser;
+
+    fn headings(source: &str) -> Vec<Heading> {
+        let stream: Vec<_> = Parser::new(source).into_offset_iter().collect();
+        collect_headings(&stream, slugify)
+    }
+
+    fn ids(source: &str) -> Vec<String> {
+        headings(source).into_iter().map(|h| h.id).collect()
+    }
+
+    #[test]
+    fn slug_punctuation() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify("snake_case and-kebab"), "snake_case-and-kebab");
+    }
+
+    #[test]
+    fn slug_non_ascii() {
+        assert_eq!(slugify("Überblick Ärger"), "überblick-ärger");
+        assert_eq!(slugify("日本語"), "日本語");
+    }
+
+    #[test]
+    fn duplicate_headings() {
+        assert_eq!(ids("# a\n# a\n# a"), vec!["a", "a-1", "a-2"]);
+    }
+
+    #[test]
+    fn explicit_ids() {
+        let stream: Vec<_> = Parser::new_ext(
+            "# a\n# b {#a}\n# a",
+            pulldown_cmark::Options::ENABLE_HEADING_ATTRIBUTES,
+        )
+        .into_offset_iter()
+        .collect();
+        let ids: Vec<_> = collect_headings(&stream, slugify)
+            .into_iter()
+            .map(|h| h.id)
+            .collect();
+        assert_eq!(ids, vec!["a-1", "a", "a-2"]);
+    }
+
+    #[test]
+    fn fragments() {
+        let headings = headings("# Überblick\n## Usage");
+        assert_eq!(fragment_heading(&headings, "#usage"), Some("usage".into()));
+        assert_eq!(
+            fragment_heading(&headings, "#%C3%BCberblick"),
+            Some("überblick".into())
+        );
+        assert_eq!(fragment_heading(&headings, "#missing"), None);
+        assert_eq!(fragment_heading(&headings, ""), None);
+        assert_eq!(percent_decode("100%-%zz%4"), "100%-%zz%4");
+    }
+
+    #[test]
+    fn empty_heading() {
+        assert_eq!(ids("#\n# `code` title"), vec!["", "code-title"]);
+    }
+
+    #[test]
+    fn table_of_content() {
+        let source = "# One\n\n## Two *a*\n\ntext\n\n### Three\n";
+        assert_eq!(
+            headings(source),
+            vec![
+                Heading {
+                    level: 1,
+                    text: "One".into(),
+                    id: "one".into(),
+                    range: 0..6,
+                },
+                Heading {
+                    level: 2,
+                    text: "Two a".into(),
+                    id: "two-a".into(),
+                    range: 7..18,
+                },
+                Heading {
+                    level: 3,
+                    text: "Three".into(),
+                    id: "three".into(),
+                    range: 25..35,
+                },
+            ]
+        )
+    }
+
+    #[test]
+    fn numbering() {
+        let nested = headings("# A\n## B\n## C\n### D\n# E\n### F");
+        let numbers = number_headings(&nested, 0);
+        let numbers: Vec<_> = nested.iter().map(|h| &numbers[&h.range.start]).collect();
+        assert_eq!(numbers, ["1", "1.1", "1.2", "1.2.1", "2", "2.0.1"]);
+
+        // the numbers start at the shallowest heading, and the offset can merge levels
+        let shallow = headings("## A\n### B\n##### C\n###### D");
+        let numbers = number_headings(&shallow, 2);
+        let numbers: Vec<_> = shallow.iter().map(|h| &numbers[&h.range.start]).collect();
+        assert_eq!(numbers, ["1", "1.1", "1.1.1", "1.1.2"]);
+    }
+}
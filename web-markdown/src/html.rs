@@ -0,0 +1,434 @@
+use crate::links;
+
+/// How the raw html of the markdown source is rendered,
+/// like `<div>` or `<span style="color: red">`.
+/// Custom components are always rendered
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HtmlPolicy {
+    /// render the html as is.
+    /// Only use it for trusted content, since it can inject scripts
+    #[default]
+    Passthrough,
+    /// render the html as text, so that the tags are visible
+    Escape,
+    /// remove the html
+    Strip,
+    /// only keep the tags and attributes of the allowlist.
+    /// Event handlers like `onclick` and urls with a forbidden scheme,
+    /// like `javascript:`, are always removed
+    Sanitize(HtmlAllowlist),
+}
+
+/// The tags and attributes kept by [`HtmlPolicy::Sanitize`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HtmlAllowlist {
+    /// the names of the allowed tags, in lowercase
+    pub tags: &'static [&'static str],
+    /// the names of the allowed attributes, in lowercase, for every allowed tag
+    pub attributes: &'static [&'static str],
+}
+
+/// the tags allowed by default when sanitizing html
+pub const DEFAULT_ALLOWED_TAGS: &[&str] = &[
+    "a",
+    "abbr",
+    "b",
+    "blockquote",
+    "br",
+    "code",
+    "dd",
+    "del",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "ins",
+    "kbd",
+    "li",
+    "mark",
+    "ol",
+    "p",
+    "pre",
+    "q",
+    "s",
+    "samp",
+    "small",
+    "span",
+    "strong",
+    "sub",
+    "summary",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "u",
+    "ul",
+];
+
+/// the attributes allowed by default when sanitizing html
+pub const DEFAULT_ALLOWED_ATTRIBUTES: &[&str] = &[
+    "alt", "class", "colspan", "dir", "height", "href", "id", "lang", "open", "rowspan", "src",
+    "start", "title", "width",
+];
+
+impl HtmlPolicy {
+    /// wether raw html `<table>` elements are rendered with this policy
+    pub fn allows_tables(&self) -> bool {
+        match self {
+            Self::Passthrough => true,
+            Self::Escape | Self::Strip => false,
+            Self::Sanitize(allowlist) => allowlist.tags.contains(&"table"),
+        }
+    }
+}
+
+impl Default for HtmlAllowlist {
+    fn default() -> Self {
+        Self {
+            tags: DEFAULT_ALLOWED_TAGS,
+            attributes: DEFAULT_ALLOWED_ATTRIBUTES,
+        }
+    }
+}
+
+/// the elements whose content is removed with them when they are not allowed
+const DROPPED_CONTENT_TAGS: &[&str] = &["script", "style", "iframe", "object", "template"];
+
+/// the attributes that contain a url
+const URL_ATTRIBUTES: &[&str] = &[
+    "href",
+    "src",
+    "action",
+    "formaction",
+    "poster",
+    "cite",
+    "background",
+    "xlink:href",
+];
+
+/// an html tag, like `<a href="x">` or `</a>`
+pub struct ParsedTag {
+    /// the lowercase name of the tag
+    pub name: String,
+    /// wether it is a closing tag
+    pub end: bool,
+    /// wether it is self-closing, like `<br/>`
+    pub self_closing: bool,
+    /// the lowercase names of the attributes, with their decoded values
+    pub attributes: Vec<(String, Option<String>)>,
+}
+
+/// `parse_tag(tag)` parses the inside of a tag, without the surrounding `<` and `>`.
+/// Returns None if it is not a tag
+pub fn parse_tag(tag: &str) -> Option<ParsedTag> {
+    let (end, tag) = match tag.strip_prefix('/') {
+        Some(tag) => (true, tag),
+        None => (false, tag),
+    };
+    if !tag.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let (tag, self_closing) = match tag.trim_end().strip_suffix('/') {
+        Some(tag) => (tag, true),
+        None => (tag, false),
+    };
+    let name_end = tag
+        .find(|c: char| c.is_whitespace() || c == '/')
+        .unwrap_or(tag.len());
+    let name = tag[..name_end].to_ascii_lowercase();
+
+    let mut attributes = Vec::new();
+    let mut rest = &tag[name_end..];
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        if rest.is_empty() {
+            break;
+        }
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+            .unwrap_or(rest.len());
+        let attribute = rest[..name_end].to_ascii_lowercase();
+        rest = rest[name_end..].trim_start();
+        let value = match rest.strip_prefix('=') {
+            Some(value) => {
+                let value = value.trim_start();
+                let (value, remaining) = match value.chars().next() {
+                    Some(quote @ ('"' | '\'')) => match value[1..].find(quote) {
+                        Some(i) => (&value[1..i + 1], &value[i + 2..]),
+                        None => (&value[1..], ""),
+                    },
+                    _ => {
+                        let end = value.find(char::is_whitespace).unwrap_or(value.len());
+                        value.split_at(end)
+                    }
+                };
+                rest = remaining;
+                Some(decode_entities(value))
+            }
+            None => None,
+        };
+        attributes.push((attribute, value));
+    }
+
+    Some(ParsedTag {
+        name,
+        end,
+        self_closing,
+        attributes,
+    })
+}
+
+/// `decode_entities(text)` replaces the numeric character references of `text`,
+/// like `&#106;` or `&#x6A;`, as well as `&colon;`, `&tab;` and `&newline;`,
+/// which could be used to hide a `javascript:` url.
+/// Unknown entities are left unchanged
+pub fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest.find(';').unwrap_or(rest.len());
+        let entity = &rest[1..end];
+        let c = match entity.strip_prefix('#') {
+            Some(n) => {
+                let code = match n.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => n.parse().ok(),
+                };
+                code.and_then(char::from_u32)
+            }
+            None => match entity.to_ascii_lowercase().as_str() {
+                "colon" => Some(':'),
+                "tab" => Some('\t'),
+                "newline" => Some('\n'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                _ => None,
+            },
+        };
+        match c {
+            Some(c) if end < rest.len() => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// escapes the characters of an attribute value
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// renders `tag` back to html, with only the allowed attributes.
+/// `allowed_schemes` are the url schemes allowed in links and images
+fn sanitize_tag(tag: ParsedTag, allowlist: &HtmlAllowlist, allowed_schemes: &[&str]) -> String {
+    if tag.end {
+        return format!("</{}>", tag.name);
+    }
+    let mut html = format!("<{}", tag.name);
+    for (name, value) in tag.attributes {
+        if name.starts_with("on") || !allowlist.attributes.contains(&name.as_str()) {
+            continue;
+        }
+        match value {
+            Some(value) => {
+                if URL_ATTRIBUTES.contains(&name.as_str()) {
+                    let image = name == "src";
+                    if links::sanitize_url(value.clone(), allowed_schemes, image) != value {
+                        continue;
+                    }
+                }
+                html.push_str(&format!(" {name}=\"{}\"", escape_attribute(&value)));
+            }
+            None => html.push_str(&format!(" {name}")),
+        }
+    }
+    if tag.self_closing {
+        html.push('/');
+    }
+    html.push('>');
+    html
+}
+
+/// `sanitize_html(raw_html, allowlist, allowed_schemes, dropped)`
+/// removes the tags and attributes of `raw_html` that are not in `allowlist`,
+/// as well as comments and event handlers.
+///
+/// `raw_html` can be a fragment of a larger piece of html,
+/// so `dropped` keeps the name of the element whose content is being removed,
+/// like `script`, from one fragment to the next.
+pub fn sanitize_html(
+    raw_html: &str,
+    allowlist: &HtmlAllowlist,
+    allowed_schemes: &[&str],
+    dropped: &mut Option<String>,
+) -> String {
+    let mut html = String::with_capacity(raw_html.len());
+    let mut rest = raw_html;
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            if dropped.is_none() {
+                html.push_str(&rest.replace('>', "&gt;"));
+            }
+            break;
+        };
+        if dropped.is_none() {
+            html.push_str(&rest[..start].replace('>', "&gt;"));
+        }
+        rest = &rest[start..];
+
+        // comments, doctypes and processing instructions are removed
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.split_once("-->").map_or("", |(_, rest)| rest);
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.split_once('>').map_or("", |(_, rest)| rest);
+            continue;
+        }
+
+        let tag_end = tag_end(rest);
+        let tag = tag_end.and_then(|end| parse_tag(&rest[1..end]));
+        let (Some(end), Some(tag)) = (tag_end, tag) else {
+            // a lone `<`, not followed by a tag
+            if dropped.is_none() {
+                html.push_str("&lt;");
+            }
+            rest = &rest[1..];
+            continue;
+        };
+        rest = &rest[end + 1..];
+
+        if let Some(name) = dropped {
+            if tag.end && tag.name == *name {
+                *dropped = None;
+            }
+            continue;
+        }
+        if allowlist.tags.contains(&tag.name.as_str()) {
+            html.push_str(&sanitize_tag(tag, allowlist, allowed_schemes));
+        } else if !tag.end && !tag.self_closing && DROPPED_CONTENT_TAGS.contains(&tag.name.as_str())
+        {
+            *dropped = Some(tag.name);
+        }
+    }
+    html
+}
+
+/// `tag_end(html)` finds the `>` that closes the tag at the start of `html`,
+/// ignoring the ones inside quoted attribute values
+pub fn tag_end(html: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in html.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '>') => return Some(i),
+            _ => (),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::DEFAULT_URL_SCHEMES;
+
+    fn sanitize(raw_html: &str) -> String {
+        let allowlist = HtmlAllowlist::default();
+        sanitize_html(raw_html, &allowlist, DEFAULT_URL_SCHEMES, &mut None)
+    }
+
+    #[test]
+    fn allowed_tags() {
+        assert_eq!(
+            sanitize("<div class=\"a\"><b>bold</b></div>"),
+            "<div class=\"a\"><b>bold</b></div>"
+        );
+        assert_eq!(sanitize("<br/>"), "<br/>");
+    }
+
+    #[test]
+    fn forbidden_tags() {
+        assert_eq!(sanitize("<form><b>x</b></form>"), "<b>x</b>");
+        assert_eq!(sanitize("<script>alert(1)</script>after"), "after");
+        assert_eq!(sanitize("a<!-- comment -->b"), "ab");
+    }
+
+    #[test]
+    fn script_across_fragments() {
+        let allowlist = HtmlAllowlist::default();
+        let mut dropped = None;
+        let mut sanitize =
+            |html: &str| sanitize_html(html, &allowlist, DEFAULT_URL_SCHEMES, &mut dropped);
+        assert_eq!(sanitize("<script>\n"), "");
+        assert_eq!(sanitize("alert(1)\n"), "");
+        assert_eq!(sanitize("</script>\n"), "\n");
+    }
+
+    #[test]
+    fn event_handlers() {
+        assert_eq!(
+            sanitize("<img src=\"a.png\" onerror=\"alert(1)\" alt='a'>"),
+            "<img src=\"a.png\" alt=\"a\">"
+        );
+        assert_eq!(sanitize("<b ONCLICK=alert(1)>"), "<b>");
+    }
+
+    #[test]
+    fn javascript_urls() {
+        assert_eq!(sanitize("<a href=\"javascript:alert(1)\">"), "<a>");
+        assert_eq!(
+            sanitize("<a href=\"jav&#x61;script&colon;alert(1)\">"),
+            "<a>"
+        );
+        assert_eq!(sanitize("<a href=\" JavaScript:alert(1)\">"), "<a>");
+        assert_eq!(
+            sanitize("<a href=\"https://a.b/?x=1&amp;y=2\">"),
+            "<a href=\"https://a.b/?x=1&amp;y=2\">"
+        );
+    }
+
+    #[test]
+    fn quoted_greater_than() {
+        assert_eq!(sanitize("<a title=\"a > b\">"), "<a title=\"a &gt; b\">");
+    }
+
+    #[test]
+    fn decode() {
+        assert_eq!(
+            decode_entities("&#106;&#X61;&colon;&unknown;&"),
+            "ja:&unknown;&"
+        );
+    }
+}
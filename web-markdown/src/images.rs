@@ -0,0 +1,301 @@
+use core::ops::Range;
+use std::collections::HashMap;
+
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+
+/// the html attributes specific to images
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImageAttributes {
+    /// the `title` attribute, shown when hovering the image
+    pub title: Option<String>,
+    /// the `width` attribute, in pixels
+    pub width: Option<String>,
+    /// the `height` attribute, in pixels
+    pub height: Option<String>,
+    /// the `loading` attribute, for example `lazy`
+    pub loading: Option<String>,
+    /// the `decoding` attribute, for example `async`
+    pub decoding: Option<String>,
+    /// the `role` attribute, `presentation` for the decorative images
+    pub role: Option<String>,
+    /// the url displayed instead of the image if it fails to load
+    pub placeholder: Option<String>,
+    /// the position of the image in the markdown source
+    pub range: Range<usize>,
+}
+
+impl ImageAttributes {
+    /// the event to report when the image at `src` fails to load
+    pub fn error_event(&self, src: &str) -> ImageErrorEvent {
+        ImageErrorEvent {
+            src: src.to_string(),
+            range: self.range.clone(),
+        }
+    }
+
+    /// `fallback_src(failed_src)` returns the url to display instead of `failed_src`.
+    /// Returns None if there is no placeholder or if the placeholder itself failed,
+    /// so that a broken placeholder doesn't trigger errors forever
+    pub fn fallback_src(&self, failed_src: &str) -> Option<&str> {
+        self.placeholder.as_deref().filter(|p| *p != failed_src)
+    }
+}
+
+/// the event reported when an image fails to load
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageErrorEvent {
+    /// the url of the image that failed to load
+    pub src: String,
+    /// the position of the image in the markdown source
+    pub range: Range<usize>,
+}
+
+/// How images should be loaded by the browser
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ImageLoading {
+    /// load images when they get close to the viewport,
+    /// and decode them asynchronously
+    #[default]
+    Lazy,
+    /// load images immediately
+    Eager,
+    /// let the browser decide
+    Auto,
+}
+
+/// `set_loading(attributes, loading)` sets the loading hints of an image
+pub fn set_loading(attributes: &mut ImageAttributes, loading: ImageLoading) {
+    let (loading, decoding) = match loading {
+        ImageLoading::Lazy => (Some("lazy"), Some("async")),
+        ImageLoading::Eager => (Some("eager"), None),
+        ImageLoading::Auto => (None, None),
+    };
+    attributes.loading = loading.map(str::to_string);
+    attributes.decoding = decoding.map(str::to_string);
+}
+
+/// `set_decorative(attributes, alt)` gives the `presentation` role to an image
+/// without alternative text, so that screen readers skip it
+pub fn set_decorative(attributes: &mut ImageAttributes, alt: &str) {
+    if alt.trim().is_empty() {
+        attributes.role = Some("presentation".into())
+    }
+}
+
+/// the width and height of an image, when they are specified
+type Size = (Option<String>, Option<String>);
+
+/// `parse_size(size)` parses an obsidian-style size hint like `100x200`,
+/// where either dimension can be omitted
+fn parse_size(size: &str) -> Option<Size> {
+    let (width, height) = size.split_once('x').unwrap_or((size, ""));
+    let dimension = |x: &str| -> Option<Option<String>> {
+        if x.is_empty() {
+            Some(None)
+        } else if x.chars().all(|c| c.is_ascii_digit()) {
+            Some(Some(x.to_string()))
+        } else {
+            None
+        }
+    };
+    match (dimension(width)?, dimension(height)?) {
+        (None, None) => None,
+        size => Some(size),
+    }
+}
+
+/// `image_attributes(title)` computes the attributes of an image
+/// from the title of the markdown image.
+/// The title can end with a size hint, like `![alt](url "title =100x200")`
+pub fn image_attributes(title: &str) -> ImageAttributes {
+    let (title, size) = match title.rsplit_once('=') {
+        Some((rest, size)) if rest.is_empty() || rest.ends_with(' ') => {
+            match parse_size(size.trim_end()) {
+                Some(size) => (rest.trim_end(), size),
+                None => (title, (None, None)),
+            }
+        }
+        _ => (title, (None, None)),
+    };
+
+    ImageAttributes {
+        title: Some(title.to_string()).filter(|t| !t.is_empty()),
+        width: size.0,
+        height: size.1,
+        ..Default::default()
+    }
+}
+
+/// `figure_captions(stream)` finds the paragraphs that only contain an image with a title.
+/// Returns the title of the image of each of them, without size hint,
+/// by position of the paragraph
+pub fn figure_captions(stream: &[(Event<'_>, Range<usize>)]) -> HashMap<usize, String> {
+    let mut captions = HashMap::new();
+    for (i, (event, range)) in stream.iter().enumerate() {
+        let (Event::Start(Tag::Paragraph), Some((Event::Start(Tag::Image { title, .. }), _))) =
+            (event, stream.get(i + 1))
+        else {
+            continue;
+        };
+        let Some(end) = stream[i + 1..]
+            .iter()
+            .position(|(e, _)| *e == Event::End(TagEnd::Image))
+        else {
+            continue;
+        };
+        let alone = matches!(
+            stream.get(i + 2 + end),
+            Some((Event::End(TagEnd::Paragraph), _))
+        );
+        if let Some(caption) = image_attributes(title).title.filter(|_| alone) {
+            captions.insert(range.start, caption);
+        }
+    }
+    captions
+}
+
+/// `parse_attribute_block(text)` parses a block like `{width=100 height=50}`
+/// at the start of `text`.
+/// Returns the size and the length of the block
+fn parse_attribute_block(text: &str) -> Option<(Size, usize)> {
+    let inner = text.strip_prefix('{')?;
+    let end = inner.find('}')?;
+    let (mut width, mut height) = (None, None);
+    for attribute in inner[..end].split_whitespace() {
+        let (key, value) = attribute.split_once('=')?;
+        let value = value.trim_end_matches("px");
+        if value.is_empty() || !value.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        match key {
+            "width" => width = Some(value.to_string()),
+            "height" => height = Some(value.to_string()),
+            _ => return None,
+        }
+    }
+    if width.is_none() && height.is_none() {
+        return None;
+    }
+    Some(((width, height), end + 2))
+}
+
+/// `fold_attribute_blocks(stream)` removes the attribute blocks
+/// that follow images, like `![alt](url){width=100}`,
+/// and turns them into a size hint at the end of the title of the image
+pub fn fold_attribute_blocks(
+    mut stream: Vec<(Event<'_>, Range<usize>)>,
+) -> Vec<(Event<'_>, Range<usize>)> {
+    let mut image_starts = Vec::new();
+    let mut emptied = Vec::new();
+    for i in 0..stream.len() {
+        match &stream[i].0 {
+            Event::Start(Tag::Image { .. }) => image_starts.push(i),
+            Event::End(TagEnd::Image) => {
+                let Some(start) = image_starts.pop() else {
+                    continue;
+                };
+                let Some((Event::Text(text), range)) = stream.get(i + 1) else {
+                    continue;
+                };
+                let Some(((width, height), len)) = parse_attribute_block(text) else {
+                    continue;
+                };
+
+                if len == text.len() {
+                    emptied.push(i + 1);
+                }
+                let rest = CowStr::from(text[len..].to_string());
+                let rest_range = (range.start + len).min(range.end)..range.end;
+                stream[i + 1] = (Event::Text(rest), rest_range);
+
+                if let Event::Start(Tag::Image { title, .. }) = &mut stream[start].0 {
+                    let size = format!(
+                        "={}x{}",
+                        width.unwrap_or_default(),
+                        height.unwrap_or_default()
+                    );
+                    *title = if title.is_empty() {
+                        size.into()
+                    } else {
+                        format!("{title} {size}").into()
+                    };
+                }
+            }
+            _ => (),
+        }
+    }
+    for i in emptied.into_iter().rev() {
+        stream.remove(i);
+    }
+    stream
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn title_only() {
+        let attributes = image_attributes("a cat");
+        assert_eq!(attributes.title.as_deref(), Some("a cat"));
+        assert_eq!(attributes.width, None);
+        assert_eq!(image_attributes("a=b").title.as_deref(), Some("a=b"));
+    }
+
+    #[test]
+    fn size_only() {
+        assert_eq!(
+            image_attributes("=100x200"),
+            ImageAttributes {
+                title: None,
+                width: Some("100".into()),
+                height: Some("200".into()),
+                ..Default::default()
+            }
+        );
+        assert_eq!(image_attributes("=x50").height.as_deref(), Some("50"));
+        assert_eq!(image_attributes("=50").width.as_deref(), Some("50"));
+    }
+
+    #[test]
+    fn title_and_size() {
+        assert_eq!(
+            image_attributes("a cat =100x"),
+            ImageAttributes {
+                title: Some("a cat".into()),
+                width: Some("100".into()),
+                height: None,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn image_error() {
+        let attributes = ImageAttributes {
+            placeholder: Some("broken.png".into()),
+            range: 3..20,
+            ..Default::default()
+        };
+        assert_eq!(
+            attributes.error_event("cat.png"),
+            ImageErrorEvent {
+                src: "cat.png".into(),
+                range: 3..20,
+            }
+        );
+        assert_eq!(attributes.fallback_src("cat.png"), Some("broken.png"));
+        assert_eq!(attributes.fallback_src("broken.png"), None);
+        assert_eq!(ImageAttributes::default().fallback_src("cat.png"), None);
+    }
+
+    #[test]
+    fn attribute_block() {
+        assert_eq!(
+            parse_attribute_block("{width=100 height=50px} rest"),
+            Some(((Some("100".into()), Some("50".into())), 23))
+        );
+        assert_eq!(parse_attribute_block("{color=red}"), None);
+        assert_eq!(parse_attribute_block("{width=100"), None);
+    }
+}
@@ -1,18 +1,184 @@
-pub use pulldown_cmark::{CowStr, Options};
-use pulldown_cmark::{Event, LinkType, Parser};
+pub use pulldown_cmark::{BlockQuoteKind, CowStr, Event, Options, Tag, TagEnd};
+use pulldown_cmark::{LinkType, Parser};
+use syntect::highlighting::ThemeSet;
+use syntect::parsing::SyntaxSet;
 
-use core::ops::Range;
-use std::collections::BTreeMap;
+use core::ops::{Range, RangeInclusive};
+use std::collections::{BTreeMap, HashMap};
 
 mod render;
-use render::Renderer;
+use render::{
+    code_block_language, footnote_texts, loose_lists, render_footnotes, DocumentState, Renderer,
+};
+pub use render::{DEFAULT_LANGUAGE_ALIASES, DEFAULT_MAX_NESTING_DEPTH};
 
 mod component;
+pub use component::UnknownComponentPolicy;
+
+mod headings;
+pub use headings::{slugify, Heading};
+
+#[cfg(feature = "ssr")]
+pub mod ssr;
+
+mod scripts;
+
+mod autolinks;
+
+mod abbreviations;
+
+mod attributes;
+
+mod marks;
+
+mod mentions;
+
+mod directives;
+pub use directives::CommentDirective;
+
+mod incremental;
+pub use incremental::CachedRender;
+
+mod blocks;
+pub use blocks::BlockRenderer;
+
+#[cfg(feature = "cache")]
+mod cache;
+#[cfg(feature = "cache")]
+pub use cache::{clear_markdown_cache, set_markdown_cache_size};
+
+#[cfg(feature = "emoji")]
+mod emoji;
+
+mod typography;
+pub use typography::TypographyPreset;
+
+mod images;
+pub use images::{ImageAttributes, ImageErrorEvent, ImageLoading};
+
+mod frontmatter;
+use frontmatter::{document_options, take_frontmatter};
+pub use frontmatter::{Frontmatter, FrontmatterOptions, FrontmatterValue};
+
+mod debug;
+pub use debug::{DebugEvent, RenderError, RenderIssue};
+
+mod stats;
+pub use stats::{DocumentStats, RenderStats, StatsConfig};
+
+mod tables;
+
+mod tasklist;
+pub use tasklist::{apply_task_toggle, TaskState, TaskToggle};
+
+mod html;
+pub use html::{HtmlAllowlist, HtmlPolicy, DEFAULT_ALLOWED_ATTRIBUTES, DEFAULT_ALLOWED_TAGS};
+
+mod config;
+pub use config::MarkdownConfig;
+
+mod links;
+pub use links::{
+    LinkAttributes, LinkInfo, LinkRelPolicy, LinkTarget, WikilinkResolution, BLOCKED_URL,
+    DEFAULT_URL_SCHEMES,
+};
 
 pub struct ElementAttributes<H> {
     pub classes: Vec<String>,
     pub style: Option<String>,
+    pub id: Option<String>,
     pub on_click: Option<H>,
+    pub on_mouseenter: Option<H>,
+    pub on_mouseleave: Option<H>,
+    /// the `title` attribute, shown when hovering the element
+    pub title: Option<String>,
+    /// the `data-*` attributes, with their full name, like `data-line`
+    pub data: Vec<(&'static str, String)>,
+    /// the `role` attribute, for example `separator`
+    pub role: Option<String>,
+    /// the `aria-label` attribute, read by screen readers
+    pub aria_label: Option<String>,
+    /// the `aria-labelledby` attribute, the id of the element that labels this one,
+    /// like the item of a task for its checkbox
+    pub aria_labelledby: Option<String>,
+    /// the `aria-level` attribute, for example the level of a heading
+    pub aria_level: Option<u8>,
+    /// the `tabindex` attribute, to make the element focusable with the keyboard
+    pub tabindex: Option<i32>,
+    /// the `colspan` attribute of a table cell
+    pub colspan: Option<u32>,
+    /// the `rowspan` attribute of a table cell
+    pub rowspan: Option<u32>,
+    /// the `type` attribute of an ordered list, like `I` for roman numerals
+    pub list_type: Option<&'static str>,
+    /// the `dir` attribute, the direction of the text of the element
+    pub dir: Option<Direction>,
+    /// the `lang` attribute, the language of the content of the element, like `ar`
+    pub lang: Option<String>,
+    /// the `disabled` attribute, for example of a read-only checkbox
+    pub disabled: bool,
+    /// the attributes written in the markdown source, like `key=value` in `# Title {key=value}`.
+    /// Unlike [`ElementAttributes::extra_attributes`], their names are not known in advance
+    pub source_attributes: Vec<(String, String)>,
+    /// wether pressing Enter or Space while the element is focused clicks it,
+    /// like a button, see [`is_activation_key`]
+    pub keyboard_activation: bool,
+}
+
+/// `task_item_id(position)` returns the id of the item of the task list
+/// whose checkbox is at `position` in the source, that labels the checkbox
+pub(crate) fn task_item_id(position: usize) -> String {
+    format!("task-{position}")
+}
+
+/// wether `key`, the `key` of a keyboard event, activates the focused element,
+/// like Enter and Space activate a button
+pub fn is_activation_key(key: &str) -> bool {
+    matches!(key, "Enter" | " " | "Spacebar")
+}
+
+/// `line_number(source, offset)` converts the byte `offset` in `source`,
+/// like the `data-source-start` of a block, into a line number, starting from 1.
+/// See [`MarkdownProps::source_positions`]
+pub fn line_number(source: &str, offset: usize) -> usize {
+    let offset = offset.min(source.len());
+    source.as_bytes()[..offset]
+        .iter()
+        .filter(|b| **b == b'\n')
+        .count()
+        + 1
+}
+
+impl<H> ElementAttributes<H> {
+    /// the attributes other than the classes, the style, the id and the event handlers,
+    /// with their name and their value
+    pub fn extra_attributes(&self) -> Vec<(&'static str, String)> {
+        let optional = [
+            ("title", self.title.clone()),
+            ("role", self.role.clone()),
+            ("aria-label", self.aria_label.clone()),
+            ("aria-labelledby", self.aria_labelledby.clone()),
+            ("aria-level", self.aria_level.map(|x| x.to_string())),
+            ("tabindex", self.tabindex.map(|x| x.to_string())),
+            ("colspan", self.colspan.map(|x| x.to_string())),
+            ("rowspan", self.rowspan.map(|x| x.to_string())),
+            ("type", self.list_type.map(|x| x.to_string())),
+            ("dir", self.dir.map(|x| x.as_str().to_string())),
+            ("lang", self.lang.clone()),
+            ("disabled", self.disabled.then(String::new)),
+        ];
+        optional
+            .into_iter()
+            .filter_map(|(name, value)| Some((name, value?)))
+            .chain(self.data.iter().cloned())
+            .collect()
+    }
+
+    /// wether pressing `key` while the element is focused clicks it,
+    /// see [`ElementAttributes::keyboard_activation`]
+    pub fn is_activated_by(&self, key: &str) -> bool {
+        self.keyboard_activation && is_activation_key(key)
+    }
 }
 
 impl<H> Default for ElementAttributes<H> {
@@ -20,11 +186,30 @@ impl<H> Default for ElementAttributes<H> {
         Self {
             style: None,
             classes: vec![],
+            id: None,
             on_click: None,
+            on_mouseenter: None,
+            on_mouseleave: None,
+            title: None,
+            data: vec![],
+            role: None,
+            aria_label: None,
+            aria_labelledby: None,
+            aria_level: None,
+            tabindex: None,
+            colspan: None,
+            rowspan: None,
+            list_type: None,
+            dir: None,
+            lang: None,
+            disabled: false,
+            source_attributes: vec![],
+            keyboard_activation: false,
         }
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum HtmlElement {
     Div,
     Span,
@@ -38,13 +223,143 @@ pub enum HtmlElement {
     Thead,
     Trow,
     Tcell,
+    /// a cell inside the head of a table
+    TheadCell,
+    /// the caption of a table
+    Caption,
+    /// a button
+    Button,
+    Italics,
+    Bold,
+    StrikeThrough,
+    /// superscript text
+    Sup,
+    /// subscript text
+    Sub,
+    Pre,
+    Code,
+    /// a definition list
+    Dl,
+    /// a term of a definition list
+    Dt,
+    /// a definition of a definition list
+    Dd,
+    /// a collapsible section
+    Details,
+    /// the title of a collapsible section
+    Summary,
+    /// an abbreviation, with its expansion in the `title` attribute
+    Abbr,
+    /// highlighted text
+    Mark,
+    /// a key or a combination of keys, like `Ctrl+C`
+    Kbd,
+    /// self-contained content, like an image with its caption
+    Figure,
+    /// the caption of a figure
+    Figcaption,
+}
+
+impl HtmlElement {
+    /// the kind of the element, like [`HtmlElementKind::Heading`] for every heading
+    pub fn kind(self) -> HtmlElementKind {
+        match self {
+            HtmlElement::Div => HtmlElementKind::Div,
+            HtmlElement::Span => HtmlElementKind::Span,
+            HtmlElement::Paragraph => HtmlElementKind::Paragraph,
+            HtmlElement::BlockQuote => HtmlElementKind::BlockQuote,
+            HtmlElement::Ul => HtmlElementKind::Ul,
+            HtmlElement::Ol(_) => HtmlElementKind::Ol,
+            HtmlElement::Li => HtmlElementKind::Li,
+            HtmlElement::Heading(_) => HtmlElementKind::Heading,
+            HtmlElement::Table => HtmlElementKind::Table,
+            HtmlElement::Thead => HtmlElementKind::Thead,
+            HtmlElement::Trow => HtmlElementKind::Trow,
+            HtmlElement::Tcell => HtmlElementKind::Tcell,
+            HtmlElement::TheadCell => HtmlElementKind::TheadCell,
+            HtmlElement::Caption => HtmlElementKind::Caption,
+            HtmlElement::Button => HtmlElementKind::Button,
+            HtmlElement::Italics => HtmlElementKind::Italics,
+            HtmlElement::Bold => HtmlElementKind::Bold,
+            HtmlElement::StrikeThrough => HtmlElementKind::StrikeThrough,
+            HtmlElement::Sup => HtmlElementKind::Sup,
+            HtmlElement::Sub => HtmlElementKind::Sub,
+            HtmlElement::Pre => HtmlElementKind::Pre,
+            HtmlElement::Code => HtmlElementKind::Code,
+            HtmlElement::Dl => HtmlElementKind::Dl,
+            HtmlElement::Dt => HtmlElementKind::Dt,
+            HtmlElement::Dd => HtmlElementKind::Dd,
+            HtmlElement::Details => HtmlElementKind::Details,
+            HtmlElement::Summary => HtmlElementKind::Summary,
+            HtmlElement::Abbr => HtmlElementKind::Abbr,
+            HtmlElement::Mark => HtmlElementKind::Mark,
+            HtmlElement::Kbd => HtmlElementKind::Kbd,
+            HtmlElement::Figure => HtmlElementKind::Figure,
+            HtmlElement::Figcaption => HtmlElementKind::Figcaption,
+        }
+    }
+}
+
+/// The kind of a [`HtmlElement`], without the start of an ordered list
+/// or the level of a heading, or an image,
+/// used to override the rendering of every element of a kind, see [`ElementOverride`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HtmlElementKind {
+    Div,
+    Span,
+    Paragraph,
+    BlockQuote,
+    Ul,
+    Ol,
+    Li,
+    Heading,
+    Table,
+    Thead,
+    Trow,
+    Tcell,
+    TheadCell,
+    Caption,
+    Button,
     Italics,
     Bold,
     StrikeThrough,
+    Sup,
+    Sub,
     Pre,
     Code,
+    Dl,
+    Dt,
+    Dd,
+    Details,
+    Summary,
+    Abbr,
+    Mark,
+    Kbd,
+    Figure,
+    Figcaption,
+    /// an image, rendered by [`Context::el_img`]
+    Img,
+}
+
+/// An element rendered by a custom callback instead of the default,
+/// for example to replace every table with a virtualized one,
+/// or to wrap every image in a lightbox.
+/// The frameworks call the callback of the kind of the element, if there is one,
+/// in [`Context::el_with_attributes`], or in [`Context::el_img`] for images
+pub struct ElementOverride<V, H> {
+    /// the element, like `HtmlElement::Heading(2)`, or None for an image
+    pub element: Option<HtmlElement>,
+    /// the rendered children of the element.
+    /// For an image, it is the default `<img>`, that the callback can wrap
+    pub children: V,
+    /// the attributes that the element would have been rendered with.
+    /// They are empty for an image
+    pub attributes: ElementAttributes<H>,
+    /// the url of an image, for example to open it in a lightbox
+    pub src: Option<String>,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct StyleLink {
     pub rel: &'static str,
     pub href: &'static str,
@@ -59,13 +374,137 @@ pub const MATH_STYLE_SHEET_LINK: StyleLink = StyleLink {
     crossorigin: "anonymous",
 };
 
+/// Configuration of the stylesheet used to display maths.
+/// The stylesheet is only loaded when the document contains maths.
+/// The default configuration loads [`MATH_STYLE_SHEET_LINK`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MathConfig {
+    /// if false, the stylesheet is never loaded
+    pub enabled: bool,
+    /// the url of the katex css stylesheet
+    pub katex_css_url: &'static str,
+    /// the subresource integrity hash of the stylesheet
+    pub integrity: &'static str,
+    /// the `crossorigin` attribute of the stylesheet link
+    pub crossorigin: &'static str,
+}
+
+impl Default for MathConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            katex_css_url: MATH_STYLE_SHEET_LINK.href,
+            integrity: MATH_STYLE_SHEET_LINK.integrity,
+            crossorigin: MATH_STYLE_SHEET_LINK.crossorigin,
+        }
+    }
+}
+
+impl MathConfig {
+    /// the link to the stylesheet
+    pub fn style_link(&self) -> StyleLink {
+        StyleLink {
+            rel: "stylesheet",
+            href: self.katex_css_url,
+            integrity: self.integrity,
+            crossorigin: self.crossorigin,
+        }
+    }
+}
+
+/// Configuration of the syntax highlighting of code blocks, done with syntect.
+/// Code blocks in a language that is not in the syntax set are not highlighted,
+/// and keep a `language-<lang>` class instead.
+#[derive(Clone, Copy, Debug)]
+pub struct SyntectConfig {
+    /// the name of the theme, like `InspiredGitHub`.
+    /// If it is not in the theme set, the default theme is used
+    pub theme: &'static str,
+    /// the syntaxes of the languages to highlight,
+    /// instead of the default ones of syntect
+    pub syntax_set: Option<&'static SyntaxSet>,
+    /// the themes to choose from, instead of the default ones of syntect
+    pub theme_set: Option<&'static ThemeSet>,
+}
+
+/// the sets are compared by address, since they can't be compared by value
+impl PartialEq for SyntectConfig {
+    fn eq(&self, other: &Self) -> bool {
+        let syntaxes = |c: &Self| c.syntax_set.map(|s| s as *const SyntaxSet);
+        let themes = |c: &Self| c.theme_set.map(|t| t as *const ThemeSet);
+        self.theme == other.theme
+            && syntaxes(self) == syntaxes(other)
+            && themes(self) == themes(other)
+    }
+}
+
+impl SyntectConfig {
+    /// highlights code with the default theme named `theme`
+    pub fn theme(theme: &'static str) -> Self {
+        Self {
+            theme,
+            syntax_set: None,
+            theme_set: None,
+        }
+    }
+}
+
+/// Configuration of the mermaid script, that draws the ` ```mermaid ` code blocks.
+/// The script is only loaded when the document contains a mermaid diagram.
+/// The default configuration loads the version 10 from jsdelivr.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MermaidConfig {
+    /// the url of the mermaid package, without the version
+    pub cdn_url: &'static str,
+    /// the version of mermaid, like `10` or `10.9.1`
+    pub version: &'static str,
+}
+
+impl Default for MermaidConfig {
+    fn default() -> Self {
+        Self {
+            cdn_url: "https://cdn.jsdelivr.net/npm/mermaid",
+            version: "10",
+        }
+    }
+}
+
+impl MermaidConfig {
+    /// the url of the script
+    pub fn script_src(&self) -> String {
+        format!("{}@{}/dist/mermaid.min.js", self.cdn_url, self.version)
+    }
+}
+
+/// The characters that start a mention like `@alice` or a hashtag like `#topic`,
+/// see [`Context::resolve_mention`] and [`Context::resolve_hashtag`].
+/// By default, they are `@` and `#`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MentionConfig {
+    /// the character before the name of a user, or None to disable mentions
+    pub mention_trigger: Option<char>,
+    /// the character before the name of a topic, or None to disable hashtags
+    pub hashtag_trigger: Option<char>,
+}
+
+impl Default for MentionConfig {
+    fn default() -> Self {
+        Self {
+            mention_trigger: Some('@'),
+            hashtag_trigger: Some('#'),
+        }
+    }
+}
+
 pub trait Context<'a, 'callback>: 'a + Copy
 where
     'callback: 'a,
 {
     type View: Clone + 'callback;
     type Handler<T: 'callback>: 'callback;
-    type MouseEvent: 'static;
+    /// the type of the events triggered when the user interacts with the markdown,
+    /// for example a mouse event on the web
+    type Event: Clone + 'callback;
 
     /// get all the properties from the context
     fn props(self) -> MarkdownProps;
@@ -74,28 +513,171 @@ where
     /// present at the top of the markdown source
     fn set_frontmatter(&mut self, frontmatter: String);
 
+    /// write the frontmatter of the document, parsed into key-value pairs.
+    /// It is only called with the `frontmatter` feature,
+    /// when the frontmatter is valid yaml or toml
+    fn set_parsed_frontmatter(&mut self, _frontmatter: Frontmatter) {}
+
+    /// write the list of all the headings of the document,
+    /// to build a table of content
+    fn set_headings(&mut self, _headings: Vec<Heading>) {}
+
+    /// write the word count and reading time of the document
+    fn set_stats(&mut self, _stats: DocumentStats) {}
+
+    /// write the list of all the links and images of the document,
+    /// for example to check them
+    fn set_document_links(&mut self, _links: Vec<LinkInfo>) {}
+
+    /// write the list of the html comments of the document that are directives,
+    /// see [`MarkdownProps::comment_directive_names`]
+    fn set_comment_directives(&mut self, _directives: Vec<CommentDirective>) {}
+
+    /// the callback that fires once, when the document is rendered.
+    /// Returns None if no one listens to it
+    fn on_render_complete(self) -> Option<Self::Handler<RenderStats>> {
+        None
+    }
+
     fn render_links(self, link: LinkDescription<Self::View>) -> Result<Self::View, String>;
 
+    /// renders maths with a custom renderer.
+    /// Returns None to use the default renderer
+    fn render_math(self, _math: MathDescription) -> Option<Self::View> {
+        None
+    }
+
+    /// renders a code block with a custom renderer.
+    /// Returns None to use the default renderer
+    fn render_code_block(self, _code_block: CodeBlockDescription) -> Option<Self::View> {
+        None
+    }
+
+    /// renders a container directive like `:::name` with a custom renderer.
+    /// Returns None to render its children in a `<div>`
+    fn render_directive(self, _directive: DirectiveDescription<Self::View>) -> Option<Self::View> {
+        None
+    }
+
+    /// renders a blockquote, or a callout like `> [!NOTE]`, with a custom renderer.
+    /// Returns None to render it as a `<blockquote>`, or as a callout
+    fn render_blockquote(
+        self,
+        _blockquote: BlockquoteDescription<Self::View>,
+    ) -> Option<Self::View> {
+        None
+    }
+
+    /// adds a `<link>` element (usually a stylesheet) to the page
+    fn mount_dynamic_link(self, _link: StyleLink) {}
+
+    /// returns true if a `<link>` with the same `href` as `link` is already on the page.
+    /// It is not mounted again, so that rendering many documents
+    /// doesn't add the same stylesheet many times
+    fn is_link_mounted(self, _link: &StyleLink) -> bool {
+        false
+    }
+
+    /// adds a `<script>` element loading `src` to the page,
+    /// unless there is already one
+    fn mount_dynamic_script(self, _src: String) {}
+
+    /// the fragment of the url of the page, like `#usage`.
+    /// Returns None if there is no page, for example when rendering to a string
+    fn location_fragment(self) -> Option<String> {
+        None
+    }
+
+    /// scrolls the element with the id `id` into view, once the document is mounted.
+    /// See [`MarkdownProps::scroll_to_fragment`]
+    fn scroll_into_view(self, _id: String) {}
+
     /// calls a callback with the given input
     fn call_handler<T>(callback: &Self::Handler<T>, input: T);
 
-    /// creates a callback that will fire when the user clicks on markdown
+    /// creates a callback that will fire when the user clicks on markdown,
+    /// on an element of the kind `element`
     fn make_md_handler(
         self,
         position: Range<usize>,
+        element: ClickedElement,
         stop_propagation: bool,
-    ) -> Self::Handler<Self::MouseEvent>;
+    ) -> Self::Handler<Self::Event>;
+
+    /// creates a callback that will fire when the user clicks on a task list checkbox.
+    /// `toggle` describes the new state of the checkbox
+    fn make_task_toggle_handler(
+        self,
+        position: Range<usize>,
+        _toggle: TaskToggle,
+    ) -> Self::Handler<Self::Event> {
+        self.make_md_handler(position, ClickedElement::TaskItem, true)
+    }
+
+    /// creates a callback that copies `code` to the clipboard when the user clicks
+    /// on the copy button of a code block.
+    /// Returns None if the clipboard is not available
+    fn make_copy_handler(self, _code: String) -> Option<Self::Handler<Self::Event>> {
+        None
+    }
+
+    /// creates a callback that copies the permalink of the heading with the id `id`,
+    /// the url of the page followed by `#id`, to the clipboard.
+    /// Returns None if the clipboard is not available
+    fn make_permalink_copy_handler(self, _id: String) -> Option<Self::Handler<Self::Event>> {
+        None
+    }
+
+    /// creates a callback that will fire when the mouse enters
+    /// (or leaves, if `entering` is false) an element of the markdown.
+    /// Returns None if no one listens to hover events
+    fn make_md_hover_handler(
+        self,
+        _position: Range<usize>,
+        _entering: bool,
+    ) -> Option<Self::Handler<Self::Event>> {
+        None
+    }
+
+    /// creates a callback that will fire when the user clicks on the link to `url`,
+    /// found at `position` in the markdown source,
+    /// for example to navigate inside a single page application.
+    /// Returns None if no one listens to link clicks
+    fn make_link_click_handler(
+        self,
+        _url: String,
+        _position: Range<usize>,
+    ) -> Option<Self::Handler<Self::Event>> {
+        None
+    }
+
+    /// creates a callback that will fire when the user clicks on the inline code `code`,
+    /// found at `position` in the markdown source, for example to copy it.
+    /// Returns None if no one listens to inline code clicks,
+    /// in which case the click is reported like for the rest of the markdown
+    fn make_inline_code_click_handler(
+        self,
+        _code: String,
+        _position: Range<usize>,
+    ) -> Option<Self::Handler<Self::Event>> {
+        None
+    }
 
     #[cfg(feature = "debug")]
     fn send_debug_info(self, info: Vec<String>);
 
+    /// write what happened while rendering the document,
+    /// like the events that were dropped or the time each phase took
+    #[cfg(feature = "debug")]
+    fn send_debug(self, _events: Vec<DebugEvent>) {}
+
     /// creates a html element
     /// `attributes` contains the html attributes for this element
     fn el_with_attributes(
         self,
         e: HtmlElement,
         inside: Self::View,
-        attributes: ElementAttributes<Self::Handler<Self::MouseEvent>>,
+        attributes: ElementAttributes<Self::Handler<Self::Event>>,
     ) -> Self::View;
 
     /// creates a html element, with default attributes
@@ -107,11 +689,11 @@ where
     fn el_span_with_inner_html(
         self,
         inner_html: String,
-        attributes: ElementAttributes<Self::Handler<Self::MouseEvent>>,
+        attributes: ElementAttributes<Self::Handler<Self::Event>>,
     ) -> Self::View;
 
     /// renders a `hr` element, with attributes
-    fn el_hr(self, attributes: ElementAttributes<Self::Handler<Self::MouseEvent>>) -> Self::View;
+    fn el_hr(self, attributes: ElementAttributes<Self::Handler<Self::Event>>) -> Self::View;
 
     /// renders a `br` element
     fn el_br(self) -> Self::View;
@@ -119,11 +701,18 @@ where
     /// takes a vector of views and return a view
     fn el_fragment(self, children: Vec<Self::View>) -> Self::View;
 
-    /// renders a link
-    fn el_a(self, children: Self::View, href: String) -> Self::View;
+    /// renders a link.
+    /// `on_click` fires when the link is clicked, see [`Context::make_link_click_handler`]
+    fn el_a(
+        self,
+        children: Self::View,
+        href: String,
+        attributes: LinkAttributes,
+        on_click: Option<Self::Handler<Self::Event>>,
+    ) -> Self::View;
 
     /// renders an image
-    fn el_img(self, src: String, alt: String) -> Self::View;
+    fn el_img(self, src: String, alt: String, attributes: ImageAttributes) -> Self::View;
 
     /// renders an empty view
     fn el_empty(self) -> Self::View {
@@ -133,11 +722,14 @@ where
     /// renders raw text
     fn el_text(self, text: CowStr<'a>) -> Self::View;
 
-    // renders a checkbox with attributes
+    /// renders a checkbox with attributes.
+    /// An `indeterminate` checkbox is neither checked nor unchecked,
+    /// like a partially complete task
     fn el_input_checkbox(
         self,
         checked: bool,
-        attributes: ElementAttributes<Self::Handler<Self::MouseEvent>>,
+        indeterminate: bool,
+        attributes: ElementAttributes<Self::Handler<Self::Event>>,
     ) -> Self::View;
 
     fn has_custom_component(self, name: &str) -> bool;
@@ -147,35 +739,74 @@ where
         input: MdComponentProps<Self::View>,
     ) -> Result<Self::View, ComponentCreationError>;
 
-    fn render_tasklist_marker(self, m: bool, position: Range<usize>) -> Self::View {
+    /// renders the checkbox of a task, with its extra `state`
+    /// if it is one of [`MarkdownProps::task_list_states`]
+    fn render_tasklist_marker(
+        self,
+        m: bool,
+        state: Option<TaskState>,
+        position: Range<usize>,
+    ) -> Self::View {
+        let toggle = TaskToggle {
+            range: position.clone(),
+            checked: !m,
+        };
+        let read_only = self.props().read_only_task_lists;
         let attributes = ElementAttributes {
-            on_click: Some(self.make_md_handler(position, true)),
+            classes: state
+                .map(|s| self.props().class_name(s.class))
+                .into_iter()
+                .collect(),
+            aria_labelledby: Some(task_item_id(position.start)),
+            on_click: (!read_only).then(|| self.make_task_toggle_handler(position, toggle)),
+            disabled: read_only,
             ..Default::default()
         };
-        self.el_input_checkbox(m, attributes)
+        self.el_input_checkbox(m, state.is_some(), attributes)
     }
 
     fn render_rule(self, range: Range<usize>) -> Self::View {
+        let classes = self
+            .props()
+            .rule_class
+            .map(|c| c.split_whitespace().map(str::to_string).collect());
         let attributes = ElementAttributes {
-            on_click: Some(self.make_md_handler(range, false)),
+            classes: classes.unwrap_or_default(),
+            on_click: Some(self.make_md_handler(range, ClickedElement::Rule, false)),
+            role: Some("separator".into()),
+            tabindex: Some(0),
+            keyboard_activation: true,
             ..Default::default()
         };
         self.el_hr(attributes)
     }
 
     fn render_code(self, s: CowStr<'a>, range: Range<usize>) -> Self::View {
-        let callback = self.make_md_handler(range.clone(), false);
+        let callback = self
+            .make_inline_code_click_handler(s.to_string(), range.clone())
+            .unwrap_or_else(|| self.make_md_handler(range.clone(), ClickedElement::Code, false));
         let attributes = ElementAttributes {
             on_click: Some(callback),
+            on_mouseenter: self.make_md_hover_handler(range.clone(), true),
+            on_mouseleave: self.make_md_hover_handler(range, false),
             ..Default::default()
         };
         self.el_with_attributes(HtmlElement::Code, self.el_text(s), attributes)
     }
 
-    fn render_text(self, s: CowStr<'a>, range: Range<usize>) -> Self::View {
-        let callback = self.make_md_handler(range, false);
+    /// renders the text `s`, found at `range` in the source,
+    /// whose clicks are reported as clicks on `element`
+    fn render_text(
+        self,
+        s: CowStr<'a>,
+        range: Range<usize>,
+        element: ClickedElement,
+    ) -> Self::View {
+        let callback = self.make_md_handler(range.clone(), element, false);
         let attributes = ElementAttributes {
             on_click: Some(callback),
+            on_mouseenter: self.make_md_hover_handler(range.clone(), true),
+            on_mouseleave: self.make_md_hover_handler(range, false),
             ..Default::default()
         };
         self.el_with_attributes(HtmlElement::Span, self.el_text(s), attributes)
@@ -183,19 +814,191 @@ where
 
     fn has_custom_links(self) -> bool;
 
-    fn render_link(self, link: LinkDescription<Self::View>) -> Result<Self::View, String> {
+    /// computes the anchor id of a heading from its text.
+    /// If two headings end up with the same id,
+    /// a numeric suffix is appended to the next ones.
+    fn heading_slug(self, text: &str) -> String {
+        slugify(text)
+    }
+
+    /// finds the page that the wikilink `[[target]]` points to.
+    /// Returns None if there is no such page.
+    /// It is called once per target and render, even if the target is linked to several times
+    fn resolve_wikilink(self, target: &str) -> Option<WikilinkResolution> {
+        Some(WikilinkResolution::identity(target))
+    }
+
+    /// finds the url of the user mentioned by `@name`, when mentions are enabled
+    /// with [`MarkdownProps::mentions`].
+    /// Returns None if the user doesn't exist,
+    /// in which case the mention gets the `md-mention-broken` class instead of a link
+    fn resolve_mention(self, _name: &str) -> Option<String> {
+        None
+    }
+
+    /// finds the url of the topic of the hashtag `#tag`, when hashtags are enabled
+    /// with [`MarkdownProps::mentions`].
+    /// Returns None if the topic doesn't exist,
+    /// in which case the hashtag gets the `md-hashtag-broken` class instead of a link
+    fn resolve_hashtag(self, _tag: &str) -> Option<String> {
+        None
+    }
+
+    fn render_link(self, mut link: LinkDescription<Self::View>) -> Result<Self::View, String> {
+        let wikilink = match link.link_type {
+            LinkType::WikiLink { .. } if !link.image => Some(link.wikilink.clone()),
+            _ => None,
+        };
+        if let Some(Some(resolution)) = &wikilink {
+            link.url = resolution.url.clone();
+        }
+        if let Some(base) = self.props().base_url {
+            link.url = links::resolve_url(base, &link.url);
+        }
+
+        let allowed_schemes = self.props().allowed_url_schemes;
+        link.url = links::sanitize_url(
+            link.url,
+            allowed_schemes.unwrap_or(DEFAULT_URL_SCHEMES),
+            link.image,
+        );
+
         if self.has_custom_links() {
             self.render_links(link)
+        } else if link.image {
+            Ok(self.el_img(link.url, link.alt, images::image_attributes(&link.title)))
+        } else {
+            let props = self.props();
+            let mut attributes = links::link_attributes(props.link_target, &link.url);
+            links::rel_attributes(&mut attributes, props.link_rel_policy, &link.url);
+            if let Some(resolution) = wikilink {
+                links::wikilink_attributes(&mut attributes, resolution);
+            }
+            for class in &mut attributes.classes {
+                *class = props.class_name(class)
+            }
+            let on_click = self.make_link_click_handler(link.url.clone(), link.range);
+            Ok(self.el_a(link.content, link.url, attributes, on_click))
+        }
+    }
+
+    /// renders the image `image`, found at `range` in the markdown source.
+    /// It is the image number `index` of the document, starting from 0
+    fn render_image(
+        self,
+        image: LinkDescription<Self::View>,
+        index: usize,
+        range: Range<usize>,
+    ) -> Result<Self::View, String> {
+        if self.has_custom_links() {
+            return self.render_link(image);
+        }
+        let props = self.props();
+        let url = match props.base_url {
+            Some(base) => links::resolve_url(base, &image.url),
+            None => image.url,
+        };
+        let url = links::sanitize_url(
+            url,
+            props.allowed_url_schemes.unwrap_or(DEFAULT_URL_SCHEMES),
+            true,
+        );
+        let mut attributes = images::image_attributes(&image.title);
+        let loading = if index < props.eager_image_count {
+            ImageLoading::Eager
         } else {
-            Ok(if link.image {
-                self.el_img(link.url, link.title)
-            } else {
-                self.el_a(link.content, link.url)
-            })
+            props.image_loading
+        };
+        images::set_loading(&mut attributes, loading);
+        attributes.placeholder = props.broken_image_placeholder.map(str::to_string);
+        attributes.range = range;
+        if props.decorative_empty_alt {
+            images::set_decorative(&mut attributes, &image.alt);
         }
+        Ok(self.el_img(url, image.alt, attributes))
     }
 }
 
+/// the event reported when the mouse enters or leaves a rendered markdown element
+#[derive(Clone, Debug)]
+pub struct MarkdownHoverEvent<E> {
+    /// the original mouse event
+    pub event: E,
+
+    /// the position in the markdown source of the hovered element
+    pub position: Range<usize>,
+
+    /// true if the mouse entered the element, false if it left it
+    pub entering: bool,
+}
+
+/// the event reported when the user clicks on a rendered markdown element
+#[derive(Clone, Debug)]
+pub struct MarkdownClickEvent<E> {
+    /// the original event triggered when the element was clicked on
+    pub event: E,
+
+    /// the corresponding range in the markdown source, as a slice of [`u8`][u8]
+    pub position: Range<usize>,
+
+    /// the kind of element that was clicked on
+    pub element: ClickedElement,
+}
+
+/// the kind of element that the user clicked on, see [`MarkdownClickEvent`].
+/// The text inside a link, a heading or a table cell is reported as that element
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum ClickedElement {
+    /// text, for example of a paragraph or a list item
+    #[default]
+    Text,
+    /// inline code
+    Code,
+    /// a code block, with its language if it has one
+    CodeBlock { language: Option<String> },
+    /// the text of a link, with its url as written in the source
+    Link { url: String },
+    /// the text of a heading, with its level as rendered, like 2 for `<h2>`
+    Heading { level: u8 },
+    /// the text of a table cell
+    TableCell { header: bool },
+    /// a horizontal rule
+    Rule,
+    /// the checkbox of a task list
+    TaskItem,
+    /// inline or display maths
+    Math,
+}
+
+/// the event reported when the user clicks on a link.
+/// The default navigation can be prevented with the original event,
+/// to route the link inside the application instead
+#[derive(Clone, Debug)]
+pub struct LinkClickEvent<E> {
+    /// the url of the link, as it is rendered in the `href` attribute
+    pub url: String,
+
+    /// the original mouse event
+    pub mouse_event: E,
+
+    /// the position of the link in the markdown source
+    pub range: Range<usize>,
+}
+
+/// the event reported when the user clicks on inline code, like `` `x` ``.
+/// Code blocks are not concerned
+#[derive(Clone, Debug)]
+pub struct CodeClickEvent<E> {
+    /// the text of the code, without the backticks
+    pub code: String,
+
+    /// the original mouse event
+    pub mouse_event: E,
+
+    /// the position of the code in the markdown source, backticks included
+    pub range: Range<usize>,
+}
+
 /// the description of a link, used to render it with a custom callback.
 /// See [pulldown_cmark::Tag::Link] for documentation
 pub struct LinkDescription<V> {
@@ -212,8 +1015,169 @@ pub struct LinkDescription<V> {
     /// the type of link
     pub link_type: LinkType,
 
+    /// the label of the definition that the link points to,
+    /// for reference links like `[text][label]`, `[label][]` or `[label]`.
+    /// It is None for inline links like `[text](url)` and autolinks
+    pub reference: Option<String>,
+
     /// wether the link is an image
     pub image: bool,
+
+    /// the description of an image, as plain text.
+    /// It is empty for links
+    pub alt: String,
+
+    /// the page that a wikilink points to, given by [`Context::resolve_wikilink`].
+    /// It is None for the other links, and for the wikilinks that can't be resolved
+    pub wikilink: Option<WikilinkResolution>,
+
+    /// the position of the link in the markdown source
+    pub range: Range<usize>,
+}
+
+/// The markdown extensions to enable, one by one.
+/// Unlike [`Options`], the default value enables the common extensions,
+/// so that one of them can be toggled without respecifying the others:
+/// ```
+/// # use web_framework_markdown::MarkdownOptions;
+/// let options = MarkdownOptions {
+///     smart_punctuation: true,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MarkdownOptions {
+    /// convert quotes to curly quotes, `--` to en dashes, ... (off by default)
+    pub smart_punctuation: bool,
+    /// github tables
+    pub tables: bool,
+    /// `~~strikethrough~~`
+    pub strikethrough: bool,
+    /// footnotes, like `[^1]`
+    pub footnotes: bool,
+    /// task lists, like `- [ ] task`
+    pub tasklists: bool,
+    /// `$inline$` and `$$display$$` maths
+    pub math: bool,
+    /// custom ids and classes for headings, like `# title {#id .class}` (off by default)
+    pub heading_attributes: bool,
+    /// `[[wikilinks]]`
+    pub wikilinks: bool,
+    /// abbreviations defined by paragraphs like `*[HTML]: HyperText Markup Language`,
+    /// whose occurrences are rendered as `<abbr>` (off by default)
+    pub abbreviations: bool,
+    /// container directives, like `:::details Title` closed by a `:::` line
+    pub directives: bool,
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        Self {
+            smart_punctuation: false,
+            tables: true,
+            strikethrough: true,
+            footnotes: true,
+            tasklists: true,
+            math: true,
+            heading_attributes: false,
+            wikilinks: true,
+            abbreviations: false,
+            directives: true,
+        }
+    }
+}
+
+impl MarkdownOptions {
+    /// the corresponding options of the parser.
+    /// Github alerts, as well as yaml and toml frontmatter, are always enabled
+    pub fn parse_options(&self) -> Options {
+        let flags = [
+            (self.smart_punctuation, Options::ENABLE_SMART_PUNCTUATION),
+            (self.tables, Options::ENABLE_TABLES),
+            (self.strikethrough, Options::ENABLE_STRIKETHROUGH),
+            (self.footnotes, Options::ENABLE_FOOTNOTES),
+            (self.tasklists, Options::ENABLE_TASKLISTS),
+            (self.math, Options::ENABLE_MATH),
+            (self.heading_attributes, Options::ENABLE_HEADING_ATTRIBUTES),
+            (self.wikilinks, Options::ENABLE_WIKILINKS),
+        ];
+        flags.into_iter().filter(|(enabled, _)| *enabled).fold(
+            Options::ENABLE_GFM
+                | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+                | Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS,
+            |options, (_, flag)| options | flag,
+        )
+    }
+}
+
+/// the description of a container directive like `:::name{key=value} ... :::`,
+/// used to render it with a custom callback,
+/// for example to create admonitions or embeds
+#[derive(Clone, Debug, PartialEq)]
+pub struct DirectiveDescription<V> {
+    /// the name of the directive, ie the word after `:::`
+    pub name: String,
+
+    /// the attributes of the directive, like `{#id .class key="value"}`,
+    /// in order. `#id` is the attribute `id`, and every `.class` is an attribute `class`
+    pub attributes: Vec<(String, String)>,
+
+    /// the rendered markdown inside the directive
+    pub children: V,
+
+    /// the position of the directive in the markdown source,
+    /// from its opening line to its closing `:::`
+    pub range: Range<usize>,
+}
+
+/// the description of a blockquote, used to render it with a custom callback,
+/// for example to style quotes with their author
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockquoteDescription<V> {
+    /// the kind of callout of the blockquote, like [`BlockQuoteKind::Note`] for `> [!NOTE]`,
+    /// or None for a plain blockquote
+    pub kind: Option<BlockQuoteKind>,
+
+    /// the rendered markdown inside the blockquote,
+    /// without the `[!NOTE]` marker of a callout
+    pub children: V,
+
+    /// the position of the blockquote in the markdown source
+    pub range: Range<usize>,
+}
+
+/// the description of a code block, used to render it with a custom callback,
+/// for example to draw diagrams
+#[derive(Clone, Debug, PartialEq)]
+pub struct CodeBlockDescription {
+    /// the language of the code block,
+    /// ie the first word after the opening fence
+    pub language: Option<String>,
+
+    /// the title of the code block, usually its file name,
+    /// set by the `title` or `filename` key of the info string,
+    /// like ` ```rust title="main.rs" `
+    pub title: Option<String>,
+
+    /// the text content of the code block
+    pub content: String,
+
+    /// the position of the code block in the markdown source
+    pub range: Range<usize>,
+}
+
+/// the description of maths, used to render them with a custom callback,
+/// for example to produce MathML
+#[derive(Clone, Debug, PartialEq)]
+pub struct MathDescription {
+    /// the latex source of the maths
+    pub content: String,
+
+    /// true for display maths (`$$...$$`), false for inline maths (`$...$`)
+    pub display_mode: bool,
+
+    /// the position of the maths in the markdown source
+    pub range: Range<usize>,
 }
 
 pub enum HtmlError {
@@ -243,7 +1207,8 @@ impl ToString for HtmlError {
 #[derive(PartialEq)]
 /// the arguments given to a markdown component.
 /// `attributes`: a map of (attribute_name, attribute_value) pairs
-/// `children`: the interior markdown of the component
+/// `children`: the interior markdown of the component, rendered
+/// `source`: the interior markdown of the component, as written in the source
 ///
 /// For example,
 /// ```md
@@ -259,18 +1224,49 @@ impl ToString for HtmlError {
 /// MdComponentProps {
 ///     attributes: BTreeMap::from([("color", "blue"), ("size", "5")]),
 ///     children: ... // html view of **hey**
+///     source: "\n**hey !**\n\n",
 /// }
 /// ```
 pub struct MdComponentProps<V> {
     pub attributes: BTreeMap<String, String>,
     pub children: V,
+    /// the raw markdown between the opening and the closing tag,
+    /// for example to split it and render it differently.
+    /// It is empty for self-closing components
+    pub source: String,
 }
 
 impl<V> MdComponentProps<V> {
     /// returns the attribute string corresponding to the key `name`.
     /// returns None if the attribute was not provided
-    pub fn get(&self, name: &str) -> Option<String> {
-        self.attributes.get(name).cloned()
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+
+    /// returns the attribute corresponding to the key `name` as a boolean.
+    /// `true`, `yes` and `1` are true, `false`, `no` and `0` are false,
+    /// regardless of the case.
+    /// Returns None if the attribute was not provided or is not a boolean
+    pub fn get_bool(&self, name: &str) -> Option<bool> {
+        match self.get(name)?.trim().to_lowercase().as_str() {
+            "true" | "yes" | "1" => Some(true),
+            "false" | "no" | "0" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// returns the attribute corresponding to the key `name` as a number,
+    /// like `i32` or `f64`.
+    /// Returns None if the attribute was not provided or can't be parsed
+    pub fn get_number<T: std::str::FromStr>(&self, name: &str) -> Option<T> {
+        self.get(name)?.trim().parse().ok()
+    }
+
+    /// returns the attribute corresponding to the key `name`, deserialized from json.
+    /// Returns None if the attribute was not provided or is not valid json for `T`
+    #[cfg(feature = "serde")]
+    pub fn get_json<T: serde::de::DeserializeOwned>(&self, name: &str) -> Option<T> {
+        serde_json::from_str(self.get(name)?).ok()
     }
 
     /// returns the attribute corresponding to the key `name`, once parsed.
@@ -314,47 +1310,1401 @@ impl<T: std::fmt::Debug> From<T> for ComponentCreationError {
     }
 }
 
-pub struct MarkdownProps {
-    pub hard_line_breaks: bool,
+/// How the line breaks inside a paragraph are rendered
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SoftBreakMode {
+    /// as a space, like a browser would render a newline in html
+    #[default]
+    Space,
+    /// as a newline character,
+    /// which is only visible with a css rule like `white-space: pre-line`
+    Newline,
+    /// as a `<br>`, so that every line break of the source is visible
+    HardBreak,
+}
 
-    pub wikilinks: bool,
+/// How the items of ordered lists are numbered
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ListStyle {
+    /// `1.`, `2.`, `3.`
+    #[default]
+    Decimal,
+    /// `a.`, `b.`, `c.`
+    LowerAlpha,
+    /// `A.`, `B.`, `C.`
+    UpperAlpha,
+    /// `i.`, `ii.`, `iii.`
+    LowerRoman,
+    /// `I.`, `II.`, `III.`
+    UpperRoman,
+}
 
-    pub parse_options: Option<pulldown_cmark::Options>,
+impl ListStyle {
+    /// the value of the `type` attribute of an ordered list with this style
+    pub fn type_attribute(self) -> &'static str {
+        match self {
+            ListStyle::Decimal => "1",
+            ListStyle::LowerAlpha => "a",
+            ListStyle::UpperAlpha => "A",
+            ListStyle::LowerRoman => "i",
+            ListStyle::UpperRoman => "I",
+        }
+    }
+}
 
-    pub theme: Option<&'static str>,
+/// The direction of the text, set with the `dir` attribute
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// left to right, like english
+    Ltr,
+    /// right to left, like arabic or hebrew
+    Rtl,
+    /// found by the browser from the first strong character of the text
+    Auto,
 }
 
-pub fn markdown_component<'a, 'callback, F: Context<'a, 'callback>>(
-    cx: F,
-    source: &'a str,
-) -> F::View {
-    let parse_options_default = Options::ENABLE_GFM
-        | Options::ENABLE_MATH
-        | Options::ENABLE_TABLES
-        | Options::ENABLE_TASKLISTS
-        | Options::ENABLE_WIKILINKS
-        | Options::ENABLE_STRIKETHROUGH
-        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS;
-    let options = cx.props().parse_options.unwrap_or(parse_options_default);
+impl Direction {
+    /// the value of the `dir` attribute
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Direction::Ltr => "ltr",
+            Direction::Rtl => "rtl",
+            Direction::Auto => "auto",
+        }
+    }
+}
+
+#[derive(Clone, Default, PartialEq)]
+pub struct MarkdownProps {
+    /// renders every line break as a `<br>`,
+    /// regardless of [`MarkdownProps::soft_break`]
+    pub hard_line_breaks: bool,
+
+    /// how line breaks inside paragraphs are rendered
+    pub soft_break: SoftBreakMode,
+
+    pub wikilinks: bool,
+
+    /// the options passed to the parser.
+    /// If set, they replace [`MarkdownProps::options`] entirely
+    pub parse_options: Option<pulldown_cmark::Options>,
+
+    /// the markdown extensions to enable
+    pub options: MarkdownOptions,
+
+    pub theme: Option<&'static str>,
+
+    /// how code blocks are highlighted.
+    /// If set, it replaces [`MarkdownProps::theme`]
+    pub syntax_highlight: Option<SyntectConfig>,
+
+    /// where links are opened when clicked
+    pub link_target: LinkTarget,
+
+    /// the `rel` keywords of the links to other websites, like `nofollow ugc`.
+    /// They come before the `noopener noreferrer` of the links opened in a new tab
+    pub link_rel_policy: LinkRelPolicy,
+
+    /// how images are loaded
+    pub image_loading: ImageLoading,
+
+    /// the number of images at the start of the document
+    /// that are always loaded eagerly,
+    /// so that they are displayed as soon as possible
+    pub eager_image_count: usize,
+
+    /// gives `role="presentation"` to the images with an empty alternative text,
+    /// like `![](divider.png)`, so that screen readers treat them as decorative.
+    /// Their `alt` attribute is still set, to an empty string
+    pub decorative_empty_alt: bool,
+
+    /// the url of the image displayed instead of images that fail to load
+    pub broken_image_placeholder: Option<&'static str>,
+
+    /// the prefix of the paragraphs that are used as the caption of a table,
+    /// when they are right before or right after it, like `Table:` in pandoc.
+    /// A caption between two tables belongs to the second one,
+    /// and a paragraph with nothing after the prefix is not a caption.
+    /// If None, tables have no caption
+    pub table_caption_prefix: Option<&'static str>,
+
+    /// wether the raw html tables, like `<table><tr><td>a</td></tr></table>`,
+    /// are rendered like markdown tables, with the same elements and alignment.
+    /// The text of their cells is not parsed as markdown,
+    /// and the other tags inside them are rendered according to [`MarkdownProps::html_policy`].
+    /// The tables with merged cells are left as raw html.
+    /// It has no effect when the policy doesn't allow raw html tables,
+    /// like [`HtmlPolicy::Strip`] or [`HtmlPolicy::Escape`]
+    pub normalize_html_tables: bool,
+
+    /// wether code blocks are wrapped in a `<div class="md-codeblock">`
+    /// with a `<button class="md-copy">` that copies their content to the clipboard.
+    /// The content is also in the `data-code` attribute of the wrapper
+    pub code_block_copy_button: bool,
+
+    /// the names of the languages of code blocks, by alias, like `javascript` for `js`.
+    /// The language is replaced by its name before highlighting it,
+    /// and in the `language-*` class. The other languages are unchanged.
+    /// They are added to [`DEFAULT_LANGUAGE_ALIASES`], and take precedence over them
+    pub language_aliases: Option<&'static HashMap<&'static str, &'static str>>,
+
+    /// wether the lines of code blocks are numbered, in a gutter.
+    /// Independently of this option, the lines selected in the info string
+    /// of a code block, like `rust {1,3-5}`, get the `md-line-highlight` class
+    pub code_block_line_numbers: bool,
+
+    /// removes the indentation that all the lines of a code block have in common,
+    /// like when it is copied from an indented context
+    pub code_block_dedent: bool,
+
+    /// removes the blank lines at the end of code blocks
+    pub code_block_trim: bool,
+
+    /// what to do with the tags that look like custom components,
+    /// like `<MyComponent/>`, but that are not registered
+    pub unknown_components: UnknownComponentPolicy,
+
+    /// how the raw html of the source is rendered.
+    /// Use [`HtmlPolicy::Sanitize`] or [`HtmlPolicy::Strip`] for untrusted content
+    pub html_policy: HtmlPolicy,
+
+    /// the number of levels that every heading is shifted down by, up to `<h6>`.
+    /// For example, with an offset of 1, `# Title` is rendered as a `<h2>`.
+    /// The levels of the [`Heading`]s of the table of content are not shifted
+    pub heading_offset: u8,
+
+    /// adds a `#` link to the anchor of every heading, with the `md-heading-anchor` class.
+    /// Clicking it copies the permalink of the heading to the clipboard
+    pub heading_anchor_links: bool,
+
+    /// prepends hierarchical numbers to the headings, like `1`, `1.1`, `1.2`, `1.2.1`,
+    /// in a `<span>` with the `md-heading-number` class.
+    /// The numbers follow the levels of the headings once [`MarkdownProps::heading_offset`]
+    /// is applied, starting at the shallowest heading of the document
+    pub heading_numbering: bool,
+
+    /// the lowest level of the headings reported to [`Context::set_headings`],
+    /// like 2 to leave the `<h1>` out of the table of content.
+    /// It doesn't change how the headings are rendered
+    pub toc_min_level: u8,
+
+    /// the highest level of the headings reported to [`Context::set_headings`],
+    /// like 3 to leave the `<h4>` to `<h6>` out of the table of content.
+    /// If 0, there is no limit
+    pub toc_max_level: u8,
+
+    /// reads a block like `{.lead #intro data-level=2}` at the end of a paragraph
+    /// as the classes, id and attributes of the paragraph, and removes it from the text.
+    /// On its own line at the end of a blockquote, like `> {#note}`,
+    /// the block applies to the blockquote
+    pub inline_attributes: bool,
+
+    /// sets `data-source-start` and `data-source-end` on the paragraphs, headings,
+    /// blockquotes, lists and tables at the top level of the document,
+    /// to the byte offsets of the block in the source, end excluded.
+    /// For example, an editor can use them to synchronize its scrolling with the rendered view,
+    /// and [`line_number`] converts them to line numbers
+    pub source_positions: bool,
+
+    /// how the items of ordered lists are numbered.
+    /// If None, the browser default is used, which is usually [`ListStyle::Decimal`]
+    pub ordered_list_style: Option<ListStyle>,
+
+    /// wether lists get the `md-list-tight` class, or `md-list-loose`
+    /// if their items are separated by blank lines,
+    /// in which case the content of their items is wrapped in paragraphs
+    pub list_tightness_classes: bool,
+
+    /// the number of elements that can be nested in each other,
+    /// like blockquotes, lists, directives or custom components.
+    /// Deeper elements are replaced by an empty `<div class="md-depth-limit">`,
+    /// or a `<span>` inside a paragraph,
+    /// so that a malicious document can't overflow the stack.
+    /// If None, [`DEFAULT_MAX_NESTING_DEPTH`] is used
+    pub max_nesting_depth: Option<usize>,
+
+    /// the number of elements, including the text, that are rendered at most.
+    /// The rest of a longer document is replaced by an empty `<div class="md-truncated">`,
+    /// so that a malicious document can't freeze the page.
+    /// If None, there is no limit
+    pub max_elements: Option<usize>,
+
+    /// the number of images that are rendered at most.
+    /// The rest of the document after the last one is replaced
+    /// by an empty `<div class="md-truncated">`.
+    /// If None, there is no limit
+    pub max_image_count: Option<usize>,
+
+    /// the direction of the text of the document.
+    /// If it or [`MarkdownProps::lang`] is set, the document is wrapped in a `<div>`,
+    /// or in [`MarkdownProps::root_wrapper`], with the corresponding attributes
+    pub dir: Option<Direction>,
+
+    /// the language of the document, like `ar` or `en-US`
+    pub lang: Option<&'static str>,
+
+    /// the element around the whole document, like a `<div>` with a class and an id.
+    /// If None, the document is a fragment,
+    /// unless [`MarkdownProps::dir`] or [`MarkdownProps::lang`] is set
+    pub root_wrapper: Option<RootWrapper>,
+
+    /// wether the paragraphs, headings and list items get `dir="auto"`,
+    /// so that each of them is displayed in the direction of its own text.
+    /// Useful for documents that mix left-to-right and right-to-left languages
+    pub auto_block_direction: bool,
+
+    /// abbreviations and their expansion, like `("HTML", "HyperText Markup Language")`,
+    /// rendered as `<abbr>` wherever they appear in the text.
+    /// The abbreviations defined in the document take precedence
+    pub abbreviations: Option<&'static HashMap<&'static str, &'static str>>,
+
+    /// renders the text between `==`, like `==this==`, as highlighted, in a `<mark>`.
+    /// Inline code and code blocks are left unchanged
+    pub highlight_marks: bool,
+
+    /// renders the keys between `++`, like `++Ctrl+C++`, in a `<kbd>`.
+    /// Inline code and code blocks are left unchanged
+    pub kbd: bool,
+
+    /// the text shown when the document has no content,
+    /// so that it still takes some space on the page.
+    /// It is rendered in a `<div class="md-empty">` if the source is empty,
+    /// or in a `<div class="md-blank">` if it only contains whitespace or html comments
+    pub empty_placeholder: Option<&'static str>,
+
+    /// if the document is a single paragraph, like a one-line caption,
+    /// its content is rendered without the `<p>` around it,
+    /// so that it can be embedded inline.
+    /// Documents with several blocks are unchanged
+    pub unwrap_single_paragraph: bool,
+
+    /// renders the checkboxes of task lists disabled and without click handler,
+    /// for published content where they can't be toggled
+    pub read_only_task_lists: bool,
+
+    /// the extra states of the tasks of task lists, by the character between the brackets,
+    /// like `'/'` for `[/]`. These tasks are rendered as indeterminate checkboxes,
+    /// with the class of their state. Clicking on them checks them.
+    /// Other markers are left as text
+    pub task_list_states: Option<&'static HashMap<char, TaskState>>,
+
+    /// with the `frontmatter` feature, a document can set some rendering options
+    /// in its frontmatter, listed in [`FrontmatterOptions`].
+    /// By default, they only apply where the corresponding prop keeps its default value.
+    /// With `frontmatter_overrides`, they take precedence over the props
+    pub frontmatter_overrides: bool,
+
+    /// scrolls the heading that the fragment of the url points to into view,
+    /// like the heading with the id `usage` for `page#usage`,
+    /// every time the document is rendered.
+    /// Nothing happens if no heading has this id
+    pub scroll_to_fragment: bool,
+
+    /// the names of the html comments that are directives, like `more` for `<!-- more -->`.
+    /// They are not rendered, whatever the html policy,
+    /// and are reported by [`Context::set_comment_directives`]
+    pub comment_directive_names: Option<&'static [&'static str]>,
+
+    /// extra css classes of the horizontal rules, separated by spaces, like `my-rule`.
+    /// They can give the `<hr>` some padding, to make it easier to click on
+    pub rule_class: Option<&'static str>,
+
+    /// renders consecutive horizontal rules, like `---` lines separated by blank lines,
+    /// as a single `<hr>`
+    pub collapse_rules: bool,
+
+    /// gives the footnote references, like `[^1]`, the text of their definition
+    /// as a `title`, that the browser shows as a tooltip when hovering them
+    pub footnote_popovers: bool,
+
+    /// renders the images that are alone in their paragraph and have a title,
+    /// like `![a cat](cat.png "My cat")`, as a `<figure>` captioned by the title.
+    /// Images inside text are unchanged
+    pub figure_images: bool,
+
+    /// a prefix added to all the css classes of the elements created by this crate,
+    /// like `language-rust` or `md-callout`, to avoid collisions with the styles of the page.
+    /// For example, with `Some("prose-")`, code blocks get the `prose-language-rust` class.
+    /// The classes written in the markdown source and by custom components are unchanged
+    pub class_prefix: Option<&'static str>,
+
+    /// the url that relative links and images are resolved against,
+    /// usually the url of the markdown document.
+    /// If None, relative urls are left unchanged
+    pub base_url: Option<&'static str>,
+
+    /// the stylesheet used to display maths.
+    /// If None, no stylesheet is loaded
+    pub math: Option<MathConfig>,
+
+    /// the mermaid script, that draws the diagrams of the ` ```mermaid ` code blocks.
+    /// The diagrams are always rendered as `<div class="mermaid">`,
+    /// but the script is only loaded if this is set
+    pub mermaid: Option<MermaidConfig>,
+
+    /// how the word count and reading time of the document are computed
+    pub stats_config: StatsConfig,
+
+    /// replaces github shortcodes like `:tada:` by the corresponding emojis.
+    /// It only has an effect when the `emoji` feature is enabled
+    pub emoji: bool,
+
+    /// turns the bare urls like `https://example.com` or `www.example.com`,
+    /// and the email addresses of the text into links,
+    /// like the autolink literals of github flavored markdown
+    pub autolink: bool,
+
+    /// turns the mentions like `@alice` and the hashtags like `#topic` of the text into links,
+    /// with the classes `md-mention` and `md-hashtag`.
+    /// Their url is given by [`Context::resolve_mention`] and [`Context::resolve_hashtag`].
+    /// Text inside links and code is never rewritten
+    pub mentions: Option<MentionConfig>,
+
+    /// the url schemes allowed in links and images.
+    /// Links with another scheme are replaced by [`BLOCKED_URL`].
+    /// If None, [`DEFAULT_URL_SCHEMES`] is used.
+    pub allowed_url_schemes: Option<&'static [&'static str]>,
+
+    /// typographic replacements applied to the text of the document, in order,
+    /// like german quotes. Code is never rewritten
+    pub typography: &'static [TypographyPreset],
+
+    /// a function that rewrites the text of the document,
+    /// after [`MarkdownProps::typography`], see [`TextTransform`]
+    pub text_transform: Option<TextTransform>,
+
+    /// a function that rewrites the parsed events before they are rendered,
+    /// see [`StreamTransform`]
+    pub transform_stream: Option<StreamTransform>,
+}
+
+/// A function that rewrites the events of a document before they are rendered,
+/// for example to replace `:emoji:` shortcodes or to linkify `@mentions`.
+/// Each event comes with its position in the markdown source.
+///
+/// It runs after the built-in rewrites
+/// (hard line breaks, sub/superscripts, image attributes and typography),
+/// and before the headings are collected and the document is rendered,
+/// so that added headings show up in the table of content,
+/// and added maths load the math stylesheet.
+#[derive(Clone, Copy, Debug)]
+pub struct StreamTransform(pub for<'a> fn(&mut Vec<(Event<'a>, Range<usize>)>));
+
+impl PartialEq for StreamTransform {
+    fn eq(&self, other: &Self) -> bool {
+        core::ptr::fn_addr_eq(self.0, other.0)
+    }
+}
+
+/// A function that rewrites the text of a document,
+/// for example to replace `(c)` by `©` or to use the quotes of a language.
+///
+/// It is called on every run of text, but not on code or raw html.
+/// A run of text ends at formatting, so `"*a*"` is rewritten in 3 parts.
+/// The rewritten text keeps the position of the original text in the source,
+/// so the clicks on any part of it report the position of the whole run
+#[derive(Clone, Copy, Debug)]
+pub struct TextTransform(pub fn(&str) -> String);
+
+impl PartialEq for TextTransform {
+    fn eq(&self, other: &Self) -> bool {
+        core::ptr::fn_addr_eq(self.0, other.0)
+    }
+}
+
+/// The element around the whole document, see [`MarkdownProps::root_wrapper`].
+/// It also gets the `dir` and `lang` attributes of the document
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RootWrapper {
+    /// the kind of the element, like [`HtmlElement::Div`]
+    pub element: HtmlElement,
+    /// the classes of the element, which are not prefixed
+    pub classes: &'static [&'static str],
+    /// the `id` of the element, so that it can be found in the page
+    pub id: Option<&'static str>,
+}
+
+/// The options that change how the markdown source is parsed into events,
+/// see [`parse_markdown`]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ParseConfig {
+    /// turns every line break into a hard line break
+    pub hard_line_breaks: bool,
+
+    /// merges consecutive horizontal rules into one
+    pub collapse_rules: bool,
+
+    /// enables `[[wikilinks]]`, even if they are disabled in the options
+    pub wikilinks: bool,
+
+    /// the options passed to the parser.
+    /// If set, they replace [`ParseConfig::options`] entirely
+    pub parse_options: Option<Options>,
+
+    /// the markdown extensions to enable
+    pub options: MarkdownOptions,
+
+    /// the prefix of the paragraphs that are used as the caption of a table
+    pub table_caption_prefix: Option<&'static str>,
+
+    /// turns the raw html tables into markdown tables
+    pub normalize_html_tables: bool,
+
+    /// turns the bare urls and email addresses of the text into links
+    pub autolink: bool,
+
+    /// replaces github shortcodes like `:tada:` by the corresponding emojis.
+    /// It only has an effect when the `emoji` feature is enabled
+    pub emoji: bool,
+
+    /// typographic replacements applied to the text
+    pub typography: &'static [TypographyPreset],
+
+    /// a function that rewrites the text
+    pub text_transform: Option<TextTransform>,
+
+    /// the extra states of the tasks of task lists, like `[/]`
+    pub task_list_states: Option<&'static HashMap<char, TaskState>>,
+
+    /// a function that rewrites the parsed events
+    pub transform_stream: Option<StreamTransform>,
+}
+
+impl MarkdownProps {
+    /// `class_name(name)` returns the css class `name`,
+    /// with [`MarkdownProps::class_prefix`] prepended if there is one
+    pub fn class_name(&self, name: &str) -> String {
+        match self.class_prefix {
+            Some(prefix) => format!("{prefix}{name}"),
+            None => name.to_string(),
+        }
+    }
+
+    /// the options of these props that change how the source is parsed
+    pub fn parse_config(&self) -> ParseConfig {
+        ParseConfig {
+            hard_line_breaks: self.hard_line_breaks,
+            collapse_rules: self.collapse_rules,
+            wikilinks: self.wikilinks,
+            parse_options: self.parse_options,
+            options: self.options,
+            table_caption_prefix: self.table_caption_prefix,
+            normalize_html_tables: self.normalize_html_tables && self.html_policy.allows_tables(),
+            autolink: self.autolink,
+            emoji: self.emoji,
+            typography: self.typography,
+            text_transform: self.text_transform,
+            task_list_states: self.task_list_states,
+            transform_stream: self.transform_stream,
+        }
+    }
+
+    /// the levels of the headings that are reported to [`Context::set_headings`],
+    /// see [`MarkdownProps::toc_min_level`] and [`MarkdownProps::toc_max_level`]
+    pub fn toc_levels(&self) -> RangeInclusive<u8> {
+        let max = match self.toc_max_level {
+            0 => 6,
+            level => level,
+        };
+        self.toc_min_level..=max
+    }
+}
+
+/// `parse_markdown(source, config)` parses `source` into the stream of events
+/// that [`markdown_component`] renders, each with its position in the source.
+/// It applies the same rewrites, like hard line breaks or image attributes,
+/// so that it can be used to index or debug a document without rendering it.
+/// The lines of the container directives, like `:::details Title` and `:::`,
+/// are moved out of their paragraph as [`Event::Html`] events,
+/// unless [`MarkdownOptions::directives`] is disabled.
+///
+/// With the `cache` feature, the events of the last documents parsed
+/// on the current thread are kept, see [`clear_markdown_cache`]
+pub fn parse_markdown<'a>(source: &'a str, config: &ParseConfig) -> Vec<(Event<'a>, Range<usize>)> {
+    #[cfg(feature = "cache")]
+    {
+        if let Some(stream) = cache::get(source, config) {
+            return stream;
+        }
+        let stream = parse_uncached(source, config);
+        cache::insert(source, config, &stream);
+        stream
+    }
+    #[cfg(not(feature = "cache"))]
+    parse_uncached(source, config)
+}
+
+fn parse_uncached<'a>(source: &'a str, config: &ParseConfig) -> Vec<(Event<'a>, Range<usize>)> {
+    #[cfg(feature = "cache")]
+    cache::count_parse();
+
+    let mut options = config
+        .parse_options
+        .unwrap_or_else(|| config.options.parse_options());
+    if config.wikilinks {
+        options |= Options::ENABLE_WIKILINKS
+    }
     let mut stream: Vec<_> = Parser::new_ext(source, options)
         .into_offset_iter()
         .collect();
 
+    if config.hard_line_breaks {
+        for (r, _) in &mut stream {
+            if *r == Event::SoftBreak {
+                *r = Event::HardBreak
+            }
+        }
+    }
+
+    if config.collapse_rules {
+        stream.dedup_by(|(e, _), (previous, _)| *e == Event::Rule && *previous == Event::Rule);
+    }
+
+    if options.intersects(Options::ENABLE_SUBSCRIPT | Options::ENABLE_SUPERSCRIPT) {
+        stream = scripts::split_scripts(
+            stream,
+            options.contains(Options::ENABLE_SUBSCRIPT),
+            options.contains(Options::ENABLE_SUPERSCRIPT),
+        );
+    }
+
+    stream = images::fold_attribute_blocks(stream);
+
+    if config.normalize_html_tables {
+        stream = tables::normalize_html_tables(stream);
+    }
+
+    if let Some(prefix) = config.table_caption_prefix {
+        stream = tables::fold_captions(stream, prefix);
+    }
+
+    if config.options.directives {
+        stream = directives::fold_directives(stream);
+    }
+
+    if let Some(states) = config.task_list_states {
+        if options.contains(Options::ENABLE_TASKLISTS) {
+            stream = tasklist::fold_task_states(stream, states);
+        }
+    }
+
+    if config.autolink {
+        stream = autolinks::linkify(stream);
+    }
+
+    #[cfg(feature = "emoji")]
+    if config.emoji {
+        stream = emoji::expand_shortcodes(stream);
+    }
+
+    if !config.typography.is_empty() || config.text_transform.is_some() {
+        stream = typography::transform_text(stream, config.typography, config.text_transform);
+    }
+
+    if let Some(transform) = config.transform_stream {
+        (transform.0)(&mut stream)
+    }
+
+    stream
+}
+
+pub fn markdown_component<'a, 'callback, F: Context<'a, 'callback>>(
+    cx: F,
+    source: &'a str,
+) -> F::View {
+    render_document(cx, source, None, None).0
+}
+
+/// `try_markdown_component(cx, source)` renders `source` like [`markdown_component`],
+/// but fails if the document has problems that are silently ignored otherwise,
+/// like unknown custom components, a malformed frontmatter
+/// or elements nested too deeply, for example to validate documents in CI.
+/// The callbacks of the context are called even if the rendering fails
+pub fn try_markdown_component<'a, 'callback, F: Context<'a, 'callback>>(
+    cx: F,
+    source: &'a str,
+) -> Result<F::View, RenderError> {
+    let (view, issues) = render_document(cx, source, None, None);
+    match issues.is_empty() {
+        true => Ok(view),
+        false => Err(RenderError { issues }),
+    }
+}
+
+/// `markdown_component_range(cx, source, range)` renders the part of `source`
+/// between the byte offsets of `range`, for example to preview a section while it is edited.
+/// The source is parsed entirely, so that the context of the section is known,
+/// and the top-level blocks that overlap with `range` are rendered entirely:
+/// a paragraph or a fenced code block that is only partly in the range
+/// is rendered as a whole, and a list is rendered with all its items.
+/// Footnotes whose definition is outside of the range are not rendered
+pub fn markdown_component_range<'a, 'callback, F: Context<'a, 'callback>>(
+    cx: F,
+    source: &'a str,
+    range: Range<usize>,
+) -> F::View {
+    render_document(cx, source, None, Some(range)).0
+}
+
+/// `markdown_component_incremental(cx, source, cache)` renders `source`
+/// like [`markdown_component`], but reuses the views of the top-level blocks
+/// that didn't change since the last render with the same `cache`,
+/// for example to preview a document while it is edited.
+/// The source is still parsed entirely, only the rendering is incremental.
+/// See [`CachedRender`] for the blocks that can be reused.
+///
+/// For a document of 1000 blocks, with headings, lists and highlighted code,
+/// rendering it again to a string after editing one paragraph
+/// took 2.0ms instead of 19ms for a full render, in release mode.
+/// The rest is spent parsing and preparing the whole document,
+/// which is not incremental
+pub fn markdown_component_incremental<'a, 'callback, F: Context<'a, 'callback>>(
+    cx: F,
+    source: &'a str,
+    cache: &mut CachedRender<F::View>,
+) -> F::View {
+    render_document(cx, source, Some(cache), None).0
+}
+
+/// returns true if `stream` only contains html comments, or nothing at all
+fn is_blank(stream: &[(Event<'_>, Range<usize>)]) -> bool {
+    stream.iter().all(|(e, _)| match e {
+        Event::Start(Tag::HtmlBlock) | Event::End(TagEnd::HtmlBlock) => true,
+        Event::Html(html) | Event::InlineHtml(html) => {
+            let html = html.trim();
+            html.starts_with("<!--") && html.ends_with("-->")
+        }
+        _ => false,
+    })
+}
+
+/// removes the start and the end of the paragraph if `stream` is a single paragraph
+fn unwrap_single_paragraph(stream: &mut Vec<(Event<'_>, Range<usize>)>) {
+    let single_paragraph = matches!(stream.first(), Some((Event::Start(Tag::Paragraph), _)))
+        && stream
+            .iter()
+            .position(|(e, _)| *e == Event::End(TagEnd::Paragraph))
+            == Some(stream.len() - 1);
+    if single_paragraph {
+        stream.pop();
+        stream.remove(0);
+    }
+}
+
+/// `collect_abbreviations(stream, source, props)` removes the abbreviation definitions
+/// from `stream`, and returns them with the glossary of `props`,
+/// the longest abbreviations first
+fn collect_abbreviations(
+    stream: &mut Vec<(Event<'_>, Range<usize>)>,
+    source: &str,
+    props: &MarkdownProps,
+) -> Vec<(String, String)> {
+    let mut abbreviations: Vec<_> = props
+        .abbreviations
+        .into_iter()
+        .flatten()
+        .map(|(a, e)| (a.to_string(), e.to_string()))
+        .collect();
+    if props.options.abbreviations {
+        let definitions = abbreviations::extract_definitions(stream, source);
+        abbreviations.retain(|(a, _)| !definitions.iter().any(|(d, _)| d == a));
+        abbreviations.extend(definitions);
+    }
+    abbreviations.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()).then(a.cmp(b)));
+    abbreviations
+}
+
+/// a document parsed and prepared to be rendered, see [`prepare_document`]
+struct PreparedDocument<'a, V> {
+    /// the events of the document
+    stream: Vec<(Event<'a>, Range<usize>)>,
+    /// the state of the rendering, with what is known of the document before rendering it
+    document: DocumentState<V>,
+    /// the rendering options set in the frontmatter
+    options: FrontmatterOptions,
+    /// wether the document only contains html comments, or nothing at all
+    blank: bool,
+}
+
+/// `prepare_document(cx, source, section)` parses `source`,
+/// restricted to the top-level blocks that overlap with `section` if it is set.
+/// It loads the stylesheets and scripts that the document needs,
+/// and reports its frontmatter, its stats and its links to `cx`, before any block is rendered
+fn prepare_document<'a, 'callback, F: Context<'a, 'callback>>(
+    mut cx: F,
+    source: &'a str,
+    section: Option<Range<usize>>,
+) -> PreparedDocument<'a, F::View> {
+    let mut stream = parse_markdown(source, &cx.props().parse_config());
+    let frontmatter = take_frontmatter(&mut stream);
+    let options = document_options(frontmatter.as_ref());
+    let abbreviations = collect_abbreviations(&mut stream, source, &cx.props());
+    let block_attributes = if cx.props().inline_attributes {
+        attributes::fold_block_attributes(&mut stream)
+    } else {
+        HashMap::new()
+    };
+    if let Some(section) = section {
+        stream = incremental::blocks_in_range(stream, &section);
+    }
+
     #[cfg(feature = "debug")]
     {
         let debug_info: Vec<String> = stream.iter().map(|x| format!("{:?}", x)).collect();
         cx.send_debug_info(debug_info)
     }
 
-    if cx.props().hard_line_breaks {
-        for (r, _) in &mut stream {
-            if *r == Event::SoftBreak {
-                *r = Event::HardBreak
+    if let Some(math) = cx.props().math.filter(|m| m.enabled) {
+        let has_math = stream
+            .iter()
+            .any(|(e, _)| matches!(e, Event::InlineMath(_) | Event::DisplayMath(_)));
+        let link = math.style_link();
+        if has_math && !cx.is_link_mounted(&link) {
+            cx.mount_dynamic_link(link)
+        }
+    }
+
+    if let Some(mermaid) = cx.props().mermaid {
+        let has_mermaid = stream.iter().any(|(e, _)| match e {
+            Event::Start(Tag::CodeBlock(kind)) => code_block_language(kind) == Some("mermaid"),
+            _ => false,
+        });
+        if has_mermaid {
+            cx.mount_dynamic_script(mermaid.script_src())
+        }
+    }
+
+    cx.set_stats(stats::collect_stats(&stream, cx.props().stats_config));
+    cx.set_document_links(links::collect_links(&stream));
+    cx.set_comment_directives(directives::collect_comment_directives(
+        &stream,
+        cx.props().comment_directive_names.unwrap_or_default(),
+    ));
+
+    let blank = is_blank(&stream);
+    if cx.props().unwrap_single_paragraph {
+        unwrap_single_paragraph(&mut stream);
+    }
+    let headings = headings::collect_headings(&stream, |text| cx.heading_slug(text));
+    let mut document = DocumentState::new(headings, options.soft_break(&cx.props()));
+    document.abbreviations = abbreviations;
+    document.block_attributes = block_attributes;
+    if cx.props().heading_numbering {
+        document.heading_numbers =
+            headings::number_headings(&document.headings, cx.props().heading_offset);
+    }
+    if let Some((raw, _kind)) = frontmatter {
+        #[cfg(feature = "frontmatter")]
+        match frontmatter::parse_frontmatter(&raw, _kind) {
+            Some(parsed) => cx.set_parsed_frontmatter(parsed),
+            None => document.issues.push(RenderIssue::MalformedFrontmatter),
+        }
+        cx.set_frontmatter(raw)
+    }
+    if cx.props().figure_images {
+        document.figures = images::figure_captions(&stream);
+    }
+    // the definitions are often after the references, so they are read beforehand
+    if cx.props().footnote_popovers {
+        document.footnote_texts = footnote_texts(&stream);
+    }
+    if cx.props().list_tightness_classes {
+        document.loose_lists = loose_lists(&stream);
+    }
+    if cx.props().normalize_html_tables {
+        document.cell_alignments = tables::cell_alignments(source, &stream);
+    }
+
+    PreparedDocument {
+        stream,
+        document,
+        options,
+        blank,
+    }
+}
+
+/// what is left of a document once it is rendered, see [`finish_document`]
+struct FinishedDocument {
+    /// the problems of the document, that make the strict mode fail
+    issues: Vec<RenderIssue>,
+    /// what happened while rendering
+    #[cfg(feature = "debug")]
+    debug_events: Vec<DebugEvent>,
+    /// the number of elements rendered
+    element_count: usize,
+}
+
+/// `finish_document(cx, document)` renders what comes after the blocks of `document`,
+/// once they are all rendered: the notice of a truncated document, then the footnotes.
+/// It reports the headings of the document to `cx`
+fn finish_document<'a, 'callback, F: Context<'a, 'callback>>(
+    mut cx: F,
+    mut document: DocumentState<F::View>,
+) -> (Vec<F::View>, FinishedDocument) {
+    let mut elements = Vec::new();
+    if document.truncated {
+        elements.push(cx.el_with_attributes(
+            HtmlElement::Div,
+            cx.el_empty(),
+            ElementAttributes {
+                classes: vec![cx.props().class_name("md-truncated")],
+                ..Default::default()
+            },
+        ));
+    }
+
+    let levels = cx.props().toc_levels();
+    let toc = document
+        .headings
+        .iter()
+        .filter(|h| levels.contains(&h.level));
+    cx.set_headings(toc.cloned().collect());
+    if cx.props().scroll_to_fragment {
+        let target = cx
+            .location_fragment()
+            .and_then(|fragment| headings::fragment_heading(&document.headings, &fragment));
+        if let Some(id) = target {
+            cx.scroll_into_view(id)
+        }
+    }
+    let finished = FinishedDocument {
+        issues: core::mem::take(&mut document.issues),
+        #[cfg(feature = "debug")]
+        debug_events: core::mem::take(&mut document.debug_events),
+        element_count: document.element_count,
+    };
+    elements.extend(render_footnotes(cx, document));
+    (elements, finished)
+}
+
+fn render_document<'a, 'callback, F: Context<'a, 'callback>>(
+    cx: F,
+    source: &'a str,
+    cache: Option<&mut CachedRender<F::View>>,
+    section: Option<Range<usize>>,
+) -> (F::View, Vec<RenderIssue>) {
+    #[cfg(not(target_arch = "wasm32"))]
+    let start = std::time::Instant::now();
+
+    let PreparedDocument {
+        stream,
+        mut document,
+        options,
+        blank,
+    } = prepare_document(cx, source, section);
+
+    #[cfg(all(feature = "debug", not(target_arch = "wasm32")))]
+    let parsed = std::time::Instant::now();
+
+    let mut elements = match cache {
+        Some(cache) => incremental::render_blocks(cx, source, stream, &mut document, cache),
+        None => Renderer::new(cx, source, &mut stream.into_iter(), &mut document).collect(),
+    };
+
+    let (end, finished) = finish_document(cx, document);
+    elements.extend(end);
+
+    let props = cx.props();
+    if let Some(placeholder) = props.empty_placeholder.filter(|_| blank) {
+        let class = if source.is_empty() {
+            "md-empty"
+        } else {
+            "md-blank"
+        };
+        elements = vec![cx.el_with_attributes(
+            HtmlElement::Div,
+            cx.el_text(placeholder.into()),
+            ElementAttributes {
+                classes: vec![props.class_name(class)],
+                ..Default::default()
+            },
+        )];
+    }
+    let (dir, lang) = (options.dir(&props), options.lang(&props));
+    let view = if let Some(wrapper) = props.root_wrapper {
+        cx.el_with_attributes(
+            wrapper.element,
+            cx.el_fragment(elements),
+            ElementAttributes {
+                classes: wrapper.classes.iter().map(|c| c.to_string()).collect(),
+                id: wrapper.id.map(String::from),
+                dir,
+                lang,
+                ..Default::default()
+            },
+        )
+    } else if dir.is_some() || lang.is_some() {
+        cx.el_with_attributes(
+            HtmlElement::Div,
+            cx.el_fragment(elements),
+            ElementAttributes {
+                dir,
+                lang,
+                ..Default::default()
+            },
+        )
+    } else {
+        cx.el_fragment(elements)
+    };
+
+    #[cfg(feature = "debug")]
+    {
+        let mut debug_events = finished.debug_events;
+        #[cfg(not(target_arch = "wasm32"))]
+        debug_events.extend([
+            DebugEvent::Timing {
+                phase: "parse",
+                duration: parsed - start,
+            },
+            DebugEvent::Timing {
+                phase: "render",
+                duration: parsed.elapsed(),
+            },
+        ]);
+        cx.send_debug(debug_events);
+    }
+
+    if let Some(handler) = cx.on_render_complete() {
+        #[cfg(not(target_arch = "wasm32"))]
+        let duration = Some(start.elapsed());
+        #[cfg(target_arch = "wasm32")]
+        let duration = None;
+        F::call_handler(
+            &handler,
+            RenderStats {
+                element_count: finished.element_count,
+                duration,
+            },
+        )
+    }
+
+    (view, finished.issues)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    type Handler = Rc<dyn Fn(())>;
+
+    /// the positions of the hovered elements, and wether the mouse entered them
+    type Hovered = Rc<RefCell<Vec<(Range<usize>, bool)>>>;
+
+    /// the urls and positions of the clicked links, or the text and positions
+    /// of the clicked inline code
+    type ClickedLinks = Rc<RefCell<Vec<(String, Range<usize>)>>>;
+
+    /// the positions and kinds of the elements whose clicks are reported
+    type ClickedElements = RefCell<Vec<(Range<usize>, ClickedElement)>>;
+
+    /// the positions of the markdown elements that were clicked on
+    type Clicks = Rc<RefCell<Vec<Range<usize>>>>;
+
+    /// what the rendered elements and their handlers report to [`HoverContext`]
+    #[derive(Default)]
+    struct Recorded {
+        clickable: ClickedElements,
+        clicks: Clicks,
+        hovered: Hovered,
+        clicked_links: ClickedLinks,
+        clicked_code: ClickedLinks,
+        handlers: RefCell<Vec<Handler>>,
+        completed: Rc<RefCell<Vec<RenderStats>>>,
+        /// the ids of the elements scrolled into view
+        scrolled: RefCell<Vec<String>>,
+        /// the attributes of the rendered rules
+        rules: RefCell<Vec<ElementAttributes<Handler>>>,
+    }
+
+    /// a context that keeps the hover and link handlers of the rendered elements,
+    /// to be able to trigger them
+    #[derive(Clone, Copy)]
+    struct HoverContext<'c> {
+        props: &'c MarkdownProps,
+        recorded: &'c Recorded,
+    }
+
+    impl<'c> HoverContext<'c> {
+        fn new(props: &'c MarkdownProps, recorded: &'c Recorded) -> Self {
+            Self { props, recorded }
+        }
+    }
+
+    impl<'a, 'c: 'a> Context<'a, 'static> for HoverContext<'c> {
+        type View = ();
+        type Handler<T: 'static> = Rc<dyn Fn(T)>;
+        type Event = ();
+
+        fn props(self) -> MarkdownProps {
+            self.props.clone()
+        }
+
+        fn set_frontmatter(&mut self, _frontmatter: String) {}
+
+        fn render_links(self, _link: LinkDescription<()>) -> Result<(), String> {
+            Ok(())
+        }
+
+        fn call_handler<T: 'static>(callback: &Self::Handler<T>, input: T) {
+            callback(input)
+        }
+
+        fn make_md_handler(
+            self,
+            position: Range<usize>,
+            element: ClickedElement,
+            _stop_propagation: bool,
+        ) -> Handler {
+            self.recorded
+                .clickable
+                .borrow_mut()
+                .push((position.clone(), element));
+            let clicks = self.recorded.clicks.clone();
+            Rc::new(move |_| clicks.borrow_mut().push(position.clone()))
+        }
+
+        fn make_md_hover_handler(self, position: Range<usize>, entering: bool) -> Option<Handler> {
+            let hovered = self.recorded.hovered.clone();
+            Some(Rc::new(move |_| {
+                hovered.borrow_mut().push((position.clone(), entering))
+            }))
+        }
+
+        fn make_link_click_handler(self, url: String, position: Range<usize>) -> Option<Handler> {
+            let clicked_links = self.recorded.clicked_links.clone();
+            Some(Rc::new(move |_| {
+                clicked_links
+                    .borrow_mut()
+                    .push((url.clone(), position.clone()))
+            }))
+        }
+
+        fn make_inline_code_click_handler(
+            self,
+            code: String,
+            position: Range<usize>,
+        ) -> Option<Handler> {
+            let clicked_code = self.recorded.clicked_code.clone();
+            Some(Rc::new(move |_| {
+                clicked_code
+                    .borrow_mut()
+                    .push((code.clone(), position.clone()))
+            }))
+        }
+
+        fn on_render_complete(self) -> Option<Rc<dyn Fn(RenderStats)>> {
+            let completed = self.recorded.completed.clone();
+            Some(Rc::new(move |stats| completed.borrow_mut().push(stats)))
+        }
+
+        // the tests run on the page `/docs#%C3%BCber-uns`
+        fn location_fragment(self) -> Option<String> {
+            Some("#%C3%BCber-uns".into())
+        }
+
+        fn scroll_into_view(self, id: String) {
+            self.recorded.scrolled.borrow_mut().push(id)
+        }
+
+        #[cfg(feature = "debug")]
+        fn send_debug_info(self, _info: Vec<String>) {}
+
+        fn el_with_attributes(self, e: HtmlElement, _inside: (), a: ElementAttributes<Handler>) {
+            let mut handlers = self.recorded.handlers.borrow_mut();
+            handlers.extend(a.on_mouseenter);
+            handlers.extend(a.on_mouseleave);
+            if matches!(e, HtmlElement::Code) {
+                handlers.extend(a.on_click);
             }
         }
+
+        fn el_span_with_inner_html(self, _html: String, _a: ElementAttributes<Handler>) {}
+
+        fn el_hr(self, a: ElementAttributes<Handler>) {
+            self.recorded
+                .handlers
+                .borrow_mut()
+                .extend(a.on_click.clone());
+            self.recorded.rules.borrow_mut().push(a);
+        }
+
+        fn el_br(self) {}
+
+        fn el_fragment(self, _children: Vec<()>) {}
+
+        fn el_a(self, _children: (), _href: String, _a: LinkAttributes, on_click: Option<Handler>) {
+            self.recorded.handlers.borrow_mut().extend(on_click);
+        }
+
+        fn el_img(self, _src: String, _alt: String, _attributes: ImageAttributes) {}
+
+        fn el_text(self, _text: CowStr<'a>) {}
+
+        fn el_input_checkbox(self, _c: bool, _i: bool, _a: ElementAttributes<Handler>) {}
+
+        fn has_custom_component(self, _name: &str) -> bool {
+            false
+        }
+
+        fn render_custom_component(
+            self,
+            _name: &str,
+            _input: MdComponentProps<()>,
+        ) -> Result<(), ComponentCreationError> {
+            Ok(())
+        }
+
+        fn has_custom_links(self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn hover_link() {
+        let props = MarkdownProps::default();
+        let recorded = Recorded::default();
+        markdown_component(HoverContext::new(&props, &recorded), "see [[page]]");
+
+        let handlers = recorded.handlers.take();
+        // the hover handlers of the 2 texts, and the click handler of the link
+        assert_eq!(handlers.len(), 5);
+        HoverContext::call_handler(&handlers[2], ());
+        HoverContext::call_handler(&handlers[3], ());
+        assert_eq!(
+            *recorded.hovered.borrow(),
+            vec![(6..10, true), (6..10, false)]
+        );
+    }
+
+    #[test]
+    fn link_click() {
+        let props = MarkdownProps::default();
+        let recorded = Recorded::default();
+        markdown_component(
+            HoverContext::new(&props, &recorded),
+            "see [the docs](/docs)",
+        );
+
+        let handlers = recorded.handlers.take();
+        HoverContext::call_handler(handlers.last().unwrap(), ());
+        assert_eq!(
+            *recorded.clicked_links.borrow(),
+            vec![("/docs".to_string(), 4..21)]
+        );
+    }
+
+    #[test]
+    fn inline_code_click() {
+        let props = MarkdownProps::default();
+        let recorded = Recorded::default();
+        markdown_component(
+            HoverContext::new(&props, &recorded),
+            "```\nblock\n```\n\nrun `cargo test`",
+        );
+
+        // the code block doesn't report its clicks as inline code
+        for handler in recorded.handlers.take() {
+            HoverContext::call_handler(&handler, ());
+        }
+        assert_eq!(
+            *recorded.clicked_code.borrow(),
+            vec![("cargo test".to_string(), 19..31)]
+        );
+    }
+
+    #[test]
+    fn clicked_elements() {
+        let props = MarkdownProps::default();
+        let recorded = Recorded::default();
+        markdown_component(
+            HoverContext::new(&props, &recorded),
+            "## Title\n\nsee [the docs](/docs)\n\n---",
+        );
+        assert_eq!(
+            recorded.clickable.take(),
+            vec![
+                (3..8, ClickedElement::Heading { level: 2 }),
+                (10..14, ClickedElement::Text),
+                (
+                    15..23,
+                    ClickedElement::Link {
+                        url: "/docs".into()
+                    }
+                ),
+                (33..36, ClickedElement::Rule),
+            ]
+        );
+    }
+
+    #[test]
+    fn rule_click() {
+        let props = MarkdownProps::default();
+        let recorded = Recorded::default();
+        markdown_component(HoverContext::new(&props, &recorded), "a\n\n---");
+
+        // the click handler of the rule is the last one
+        let handlers = recorded.handlers.take();
+        HoverContext::call_handler(handlers.last().unwrap(), ());
+        assert_eq!(*recorded.clicks.borrow(), vec![3..6]);
     }
 
-    let elements = Renderer::new(cx, &mut stream.into_iter()).collect::<Vec<_>>();
+    #[test]
+    fn rule_keyboard_activation() {
+        let props = MarkdownProps::default();
+        let recorded = Recorded::default();
+        markdown_component(HoverContext::new(&props, &recorded), "a\n\n---");
 
-    cx.el_fragment(elements)
+        let rules = recorded.rules.take();
+        let [rule] = &rules[..] else {
+            panic!("expected a single rule")
+        };
+        assert_eq!(rule.tabindex, Some(0));
+        assert_eq!(rule.role.as_deref(), Some("separator"));
+        assert!(!rule.is_activated_by("a"));
+        assert!(rule.is_activated_by("Enter"));
+        assert!(rule.is_activated_by(" "));
+        // the other elements are not activated by the keyboard
+        assert!(!ElementAttributes::<Handler>::default().is_activated_by("Enter"));
+    }
+
+    #[test]
+    fn render_complete() {
+        let props = MarkdownProps::default();
+        let recorded = Recorded::default();
+        markdown_component(
+            HoverContext::new(&props, &recorded),
+            "# Title\n\nsome *text*\n\n- a\n- b",
+        );
+
+        let completed = recorded.completed.borrow();
+        assert_eq!(completed.len(), 1);
+        // h1, text, p, text, em, text, ul, 2 li, 2 texts
+        assert_eq!(completed[0].element_count, 11);
+        assert!(completed[0].duration.is_some());
+    }
+
+    #[test]
+    fn scroll_to_fragment() {
+        let scrolled = |source: &str, scroll_to_fragment| {
+            let props = MarkdownProps {
+                scroll_to_fragment,
+                ..Default::default()
+            };
+            let recorded = Recorded::default();
+            markdown_component(HoverContext::new(&props, &recorded), source);
+            recorded.scrolled.take()
+        };
+        let source = "# Team\n\n## Über uns\n\n## Jobs";
+        assert_eq!(scrolled(source, true), vec!["über-uns"]);
+        assert!(scrolled(source, false).is_empty());
+        assert!(scrolled("# Team\n\n## Jobs", true).is_empty());
+    }
+
+    /// the core only uses the generic events of the context, not the dom,
+    /// and this test also runs with `cargo test --no-default-features`
+    #[test]
+    fn core_without_web() {
+        let props = MarkdownProps::default();
+        let stream = parse_markdown("# Title [a](/a)", &props.parse_config());
+        assert_eq!(links::collect_links(&stream).len(), 1);
+
+        let recorded = Recorded::default();
+        let cx = HoverContext::new(&props, &recorded);
+        let (_, issues) = render_document(cx, "# Title [a](/a)", None, None);
+        assert!(issues.is_empty());
+        assert_eq!(recorded.completed.borrow().len(), 1);
+    }
+
+    fn component_props(attributes: &[(&str, &str)]) -> MdComponentProps<()> {
+        MdComponentProps {
+            attributes: attributes
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            children: (),
+            source: String::new(),
+        }
+    }
+
+    #[test]
+    fn component_attribute() {
+        let props = component_props(&[("color", "blue")]);
+        assert_eq!(props.get("color"), Some("blue"));
+        assert_eq!(props.get("size"), None);
+    }
+
+    #[test]
+    fn component_bool_attribute() {
+        let props = component_props(&[("a", "true"), ("b", "No"), ("c", " 1 "), ("d", "maybe")]);
+        assert_eq!(props.get_bool("a"), Some(true));
+        assert_eq!(props.get_bool("b"), Some(false));
+        assert_eq!(props.get_bool("c"), Some(true));
+        assert_eq!(props.get_bool("d"), None);
+        assert_eq!(props.get_bool("e"), None);
+    }
+
+    #[test]
+    fn component_number_attribute() {
+        let props = component_props(&[("size", "5"), ("ratio", "0.5"), ("bad", "5px")]);
+        assert_eq!(props.get_number::<i32>("size"), Some(5));
+        assert_eq!(props.get_number::<f64>("ratio"), Some(0.5));
+        assert_eq!(props.get_number::<i32>("ratio"), None);
+        assert_eq!(props.get_number::<u8>("bad"), None);
+        assert_eq!(props.get_number::<u8>("missing"), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn component_json_attribute() {
+        let props = component_props(&[("list", "[1, 2, 3]"), ("bad", "[1, 2")]);
+        assert_eq!(props.get_json::<Vec<u32>>("list"), Some(vec![1, 2, 3]));
+        assert_eq!(props.get_json::<Vec<String>>("list"), None);
+        assert_eq!(props.get_json::<Vec<u32>>("bad"), None);
+        assert_eq!(props.get_json::<Vec<u32>>("missing"), None);
+    }
+
+    #[test]
+    fn parsed_stream() {
+        let config = ParseConfig {
+            hard_line_breaks: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_markdown("*a*\nb", &config),
+            vec![
+                (Event::Start(Tag::Paragraph), 0..5),
+                (Event::Start(Tag::Emphasis), 0..3),
+                (Event::Text("a".into()), 1..2),
+                (Event::End(TagEnd::Emphasis), 0..3),
+                (Event::HardBreak, 3..4),
+                (Event::Text("b".into()), 4..5),
+                (Event::End(TagEnd::Paragraph), 0..5),
+            ]
+        );
+    }
+
+    #[test]
+    fn parsed_wikilinks() {
+        let is_wikilink = |(e, _): &(Event, Range<usize>)| {
+            matches!(
+                e,
+                Event::Start(Tag::Link {
+                    link_type: LinkType::WikiLink { .. },
+                    ..
+                })
+            )
+        };
+        let config = ParseConfig {
+            parse_options: Some(Options::empty()),
+            ..Default::default()
+        };
+        assert!(!parse_markdown("[[page]]", &config).iter().any(is_wikilink));
+        let config = ParseConfig {
+            wikilinks: true,
+            ..config
+        };
+        assert!(parse_markdown("[[page]]", &config).iter().any(is_wikilink));
+    }
 }
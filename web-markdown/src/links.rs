@@ -0,0 +1,564 @@
+use core::ops::Range;
+
+use pulldown_cmark::{Event, LinkType, Tag, TagEnd};
+
+/// Where links should be opened when clicked
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LinkTarget {
+    /// open every link in the current tab
+    #[default]
+    SameTab,
+    /// open every link in a new tab, except anchors inside the document
+    NewTab,
+    /// open links to other websites in a new tab
+    NewTabExternalOnly,
+}
+
+/// the `rel` keywords of the links to other websites,
+/// for example to tell search engines not to follow the links of user-generated content
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LinkRelPolicy {
+    /// no keywords
+    #[default]
+    None,
+    /// `nofollow`
+    NoFollow,
+    /// `ugc`, for user-generated content
+    Ugc,
+    /// `nofollow ugc`
+    NoFollowUgc,
+    /// the given keywords, separated by spaces
+    Custom(&'static str),
+}
+
+impl LinkRelPolicy {
+    /// the keywords of the policy, separated by spaces
+    pub fn keywords(self) -> &'static str {
+        match self {
+            LinkRelPolicy::None => "",
+            LinkRelPolicy::NoFollow => "nofollow",
+            LinkRelPolicy::Ugc => "ugc",
+            LinkRelPolicy::NoFollowUgc => "nofollow ugc",
+            LinkRelPolicy::Custom(keywords) => keywords,
+        }
+    }
+}
+
+/// the html attributes specific to links
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LinkAttributes {
+    /// the `target` attribute, for example `_blank`
+    pub target: Option<String>,
+    /// the `rel` attribute, for example `noopener noreferrer`
+    pub rel: Option<String>,
+    /// the `title` attribute, shown when hovering the link
+    pub title: Option<String>,
+    /// the css classes of the link
+    pub classes: Vec<String>,
+}
+
+/// the page a wikilink like `[[Page]]` points to
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WikilinkResolution {
+    /// the url of the page
+    pub url: String,
+    /// wether the page exists.
+    /// Links to missing pages get the `md-wikilink-broken` class
+    pub exists: bool,
+    /// the title of the page, shown when hovering the link
+    pub title: Option<String>,
+}
+
+impl WikilinkResolution {
+    /// the resolution used when there is no resolver:
+    /// the target is used as the url, and the page is assumed to exist
+    pub fn identity(target: &str) -> Self {
+        Self {
+            url: target.to_string(),
+            exists: true,
+            title: None,
+        }
+    }
+}
+
+/// `wikilink_attributes(attributes, resolution)` adds the class and title of a wikilink
+/// to `attributes`. `resolution` is None when the target could not be resolved
+pub fn wikilink_attributes(
+    attributes: &mut LinkAttributes,
+    resolution: Option<WikilinkResolution>,
+) {
+    let exists = resolution.as_ref().is_some_and(|r| r.exists);
+    let class = if exists {
+        "md-wikilink"
+    } else {
+        "md-wikilink-broken"
+    };
+    attributes.classes.push(class.into());
+    if let Some(title) = resolution.and_then(|r| r.title) {
+        attributes.title = Some(title);
+    }
+}
+
+/// `is_external_url(url)` returns true if `url` goes to another website,
+/// ie it is an absolute http(s) url or a protocol-relative url
+pub fn is_external_url(url: &str) -> bool {
+    let url = url.trim_start();
+    if url.starts_with("//") {
+        return true;
+    }
+    match url.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https")
+        }
+        None => false,
+    }
+}
+
+/// `link_attributes(target, url)` computes the attributes of a link to `url`.
+/// Links opened in a new tab get `rel="noopener noreferrer"`
+pub fn link_attributes(target: LinkTarget, url: &str) -> LinkAttributes {
+    let new_tab = match target {
+        LinkTarget::SameTab => false,
+        LinkTarget::NewTab => !url.starts_with('#'),
+        LinkTarget::NewTabExternalOnly => is_external_url(url),
+    };
+
+    if new_tab {
+        LinkAttributes {
+            target: Some("_blank".into()),
+            rel: Some("noopener noreferrer".into()),
+            ..Default::default()
+        }
+    } else {
+        LinkAttributes::default()
+    }
+}
+
+/// `rel_attributes(attributes, policy, url)` adds the keywords of `policy`
+/// to the `rel` of `attributes` if `url` goes to another website,
+/// before the `noopener noreferrer` of the links opened in a new tab
+pub fn rel_attributes(attributes: &mut LinkAttributes, policy: LinkRelPolicy, url: &str) {
+    let keywords = policy.keywords().trim();
+    if keywords.is_empty() || !is_external_url(url) {
+        return;
+    }
+    attributes.rel = Some(match attributes.rel.take() {
+        Some(rel) => format!("{keywords} {rel}"),
+        None => keywords.to_string(),
+    });
+}
+
+/// the url used instead of a link with a dangerous scheme
+pub const BLOCKED_URL: &str = "about:blank#blocked";
+
+/// the schemes allowed by default in links and images.
+/// Relative urls are always allowed
+pub const DEFAULT_URL_SCHEMES: &[&str] = &["http", "https", "mailto", "tel", "ftp"];
+
+/// `normalize_url(url)` removes the characters that a browser ignores in a url:
+/// surrounding spaces and control characters,
+/// as well as tabs and newlines anywhere inside.
+fn normalize_url(url: &str) -> String {
+    url.trim_matches(|c: char| c.is_ascii_control() || c == ' ')
+        .chars()
+        .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+        .collect()
+}
+
+/// `url_scheme(url)` returns the lowercase scheme of a normalized `url`.
+/// Returns `Ok(None)` if the url is relative,
+/// and `Err(())` if the scheme contains unexpected characters.
+fn url_scheme(url: &str) -> Result<Option<String>, ()> {
+    let scheme = match url.split_once(':') {
+        Some((scheme, _)) if !scheme.contains(['/', '?', '#']) => scheme,
+        _ => return Ok(None),
+    };
+
+    let valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+
+    if valid {
+        Ok(Some(scheme.to_ascii_lowercase()))
+    } else {
+        Err(())
+    }
+}
+
+/// `sanitize_url(url, allowed_schemes, image)` returns `url` if its scheme is allowed,
+/// or [`BLOCKED_URL`] otherwise.
+/// `data:image/...` urls are allowed for images only.
+pub fn sanitize_url(url: String, allowed_schemes: &[&str], image: bool) -> String {
+    let normalized = normalize_url(&url);
+    let allowed = match url_scheme(&normalized) {
+        Ok(None) => true,
+        Ok(Some(scheme)) if scheme == "data" => {
+            let media_type = normalized["data:".len()..].trim_start();
+            image
+                && media_type
+                    .get(.."image/".len())
+                    .is_some_and(|x| x.eq_ignore_ascii_case("image/"))
+        }
+        Ok(Some(scheme)) => allowed_schemes
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(&scheme)),
+        Err(()) => false,
+    };
+
+    if allowed {
+        url
+    } else {
+        BLOCKED_URL.to_string()
+    }
+}
+
+/// `split_origin(url)` splits an absolute url like `https://a.b/c`
+/// into its scheme and authority `https://a.b` and its path `/c`.
+/// Relative urls have an empty origin
+fn split_origin(url: &str) -> (&str, &str) {
+    let authority_start = if url.starts_with("//") {
+        2
+    } else {
+        match url.find("://") {
+            Some(i) if matches!(url_scheme(url), Ok(Some(_))) => i + 3,
+            _ => return ("", url),
+        }
+    };
+    let path_start = url[authority_start..]
+        .find('/')
+        .map_or(url.len(), |i| authority_start + i);
+    url.split_at(path_start)
+}
+
+/// `remove_dot_segments(path)` resolves the `.` and `..` segments of `path`.
+/// `..` never goes above the root
+fn remove_dot_segments(path: &str) -> String {
+    let (root, path) = match path.strip_prefix('/') {
+        Some(path) => ("/", path),
+        None => ("", path),
+    };
+    let parts: Vec<&str> = path.split('/').collect();
+    let mut segments = Vec::with_capacity(parts.len());
+    for (i, part) in parts.iter().enumerate() {
+        let last = i + 1 == parts.len();
+        match *part {
+            "." => (),
+            ".." => {
+                segments.pop();
+            }
+            part => {
+                segments.push(part);
+                continue;
+            }
+        }
+        // `a/..` is the directory `/`, not the file ``
+        if last {
+            segments.push("");
+        }
+    }
+    format!("{root}{}", segments.join("/"))
+}
+
+/// `resolve_url(base, url)` resolves the relative `url` against the `base` url,
+/// like a browser would for a document located at `base`.
+/// The query and fragment of `url` are preserved.
+/// Absolute urls, protocol-relative urls and anchors are returned unchanged
+pub fn resolve_url(base: &str, url: &str) -> String {
+    let relative = matches!(url_scheme(url), Ok(None));
+    if url.is_empty() || url.starts_with('#') || url.starts_with("//") || !relative {
+        return url.to_string();
+    }
+
+    let base = &base[..base.find(['?', '#']).unwrap_or(base.len())];
+    let (origin, base_path) = split_origin(base);
+    let (path, suffix) = url.split_at(url.find(['?', '#']).unwrap_or(url.len()));
+
+    let path = if path.starts_with('/') {
+        path.to_string()
+    } else if path.is_empty() {
+        base_path.to_string()
+    } else {
+        let directory = base_path.rfind('/').map_or("", |i| &base_path[..=i]);
+        let directory = if directory.is_empty() && !origin.is_empty() {
+            "/"
+        } else {
+            directory
+        };
+        format!("{directory}{path}")
+    };
+    format!("{origin}{}{suffix}", remove_dot_segments(&path))
+}
+
+/// `link_reference(link_type, id)` returns the label of the definition
+/// that a reference link like `[text][label]`, `[label][]` or `[label]` points to,
+/// from the type and the id of the link given by the parser.
+/// Returns None for the other links
+pub fn link_reference(link_type: LinkType, id: &str) -> Option<String> {
+    use LinkType::*;
+    match link_type {
+        Reference | ReferenceUnknown | Collapsed | CollapsedUnknown | Shortcut
+        | ShortcutUnknown => Some(id.to_string()),
+        Inline | Autolink | Email | WikiLink { .. } => None,
+    }
+}
+
+/// A link or an image of the document,
+/// for example to check that none of them is broken
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinkInfo {
+    /// the url of the link, as written in the markdown source
+    pub url: String,
+    /// the text of the link without formatting, or the alt text of an image
+    pub text: String,
+    /// wether it is an image
+    pub is_image: bool,
+    /// the kind of link, like inline or reference
+    pub link_type: LinkType,
+    /// the position of the link in the markdown source
+    pub range: Range<usize>,
+}
+
+/// `collect_links(stream)` lists every link and image in `stream`,
+/// in the order of the document.
+/// A link containing an image comes before it
+pub fn collect_links(stream: &[(Event<'_>, Range<usize>)]) -> Vec<LinkInfo> {
+    let mut links = Vec::new();
+    let mut open = Vec::new();
+    for (event, range) in stream {
+        match event {
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                ..
+            })
+            | Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                ..
+            }) => {
+                open.push(links.len());
+                links.push(LinkInfo {
+                    url: dest_url.to_string(),
+                    text: String::new(),
+                    is_image: matches!(event, Event::Start(Tag::Image { .. })),
+                    link_type: *link_type,
+                    range: range.clone(),
+                })
+            }
+            Event::End(TagEnd::Link | TagEnd::Image) => {
+                open.pop();
+            }
+            Event::Text(s) | Event::Code(s) => {
+                for &i in &open {
+                    links[i].text.push_str(s)
+                }
+            }
+            _ => (),
+        }
+    }
+    links
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse_markdown, ParseConfig};
+    use pulldown_cmark::{Event, Tag};
+
+    fn sanitize(url: &str) -> String {
+        sanitize_url(url.into(), DEFAULT_URL_SCHEMES, false)
+    }
+
+    /// the url, type and reference of the first link of `source`
+    fn parse_link(source: &str) -> (String, LinkType, Option<String>) {
+        parse_markdown(source, &ParseConfig::default())
+            .into_iter()
+            .find_map(|(e, _)| match e {
+                Event::Start(Tag::Link {
+                    link_type,
+                    dest_url,
+                    id,
+                    ..
+                }) => Some((
+                    dest_url.to_string(),
+                    link_type,
+                    link_reference(link_type, &id),
+                )),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn reference_links() {
+        let definition = "\n\n[ref]: /url";
+        let reference = |link_type| ("/url".to_string(), link_type, Some("ref".to_string()));
+        assert_eq!(
+            parse_link(&format!("[text][ref]{definition}")),
+            reference(LinkType::Reference)
+        );
+        assert_eq!(
+            parse_link(&format!("[ref][]{definition}")),
+            reference(LinkType::Collapsed)
+        );
+        assert_eq!(
+            parse_link(&format!("[ref]{definition}")),
+            reference(LinkType::Shortcut)
+        );
+        assert_eq!(
+            parse_link("[text](/url)"),
+            ("/url".to_string(), LinkType::Inline, None)
+        );
+    }
+
+    #[test]
+    fn safe_urls() {
+        assert_eq!(sanitize("https://example.com"), "https://example.com");
+        assert_eq!(sanitize("./foo.md"), "./foo.md");
+        assert_eq!(sanitize("#x"), "#x");
+        assert_eq!(sanitize("/a:b"), "/a:b");
+        assert_eq!(sanitize("mailto:a@b.c"), "mailto:a@b.c");
+    }
+
+    #[test]
+    fn javascript_urls() {
+        assert_eq!(sanitize("javascript:alert(1)"), BLOCKED_URL);
+        assert_eq!(sanitize("JavaScript:alert(1)"), BLOCKED_URL);
+        assert_eq!(sanitize("  javascript:alert(1)"), BLOCKED_URL);
+        assert_eq!(sanitize("\x01javascript:alert(1)"), BLOCKED_URL);
+        assert_eq!(sanitize("java\tscr\nipt:alert(1)"), BLOCKED_URL);
+        assert_eq!(sanitize("java\x00script:alert(1)"), BLOCKED_URL);
+        assert_eq!(sanitize("vbscript:msgbox"), BLOCKED_URL);
+    }
+
+    #[test]
+    fn data_urls() {
+        let png = "data:image/png;base64,AAAA";
+        assert_eq!(sanitize_url(png.into(), DEFAULT_URL_SCHEMES, true), png);
+        assert_eq!(sanitize(png), BLOCKED_URL);
+        let padded = "\x01 data:image/png;base64,AAAA";
+        assert_eq!(
+            sanitize_url(padded.into(), DEFAULT_URL_SCHEMES, true),
+            padded
+        );
+        let html = "data:text/html,<script>alert(1)</script>";
+        assert_eq!(
+            sanitize_url(html.into(), DEFAULT_URL_SCHEMES, true),
+            BLOCKED_URL
+        );
+    }
+
+    #[test]
+    fn custom_schemes() {
+        assert_eq!(
+            sanitize_url("obsidian://x".into(), &["obsidian"], false),
+            "obsidian://x"
+        );
+        assert_eq!(
+            sanitize_url("https://x".into(), &["obsidian"], false),
+            BLOCKED_URL
+        );
+    }
+
+    #[test]
+    fn external_urls() {
+        assert!(is_external_url("https://example.com"));
+        assert!(is_external_url("HTTP://example.com"));
+        assert!(is_external_url("//example.com/a"));
+        assert!(!is_external_url("./foo"));
+        assert!(!is_external_url("#x"));
+        assert!(!is_external_url("mailto:a@b.c"));
+    }
+
+    #[test]
+    fn new_tab_external_only() {
+        let target = LinkTarget::NewTabExternalOnly;
+        let external = link_attributes(target, "https://example.com");
+        assert_eq!(external.target.as_deref(), Some("_blank"));
+        assert_eq!(external.rel.as_deref(), Some("noopener noreferrer"));
+        assert_eq!(link_attributes(target, "./foo"), LinkAttributes::default());
+        assert_eq!(link_attributes(target, "#x"), LinkAttributes::default());
+        assert_eq!(link_attributes(target, "mailto:a@b.c"), Default::default());
+    }
+
+    #[test]
+    fn new_tab() {
+        let target = LinkTarget::NewTab;
+        assert!(link_attributes(target, "./foo").target.is_some());
+        assert!(link_attributes(target, "mailto:a@b.c").target.is_some());
+        assert!(link_attributes(target, "#x").target.is_none());
+    }
+
+    #[test]
+    fn same_tab() {
+        let attributes = link_attributes(LinkTarget::SameTab, "https://example.com");
+        assert_eq!(attributes, LinkAttributes::default());
+    }
+
+    #[test]
+    fn rel_policy() {
+        let url = "https://example.com";
+        let mut attributes = link_attributes(LinkTarget::NewTab, url);
+        rel_attributes(&mut attributes, LinkRelPolicy::NoFollowUgc, url);
+        assert_eq!(
+            attributes.rel.as_deref(),
+            Some("nofollow ugc noopener noreferrer")
+        );
+
+        let mut attributes = link_attributes(LinkTarget::SameTab, url);
+        rel_attributes(&mut attributes, LinkRelPolicy::Custom("sponsored"), url);
+        assert_eq!(attributes.rel.as_deref(), Some("sponsored"));
+
+        let mut attributes = link_attributes(LinkTarget::NewTab, "./docs");
+        rel_attributes(&mut attributes, LinkRelPolicy::NoFollow, "./docs");
+        assert_eq!(attributes.rel.as_deref(), Some("noopener noreferrer"));
+    }
+
+    #[test]
+    fn relative_urls() {
+        let base = "https://example.com/docs/guide/intro.md";
+        assert_eq!(
+            resolve_url(base, "./x.md"),
+            "https://example.com/docs/guide/x.md"
+        );
+        assert_eq!(
+            resolve_url(base, "../img/a.png"),
+            "https://example.com/docs/img/a.png"
+        );
+        assert_eq!(
+            resolve_url(base, "x.md?a=1#part"),
+            "https://example.com/docs/guide/x.md?a=1#part"
+        );
+        assert_eq!(resolve_url(base, "../../../x"), "https://example.com/x");
+        assert_eq!(resolve_url(base, ".."), "https://example.com/docs/");
+        assert_eq!(
+            resolve_url("https://example.com", "x"),
+            "https://example.com/x"
+        );
+        assert_eq!(resolve_url("/docs/", "./x"), "/docs/x");
+        assert_eq!(resolve_url("docs/a.md", "../x"), "x");
+    }
+
+    #[test]
+    fn absolute_paths() {
+        let base = "https://example.com/docs/intro.md?v=2";
+        assert_eq!(resolve_url(base, "/x"), "https://example.com/x");
+        assert_eq!(resolve_url(base, "/a/./b/../x"), "https://example.com/a/x");
+        assert_eq!(
+            resolve_url(base, "?v=3"),
+            "https://example.com/docs/intro.md?v=3"
+        );
+        assert_eq!(resolve_url("/docs/", "/x"), "/x");
+    }
+
+    #[test]
+    fn unchanged_urls() {
+        let base = "https://example.com/docs/";
+        assert_eq!(resolve_url(base, "#x"), "#x");
+        assert_eq!(resolve_url(base, "https://x"), "https://x");
+        assert_eq!(resolve_url(base, "//cdn.com/a"), "//cdn.com/a");
+        assert_eq!(resolve_url(base, "mailto:a@b.c"), "mailto:a@b.c");
+        assert_eq!(resolve_url(base, ""), "");
+    }
+}
@@ -0,0 +1,95 @@
+use core::ops::Range;
+
+/// `closing_delimiter(text, start, delimiter)` returns the position of the `delimiter`
+/// that closes the span whose content starts at `start`
+fn closing_delimiter(text: &str, start: usize, delimiter: &str) -> Option<usize> {
+    let mut search = start;
+    while let Some(offset) = text[search..].find(delimiter) {
+        let end = search + offset;
+        if end > start && !text[..end].ends_with(char::is_whitespace) {
+            return Some(end);
+        }
+        search = end + 1;
+    }
+    None
+}
+
+/// `split_delimited(text, delimiter)` splits `text` into the parts between two `delimiter`,
+/// like `==this==` for `==`, and the parts in between.
+/// The delimiters are not part of the delimited parts.
+/// Like emphasis, the content can't start or end with whitespace,
+/// and a delimiter without a matching one is left unchanged
+pub fn split_delimited(text: &str, delimiter: &str) -> Vec<(Range<usize>, bool)> {
+    let mut parts = Vec::new();
+    let mut last = 0;
+    let mut search = 0;
+    while let Some(offset) = text[search..].find(delimiter) {
+        let start = search + offset + delimiter.len();
+        let opens =
+            text[start..].starts_with(|c: char| !c.is_whitespace() && !delimiter.starts_with(c));
+        let Some(end) = closing_delimiter(text, start, delimiter).filter(|_| opens) else {
+            search = search + offset + 1;
+            continue;
+        };
+        if last < search + offset {
+            parts.push((last..search + offset, false));
+        }
+        parts.push((start..end, true));
+        last = end + delimiter.len();
+        search = last;
+    }
+    if last < text.len() {
+        parts.push((last..text.len(), false));
+    }
+    parts
+}
+
+/// `split_marks(text)` splits `text` into the parts that are highlighted,
+/// like `==this==`, and the parts in between
+pub fn split_marks(text: &str) -> Vec<(Range<usize>, bool)> {
+    split_delimited(text, "==")
+}
+
+/// `split_keys(text)` splits `text` into the keys, like `++Ctrl+C++`,
+/// and the parts in between
+pub fn split_keys(text: &str) -> Vec<(Range<usize>, bool)> {
+    split_delimited(text, "++")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn balanced() {
+        assert_eq!(
+            split_marks("a ==b c== d ==e=="),
+            vec![(0..2, false), (4..7, true), (9..12, false), (14..15, true)]
+        );
+    }
+
+    #[test]
+    fn unbalanced() {
+        assert_eq!(split_marks("a ==b"), vec![(0..5, false)]);
+        assert_eq!(split_marks("a == b =="), vec![(0..9, false)]);
+        assert_eq!(split_marks("===="), vec![(0..4, false)]);
+        assert_eq!(
+            split_marks("x ==a== ==b"),
+            vec![(0..2, false), (4..5, true), (7..11, false)]
+        );
+    }
+
+    #[test]
+    fn keys() {
+        assert_eq!(
+            split_keys("press ++Ctrl+C++ or ++Ctrl++"),
+            vec![
+                (0..6, false),
+                (8..14, true),
+                (16..20, false),
+                (22..26, true)
+            ]
+        );
+        assert_eq!(split_keys("c++ and c++"), vec![(0..11, false)]);
+    }
+}
@@ -0,0 +1,111 @@
+use core::ops::Range;
+
+use crate::MentionConfig;
+
+/// What a token like `@alice` or `#topic` refers to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MentionKind {
+    /// a user, like `@alice`
+    Mention,
+    /// a topic, like `#topic`
+    Hashtag,
+}
+
+/// the characters that can't be right before a mention,
+/// so that `me@example.com` or `a#b` are not mentions
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '+' | '-' | '@' | '#' | '/')
+}
+
+/// the characters of the name of a mention
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-')
+}
+
+/// `split_mentions(text, config)` splits `text` into the mentions like `@alice`,
+/// the hashtags like `#topic`, and the parts in between.
+/// The trigger character is part of the mention.
+/// A mention only starts at the beginning of a word
+pub fn split_mentions(
+    text: &str,
+    config: &MentionConfig,
+) -> Vec<(Range<usize>, Option<MentionKind>)> {
+    let mut parts = Vec::new();
+    let mut last = 0;
+    let mut i = 0;
+    while let Some(c) = text[i..].chars().next() {
+        let kind = match Some(c) {
+            t if t == config.mention_trigger => Some(MentionKind::Mention),
+            t if t == config.hashtag_trigger => Some(MentionKind::Hashtag),
+            _ => None,
+        };
+        let start = i + c.len_utf8();
+        let rest = &text[start..];
+        let name = rest[..rest.find(|c: char| !is_name_char(c)).unwrap_or(rest.len())]
+            .trim_end_matches('-');
+        match kind.filter(|_| !name.is_empty() && !text[..i].ends_with(is_word_char)) {
+            Some(kind) => {
+                if last < i {
+                    parts.push((last..i, None));
+                }
+                last = start + name.len();
+                parts.push((i..last, Some(kind)));
+                i = last;
+            }
+            None => i = start,
+        }
+    }
+    if last < text.len() {
+        parts.push((last..text.len(), None));
+    }
+    parts
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn split(text: &str) -> Vec<(Range<usize>, Option<MentionKind>)> {
+        split_mentions(text, &MentionConfig::default())
+    }
+
+    #[test]
+    fn mentions() {
+        assert_eq!(
+            split("hi @alice, #rust-lang-"),
+            vec![
+                (0..3, None),
+                (3..9, Some(MentionKind::Mention)),
+                (9..11, None),
+                (11..21, Some(MentionKind::Hashtag)),
+                (21..22, None),
+            ]
+        );
+        assert_eq!(
+            split("(@bob)"),
+            vec![
+                (0..1, None),
+                (1..5, Some(MentionKind::Mention)),
+                (5..6, None)
+            ]
+        );
+    }
+
+    #[test]
+    fn not_mentions() {
+        assert_eq!(split("email@x.com"), vec![(0..11, None)]);
+        assert_eq!(split("a#b and @ alone"), vec![(0..15, None)]);
+    }
+
+    #[test]
+    fn custom_triggers() {
+        let config = MentionConfig {
+            mention_trigger: Some('+'),
+            hashtag_trigger: None,
+        };
+        assert_eq!(
+            split_mentions("+alice @bob #c", &config),
+            vec![(0..6, Some(MentionKind::Mention)), (6..14, None)]
+        );
+    }
+}
@@ -1,11 +1,16 @@
-use core::ops::Range;
+use core::ops::{Range, RangeInclusive};
 
 use core::marker::PhantomData;
+use std::collections::{HashMap, HashSet};
 
-use syntect::highlighting::ThemeSet;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::html::{styled_line_to_highlighted_html, IncludeBackground};
 use syntect::parsing::SyntaxSet;
 
-use pulldown_cmark::{Alignment, CodeBlockKind, Event, Tag, TagEnd};
+use pulldown_cmark::{
+    Alignment, BlockQuoteKind, CodeBlockKind, CowStr, Event, HeadingLevel, LinkType, Tag, TagEnd,
+};
 
 #[derive(Eq, PartialEq)]
 enum MathMode {
@@ -17,9 +22,25 @@ enum MathMode {
 use katex;
 
 use super::HtmlElement::*;
-use super::{Context, ElementAttributes, HtmlError, LinkDescription, MdComponentProps};
+use super::{
+    BlockquoteDescription, ClickedElement, CodeBlockDescription, Context, DebugEvent, Direction,
+    DirectiveDescription, ElementAttributes, HtmlError, LinkAttributes, LinkDescription, ListStyle,
+    MarkdownProps, MathDescription, MdComponentProps, RenderIssue, SoftBreakMode,
+};
 
-use crate::component::{ComponentCall, CustomHtmlTag, CustomHtmlTagError};
+use crate::abbreviations::split_abbreviations;
+use crate::attributes::BlockAttributes;
+use crate::component::{ComponentCall, CustomHtmlTag, CustomHtmlTagError, UnknownComponentPolicy};
+use crate::directives::{comment_directive, Directive};
+use crate::headings::heading_id;
+use crate::html::{sanitize_html, HtmlPolicy};
+use crate::links::{
+    link_attributes, link_reference, rel_attributes, resolve_url, sanitize_url, WikilinkResolution,
+    DEFAULT_URL_SCHEMES,
+};
+use crate::marks::{split_keys, split_marks};
+use crate::mentions::{split_mentions, MentionKind};
+use crate::tasklist::task_state;
 
 // load the default syntect options to highlight code
 lazy_static::lazy_static! {
@@ -32,12 +53,6 @@ lazy_static::lazy_static! {
 }
 
 impl HtmlError {
-    fn not_implemented(message: impl ToString) -> Self {
-        HtmlError::NotImplemented(message.to_string())
-    }
-    fn syntax(message: impl ToString) -> Self {
-        HtmlError::Syntax(message.to_string())
-    }
     fn component(name: impl ToString, msg: impl ToString) -> Self {
         HtmlError::CustomComponent {
             name: name.to_string(),
@@ -46,65 +61,463 @@ impl HtmlError {
     }
 }
 
-/// `highlight_code(content, ss, ts)` render the content `content`
-/// with syntax highlighting
-fn highlight_code(theme_name: Option<&str>, content: &str, kind: &CodeBlockKind) -> Option<String> {
-    let lang = match kind {
-        CodeBlockKind::Fenced(x) => x,
-        CodeBlockKind::Indented => return None,
+/// `code_block_language(kind)` extracts the language of a fenced code block
+/// from its info string, ignoring anything after the first word.
+/// Returns None for indented code blocks or fences without a language,
+/// like ` ```title="main.rs" `
+pub(crate) fn code_block_language<'k>(kind: &'k CodeBlockKind) -> Option<&'k str> {
+    match kind {
+        CodeBlockKind::Fenced(info) => info
+            .split(|c: char| c.is_whitespace() || c == ',' || c == '{')
+            .next()
+            .filter(|lang| !lang.is_empty() && title_value(lang).is_none()),
+        CodeBlockKind::Indented => None,
+    }
+}
+
+lazy_static::lazy_static! {
+    /// The aliases that [`crate::MarkdownProps::language_aliases`] are added to:
+    /// the short names of languages and their usual name, that highlighters know
+    pub static ref DEFAULT_LANGUAGE_ALIASES: HashMap<&'static str, &'static str> =
+        HashMap::from([
+            ("js", "javascript"),
+            ("ts", "typescript"),
+            ("py", "python"),
+            ("rb", "ruby"),
+            ("rs", "rust"),
+            ("sh", "bash"),
+            ("shell", "bash"),
+            ("zsh", "bash"),
+            ("yml", "yaml"),
+            ("md", "markdown"),
+            ("c++", "cpp"),
+            ("golang", "go"),
+        ]);
+}
+
+/// `language_alias(props, lang)` returns the name that the language `lang`
+/// of a code block is an alias of, like `javascript` for `js`,
+/// or `lang` itself if it is not an alias.
+/// The aliases of the props take precedence over the default ones
+fn language_alias<'l>(props: &MarkdownProps, lang: &'l str) -> &'l str {
+    props
+        .language_aliases
+        .and_then(|aliases| aliases.get(lang))
+        .or_else(|| DEFAULT_LANGUAGE_ALIASES.get(lang))
+        .copied()
+        .unwrap_or(lang)
+}
+
+/// the keys of the info string of a fenced code block that set its title
+const TITLE_KEYS: &[&str] = &["title", "filename"];
+
+/// `title_value(s)` returns the value of `s` if it starts with a title key,
+/// like `main.rs` for `title="main.rs"`.
+/// The value is quoted to contain spaces, and unquoted values end at the first space
+fn title_value(s: &str) -> Option<&str> {
+    let value = TITLE_KEYS
+        .iter()
+        .find_map(|key| s.strip_prefix(key)?.strip_prefix('='))?;
+    Some(match value.strip_prefix('"') {
+        Some(quoted) => quoted.split('"').next().unwrap_or_default(),
+        None => value.split(char::is_whitespace).next().unwrap_or_default(),
+    })
+}
+
+/// `code_block_title(kind)` extracts the title of a fenced code block,
+/// usually its file name, from the `title` or `filename` key of its info string,
+/// like `rust title="main.rs"`.
+/// Returns None if there is no title
+fn code_block_title(kind: &CodeBlockKind) -> Option<String> {
+    let CodeBlockKind::Fenced(info) = kind else {
+        return None;
     };
+    info.char_indices()
+        .filter(|(i, _)| *i == 0 || info[..*i].ends_with(char::is_whitespace))
+        .find_map(|(i, _)| title_value(&info[i..]))
+        .filter(|title| !title.is_empty())
+        .map(str::to_string)
+}
+
+/// the spaces and tabs at the start of `line`
+fn indentation(line: &str) -> &str {
+    let len = line.len() - line.trim_start_matches([' ', '\t']).len();
+    &line[..len]
+}
+
+/// `dedent(code)` removes the indentation that all the lines of `code` have in common,
+/// keeping their relative indentation.
+/// Only spaces and tabs are removed, and blank lines don't count
+fn dedent(code: &str) -> String {
+    let common = code
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(indentation)
+        .reduce(|common, indent| {
+            let len = common
+                .chars()
+                .zip(indent.chars())
+                .take_while(|(a, b)| a == b)
+                .count();
+            &common[..len]
+        })
+        .unwrap_or_default();
+    code.split_inclusive('\n')
+        .map(|line| match line.strip_prefix(common) {
+            Some(rest) => rest,
+            None => line.trim_start_matches([' ', '\t']),
+        })
+        .collect()
+}
 
-    let theme_name = theme_name.clone().unwrap_or("base16-ocean.light");
-    let theme = THEME_SET
+/// `trim_blank_lines(code)` removes the blank lines at the end of `code`,
+/// and the spaces at the end of its last line
+fn trim_blank_lines(code: &str) -> String {
+    let trimmed = code.trim_end();
+    let newline = code[trimmed.len()..].contains('\n');
+    match (trimmed.is_empty(), newline) {
+        (true, _) => String::new(),
+        (false, true) => format!("{trimmed}\n"),
+        (false, false) => trimmed.to_string(),
+    }
+}
+
+/// `highlighted_lines(kind)` extracts the lines to highlight
+/// from the `{...}` spec of the info string of a fenced code block,
+/// like `rust {1,3-5}`.
+/// Lines are numbered from 1, and malformed entries are ignored.
+/// Returns an empty list if there is no spec
+fn highlighted_lines(kind: &CodeBlockKind) -> Vec<RangeInclusive<usize>> {
+    let CodeBlockKind::Fenced(info) = kind else {
+        return Vec::new();
+    };
+    let Some(spec) = info
+        .split_once('{')
+        .and_then(|(_, rest)| rest.split_once('}'))
+        .map(|(spec, _)| spec)
+    else {
+        return Vec::new();
+    };
+    spec.split(',')
+        .map(str::trim)
+        .filter_map(|item| match item.split_once('-') {
+            Some((start, end)) => Some(start.trim().parse().ok()?..=end.trim().parse().ok()?),
+            None => item.parse().ok().map(|line| line..=line),
+        })
+        .collect()
+}
+
+/// the theme named `theme_name`, or the default theme if None
+fn theme(theme_name: Option<&str>) -> &'static Theme {
+    let theme_name = theme_name.unwrap_or("base16-ocean.light");
+    THEME_SET.themes.get(theme_name).expect("unknown theme")
+}
+
+/// the syntaxes and the theme used to highlight code,
+/// from [`MarkdownProps::syntax_highlight`] if it is set,
+/// or else from [`MarkdownProps::theme`].
+/// An unknown theme in [`MarkdownProps::syntax_highlight`] falls back to the default theme
+fn highlighting(props: &MarkdownProps) -> (&'static SyntaxSet, &'static Theme) {
+    let Some(config) = props.syntax_highlight else {
+        return (&*SYNTAX_SET, theme(props.theme));
+    };
+    let themes = config.theme_set.unwrap_or(&*THEME_SET);
+    let theme = themes
         .themes
-        .get(theme_name)
-        .expect("unknown theme")
-        .clone();
+        .get(config.theme)
+        .unwrap_or_else(|| theme(None));
+    (config.syntax_set.unwrap_or(&*SYNTAX_SET), theme)
+}
 
-    Some(
-        syntect::html::highlighted_html_for_string(
-            content,
-            &SYNTAX_SET,
-            SYNTAX_SET.find_syntax_by_token(lang)?,
-            &theme,
-        )
-        .ok()?,
+/// `highlight_code(props, content, lang)` render the content `content`
+/// with syntax highlighting.
+/// Returns the html of the code, and the css of the background of the theme
+fn highlight_code(
+    props: &MarkdownProps,
+    content: &str,
+    lang: &str,
+) -> Option<(String, Option<String>)> {
+    let (lines, background) = highlight_code_lines(props, content, lang)?;
+    Some((lines.concat(), background))
+}
+
+/// `highlight_code_lines(props, content, lang)` highlights `content`
+/// one line at a time, so that every line can be wrapped in its own element.
+/// Returns the html of every line, and the css of the background of the theme
+fn highlight_code_lines(
+    props: &MarkdownProps,
+    content: &str,
+    lang: &str,
+) -> Option<(Vec<String>, Option<String>)> {
+    let (syntax_set, theme) = highlighting(props);
+    let mut highlighter = HighlightLines::new(syntax_set.find_syntax_by_token(lang)?, theme);
+    let lines = content
+        .split_inclusive('\n')
+        .map(|line| {
+            let regions = highlighter.highlight_line(line, syntax_set).ok()?;
+            styled_line_to_highlighted_html(&regions, IncludeBackground::No).ok()
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let background = theme
+        .settings
+        .background
+        .map(|c| format!("background-color:#{:02x}{:02x}{:02x};", c.r, c.g, c.b));
+    Some((lines, background))
+}
+
+/// renders a code block one line at a time,
+/// with the line numbers in a gutter if `line_numbers` is true,
+/// and the lines that are in `highlighted` marked with the `md-line-highlight` class.
+///
+/// Every line is a `<span class="md-line">` with a `data-line` attribute,
+/// and its number is a `<span class="md-line-number">`.
+fn render_code_lines<'a, 'callback, F: Context<'a, 'callback>>(
+    cx: F,
+    source: &str,
+    lang: Option<&str>,
+    line_numbers: bool,
+    highlighted: &[RangeInclusive<usize>],
+    mut code_attributes: ElementAttributes<F::Handler<F::Event>>,
+) -> F::View {
+    let props = cx.props();
+    let highlighted_code = lang.and_then(|lang| highlight_code_lines(&props, source, lang));
+    let lines: Vec<_> = match highlighted_code {
+        Some((lines, background)) => {
+            code_attributes.style = background;
+            lines
+                .into_iter()
+                .map(|html| cx.el_span_with_inner_html(html, Default::default()))
+                .collect()
+        }
+        None => source
+            .split_inclusive('\n')
+            .map(|line| cx.el_text(line.to_string().into()))
+            .collect(),
+    };
+
+    let lines = lines
+        .into_iter()
+        .enumerate()
+        .map(|(i, content)| {
+            let n = i + 1;
+            let mut classes = vec![props.class_name("md-line")];
+            if highlighted.iter().any(|r| r.contains(&n)) {
+                classes.push(props.class_name("md-line-highlight"))
+            }
+            let content = if line_numbers {
+                let number = cx.el_with_attributes(
+                    Span,
+                    cx.el_text(n.to_string().into()),
+                    ElementAttributes {
+                        classes: vec![props.class_name("md-line-number")],
+                        ..Default::default()
+                    },
+                );
+                cx.el_fragment(vec![number, content])
+            } else {
+                content
+            };
+            cx.el_with_attributes(
+                Span,
+                content,
+                ElementAttributes {
+                    classes,
+                    data: vec![("data-line", n.to_string())],
+                    ..Default::default()
+                },
+            )
+        })
+        .collect();
+
+    if line_numbers {
+        code_attributes
+            .classes
+            .push(props.class_name("md-line-numbers"))
+    }
+    let inner_attributes = ElementAttributes {
+        classes: language_classes(&props, lang),
+        ..Default::default()
+    };
+    cx.el_with_attributes(
+        Pre,
+        cx.el_with_attributes(Code, cx.el_fragment(lines), inner_attributes),
+        code_attributes,
     )
 }
 
+/// the `language-<lang>` class of the `<code>` element of a code block,
+/// for client-side highlighters and for styling
+fn language_classes(props: &MarkdownProps, lang: Option<&str>) -> Vec<String> {
+    lang.map(|x| props.class_name(&format!("language-{x}")))
+        .into_iter()
+        .collect()
+}
+
 /// renders a source code in a code block, with syntax highlighting if possible.
 /// `cx`: the current markdown context
 /// `source`: the source to render
 /// `range`: the position of the code in the original source
+///
+/// If the block has a language, the `<code>` element gets a `language-<lang>` class,
+/// whether it is highlighted or not,
+/// so that client-side highlighters and stylesheets can pick it up.
+/// The aliases of languages, like `js`, are replaced by their name first.
+///
+/// If line numbers are enabled, or if the info string selects lines to highlight
+/// (like `rust {1,3-5}`), every line is rendered in its own element.
+///
+/// If the info string sets a title, like `rust title="main.rs"`,
+/// it is rendered above the block in a `<div class="md-code-title">`.
 fn render_code_block<'a, 'callback, F: Context<'a, 'callback>>(
     cx: F,
     source: String,
     k: &CodeBlockKind,
     range: Range<usize>,
 ) -> F::View {
+    let props = cx.props();
+    let source = if props.code_block_dedent {
+        dedent(&source)
+    } else {
+        source
+    };
+    let source = if props.code_block_trim {
+        trim_blank_lines(&source)
+    } else {
+        source
+    };
+    let lang = code_block_language(k).map(|lang| language_alias(&props, lang));
+    let title = code_block_title(k);
+
+    let description = CodeBlockDescription {
+        language: lang.map(str::to_string),
+        title: title.clone(),
+        content: source.clone(),
+        range: range.clone(),
+    };
+    if let Some(view) = cx.render_code_block(description) {
+        return view;
+    }
+
+    // the diagram is drawn by the mermaid script, in place of its source
+    if lang == Some("mermaid") {
+        let attributes = ElementAttributes {
+            classes: vec!["mermaid".into()],
+            ..Default::default()
+        };
+        return cx.el_with_attributes(Div, cx.el_text(source.into()), attributes);
+    }
+
     let code_attributes = ElementAttributes {
-        on_click: Some(cx.make_md_handler(range, true)),
+        on_click: Some(cx.make_md_handler(
+            range,
+            ClickedElement::CodeBlock {
+                language: lang.map(str::to_string),
+            },
+            true,
+        )),
         ..Default::default()
     };
 
-    match highlight_code(cx.props().theme, &source, &k) {
-        None => cx.el_with_attributes(
-            Code,
-            cx.el(Code, cx.el_text(source.into())),
+    let copy_button = props.code_block_copy_button;
+    let line_numbers = props.code_block_line_numbers;
+    let highlighted = highlighted_lines(k);
+    let block = if line_numbers || !highlighted.is_empty() {
+        render_code_lines(
+            cx,
+            &source,
+            lang,
+            line_numbers,
+            &highlighted,
             code_attributes,
-        ),
-        Some(x) => cx.el_span_with_inner_html(x, code_attributes),
+        )
+    } else {
+        let (code, style) = match lang.and_then(|lang| highlight_code(&props, &source, lang)) {
+            Some((html, background)) => (
+                cx.el_span_with_inner_html(html, Default::default()),
+                background,
+            ),
+            None => (cx.el_text(source.clone().into()), None),
+        };
+        let inner_attributes = ElementAttributes {
+            classes: language_classes(&props, lang),
+            ..Default::default()
+        };
+        cx.el_with_attributes(
+            Pre,
+            cx.el_with_attributes(Code, code, inner_attributes),
+            ElementAttributes {
+                style,
+                ..code_attributes
+            },
+        )
+    };
+    let block = if copy_button {
+        render_copy_button(cx, block, source)
+    } else {
+        block
+    };
+    match title {
+        Some(title) => {
+            let title_attributes = ElementAttributes {
+                classes: vec![props.class_name("md-code-title")],
+                ..Default::default()
+            };
+            let title = cx.el_with_attributes(Div, cx.el_text(title.into()), title_attributes);
+            cx.el_fragment(vec![title, block])
+        }
+        None => block,
     }
 }
 
-#[cfg(feature = "maths")]
+/// wraps the code `block` in a `<div class="md-codeblock">`,
+/// with a button that copies its `source` to the clipboard
+fn render_copy_button<'a, 'callback, F: Context<'a, 'callback>>(
+    cx: F,
+    block: F::View,
+    source: String,
+) -> F::View {
+    let props = cx.props();
+    let button_attributes = ElementAttributes {
+        classes: vec![props.class_name("md-copy")],
+        on_click: cx.make_copy_handler(source.clone()),
+        ..Default::default()
+    };
+    let button = cx.el_with_attributes(Button, cx.el_text("Copy".into()), button_attributes);
+    let wrapper_attributes = ElementAttributes {
+        classes: vec![props.class_name("md-codeblock")],
+        data: vec![("data-code", source)],
+        ..Default::default()
+    };
+    cx.el_with_attributes(Div, cx.el_fragment(vec![block, button]), wrapper_attributes)
+}
+
 /// `render_maths(content)` returns a html node
-/// with the latex content `content` compiled inside
+/// with the latex content `content` compiled inside.
+/// The custom renderer of the context is used if there is one,
+/// otherwise the maths are compiled with katex
 fn render_maths<'a, 'callback, F: Context<'a, 'callback>>(
     cx: F,
     content: &str,
     display_mode: MathMode,
     range: Range<usize>,
+) -> Result<F::View, HtmlError> {
+    let description = MathDescription {
+        content: content.to_string(),
+        display_mode: display_mode == MathMode::Display,
+        range: range.clone(),
+    };
+    match cx.render_math(description) {
+        Some(view) => Ok(view),
+        None => render_default_maths(cx, content, display_mode, range),
+    }
+}
+
+#[cfg(feature = "maths")]
+fn render_default_maths<'a, 'callback, F: Context<'a, 'callback>>(
+    cx: F,
+    content: &str,
+    display_mode: MathMode,
+    range: Range<usize>,
 ) -> Result<F::View, HtmlError> {
     let opts = katex::Opts::builder()
         .display_mode(display_mode == MathMode::Display)
@@ -116,10 +529,10 @@ fn render_maths<'a, 'callback, F: Context<'a, 'callback>>(
         MathMode::Display => "math-flow",
     };
 
-    let callback = cx.make_md_handler(range, true);
+    let callback = cx.make_md_handler(range, ClickedElement::Math, true);
 
     let attributes = ElementAttributes {
-        classes: vec![class_name.to_string()],
+        classes: vec![cx.props().class_name(class_name)],
         on_click: Some(callback),
         ..Default::default()
     };
@@ -129,30 +542,366 @@ fn render_maths<'a, 'callback, F: Context<'a, 'callback>>(
         Err(_) => Err(HtmlError::Math),
     }
 }
+
+/// without katex, the raw latex source is displayed
 #[cfg(not(feature = "maths"))]
-fn render_maths<'a, 'callback, F: Context<'a, 'callback>>(
-    _cx: F,
-    _content: &str,
-    _display_mode: MathMode,
-    _range: Range<usize>,
+fn render_default_maths<'a, 'callback, F: Context<'a, 'callback>>(
+    cx: F,
+    content: &str,
+    display_mode: MathMode,
+    range: Range<usize>,
 ) -> Result<F::View, HtmlError> {
-    Err(HtmlError::UnAvailable(
-        "Math was not enabled during compilation of the library. Please unable the `maths` feature"
-            .into(),
-    ))
+    let class_name = match display_mode {
+        MathMode::Inline => "math-inline",
+        MathMode::Display => "math-display",
+    };
+
+    let props = cx.props();
+    let attributes = ElementAttributes {
+        classes: vec![props.class_name("math"), props.class_name(class_name)],
+        on_click: Some(cx.make_md_handler(range, ClickedElement::Math, true)),
+        ..Default::default()
+    };
+
+    let text = cx.el_text(content.to_string().into());
+    Ok(cx.el_with_attributes(Span, text, attributes))
+}
+
+/// `source_attributes(attrs)` keeps the `key=value` attributes of a heading
+/// that are safe to set on the element.
+/// The event handlers, like `onclick`, and the attributes set by the renderer are dropped
+fn source_attributes(attrs: &[(CowStr, Option<CowStr>)]) -> Vec<(String, String)> {
+    attrs
+        .iter()
+        .filter(|(name, _)| {
+            let lowercase = name.to_ascii_lowercase();
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                && !lowercase.starts_with("on")
+                && !["id", "class", "style"].contains(&lowercase.as_str())
+        })
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                value.as_deref().unwrap_or_default().into(),
+            )
+        })
+        .collect()
+}
+
+/// `render_callout(cx, kind, children)` renders a github-style alert,
+/// ie a blockquote starting with a marker like `[!NOTE]`
+fn render_callout<'a, 'callback, F: Context<'a, 'callback>>(
+    cx: F,
+    kind: BlockQuoteKind,
+    children: F::View,
+) -> F::View {
+    let (name, title) = match kind {
+        BlockQuoteKind::Note => ("note", "Note"),
+        BlockQuoteKind::Tip => ("tip", "Tip"),
+        BlockQuoteKind::Important => ("important", "Important"),
+        BlockQuoteKind::Warning => ("warning", "Warning"),
+        BlockQuoteKind::Caution => ("caution", "Caution"),
+    };
+
+    let props = cx.props();
+    let title = cx.el_with_attributes(
+        Paragraph,
+        cx.el_text(title.into()),
+        ElementAttributes {
+            classes: vec![props.class_name("md-callout-title")],
+            ..Default::default()
+        },
+    );
+
+    cx.el_with_attributes(
+        Div,
+        cx.el_fragment(vec![title, children]),
+        ElementAttributes {
+            classes: vec![
+                props.class_name("md-callout"),
+                props.class_name(&format!("md-callout-{name}")),
+            ],
+            ..Default::default()
+        },
+    )
 }
 
 /// `align_string(align)` gives the css string
-/// that is used to align text according to `align`
-fn align_string(align: Alignment) -> &'static str {
+/// that is used to align text according to `align`.
+/// Returns None if the column has no explicit alignment
+fn align_string(align: Alignment) -> Option<&'static str> {
     match align {
-        Alignment::Left => "text-align: left",
-        Alignment::Right => "text-align: right",
-        Alignment::Center => "text-align: center",
-        Alignment::None => "",
+        Alignment::Left => Some("text-align: left"),
+        Alignment::Right => Some("text-align: right"),
+        Alignment::Center => Some("text-align: center"),
+        Alignment::None => None,
+    }
+}
+
+/// Information about the whole document,
+/// shared by the renderers of all the nested elements
+pub struct DocumentState<V> {
+    /// the headings of the document, with their anchor ids
+    pub headings: Vec<crate::Heading>,
+    /// the number of every footnote, by order of first appearance,
+    /// indexed by their lowercase label
+    footnote_numbers: HashMap<String, usize>,
+    /// the rendered footnote definitions, with their number
+    footnotes: Vec<(usize, V)>,
+    /// the text of every footnote definition, indexed by their lowercase label,
+    /// when the references show it, see [`crate::MarkdownProps::footnote_popovers`]
+    pub footnote_texts: HashMap<String, String>,
+    /// the number of images rendered so far
+    pub image_count: usize,
+    /// the number of elements rendered so far
+    pub element_count: usize,
+    /// how line breaks inside paragraphs are rendered
+    pub soft_break: SoftBreakMode,
+    /// the raw html element whose content is being removed by the sanitizer,
+    /// like `script`
+    dropped_html: Option<String>,
+    /// what happened while rendering, see [`Context::send_debug`]
+    #[cfg(feature = "debug")]
+    pub debug_events: Vec<DebugEvent>,
+    /// the problems of the document, that make the strict mode fail
+    pub issues: Vec<RenderIssue>,
+    /// the abbreviations of the document and their expansion,
+    /// rendered as `<abbr>` in the text
+    pub abbreviations: Vec<(String, String)>,
+    /// the captions of the paragraphs rendered as figures,
+    /// by position of the paragraph
+    pub figures: HashMap<usize, String>,
+    /// the positions of the loose lists, whose items are wrapped in paragraphs
+    pub loose_lists: HashSet<usize>,
+    /// the alignment of the cells of the raw html tables, by position of the cell
+    pub cell_alignments: HashMap<usize, Alignment>,
+    /// the attributes of the paragraphs and blockquotes set by a block like `{.lead}`,
+    /// by position of the element
+    pub block_attributes: HashMap<usize, BlockAttributes>,
+    /// the numbers prepended to the headings, like `1.2`, by position of the heading,
+    /// see [`crate::MarkdownProps::heading_numbering`]
+    pub heading_numbers: HashMap<usize, String>,
+    /// the number of links the renderer is inside of,
+    /// where mentions are not turned into links
+    link_depth: usize,
+    /// for each list item the renderer is inside of,
+    /// the position of its task checkbox if it is a task
+    task_items: Vec<Option<usize>>,
+    /// the kind of element that the clicks on the text are reported as,
+    /// like the heading the text is in
+    clicked_element: ClickedElement,
+    /// wether the document has more elements or images than the props allow,
+    /// in which case the rest of it is not rendered
+    pub truncated: bool,
+    /// the wikilinks resolved so far by the context, by target,
+    /// so that each target is resolved once per render
+    resolved_wikilinks: HashMap<String, Option<WikilinkResolution>>,
+    /// the mentions and hashtags resolved so far by the context, by kind and name
+    resolved_mentions: HashMap<(MentionKind, String), Option<String>>,
+}
+
+impl<V> DocumentState<V> {
+    pub fn new(headings: Vec<crate::Heading>, soft_break: SoftBreakMode) -> Self {
+        Self {
+            headings,
+            footnote_numbers: HashMap::new(),
+            footnotes: Vec::new(),
+            footnote_texts: HashMap::new(),
+            image_count: 0,
+            element_count: 0,
+            soft_break,
+            dropped_html: None,
+            #[cfg(feature = "debug")]
+            debug_events: Vec::new(),
+            issues: Vec::new(),
+            abbreviations: Vec::new(),
+            figures: HashMap::new(),
+            loose_lists: HashSet::new(),
+            cell_alignments: HashMap::new(),
+            block_attributes: HashMap::new(),
+            heading_numbers: HashMap::new(),
+            link_depth: 0,
+            task_items: Vec::new(),
+            clicked_element: ClickedElement::Text,
+            truncated: false,
+            resolved_wikilinks: HashMap::new(),
+            resolved_mentions: HashMap::new(),
+        }
+    }
+
+    /// records the debug event made by `event`, see [`Context::send_debug`].
+    /// It is only made with the `debug` feature
+    #[cfg_attr(not(feature = "debug"), allow(unused_variables))]
+    pub fn debug(&mut self, event: impl FnOnce() -> DebugEvent) {
+        #[cfg(feature = "debug")]
+        self.debug_events.push(event())
+    }
+
+    /// forgets the wikilinks and mentions resolved so far,
+    /// so that they are resolved again by the context the next time they appear
+    pub fn clear_resolutions(&mut self) {
+        self.resolved_wikilinks.clear();
+        self.resolved_mentions.clear();
+    }
+
+    /// returns the number of the footnote `label`,
+    /// and wether it is the first time it appears in the document
+    fn footnote_number(&mut self, label: &str) -> (usize, bool) {
+        let next = self.footnote_numbers.len() + 1;
+        let mut first = false;
+        let n = *self
+            .footnote_numbers
+            .entry(label.to_lowercase())
+            .or_insert_with(|| {
+                first = true;
+                next
+            });
+        (n, first)
+    }
+}
+
+/// `footnote_texts(stream)` returns the text of every footnote definition of `stream`,
+/// without formatting, indexed by their lowercase label
+pub fn footnote_texts(stream: &[(Event<'_>, Range<usize>)]) -> HashMap<String, String> {
+    let mut texts = HashMap::new();
+    let mut current: Option<(String, String)> = None;
+    for (event, _) in stream {
+        match event {
+            Event::Start(Tag::FootnoteDefinition(label)) => {
+                current = Some((label.to_lowercase(), String::new()))
+            }
+            Event::End(TagEnd::FootnoteDefinition) => {
+                if let Some((label, text)) = current.take() {
+                    texts.insert(label, text.trim().to_string());
+                }
+            }
+            Event::Text(s) | Event::Code(s) => {
+                if let Some((_, text)) = &mut current {
+                    text.push_str(s)
+                }
+            }
+            Event::SoftBreak | Event::HardBreak | Event::End(TagEnd::Paragraph) => {
+                if let Some((_, text)) = &mut current {
+                    text.push(' ')
+                }
+            }
+            _ => (),
+        }
+    }
+    texts
+}
+
+/// `loose_lists(stream)` returns the positions of the loose lists of `stream`.
+/// The parser wraps the content of the items of a loose list in paragraphs,
+/// unlike the items of a tight list
+pub fn loose_lists(stream: &[(Event<'_>, Range<usize>)]) -> HashSet<usize> {
+    let mut loose = HashSet::new();
+    // the tags the current event is in, with their position
+    let mut open: Vec<(&Tag, usize)> = Vec::new();
+    for (event, range) in stream {
+        match event {
+            Event::Start(tag) => {
+                if let (Tag::Paragraph, [.., (Tag::List(_), list), (Tag::Item, _)]) =
+                    (tag, &open[..])
+                {
+                    loose.insert(*list);
+                }
+                open.push((tag, range.start));
+            }
+            Event::End(_) => {
+                open.pop();
+            }
+            _ => (),
+        }
+    }
+    loose
+}
+
+/// renders the footnote definitions collected in `document`,
+/// as an ordered list with links back to the references.
+/// Returns None if the document has no footnotes
+pub fn render_footnotes<'a, 'callback, F: Context<'a, 'callback>>(
+    cx: F,
+    document: DocumentState<F::View>,
+) -> Option<F::View> {
+    let mut footnotes = document.footnotes;
+    if footnotes.is_empty() {
+        return None;
+    }
+    footnotes.sort_by_key(|(n, _)| *n);
+
+    let items = footnotes
+        .into_iter()
+        .map(|(n, content)| {
+            let back_reference = cx.el_a(
+                cx.el_text("↩".into()),
+                format!("#fnref-{n}"),
+                LinkAttributes::default(),
+                None,
+            );
+            cx.el_with_attributes(
+                Li,
+                cx.el_fragment(vec![content, back_reference]),
+                ElementAttributes {
+                    id: Some(format!("fn-{n}")),
+                    ..Default::default()
+                },
+            )
+        })
+        .collect();
+
+    Some(cx.el_with_attributes(
+        Div,
+        cx.el(Ol(1), cx.el_fragment(items)),
+        ElementAttributes {
+            classes: vec![cx.props().class_name("footnotes")],
+            ..Default::default()
+        },
+    ))
+}
+
+/// wether the element that ends with `end` only contains inline content,
+/// where a `<div>` is not allowed
+fn has_inline_content(end: TagEnd) -> bool {
+    matches!(
+        end,
+        TagEnd::Paragraph
+            | TagEnd::Heading(_)
+            | TagEnd::TableCell
+            | TagEnd::Emphasis
+            | TagEnd::Strong
+            | TagEnd::Strikethrough
+            | TagEnd::Superscript
+            | TagEnd::Subscript
+            | TagEnd::Link
+            | TagEnd::Image
+    )
+}
+
+/// the custom component tag of `event`, if it is one, like `<Box>` or `</Box>`
+fn component_tag(event: &Event) -> Option<CustomHtmlTag> {
+    match event {
+        Event::Html(html) | Event::InlineHtml(html) if can_be_custom_component(html) => {
+            html.trim().parse().ok()
+        }
+        _ => None,
     }
 }
 
+/// the directive of `event`, if it is a `:::` line moved out of its paragraph
+fn directive_line<'e>(event: &'e Event) -> Option<Directive<'e>> {
+    match event {
+        Event::Html(html) => Directive::parse(html),
+        _ => None,
+    }
+}
+
+/// The default of [`crate::MarkdownProps::max_nesting_depth`]
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 100;
+
 /// Manage the creation of a [`F::View`]
 /// from a stream of markdown events
 pub struct Renderer<'a, 'callback, 'c, I, F>
@@ -164,6 +913,8 @@ where
     __marker: PhantomData<&'callback ()>,
     /// the markdown context
     cx: F,
+    /// the markdown source, that the custom components get their raw children from
+    source: &'a str,
     /// the stream of markdown [`Event`]s
     stream: &'c mut I,
     /// the alignment settings inside the current table
@@ -171,11 +922,43 @@ where
     /// the current horizontal index of the cell we are in.
     /// TODO: remove it
     cell_index: usize,
+    /// wether we are inside the head of a table
+    in_table_head: bool,
     /// the root tag that this renderer is rendering
     end_tag: Option<TagEnd>,
-    /// the current component we are inside of.
-    /// custom components doesn't allow nesting.
+    /// the custom component that this renderer is rendering the children of.
+    /// The renderer stops at the closing tag of this component
     current_component: Option<String>,
+    /// wether this renderer renders the content of a directive,
+    /// like `:::details`, and stops at the `:::` line that closes it
+    in_directive: bool,
+    /// the position of the end of the `:::` line that closed the directive
+    /// that this renderer rendered
+    directive_end: Option<usize>,
+    /// the position where the children of the custom component
+    /// that this renderer rendered end: the start of its closing tag,
+    /// or the end of the enclosing element if it was never closed
+    component_end: Option<usize>,
+    /// wether the closing tag of the enclosing element was consumed
+    /// by a custom component that was never closed,
+    /// in which case the renderer must stop
+    ended: bool,
+    /// the number of renderers this renderer is nested in
+    depth: usize,
+    /// the state of the whole document
+    document: &'c mut DocumentState<F::View>,
+}
+
+/// `sub_range(text, range, part)` returns the position in the source of the part `part`
+/// of `text`, found at `range` in the source.
+/// If the text doesn't map exactly to the source, for example with entities,
+/// it is the position of the whole text
+fn sub_range(text: &str, range: &Range<usize>, part: &Range<usize>) -> Range<usize> {
+    if text.len() == range.len() {
+        range.start + part.start..range.start + part.end
+    } else {
+        range.clone()
+    }
 }
 
 /// Returns true if `raw_html`:
@@ -195,6 +978,13 @@ fn can_be_custom_component(raw_html: &str) -> bool {
     fst == '<' && last == '>' && middle.into_iter().all(|c| c != &'<' && c != &'>')
 }
 
+/// Returns true if `name` is the name of a custom component,
+/// ie if it starts with an uppercase letter, like `MyComponent`.
+/// Html elements are conventionally lowercase
+fn is_component_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_uppercase())
+}
+
 impl<'a, 'callback, 'c, I, F> Iterator for Renderer<'a, 'callback, 'c, I, F>
 where
     I: Iterator<Item = (Event<'a>, Range<usize>)>,
@@ -205,8 +995,33 @@ where
 
     fn next(&mut self) -> Option<Self::Item> {
         use Event::*;
-        let (item, range): (Event<'a>, Range<usize>) = self.stream.next()?;
-        let range = range.clone();
+        if self.ended {
+            return None;
+        }
+        // the enclosing renderers stop too, without rendering the rest of the document
+        if self.document.truncated {
+            self.stream.by_ref().for_each(drop);
+            return None;
+        }
+        // html blocks are transparent,
+        // so that a custom component can span several blocks
+        let (item, range): (Event<'a>, Range<usize>) = loop {
+            match self.stream.next()? {
+                (Start(Tag::HtmlBlock), _) | (End(TagEnd::HtmlBlock), _) => continue,
+                x => break x,
+            }
+        };
+
+        if let Some(limit) = self.exceeded_limit(&item) {
+            self.document.truncated = true;
+            self.document.issues.push(RenderIssue::Truncated);
+            self.document.debug(|| DebugEvent::Truncated {
+                limit,
+                position: range.start,
+            });
+            self.stream.by_ref().for_each(drop);
+            return None;
+        }
 
         let cx = self.cx;
 
@@ -216,31 +1031,69 @@ where
                 // check if the closing tag is the tag that was open
                 // when this renderer was created
                 match self.end_tag {
-                    Some(t) if t == end => return None,
+                    Some(t) if t == end => {
+                        // a component that was never closed ends with the enclosing element
+                        self.ended = self.current_component.is_some() || self.in_directive;
+                        self.component_end = Some(range.end);
+                        return None;
+                    }
                     Some(t) => panic!("{end:?} is a wrong closing tag, expected {t:?}"),
                     None => panic!("didn't expect a closing tag"),
                 }
             }
-            Text(s) => Ok(cx.render_text(s, range)),
+            Text(s) => Ok(self.text(s, range)),
             Code(s) => Ok(cx.render_code(s, range)),
+            InlineHtml(raw_html) | Html(raw_html) if self.closes_component(&raw_html) => {
+                self.component_end = Some(range.start);
+                return None;
+            }
+            InlineHtml(raw_html) | Html(raw_html)
+                if comment_directive(
+                    &raw_html,
+                    &range,
+                    cx.props().comment_directive_names.unwrap_or_default(),
+                )
+                .is_some() =>
+            {
+                Ok(cx.el_empty())
+            }
             InlineHtml(s) => self.html(&s, range),
-            Html(raw_html) => self.html(&raw_html, range),
-            FootnoteReference(_) => Err(HtmlError::not_implemented("footnotes refs")),
-            SoftBreak => Ok(cx.el_text(" ".into())),
+            Html(raw_html) => match Directive::parse(&raw_html) {
+                Some(Directive::End) if self.in_directive => {
+                    self.directive_end = Some(range.end);
+                    return None;
+                }
+                Some(directive) => Ok(self.directive(directive, range)),
+                None => self.html(&raw_html, range),
+            },
+            FootnoteReference(label) => Ok(self.footnote_reference(&label)),
+            SoftBreak => Ok(match self.document.soft_break {
+                SoftBreakMode::Space => cx.el_text(" ".into()),
+                SoftBreakMode::Newline => cx.el_text("\n".into()),
+                SoftBreakMode::HardBreak => cx.el_br(),
+            }),
             HardBreak => Ok(self.cx.el_br()),
             Rule => Ok(cx.render_rule(range)),
-            TaskListMarker(m) => Ok(cx.render_tasklist_marker(m, range)),
+            TaskListMarker(m) => {
+                let states = cx.props().task_list_states;
+                let state = task_state(self.source, &range, states);
+                if let Some(task) = self.document.task_items.last_mut() {
+                    *task = Some(range.start)
+                }
+                Ok(cx.render_tasklist_marker(m, state, range))
+            }
             InlineMath(content) => render_maths(self.cx, &content, MathMode::Inline, range),
             DisplayMath(content) => render_maths(self.cx, &content, MathMode::Display, range),
         };
 
+        self.document.element_count += 1;
         Some(rendered.unwrap_or_else(|e| {
             self.cx.el_with_attributes(
                 Span,
                 self.cx
                     .el_fragment(vec![self.cx.el_text(e.to_string().into()), self.cx.el_br()]),
                 ElementAttributes {
-                    classes: vec!["markdown-error".to_string()],
+                    classes: vec![self.cx.props().class_name("markdown-error")],
                     on_click: None,
                     ..Default::default()
                 },
@@ -256,15 +1109,63 @@ where
 {
     /// creates a new renderer from a stream of events.
     /// It returns an iterator of [`F::View`]
-    pub fn new(cx: F, events: &'c mut I) -> Self {
+    pub fn new(
+        cx: F,
+        source: &'a str,
+        events: &'c mut I,
+        document: &'c mut DocumentState<F::View>,
+    ) -> Self {
         Self {
             __marker: PhantomData,
             cx,
+            source,
             stream: events,
             column_alignment: None,
             cell_index: 0,
+            in_table_head: false,
             end_tag: None,
             current_component: None,
+            in_directive: false,
+            directive_end: None,
+            component_end: None,
+            ended: false,
+            depth: 0,
+            document,
+        }
+    }
+
+    /// the name of the limit of the props, like `max_elements`,
+    /// that rendering `event` would exceed
+    fn exceeded_limit(&self, event: &Event) -> Option<&'static str> {
+        let props = self.cx.props();
+        if props
+            .max_elements
+            .is_some_and(|max| self.document.element_count >= max)
+        {
+            return Some("max_elements");
+        }
+        let is_image = matches!(event, Event::Start(Tag::Image { .. }));
+        if is_image
+            && props
+                .max_image_count
+                .is_some_and(|max| self.document.image_count >= max)
+        {
+            return Some("max_image_count");
+        }
+        None
+    }
+
+    /// returns true if `raw_html` is the closing tag
+    /// of the component that this renderer is rendering
+    fn closes_component(&self, raw_html: &str) -> bool {
+        match &self.current_component {
+            Some(current_name) if can_be_custom_component(raw_html) => {
+                match raw_html.trim().parse() {
+                    Ok(CustomHtmlTag::End(name)) => &name == current_name,
+                    _ => false,
+                }
+            }
+            _ => false,
         }
     }
 
@@ -272,100 +1173,141 @@ where
     /// - If it looks like `<Component/>` and Component is registered,
     ///     render the corresponding component.
     /// - If it looks like `<Component>`, and Component is registered,
-    ///     extract markdown `<Component/>` is found.
-    /// In any other cases, render the string as raw html.
+    ///     render the markdown until `</Component>` as the children of the component.
+    ///     Components can be nested, and the children can span several blocks.
+    ///     If `</Component>` is missing, the children end with the enclosing element.
+    /// - If it looks like a component, but Component is not registered,
+    ///     follow the [`UnknownComponentPolicy`] of the props.
     ///
-    /// TODO: document (and fix?) how this behaves if given an open tag and not a closing one.
-    fn html(&mut self, raw_html: &str, _range: Range<usize>) -> Result<F::View, HtmlError> {
-        // TODO: refactor
-
-        match &self.current_component {
-            Some(current_name) => {
-                if self.end_tag.is_some() {
-                    return Err(HtmlError::component(
-                        raw_html,
-                        "please make sure there is a newline before the end of your component",
-                    ));
+    /// In any other cases, render the string as raw html.
+    fn html(&mut self, raw_html: &str, range: Range<usize>) -> Result<F::View, HtmlError> {
+        // If making a new html tag, check if it has a name that is a valid custom component name.
+        // If so, render it accordingly (as the component or error).
+        // Otherwise fall through to the catch all inline html case below.
+        if can_be_custom_component(raw_html) {
+            let name = match raw_html.trim().parse() {
+                Ok(CustomHtmlTag::Inline(s)) => {
+                    if self.cx.has_custom_component(&s.name) {
+                        return self.custom_component_inline(s);
+                    }
+                    s.name
                 }
-                match raw_html.parse() {
-                    Ok(CustomHtmlTag::End(name)) if &name == current_name => {
-                        Ok(self.next().unwrap_or(self.cx.el_empty()))
+                Ok(CustomHtmlTag::End(name)) => {
+                    if self.cx.has_custom_component(&name) {
+                        return Err(HtmlError::component(name, "expected start, not end"));
                     }
-                    Ok(_) => Err(HtmlError::component(
-                        current_name,
-                        "expected end of component",
-                    )),
-                    Err(e) => Err(HtmlError::syntax(e.message)),
+                    name
                 }
-            }
-            None => {
-                // If making a new html tag, check if it has a name that is a valid custom component name.
-                // If so, render it accordingly (as the component or error).
-                // Otherwise fall through to the catch all inline html case below.
-                if can_be_custom_component(raw_html) {
-                    match raw_html.parse() {
-                        Ok(CustomHtmlTag::Inline(s)) => {
-                            if self.cx.has_custom_component(&s.name) {
-                                return self.custom_component_inline(s);
-                            }
-                        }
-                        Ok(CustomHtmlTag::End(name)) => {
-                            if self.cx.has_custom_component(&name) {
-                                return Err(HtmlError::component(name, "expected start, not end"));
-                            }
-                        }
-                        Ok(CustomHtmlTag::Start(s)) => {
-                            if self.cx.has_custom_component(&s.name) {
-                                return self.custom_component(s);
-                            }
-                        }
-                        Err(CustomHtmlTagError {
-                            name: Some(name),
-                            message,
-                        }) => {
-                            if self.cx.has_custom_component(&name) {
-                                return Err(HtmlError::component(
-                                    name,
-                                    format!("not a valid component: {message}"),
-                                ));
-                            }
-                        }
-                        // Component did not parse as a custom component far enough to get a name, so fall through to raw html.
-                        Err(CustomHtmlTagError {
-                            name: None,
-                            message: _,
-                        }) => {}
-                    };
+                Ok(CustomHtmlTag::Start(s)) => {
+                    if self.cx.has_custom_component(&s.name) {
+                        return self.custom_component(s, range.end);
+                    }
+                    s.name
+                }
+                Err(CustomHtmlTagError {
+                    name: Some(name),
+                    message,
+                }) => {
+                    if self.cx.has_custom_component(&name) {
+                        return Err(HtmlError::component(
+                            name,
+                            format!("not a valid component: {message}"),
+                        ));
+                    }
+                    name
+                }
+                // Component did not parse as a custom component far enough to get a name, so fall through to raw html.
+                Err(CustomHtmlTagError {
+                    name: None,
+                    message: _,
+                }) => String::new(),
+            };
+            if is_component_name(&name) {
+                self.document
+                    .debug(|| DebugEvent::ComponentNotFound { name: name.clone() });
+                self.document
+                    .issues
+                    .push(RenderIssue::UnknownComponent(name.clone()));
+                if self.cx.props().unknown_components == UnknownComponentPolicy::Drop {
+                    return Ok(self.cx.el_empty());
                 }
-                // Not a custom component, so render html as is without and parsing/validation.
-                Ok(self
-                    .cx
-                    .el_span_with_inner_html(raw_html.to_string(), Default::default()))
             }
         }
+        // Not a custom component, so render html according to the policy
+        let props = self.cx.props();
+        Ok(match props.html_policy {
+            HtmlPolicy::Passthrough => self
+                .cx
+                .el_span_with_inner_html(raw_html.to_string(), Default::default()),
+            HtmlPolicy::Escape => self.cx.el_text(raw_html.to_string().into()),
+            HtmlPolicy::Strip => {
+                self.document.debug(|| DebugEvent::UnhandledEvent {
+                    event: format!("{:?}", Event::Html(raw_html.into())),
+                    range,
+                });
+                self.cx.el_empty()
+            }
+            HtmlPolicy::Sanitize(allowlist) => {
+                let html = sanitize_html(
+                    raw_html,
+                    &allowlist,
+                    props.allowed_url_schemes.unwrap_or(DEFAULT_URL_SCHEMES),
+                    &mut self.document.dropped_html,
+                );
+                if html.trim().is_empty() {
+                    self.cx.el_empty()
+                } else {
+                    self.cx.el_span_with_inner_html(html, Default::default())
+                }
+            }
+        })
     }
 
-    /// Renders a custom component with children.
-    fn custom_component(&mut self, description: ComponentCall) -> Result<F::View, HtmlError> {
+    /// Renders a custom component with children,
+    /// whose opening tag ends at `start` in the source.
+    fn custom_component(
+        &mut self,
+        description: ComponentCall,
+        start: usize,
+    ) -> Result<F::View, HtmlError> {
         let name: &str = &description.name;
         if !self.cx.has_custom_component(name) {
             return Err(HtmlError::component(name, "not a valid component"));
         }
+        if self.depth_exceeded() {
+            let end = self.skip_nested(
+                |e| matches!(component_tag(e), Some(CustomHtmlTag::Start(c)) if c.name == name),
+                |e| matches!(component_tag(e), Some(CustomHtmlTag::End(n)) if n == name),
+            );
+            self.component_end = end.map(|r| r.start);
+            return Ok(self.depth_limit(self.end_tag));
+        }
 
-        let sub_renderer = Renderer {
+        let mut sub_renderer = Renderer {
             __marker: PhantomData,
             cx: self.cx,
+            source: self.source,
             stream: self.stream,
             column_alignment: self.column_alignment.clone(),
             cell_index: 0,
+            in_table_head: self.in_table_head,
             end_tag: self.end_tag,
             current_component: Some(description.name.clone()),
+            in_directive: false,
+            directive_end: None,
+            component_end: None,
+            ended: false,
+            depth: self.depth + 1,
+            document: self.document,
         };
-        let children = self.cx.el_fragment(sub_renderer.collect());
+        let children = self.cx.el_fragment(sub_renderer.by_ref().collect());
+        self.ended = sub_renderer.ended;
+        let end = sub_renderer.component_end.unwrap_or(self.source.len());
 
         let props = MdComponentProps {
             attributes: description.attributes,
             children,
+            source: self.source.get(start..end).unwrap_or_default().to_string(),
         };
 
         match self.cx.render_custom_component(name, props) {
@@ -390,6 +1332,7 @@ where
         let props = MdComponentProps {
             attributes: description.attributes,
             children: self.cx.el_empty(),
+            source: String::new(),
         };
 
         match self.cx.render_custom_component(name, props) {
@@ -401,74 +1344,516 @@ where
         }
     }
 
+    /// renders a directive moved out of its paragraph by `fold_directives`,
+    /// found at `range` in the source.
+    /// A `:::details Title` line starts a collapsible section,
+    /// and any other `:::name{attributes}` line starts a container
+    /// rendered by [`Context::render_directive`], or in a `<div>` by default.
+    /// They contain the markdown until the matching `:::` line.
+    /// If the `:::` line is missing, they end with the enclosing element
+    fn directive(&mut self, directive: Directive, range: Range<usize>) -> F::View {
+        let cx = self.cx;
+        if directive == Directive::End {
+            // the end of a directive that was started inside another element
+            return cx.el(Paragraph, cx.el_text(":::".into()));
+        }
+        if self.depth_exceeded() {
+            self.skip_nested(
+                |e| directive_line(e).is_some_and(|d| d != Directive::End),
+                |e| directive_line(e) == Some(Directive::End),
+            );
+            return self.depth_limit(self.end_tag);
+        }
+
+        let mut sub_renderer = Renderer {
+            __marker: PhantomData,
+            cx,
+            source: self.source,
+            stream: self.stream,
+            column_alignment: self.column_alignment.clone(),
+            cell_index: 0,
+            in_table_head: self.in_table_head,
+            end_tag: self.end_tag,
+            current_component: None,
+            in_directive: true,
+            directive_end: None,
+            component_end: None,
+            ended: false,
+            depth: self.depth + 1,
+            document: self.document,
+        };
+        let mut children: Vec<_> = sub_renderer.by_ref().collect();
+        self.ended = sub_renderer.ended;
+        let end = match sub_renderer.directive_end {
+            Some(end) => end,
+            None => {
+                self.document.debug(|| DebugEvent::ParseWarning {
+                    message: "the directive is never closed by a `:::` line".into(),
+                    range: range.clone(),
+                });
+                range.end
+            }
+        };
+
+        match directive {
+            Directive::Details(title) => {
+                let title = title.unwrap_or("Details").to_string();
+                children.insert(0, cx.el(Summary, cx.el_text(title.into())));
+                cx.el(Details, cx.el_fragment(children))
+            }
+            Directive::Container { name, attributes } => {
+                let children = cx.el_fragment(children);
+                let description = DirectiveDescription {
+                    name: name.to_string(),
+                    attributes,
+                    children: children.clone(),
+                    range: range.start..end,
+                };
+                cx.render_directive(description)
+                    .unwrap_or_else(|| cx.el(Div, children))
+            }
+            Directive::End => unreachable!(),
+        }
+    }
+
     /// renders events in a new renderer,
-    /// recursively, until the end of the tag
+    /// recursively, until the end of the tag.
+    /// Past [`crate::MarkdownProps::max_nesting_depth`] renderers,
+    /// the events are skipped and replaced by a placeholder,
+    /// so that deeply nested documents can't overflow the stack
     fn children(&mut self, tag: Tag<'a>) -> F::View {
+        if self.depth_exceeded() {
+            let end = tag.to_end();
+            self.skip_children(tag);
+            return self.depth_limit(Some(end));
+        }
+
         let sub_renderer = Renderer {
             __marker: PhantomData,
             cx: self.cx,
+            source: self.source,
             stream: self.stream,
             column_alignment: self.column_alignment.clone(),
             cell_index: 0,
+            in_table_head: self.in_table_head,
             end_tag: Some(tag.to_end()),
-            current_component: self.current_component.clone(),
+            current_component: None,
+            in_directive: false,
+            directive_end: None,
+            component_end: None,
+            ended: false,
+            depth: self.depth + 1,
+            document: self.document,
         };
         self.cx.el_fragment(sub_renderer.collect())
     }
 
-    /// extract the text from the next text event
-    fn children_text(&mut self, tag: Tag<'a>) -> Option<String> {
-        let text = match self.stream.next() {
-            Some((Event::Text(s), _)) => Some(s.to_string()),
-            None => None,
-            _ => panic!("expected string event, got something else"),
+    /// wether this renderer is nested in
+    /// [`crate::MarkdownProps::max_nesting_depth`] renderers or more,
+    /// so that it can't create another one
+    fn depth_exceeded(&self) -> bool {
+        let max_depth = self
+            .cx
+            .props()
+            .max_nesting_depth
+            .unwrap_or(DEFAULT_MAX_NESTING_DEPTH);
+        self.depth >= max_depth
+    }
+
+    /// the placeholder of the content skipped past the depth limit,
+    /// in an element that ends with `end`:
+    /// a `<span>` if the element only contains inline content, like a paragraph,
+    /// or else a `<div>`
+    fn depth_limit(&mut self, end: Option<TagEnd>) -> F::View {
+        self.document.issues.push(RenderIssue::DepthLimitExceeded);
+        let element = if end.is_some_and(has_inline_content) {
+            Span
+        } else {
+            Div
+        };
+        self.cx.el_with_attributes(
+            element,
+            self.cx.el_empty(),
+            ElementAttributes {
+                classes: vec![self.cx.props().class_name("md-depth-limit")],
+                ..Default::default()
+            },
+        )
+    }
+
+    /// consumes the events of a directive or a custom component past the depth limit,
+    /// until the event that `closes` it, without rendering them.
+    /// The nested directives or components that `opens` starts need their own closing event.
+    /// Like a renderer, it stops at the end of the enclosing element,
+    /// and it ignores the html blocks.
+    /// Returns the range of the closing event, if there is one
+    fn skip_nested(
+        &mut self,
+        opens: impl Fn(&Event) -> bool,
+        closes: impl Fn(&Event) -> bool,
+    ) -> Option<Range<usize>> {
+        let mut nested = 0;
+        let mut tags = 0;
+        for (event, range) in self.stream.by_ref() {
+            match event {
+                // html blocks are transparent, like in `next`
+                Event::Start(Tag::HtmlBlock) | Event::End(TagEnd::HtmlBlock) => continue,
+                Event::Start(_) => tags += 1,
+                Event::End(_) if tags == 0 => {
+                    self.ended = true;
+                    return None;
+                }
+                Event::End(_) => tags -= 1,
+                _ if closes(&event) && nested == 0 => return Some(range),
+                _ if closes(&event) => nested -= 1,
+                _ if opens(&event) => nested += 1,
+                _ => (),
+            }
+            self.document.debug(|| DebugEvent::UnhandledEvent {
+                event: format!("{event:?}"),
+                range,
+            })
+        }
+        None
+    }
+
+    /// renders the children of `tag` like [`Self::children`],
+    /// with the clicks on their text reported as clicks on `element`
+    fn clickable_children(&mut self, tag: Tag<'a>, element: ClickedElement) -> F::View {
+        let outer = core::mem::replace(&mut self.document.clicked_element, element);
+        let children = self.children(tag);
+        self.document.clicked_element = outer;
+        children
+    }
+
+    /// renders the text `s`, found at `range` in the source,
+    /// with the mentions like `@alice` as links
+    /// if [`crate::MarkdownProps::mentions`] is set
+    fn text(&mut self, s: CowStr<'a>, range: Range<usize>) -> F::View {
+        let cx = self.cx;
+        let Some(config) = cx
+            .props()
+            .mentions
+            .filter(|_| self.document.link_depth == 0)
+        else {
+            return self.key_text(s, range);
+        };
+        let parts = split_mentions(&s, &config);
+        if !parts.iter().any(|(_, kind)| kind.is_some()) {
+            return self.key_text(s, range);
+        }
+        let parts = parts
+            .into_iter()
+            .map(|(r, kind)| {
+                let part_range = sub_range(&s, &range, &r);
+                let text: CowStr = s[r].to_string().into();
+                match kind {
+                    Some(kind) => self.mention(text, part_range, kind),
+                    None => self.key_text(text, part_range),
+                }
+            })
+            .collect();
+        cx.el_fragment(parts)
+    }
+
+    /// renders the mention or the hashtag `s`, like `@alice` or `#topic`,
+    /// as a link to the url given by the context,
+    /// or in a span with the `md-mention-broken` or `md-hashtag-broken` class
+    /// if it can't be resolved
+    fn mention(&mut self, s: CowStr<'a>, range: Range<usize>, kind: MentionKind) -> F::View {
+        let cx = self.cx;
+        let props = cx.props();
+        let name = &s[s.chars().next().map_or(0, char::len_utf8)..];
+        let class = match kind {
+            MentionKind::Mention => "md-mention",
+            MentionKind::Hashtag => "md-hashtag",
         };
+        let url = self
+            .document
+            .resolved_mentions
+            .entry((kind, name.to_string()))
+            .or_insert_with(|| match kind {
+                MentionKind::Mention => cx.resolve_mention(name),
+                MentionKind::Hashtag => cx.resolve_hashtag(name),
+            })
+            .clone();
+        let content = cx.render_text(
+            s.clone(),
+            range.clone(),
+            self.document.clicked_element.clone(),
+        );
+        let Some(url) = url else {
+            let attributes = ElementAttributes {
+                classes: vec![props.class_name(&format!("{class}-broken"))],
+                ..Default::default()
+            };
+            return cx.el_with_attributes(Span, content, attributes);
+        };
+        // like the other links, see `Context::render_link`
+        let url = match props.base_url {
+            Some(base) => resolve_url(base, &url),
+            None => url,
+        };
+        let allowed_schemes = props.allowed_url_schemes.unwrap_or(DEFAULT_URL_SCHEMES);
+        let url = sanitize_url(url, allowed_schemes, false);
+        let mut attributes = link_attributes(props.link_target, &url);
+        rel_attributes(&mut attributes, props.link_rel_policy, &url);
+        attributes.classes.push(class.into());
+        for class in &mut attributes.classes {
+            *class = props.class_name(class)
+        }
+        let on_click = cx.make_link_click_handler(url.clone(), range);
+        cx.el_a(content, url, attributes, on_click)
+    }
+
+    /// renders the text `s`, found at `range` in the source,
+    /// with the keys like `++Ctrl+C++` in `<kbd>` elements
+    /// if [`crate::MarkdownProps::kbd`] is set
+    fn key_text(&self, s: CowStr<'a>, range: Range<usize>) -> F::View {
+        let cx = self.cx;
+        if !cx.props().kbd {
+            return self.marked_text(s, range);
+        }
+        let parts = split_keys(&s);
+        if !parts.iter().any(|(_, key)| *key) {
+            return self.marked_text(s, range);
+        }
+        let parts = parts
+            .into_iter()
+            .map(|(r, key)| {
+                let part_range = sub_range(&s, &range, &r);
+                let text: CowStr = s[r].to_string().into();
+                match key {
+                    true => cx.el(
+                        Kbd,
+                        cx.render_text(text, part_range, self.document.clicked_element.clone()),
+                    ),
+                    false => self.marked_text(text, part_range),
+                }
+            })
+            .collect();
+        cx.el_fragment(parts)
+    }
+
+    /// renders the text `s`, found at `range` in the source,
+    /// with the `==highlighted==` parts in `<mark>` elements
+    /// if [`crate::MarkdownProps::highlight_marks`] is set
+    fn marked_text(&self, s: CowStr<'a>, range: Range<usize>) -> F::View {
+        let cx = self.cx;
+        if !cx.props().highlight_marks {
+            return self.abbreviated_text(s, range);
+        }
+        let parts = split_marks(&s);
+        if !parts.iter().any(|(_, marked)| *marked) {
+            return self.abbreviated_text(s, range);
+        }
+        let parts = parts
+            .into_iter()
+            .map(|(r, marked)| {
+                let part_range = sub_range(&s, &range, &r);
+                let text = self.abbreviated_text(s[r].to_string().into(), part_range);
+                match marked {
+                    true => cx.el(Mark, text),
+                    false => text,
+                }
+            })
+            .collect();
+        cx.el_fragment(parts)
+    }
+
+    /// renders the text `s`, found at `range` in the source,
+    /// with the abbreviations of the document in `<abbr>` elements
+    fn abbreviated_text(&self, s: CowStr<'a>, range: Range<usize>) -> F::View {
+        let cx = self.cx;
+        let parts = split_abbreviations(&s, &self.document.abbreviations);
+        if !parts.iter().any(|(_, expansion)| expansion.is_some()) {
+            return cx.render_text(s, range, self.document.clicked_element.clone());
+        }
+        let parts = parts
+            .into_iter()
+            .map(|(r, expansion)| {
+                let part_range = sub_range(&s, &range, &r);
+                let text = cx.render_text(
+                    s[r].to_string().into(),
+                    part_range,
+                    self.document.clicked_element.clone(),
+                );
+                match expansion {
+                    Some(expansion) => cx.el_with_attributes(
+                        Abbr,
+                        text,
+                        ElementAttributes {
+                            title: Some(expansion.to_string()),
+                            ..Default::default()
+                        },
+                    ),
+                    None => text,
+                }
+            })
+            .collect();
+        cx.el_fragment(parts)
+    }
 
-        self.assert_closing_tag(tag.to_end());
+    /// renders a reference to the footnote `label`,
+    /// as a superscript link to its definition
+    fn footnote_reference(&mut self, label: &str) -> F::View {
+        let cx = self.cx;
+        let (n, first) = self.document.footnote_number(label);
+        let title = self
+            .document
+            .footnote_texts
+            .get(&label.to_lowercase())
+            .cloned();
+        let link = cx.el_a(
+            cx.el_text(n.to_string().into()),
+            format!("#fn-{n}"),
+            LinkAttributes::default(),
+            None,
+        );
+        cx.el_with_attributes(
+            Sup,
+            link,
+            ElementAttributes {
+                classes: vec![cx.props().class_name("footnote-reference")],
+                // only the first reference is the target of the back link,
+                // to keep ids unique
+                id: first.then(|| format!("fnref-{n}")),
+                title,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// consumes the events until the end of `tag`, that only contains text, like a code block,
+    /// and returns all that text exactly. None if there is none
+    fn children_text(&mut self, tag: Tag<'a>) -> Option<String> {
+        // the text of a code block nested in a list or a quote is split in several events,
+        // since the indentation or the `>` of every line is not part of it
+        let end = tag.to_end();
+        let mut text: Option<String> = None;
+        for (event, _) in self.stream.by_ref() {
+            match event {
+                Event::Text(s) => text.get_or_insert_with(String::new).push_str(&s),
+                Event::End(t) if t == end => break,
+                _ => panic!("expected string event, got something else"),
+            }
+        }
         text
     }
 
-    // check that the closing tag is what was expected
-    fn assert_closing_tag(&mut self, end: TagEnd) {
-        let end_tag = &self
-            .stream
-            .next()
-            .expect("this event should be the closing tag")
-            .0;
-        assert_eq!(end_tag, &Event::End(end));
+    /// consumes the events until the end of `tag`,
+    /// and returns their text content without formatting
+    fn children_plain_text(&mut self, tag: Tag<'a>) -> String {
+        let end = tag.to_end();
+        let mut text = String::new();
+        let mut depth = 0;
+        for (event, _) in self.stream.by_ref() {
+            match event {
+                Event::Start(t) if t.to_end() == end => depth += 1,
+                Event::End(t) if t == end => {
+                    if depth == 0 {
+                        break;
+                    }
+                    depth -= 1
+                }
+                Event::Text(s) | Event::Code(s) => text.push_str(&s),
+                Event::SoftBreak | Event::HardBreak => text.push(' '),
+                _ => (),
+            }
+        }
+        text
+    }
+
+    /// the direction of the paragraphs, headings and list items,
+    /// see [`crate::MarkdownProps::auto_block_direction`]
+    fn block_direction(&self) -> Option<Direction> {
+        self.cx
+            .props()
+            .auto_block_direction
+            .then_some(Direction::Auto)
+    }
+
+    /// the `data-source-start` and `data-source-end` attributes of a top-level block
+    /// at `range` in the markdown source, see [`crate::MarkdownProps::source_positions`]
+    fn source_position(&self, range: &Range<usize>) -> Vec<(&'static str, String)> {
+        if self.depth > 0 || !self.cx.props().source_positions {
+            return Vec::new();
+        }
+        vec![
+            ("data-source-start", range.start.to_string()),
+            ("data-source-end", range.end.to_string()),
+        ]
+    }
+
+    /// the attributes set by a block like `{.lead}` on the element at `position`,
+    /// see [`crate::MarkdownProps::inline_attributes`]
+    fn block_attributes(&self, position: usize) -> BlockAttributes {
+        self.document
+            .block_attributes
+            .get(&position)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// consumes the events until the end of `tag`, without rendering them.
+    /// They are reported as [`DebugEvent::UnhandledEvent`]s
+    fn skip_children(&mut self, tag: Tag<'a>) {
+        let end = tag.to_end();
+        let mut depth = 0;
+        for (event, range) in self.stream.by_ref() {
+            match event {
+                Event::Start(_) => depth += 1,
+                Event::End(t) if depth == 0 => {
+                    assert_eq!(t, end);
+                    break;
+                }
+                Event::End(_) => {
+                    depth -= 1;
+                    continue;
+                }
+                _ => (),
+            }
+            self.document.debug(|| DebugEvent::UnhandledEvent {
+                event: format!("{event:?}"),
+                range,
+            })
+        }
     }
 
+    /// renders `tag` and its children.
+    /// The elements with attributes are rendered by their own methods,
+    /// so that the stack frame of this function, used by every nested element, stays small
     fn render_tag(&mut self, tag: Tag<'a>, range: Range<usize>) -> Result<F::View, HtmlError> {
-        let mut cx = self.cx;
+        let cx = self.cx;
         Ok(match tag.clone() {
-            Tag::HtmlBlock => self.children(tag),
-            Tag::Paragraph => cx.el(Paragraph, self.children(tag)),
-            Tag::Heading { level, .. } => cx.el(Heading(level as u8), self.children(tag)),
-            Tag::BlockQuote(_) => cx.el(BlockQuote, self.children(tag)),
-            Tag::CodeBlock(k) => {
-                render_code_block(cx, self.children_text(tag).unwrap_or_default(), &k, range)
+            Tag::HtmlBlock => unreachable!("html blocks are skipped by `next`"),
+            // the captions of tables are moved inside them by `fold_captions`
+            Tag::Paragraph if self.end_tag == Some(TagEnd::Table) => {
+                cx.el(Caption, self.children(tag))
             }
-            Tag::List(Some(n0)) => cx.el(Ol(n0 as i32), self.children(tag)),
-            Tag::List(None) => cx.el(Ul, self.children(tag)),
-            Tag::Item => cx.el(Li, self.children(tag)),
-            Tag::Table(align) => {
-                self.column_alignment = Some(align);
-                cx.el(Table, self.children(tag))
+            Tag::Paragraph if self.document.figures.contains_key(&range.start) => {
+                self.figure(tag, range.start)
             }
-            Tag::TableHead => cx.el(Thead, self.children(tag)),
-            Tag::TableRow => cx.el(Trow, self.children(tag)),
-            Tag::TableCell => {
-                let align = self.column_alignment.clone().unwrap()[self.cell_index];
-                self.cell_index += 1;
-                cx.el_with_attributes(
-                    Tcell,
-                    self.children(tag),
-                    ElementAttributes {
-                        style: Some(align_string(align).to_string()),
-                        ..Default::default()
-                    },
-                )
+            Tag::Paragraph => self.paragraph(tag, range),
+            Tag::Heading {
+                level,
+                classes,
+                attrs,
+                ..
+            } => self.heading(tag, level, classes, attrs, range),
+            Tag::BlockQuote(kind) => self.blockquote(tag, kind, range),
+            Tag::CodeBlock(k) => self.code_block(tag, k, range),
+            Tag::List(first) => self.list(tag, first, range),
+            Tag::Item => self.item(tag),
+            Tag::Table(align) => self.table(tag, align, range),
+            Tag::TableHead => {
+                self.in_table_head = true;
+                let head = cx.el(Thead, self.children(tag));
+                self.in_table_head = false;
+                head
             }
+            Tag::TableRow => cx.el(Trow, self.children(tag)),
+            Tag::TableCell => self.table_cell(tag, range),
             Tag::Emphasis => cx.el(Italics, self.children(tag)),
             Tag::Strong => cx.el(Bold, self.children(tag)),
             Tag::Strikethrough => cx.el(StrikeThrough, self.children(tag)),
@@ -476,60 +1861,422 @@ where
                 link_type,
                 dest_url,
                 title,
-                ..
+                id,
             } => {
-                let description = LinkDescription {
-                    url: dest_url.to_string(),
-                    title: title.to_string(),
-                    content: self.children(tag),
-                    link_type,
-                    image: true,
-                };
-                cx.render_link(description).map_err(HtmlError::Link)?
+                let reference = link_reference(link_type, &id);
+                self.image(tag, link_type, dest_url, title, reference, range)?
             }
             Tag::Link {
                 link_type,
                 dest_url,
                 title,
-                ..
+                id,
             } => {
-                let description = LinkDescription {
-                    url: dest_url.to_string(),
-                    title: title.to_string(),
-                    content: self.children(tag),
-                    link_type,
-                    image: false,
-                };
-                cx.render_link(description).map_err(HtmlError::Link)?
-            }
-            Tag::FootnoteDefinition(_) => {
-                return Err(HtmlError::not_implemented("footnote not implemented"))
-            }
-            Tag::MetadataBlock { .. } => {
-                if let Some(text) = self.children_text(tag) {
-                    cx.set_frontmatter(text)
-                }
-                cx.el_empty()
-            }
-            Tag::DefinitionList => {
-                return Err(HtmlError::not_implemented(
-                    "definition list not implemented",
-                ))
-            }
-            Tag::DefinitionListTitle => {
-                return Err(HtmlError::not_implemented(
-                    "definition list not implemented",
-                ))
-            }
-            Tag::DefinitionListDefinition => {
-                return Err(HtmlError::not_implemented(
-                    "definition list not implemented",
-                ))
+                let reference = link_reference(link_type, &id);
+                self.link(tag, link_type, dest_url, title, reference, range)?
             }
-            Tag::Superscript => {
-                return Err(HtmlError::not_implemented("superscript not implemented"))
+            Tag::FootnoteDefinition(label) => self.footnote_definition(tag, label),
+            Tag::MetadataBlock(_) => {
+                unreachable!("metadata blocks are removed by `take_frontmatter`")
             }
-            Tag::Subscript => return Err(HtmlError::not_implemented("subscript not implemented")),
+            Tag::DefinitionList => cx.el(Dl, self.children(tag)),
+            Tag::DefinitionListTitle => cx.el(Dt, self.children(tag)),
+            Tag::DefinitionListDefinition => cx.el(Dd, self.children(tag)),
+            Tag::Superscript => cx.el(Sup, self.children(tag)),
+            Tag::Subscript => cx.el(Sub, self.children(tag)),
         })
     }
+
+    /// renders the code block `tag` of kind `kind`, found at `range`
+    fn code_block(
+        &mut self,
+        tag: Tag<'a>,
+        kind: CodeBlockKind<'a>,
+        range: Range<usize>,
+    ) -> F::View {
+        let source = self.children_text(tag).unwrap_or_default();
+        render_code_block(self.cx, source, &kind, range)
+    }
+
+    /// renders the footnote definition `tag` at the end of the document
+    /// and leaves nothing in its place
+    fn footnote_definition(&mut self, tag: Tag<'a>, label: CowStr<'a>) -> F::View {
+        let (n, _) = self.document.footnote_number(&label);
+        let content = self.children(tag);
+        self.document.footnotes.push((n, content));
+        self.cx.el_empty()
+    }
+
+    /// renders the paragraph `tag` starting at `start`, made of a titled image,
+    /// as a figure with the title of the image as its caption
+    fn figure(&mut self, tag: Tag<'a>, start: usize) -> F::View {
+        let cx = self.cx;
+        let image = self.children(tag);
+        let caption = self.document.figures[&start].clone();
+        let caption = cx.el(Figcaption, cx.el_text(caption.into()));
+        cx.el(Figure, cx.el_fragment(vec![image, caption]))
+    }
+
+    /// renders the paragraph `tag` found at `range`,
+    /// with the attributes of its attribute block
+    fn paragraph(&mut self, tag: Tag<'a>, range: Range<usize>) -> F::View {
+        let block = self.block_attributes(range.start);
+        let children = self.children(tag);
+        self.cx.el_with_attributes(
+            Paragraph,
+            children,
+            ElementAttributes {
+                id: block.id,
+                classes: block.classes,
+                dir: self.block_direction(),
+                data: self.source_position(&range),
+                source_attributes: source_attributes(&block.attributes),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// renders the blockquote `tag` found at `range`
+    /// with the callback of the context if there is one,
+    /// or else as a callout if it has a `kind`,
+    /// or with the attributes of its attribute block
+    fn blockquote(
+        &mut self,
+        tag: Tag<'a>,
+        kind: Option<BlockQuoteKind>,
+        range: Range<usize>,
+    ) -> F::View {
+        let cx = self.cx;
+        let block = self.block_attributes(range.start);
+        let data = self.source_position(&range);
+        let children = self.children(tag);
+        let description = BlockquoteDescription {
+            kind,
+            children: children.clone(),
+            range,
+        };
+        cx.render_blockquote(description)
+            .unwrap_or_else(|| match kind {
+                Some(kind) => render_callout(cx, kind, children),
+                None => cx.el_with_attributes(
+                    BlockQuote,
+                    children,
+                    ElementAttributes {
+                        id: block.id,
+                        classes: block.classes,
+                        data,
+                        source_attributes: source_attributes(&block.attributes),
+                        ..Default::default()
+                    },
+                ),
+            })
+    }
+
+    /// renders the heading `tag` of level `level`, starting at `range`,
+    /// with the `classes` and the attributes `attrs` written in the source
+    fn heading(
+        &mut self,
+        tag: Tag<'a>,
+        level: HeadingLevel,
+        classes: Vec<CowStr<'a>>,
+        attrs: Vec<(CowStr<'a>, Option<CowStr<'a>>)>,
+        range: Range<usize>,
+    ) -> F::View {
+        let cx = self.cx;
+        let level = (level as u8)
+            .saturating_add(cx.props().heading_offset)
+            .min(6);
+        let id = heading_id(&self.document.headings, range.start);
+        let element = ClickedElement::Heading { level };
+        let mut children = self.clickable_children(tag, element);
+        if let Some(number) = self.document.heading_numbers.get(&range.start) {
+            let number = cx.el_with_attributes(
+                Span,
+                cx.el_text(format!("{number} ").into()),
+                ElementAttributes {
+                    classes: vec![cx.props().class_name("md-heading-number")],
+                    ..Default::default()
+                },
+            );
+            children = cx.el_fragment(vec![number, children]);
+        }
+        if let Some(id) = id.clone().filter(|_| cx.props().heading_anchor_links) {
+            let anchor = cx.el_a(
+                cx.el_text("#".into()),
+                format!("#{id}"),
+                LinkAttributes {
+                    classes: vec![cx.props().class_name("md-heading-anchor")],
+                    ..Default::default()
+                },
+                cx.make_permalink_copy_handler(id),
+            );
+            children = cx.el_fragment(vec![children, anchor]);
+        }
+        cx.el_with_attributes(
+            Heading(level),
+            children,
+            ElementAttributes {
+                id,
+                classes: classes.iter().map(|c| c.to_string()).collect(),
+                aria_level: Some(level),
+                dir: self.block_direction(),
+                data: self.source_position(&range),
+                source_attributes: source_attributes(&attrs),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// renders the list `tag` found at `range`,
+    /// numbered from `first` if it is ordered
+    fn list(&mut self, tag: Tag<'a>, first: Option<u64>, range: Range<usize>) -> F::View {
+        let cx = self.cx;
+        let props = cx.props();
+        let tightness = if self.document.loose_lists.contains(&range.start) {
+            "md-list-loose"
+        } else {
+            "md-list-tight"
+        };
+        let classes = props
+            .list_tightness_classes
+            .then(|| props.class_name(tightness))
+            .into_iter()
+            .collect();
+        let element = match first {
+            Some(n0) => Ol(n0 as i32),
+            None => Ul,
+        };
+        cx.el_with_attributes(
+            element,
+            self.children(tag),
+            ElementAttributes {
+                classes,
+                list_type: first
+                    .and(props.ordered_list_style)
+                    .map(ListStyle::type_attribute),
+                data: self.source_position(&range),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// renders the table `tag` found at `range`, with the alignment `align` of its columns
+    fn table(&mut self, tag: Tag<'a>, align: Vec<Alignment>, range: Range<usize>) -> F::View {
+        self.column_alignment = Some(align);
+        let data = self.source_position(&range);
+        let children = self.children(tag);
+        self.cx.el_with_attributes(
+            Table,
+            children,
+            ElementAttributes {
+                data,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// renders the list item `tag`
+    fn item(&mut self, tag: Tag<'a>) -> F::View {
+        self.document.task_items.push(None);
+        let children = self.children(tag);
+        // the item of a task labels its checkbox
+        let task = self.document.task_items.pop().flatten();
+        self.cx.el_with_attributes(
+            Li,
+            children,
+            ElementAttributes {
+                id: task.map(crate::task_item_id),
+                dir: self.block_direction(),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// renders the table cell `tag` found at `range`,
+    /// aligned like its column unless it has its own alignment
+    fn table_cell(&mut self, tag: Tag<'a>, range: Range<usize>) -> F::View {
+        let align = match self.document.cell_alignments.get(&range.start) {
+            Some(align) => *align,
+            None => self
+                .column_alignment
+                .as_ref()
+                .and_then(|a| a.get(self.cell_index).copied())
+                .unwrap_or(Alignment::None),
+        };
+        self.cell_index += 1;
+        let element = if self.in_table_head { TheadCell } else { Tcell };
+        let clicked = ClickedElement::TableCell {
+            header: self.in_table_head,
+        };
+        let children = self.clickable_children(tag, clicked);
+        self.cx.el_with_attributes(
+            element,
+            children,
+            ElementAttributes {
+                style: align_string(align).map(|x| x.to_string()),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// renders the image `tag`, whose alt text is the text of its children
+    fn image(
+        &mut self,
+        tag: Tag<'a>,
+        link_type: LinkType,
+        dest_url: CowStr<'a>,
+        title: CowStr<'a>,
+        reference: Option<String>,
+        range: Range<usize>,
+    ) -> Result<F::View, HtmlError> {
+        let alt = self.children_plain_text(tag);
+        let description = LinkDescription {
+            url: dest_url.to_string(),
+            title: title.to_string(),
+            content: self.cx.el_text(alt.clone().into()),
+            link_type,
+            reference,
+            image: true,
+            alt,
+            wikilink: None,
+            range: range.clone(),
+        };
+        let index = self.document.image_count;
+        self.document.image_count += 1;
+        self.cx
+            .render_image(description, index, range)
+            .map_err(HtmlError::Link)
+    }
+
+    /// renders the link `tag`, without turning the mentions of its text into links
+    fn link(
+        &mut self,
+        tag: Tag<'a>,
+        link_type: LinkType,
+        dest_url: CowStr<'a>,
+        title: CowStr<'a>,
+        reference: Option<String>,
+        range: Range<usize>,
+    ) -> Result<F::View, HtmlError> {
+        self.document.link_depth += 1;
+        let element = ClickedElement::Link {
+            url: dest_url.to_string(),
+        };
+        let content = self.clickable_children(tag, element);
+        self.document.link_depth -= 1;
+        let wikilink = match link_type {
+            LinkType::WikiLink { .. } => self
+                .document
+                .resolved_wikilinks
+                .entry(dest_url.to_string())
+                .or_insert_with(|| self.cx.resolve_wikilink(&dest_url))
+                .clone(),
+            _ => None,
+        };
+        let description = LinkDescription {
+            url: dest_url.to_string(),
+            title: title.to_string(),
+            content,
+            link_type,
+            reference,
+            image: false,
+            alt: String::new(),
+            wikilink,
+            range,
+        };
+        self.cx.render_link(description).map_err(HtmlError::Link)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn language_of_fenced_block() {
+        let kind = CodeBlockKind::Fenced("python".into());
+        assert_eq!(code_block_language(&kind), Some("python"));
+    }
+
+    #[test]
+    fn language_ignores_extra_info() {
+        let kind = CodeBlockKind::Fenced("rust ignore".into());
+        assert_eq!(code_block_language(&kind), Some("rust"));
+        let kind = CodeBlockKind::Fenced("rust,no_run".into());
+        assert_eq!(code_block_language(&kind), Some("rust"));
+    }
+
+    #[test]
+    fn no_language() {
+        assert_eq!(code_block_language(&CodeBlockKind::Indented), None);
+        assert_eq!(code_block_language(&CodeBlockKind::Fenced("".into())), None);
+        let kind = CodeBlockKind::Fenced("title=\"main.rs\"".into());
+        assert_eq!(code_block_language(&kind), None);
+    }
+
+    #[test]
+    fn title_of_fenced_block() {
+        let kind = CodeBlockKind::Fenced("rust title=\"src/main.rs\"".into());
+        assert_eq!(code_block_title(&kind), Some("src/main.rs".into()));
+        let kind = CodeBlockKind::Fenced("toml {2} filename=Cargo.toml".into());
+        assert_eq!(code_block_title(&kind), Some("Cargo.toml".into()));
+        let kind = CodeBlockKind::Fenced("text title=\"my notes\"".into());
+        assert_eq!(code_block_title(&kind), Some("my notes".into()));
+    }
+
+    #[test]
+    fn no_title() {
+        let kind = CodeBlockKind::Fenced("rust subtitle=x".into());
+        assert_eq!(code_block_title(&kind), None);
+        let kind = CodeBlockKind::Fenced("rust title=\"\"".into());
+        assert_eq!(code_block_title(&kind), None);
+        assert_eq!(code_block_title(&CodeBlockKind::Indented), None);
+    }
+
+    #[test]
+    fn dedented() {
+        assert_eq!(
+            dedent("    fn a() {\n\n        b\n    }\n"),
+            "fn a() {\n\n    b\n}\n"
+        );
+        assert_eq!(dedent("  a\n\tb\n"), "  a\n\tb\n");
+        assert_eq!(dedent("\t\ta\n\t  b\n"), "\ta\n  b\n");
+        assert_eq!(dedent("a\n  b"), "a\n  b");
+    }
+
+    #[test]
+    fn trimmed() {
+        assert_eq!(trim_blank_lines("a\n  b  \n\n \n"), "a\n  b\n");
+        assert_eq!(trim_blank_lines("a"), "a");
+        assert_eq!(trim_blank_lines("\n\n"), "");
+    }
+
+    #[test]
+    fn highlight_single_line() {
+        let kind = CodeBlockKind::Fenced("rust {2}".into());
+        assert_eq!(highlighted_lines(&kind), vec![2..=2]);
+    }
+
+    #[test]
+    fn highlight_line_range() {
+        let kind = CodeBlockKind::Fenced("rust{1-3}".into());
+        assert_eq!(highlighted_lines(&kind), vec![1..=3]);
+    }
+
+    #[test]
+    fn highlight_lines_and_ranges() {
+        let kind = CodeBlockKind::Fenced("rust {1, 4-5}".into());
+        assert_eq!(highlighted_lines(&kind), vec![1..=1, 4..=5]);
+        let kind = CodeBlockKind::Fenced("{1,x,4-}".into());
+        assert_eq!(highlighted_lines(&kind), vec![1..=1]);
+    }
+
+    #[test]
+    fn no_highlighted_lines() {
+        let kind = CodeBlockKind::Fenced("rust".into());
+        assert!(highlighted_lines(&kind).is_empty());
+        let kind = CodeBlockKind::Fenced("rust {}".into());
+        assert!(highlighted_lines(&kind).is_empty());
+        assert!(highlighted_lines(&CodeBlockKind::Indented).is_empty());
+    }
 }
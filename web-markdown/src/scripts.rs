@@ -0,0 +1,168 @@
+use core::ops::Range;
+
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+
+/// pulldown_cmark only recognizes `~sub~` and `^sup^`
+/// when they are not inside a word, so `H~2~O` stays plain text.
+/// `split_scripts(stream, subscript, superscript)` finds the remaining
+/// delimiters inside text and turns them into subscript and superscript tags.
+/// Like pandoc, the content between the delimiters must not contain spaces.
+pub fn split_scripts<'a>(
+    stream: Vec<(Event<'a>, Range<usize>)>,
+    subscript: bool,
+    superscript: bool,
+) -> Vec<(Event<'a>, Range<usize>)> {
+    let mut delimiters = Vec::new();
+    if subscript {
+        delimiters.push('~')
+    }
+    if superscript {
+        delimiters.push('^')
+    }
+    if delimiters.is_empty() {
+        return stream;
+    }
+
+    let mut result = Vec::with_capacity(stream.len());
+    let mut verbatim_depth = 0;
+    for (event, range) in merge_text(stream) {
+        match &event {
+            Event::Start(Tag::CodeBlock(_) | Tag::MetadataBlock(_)) => verbatim_depth += 1,
+            Event::End(TagEnd::CodeBlock | TagEnd::MetadataBlock(_)) => verbatim_depth -= 1,
+            Event::Text(text) if verbatim_depth == 0 && text.len() == range.len() => {
+                split_text(text, range.start, &delimiters, &mut result);
+                continue;
+            }
+            _ => (),
+        }
+        result.push((event, range))
+    }
+    result
+}
+
+/// merges the consecutive text events that map exactly to the source,
+/// since pulldown_cmark splits text on unmatched delimiters
+pub fn merge_text(stream: Vec<(Event<'_>, Range<usize>)>) -> Vec<(Event<'_>, Range<usize>)> {
+    let mut result: Vec<(Event, Range<usize>)> = Vec::with_capacity(stream.len());
+    for (event, range) in stream {
+        if let (Event::Text(text), Some((Event::Text(previous), previous_range))) =
+            (&event, result.last_mut())
+        {
+            if previous_range.end == range.start
+                && previous.len() == previous_range.len()
+                && text.len() == range.len()
+            {
+                *previous = CowStr::from(format!("{previous}{text}"));
+                previous_range.end = range.end;
+                continue;
+            }
+        }
+        result.push((event, range))
+    }
+    result
+}
+
+fn split_text<'a>(
+    text: &str,
+    offset: usize,
+    delimiters: &[char],
+    result: &mut Vec<(Event<'a>, Range<usize>)>,
+) {
+    let mut start = 0;
+    let mut i = 0;
+    while let Some((open, delimiter)) = text[i..]
+        .char_indices()
+        .find(|(_, c)| delimiters.contains(c))
+        .map(|(j, c)| (i + j, c))
+    {
+        let inner_start = open + delimiter.len_utf8();
+        let inner_len = text[inner_start..]
+            .find(|c: char| c == delimiter || c.is_whitespace())
+            .filter(|&n| n > 0 && text[inner_start + n..].starts_with(delimiter));
+
+        let Some(inner_len) = inner_len else {
+            i = inner_start;
+            continue;
+        };
+        let inner_end = inner_start + inner_len;
+        let close = inner_end + delimiter.len_utf8();
+
+        if start < open {
+            let before = text[start..open].to_string();
+            result.push((Event::Text(before.into()), offset + start..offset + open));
+        }
+        let (tag, end) = if delimiter == '~' {
+            (Tag::Subscript, TagEnd::Subscript)
+        } else {
+            (Tag::Superscript, TagEnd::Superscript)
+        };
+        let inner = text[inner_start..inner_end].to_string();
+        result.push((Event::Start(tag), offset + open..offset + close));
+        result.push((
+            Event::Text(inner.into()),
+            offset + inner_start..offset + inner_end,
+        ));
+        result.push((Event::End(end), offset + open..offset + close));
+
+        start = close;
+        i = close;
+    }
+
+    if start < text.len() {
+        let after = text[start..].to_string();
+        result.push((
+            Event::Text(after.into()),
+            offset + start..offset + text.len(),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pulldown_cmark::{Options, Parser};
+
+    fn events(source: &str) -> Vec<Event<'_>> {
+        let options = Options::ENABLE_SUBSCRIPT | Options::ENABLE_SUPERSCRIPT;
+        let stream = Parser::new_ext(source, options)
+            .into_offset_iter()
+            .collect();
+        split_scripts(stream, true, true)
+            .into_iter()
+            .map(|(e, _)| e)
+            .collect()
+    }
+
+    #[test]
+    fn inside_words() {
+        assert_eq!(
+            events("H~2~O")[1..6],
+            [
+                Event::Text("H".into()),
+                Event::Start(Tag::Subscript),
+                Event::Text("2".into()),
+                Event::End(TagEnd::Subscript),
+                Event::Text("O".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_spaces() {
+        assert_eq!(
+            events("x~a b~y 2^"),
+            [
+                Event::Start(Tag::Paragraph),
+                Event::Text("x~a b~y 2^".into()),
+                Event::End(TagEnd::Paragraph),
+            ]
+        );
+    }
+
+    #[test]
+    fn code_blocks() {
+        assert!(events("```\nH~2~O\n```")
+            .iter()
+            .all(|e| !matches!(e, Event::Start(Tag::Subscript))));
+    }
+}
@@ -0,0 +1,2816 @@
+//! A [`Context`] that renders markdown to a html string,
+//! without any dom or web framework.
+//! Useful for server-side rendering or static site generation.
+
+use core::cell::RefCell;
+use core::ops::Range;
+use std::collections::{BTreeMap, HashMap};
+
+#[cfg(feature = "debug")]
+use crate::DebugEvent;
+use crate::{
+    markdown_component, BlockquoteDescription, ClickedElement, CodeBlockDescription,
+    CommentDirective, ComponentCreationError, Context, CowStr, DirectiveDescription, DocumentStats,
+    ElementAttributes, ElementOverride, Frontmatter, Heading, HtmlElement, HtmlElementKind,
+    ImageAttributes, LinkAttributes, LinkDescription, LinkInfo, MarkdownProps, MathDescription,
+    MdComponentProps, StyleLink, WikilinkResolution,
+};
+
+/// The metadata collected while rendering a document to a string
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StringMetadata {
+    /// the frontmatter of the document, if there is one
+    pub frontmatter: Option<String>,
+    /// the frontmatter of the document parsed into key-value pairs,
+    /// if it is valid yaml or toml
+    pub parsed_frontmatter: Option<Frontmatter>,
+    /// the headings of the document
+    pub headings: Vec<Heading>,
+    /// the links that would be added to the page
+    pub links: Vec<StyleLink>,
+    /// the urls of the scripts that would be added to the page
+    pub scripts: Vec<String>,
+    /// the word count and reading time of the document
+    pub stats: DocumentStats,
+    /// the links and images of the document
+    pub document_links: Vec<LinkInfo>,
+    /// the html comments of the document that are directives, like `<!-- more -->`
+    pub comment_directives: Vec<CommentDirective>,
+    /// what happened while rendering the document, see [`Context::send_debug`].
+    /// It is only collected with the `debug` feature
+    #[cfg(feature = "debug")]
+    pub debug_events: Vec<DebugEvent>,
+}
+
+/// finds the page that a wikilink points to
+type WikilinkResolver = dyn Fn(&str) -> Option<WikilinkResolution>;
+
+/// finds the url of a mention or a hashtag, from its name
+type MentionResolver = dyn Fn(&str) -> Option<String>;
+
+/// renders a custom component to html, from its attributes and its rendered children
+pub type StringComponent =
+    dyn Fn(MdComponentProps<String>) -> Result<String, ComponentCreationError>;
+
+/// the custom components, by name
+pub type StringComponents = BTreeMap<String, Box<StringComponent>>;
+
+/// renders a container directive to html.
+/// Returns None to render it in a `<div>`
+pub type DirectiveRenderer = dyn Fn(DirectiveDescription<String>) -> Option<String>;
+
+/// renders a blockquote or a callout to html.
+/// Returns None to render it as usual
+pub type BlockquoteRenderer = dyn Fn(BlockquoteDescription<String>) -> Option<String>;
+
+/// renders an element to html instead of the default tag
+pub type ElementRenderer = dyn Fn(ElementOverride<String, ()>) -> String;
+
+/// the renderers of the elements, by kind of element
+pub type ElementOverrides = HashMap<HtmlElementKind, Box<ElementRenderer>>;
+
+/// A [`Context`] where views are html strings.
+/// Event handlers are ignored, since there is no dom to attach them to.
+#[derive(Clone, Copy)]
+pub struct StringContext<'p> {
+    props: &'p MarkdownProps,
+    metadata: &'p RefCell<StringMetadata>,
+    math_renderer: Option<&'p dyn Fn(MathDescription) -> String>,
+    code_block_renderer: Option<&'p dyn Fn(CodeBlockDescription) -> String>,
+    directive_renderer: Option<&'p DirectiveRenderer>,
+    blockquote_renderer: Option<&'p BlockquoteRenderer>,
+    wikilink_resolver: Option<&'p WikilinkResolver>,
+    mention_resolver: Option<&'p MentionResolver>,
+    hashtag_resolver: Option<&'p MentionResolver>,
+    components: Option<&'p StringComponents>,
+    element_overrides: Option<&'p ElementOverrides>,
+}
+
+impl<'p> StringContext<'p> {
+    pub fn new(props: &'p MarkdownProps, metadata: &'p RefCell<StringMetadata>) -> Self {
+        Self {
+            props,
+            metadata,
+            math_renderer: None,
+            code_block_renderer: None,
+            directive_renderer: None,
+            blockquote_renderer: None,
+            wikilink_resolver: None,
+            mention_resolver: None,
+            hashtag_resolver: None,
+            components: None,
+            element_overrides: None,
+        }
+    }
+
+    /// uses `renderer` to render maths to html, instead of katex
+    pub fn with_math_renderer(self, renderer: &'p dyn Fn(MathDescription) -> String) -> Self {
+        Self {
+            math_renderer: Some(renderer),
+            ..self
+        }
+    }
+
+    /// uses `renderer` to render code blocks to html, instead of syntect
+    pub fn with_code_block_renderer(
+        self,
+        renderer: &'p dyn Fn(CodeBlockDescription) -> String,
+    ) -> Self {
+        Self {
+            code_block_renderer: Some(renderer),
+            ..self
+        }
+    }
+
+    /// uses `renderer` to render the container directives like `:::name`,
+    /// instead of a `<div>`
+    pub fn with_directive_renderer(self, renderer: &'p DirectiveRenderer) -> Self {
+        Self {
+            directive_renderer: Some(renderer),
+            ..self
+        }
+    }
+
+    /// uses `renderer` to render the blockquotes and the callouts
+    pub fn with_blockquote_renderer(self, renderer: &'p BlockquoteRenderer) -> Self {
+        Self {
+            blockquote_renderer: Some(renderer),
+            ..self
+        }
+    }
+
+    /// uses `overrides` to render the elements of some kinds,
+    /// like every table, instead of the default tags
+    pub fn with_element_overrides(self, overrides: &'p ElementOverrides) -> Self {
+        Self {
+            element_overrides: Some(overrides),
+            ..self
+        }
+    }
+
+    /// uses `resolver` to find the pages that wikilinks point to
+    pub fn with_wikilink_resolver(self, resolver: &'p WikilinkResolver) -> Self {
+        Self {
+            wikilink_resolver: Some(resolver),
+            ..self
+        }
+    }
+
+    /// uses `resolver` to find the urls of the users mentioned like `@alice`
+    pub fn with_mention_resolver(self, resolver: &'p MentionResolver) -> Self {
+        Self {
+            mention_resolver: Some(resolver),
+            ..self
+        }
+    }
+
+    /// uses `resolver` to find the urls of the hashtags like `#topic`
+    pub fn with_hashtag_resolver(self, resolver: &'p MentionResolver) -> Self {
+        Self {
+            hashtag_resolver: Some(resolver),
+            ..self
+        }
+    }
+
+    /// renders the custom components called in the markdown with `components`
+    pub fn with_components(self, components: &'p StringComponents) -> Self {
+        Self {
+            components: Some(components),
+            ..self
+        }
+    }
+}
+
+/// `render_markdown_to_string(source, props)` renders the markdown `source` to a html string
+pub fn render_markdown_to_string(source: &str, props: &MarkdownProps) -> String {
+    render_markdown_to_string_with_metadata(source, props).0
+}
+
+/// same as [`render_markdown_to_string`],
+/// but also returns the metadata of the document
+pub fn render_markdown_to_string_with_metadata(
+    source: &str,
+    props: &MarkdownProps,
+) -> (String, StringMetadata) {
+    let metadata = RefCell::new(StringMetadata::default());
+    let html = markdown_component(StringContext::new(props, &metadata), source);
+    (html, metadata.into_inner())
+}
+
+/// escapes the html special characters of `text`,
+/// so that it can be used as text content or as an attribute value
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// the name of the html tag corresponding to `e`
+fn tag_name(e: &HtmlElement) -> String {
+    use HtmlElement::*;
+    match e {
+        Div => "div".into(),
+        Span => "span".into(),
+        Paragraph => "p".into(),
+        BlockQuote => "blockquote".into(),
+        Ul => "ul".into(),
+        Ol(_) => "ol".into(),
+        Li => "li".into(),
+        Heading(n) => format!("h{}", n.clamp(&1, &6)),
+        Table => "table".into(),
+        Thead => "thead".into(),
+        Trow => "tr".into(),
+        Tcell => "td".into(),
+        TheadCell => "th".into(),
+        Caption => "caption".into(),
+        Button => "button".into(),
+        Italics => "i".into(),
+        Bold => "b".into(),
+        StrikeThrough => "s".into(),
+        Sup => "sup".into(),
+        Sub => "sub".into(),
+        Pre => "pre".into(),
+        Code => "code".into(),
+        Dl => "dl".into(),
+        Dt => "dt".into(),
+        Dd => "dd".into(),
+        Details => "details".into(),
+        Summary => "summary".into(),
+        Abbr => "abbr".into(),
+        Mark => "mark".into(),
+        Kbd => "kbd".into(),
+        Figure => "figure".into(),
+        Figcaption => "figcaption".into(),
+    }
+}
+
+/// the attributes that are written without a value, like `checked`
+const BOOLEAN_ATTRIBUTES: &[&str] = &["disabled"];
+
+/// renders the attributes of an element, with a leading space before each
+fn attributes_string<H>(attributes: &ElementAttributes<H>) -> String {
+    let mut result = String::new();
+    if let Some(id) = &attributes.id {
+        result.push_str(&format!(" id=\"{}\"", escape(id)));
+    }
+    if !attributes.classes.is_empty() {
+        let classes = attributes.classes.join(" ");
+        result.push_str(&format!(" class=\"{}\"", escape(&classes)));
+    }
+    if let Some(style) = &attributes.style {
+        result.push_str(&format!(" style=\"{}\"", escape(style)));
+    }
+    for (name, value) in attributes.extra_attributes() {
+        if BOOLEAN_ATTRIBUTES.contains(&name) {
+            result.push_str(&format!(" {name}"));
+        } else {
+            result.push_str(&format!(" {name}=\"{}\"", escape(&value)));
+        }
+    }
+    for (name, value) in &attributes.source_attributes {
+        result.push_str(&format!(" {name}=\"{}\"", escape(value)));
+    }
+    result
+}
+
+impl<'a, 'p: 'a> Context<'a, 'static> for StringContext<'p> {
+    type View = String;
+
+    type Handler<T: 'static> = ();
+
+    type Event = ();
+
+    fn props(self) -> MarkdownProps {
+        self.props.clone()
+    }
+
+    fn set_frontmatter(&mut self, frontmatter: String) {
+        self.metadata.borrow_mut().frontmatter = Some(frontmatter)
+    }
+
+    fn set_parsed_frontmatter(&mut self, frontmatter: Frontmatter) {
+        self.metadata.borrow_mut().parsed_frontmatter = Some(frontmatter)
+    }
+
+    fn set_headings(&mut self, headings: Vec<Heading>) {
+        self.metadata.borrow_mut().headings = headings
+    }
+
+    fn set_stats(&mut self, stats: DocumentStats) {
+        self.metadata.borrow_mut().stats = stats
+    }
+
+    fn set_document_links(&mut self, links: Vec<LinkInfo>) {
+        self.metadata.borrow_mut().document_links = links
+    }
+
+    fn set_comment_directives(&mut self, directives: Vec<CommentDirective>) {
+        self.metadata.borrow_mut().comment_directives = directives
+    }
+
+    #[cfg(feature = "debug")]
+    fn send_debug(self, events: Vec<DebugEvent>) {
+        self.metadata.borrow_mut().debug_events = events
+    }
+
+    fn render_code_block(self, code_block: CodeBlockDescription) -> Option<Self::View> {
+        self.code_block_renderer.map(|f| f(code_block))
+    }
+
+    fn render_directive(self, directive: DirectiveDescription<String>) -> Option<Self::View> {
+        self.directive_renderer.and_then(|f| f(directive))
+    }
+
+    fn render_blockquote(self, blockquote: BlockquoteDescription<String>) -> Option<Self::View> {
+        self.blockquote_renderer.and_then(|f| f(blockquote))
+    }
+
+    fn render_math(self, math: MathDescription) -> Option<Self::View> {
+        self.math_renderer.map(|f| f(math))
+    }
+
+    fn resolve_wikilink(self, target: &str) -> Option<WikilinkResolution> {
+        match self.wikilink_resolver {
+            Some(f) => f(target),
+            None => Some(WikilinkResolution::identity(target)),
+        }
+    }
+
+    fn resolve_mention(self, name: &str) -> Option<String> {
+        self.mention_resolver.and_then(|f| f(name))
+    }
+
+    fn resolve_hashtag(self, tag: &str) -> Option<String> {
+        self.hashtag_resolver.and_then(|f| f(tag))
+    }
+
+    fn mount_dynamic_link(self, link: StyleLink) {
+        self.metadata.borrow_mut().links.push(link)
+    }
+
+    fn is_link_mounted(self, link: &StyleLink) -> bool {
+        let metadata = self.metadata.borrow();
+        metadata.links.iter().any(|l| l.href == link.href)
+    }
+
+    fn mount_dynamic_script(self, src: String) {
+        let mut metadata = self.metadata.borrow_mut();
+        if !metadata.scripts.contains(&src) {
+            metadata.scripts.push(src)
+        }
+    }
+
+    fn render_links(self, _link: LinkDescription<Self::View>) -> Result<Self::View, String> {
+        Err("custom links are not supported when rendering to a string".into())
+    }
+
+    fn call_handler<T: 'static>(_callback: &Self::Handler<T>, _input: T) {}
+
+    fn make_md_handler(
+        self,
+        _position: Range<usize>,
+        _element: ClickedElement,
+        _stop_propagation: bool,
+    ) -> Self::Handler<Self::Event> {
+    }
+
+    #[cfg(feature = "debug")]
+    fn send_debug_info(self, _info: Vec<String>) {}
+
+    fn el_with_attributes(
+        self,
+        e: HtmlElement,
+        inside: Self::View,
+        attributes: ElementAttributes<()>,
+    ) -> Self::View {
+        if let Some(f) = self.element_overrides.and_then(|o| o.get(&e.kind())) {
+            return f(ElementOverride {
+                element: Some(e),
+                children: inside,
+                attributes,
+                src: None,
+            });
+        }
+        let tag = tag_name(&e);
+        let start = match e {
+            HtmlElement::Ol(start) => format!(" start=\"{start}\""),
+            _ => String::new(),
+        };
+        let attributes = attributes_string(&attributes);
+        format!("<{tag}{start}{attributes}>{inside}</{tag}>")
+    }
+
+    fn el_span_with_inner_html(
+        self,
+        inner_html: String,
+        attributes: ElementAttributes<()>,
+    ) -> Self::View {
+        let attributes = attributes_string(&attributes);
+        format!("<span{attributes}>{inner_html}</span>")
+    }
+
+    fn el_hr(self, attributes: ElementAttributes<()>) -> Self::View {
+        format!("<hr{}/>", attributes_string(&attributes))
+    }
+
+    fn el_br(self) -> Self::View {
+        "<br/>".into()
+    }
+
+    fn el_fragment(self, children: Vec<Self::View>) -> Self::View {
+        children.concat()
+    }
+
+    fn el_a(
+        self,
+        children: Self::View,
+        href: String,
+        attributes: LinkAttributes,
+        _on_click: Option<()>,
+    ) -> Self::View {
+        let mut a = format!("<a href=\"{}\"", escape(&href));
+        if let Some(target) = attributes.target {
+            a.push_str(&format!(" target=\"{}\"", escape(&target)));
+        }
+        if let Some(rel) = attributes.rel {
+            a.push_str(&format!(" rel=\"{}\"", escape(&rel)));
+        }
+        if let Some(title) = attributes.title {
+            a.push_str(&format!(" title=\"{}\"", escape(&title)));
+        }
+        if !attributes.classes.is_empty() {
+            let classes = attributes.classes.join(" ");
+            a.push_str(&format!(" class=\"{}\"", escape(&classes)));
+        }
+        format!("{a}>{children}</a>")
+    }
+
+    fn el_img(self, src: String, alt: String, attributes: ImageAttributes) -> Self::View {
+        let mut img = format!("<img src=\"{}\" alt=\"{}\"", escape(&src), escape(&alt));
+        let optional = [
+            ("title", attributes.title),
+            ("width", attributes.width),
+            ("height", attributes.height),
+            ("loading", attributes.loading),
+            ("decoding", attributes.decoding),
+            ("role", attributes.role),
+        ];
+        for (name, value) in optional {
+            if let Some(value) = value {
+                img.push_str(&format!(" {name}=\"{}\"", escape(&value)));
+            }
+        }
+        let img = format!("{img}/>");
+        match self
+            .element_overrides
+            .and_then(|o| o.get(&HtmlElementKind::Img))
+        {
+            Some(f) => f(ElementOverride {
+                element: None,
+                children: img,
+                attributes: Default::default(),
+                src: Some(src),
+            }),
+            None => img,
+        }
+    }
+
+    fn el_text(self, text: CowStr<'a>) -> Self::View {
+        escape(&text)
+    }
+
+    fn el_input_checkbox(
+        self,
+        checked: bool,
+        indeterminate: bool,
+        attributes: ElementAttributes<()>,
+    ) -> Self::View {
+        let checked = if checked { " checked" } else { "" };
+        // the indeterminate state is not an html attribute
+        let mixed = if indeterminate {
+            " aria-checked=\"mixed\""
+        } else {
+            ""
+        };
+        format!(
+            "<input type=\"checkbox\"{checked}{mixed}{}/>",
+            attributes_string(&attributes)
+        )
+    }
+
+    fn has_custom_component(self, name: &str) -> bool {
+        self.components.is_some_and(|c| c.contains_key(name))
+    }
+
+    fn render_custom_component(
+        self,
+        name: &str,
+        input: MdComponentProps<Self::View>,
+    ) -> Result<Self::View, ComponentCreationError> {
+        match self.components.and_then(|c| c.get(name)) {
+            Some(component) => component(input),
+            None => Err(ComponentCreationError(format!(
+                "no component named `{name}`"
+            ))),
+        }
+    }
+
+    fn has_custom_links(self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        markdown_component_incremental, markdown_component_range, try_markdown_component,
+        BlockRenderer, CachedRender, RenderError, RenderIssue,
+    };
+    use core::cell::Cell;
+    use pulldown_cmark::Options;
+    use std::rc::Rc;
+
+    fn render(source: &str) -> String {
+        render_markdown_to_string(source, &MarkdownProps::default())
+    }
+
+    #[test]
+    fn paragraph() {
+        assert_eq!(
+            render("Hello *world* & **you**"),
+            "<p><span>Hello </span><i><span>world</span></i><span> &amp; </span><b><span>you</span></b></p>"
+        )
+    }
+
+    #[test]
+    fn headings_have_ids() {
+        let (html, metadata) =
+            render_markdown_to_string_with_metadata("# Title\n## Title", &MarkdownProps::default());
+        assert_eq!(
+            html,
+            "<h1 id=\"title\" aria-level=\"1\"><span>Title</span></h1>\
+            <h2 id=\"title-1\" aria-level=\"2\"><span>Title</span></h2>"
+        );
+        assert_eq!(metadata.headings.len(), 2);
+    }
+
+    #[test]
+    fn toc_levels() {
+        let props = MarkdownProps {
+            toc_min_level: 2,
+            toc_max_level: 3,
+            ..Default::default()
+        };
+        let (html, metadata) =
+            render_markdown_to_string_with_metadata("# A\n## B\n### C\n#### D", &props);
+        let levels: Vec<_> = metadata.headings.iter().map(|h| h.level).collect();
+        assert_eq!(levels, vec![2, 3]);
+        assert_eq!(metadata.headings[0].text, "B");
+        // every heading is still rendered
+        assert!(html.contains("<h1 id=\"a\"") && html.contains("<h4 id=\"d\""));
+
+        let (_, metadata) =
+            render_markdown_to_string_with_metadata("# A\n###### F", &MarkdownProps::default());
+        assert_eq!(metadata.headings.len(), 2);
+    }
+
+    #[test]
+    fn document_links() {
+        use pulldown_cmark::LinkType;
+        let source = "[the *docs*](/docs) ![a cat](cat.png)\n\nsee [home][h]\n\n[h]: /home";
+        let (_, metadata) =
+            render_markdown_to_string_with_metadata(source, &MarkdownProps::default());
+        let link = |url: &str, text: &str, is_image, link_type, range| LinkInfo {
+            url: url.into(),
+            text: text.into(),
+            is_image,
+            link_type,
+            range,
+        };
+        assert_eq!(
+            metadata.document_links,
+            vec![
+                link("/docs", "the docs", false, LinkType::Inline, 0..19),
+                link("cat.png", "a cat", true, LinkType::Inline, 20..37),
+                link("/home", "home", false, LinkType::Reference, 43..52),
+            ]
+        );
+    }
+
+    #[test]
+    fn comment_directives() {
+        let props = MarkdownProps {
+            comment_directive_names: Some(&["more"]),
+            ..Default::default()
+        };
+        let source = "intro\n\n<!-- more -->\n\nrest <!-- note -->";
+        let (html, metadata) = render_markdown_to_string_with_metadata(source, &props);
+        assert_eq!(
+            html,
+            "<p><span>intro</span></p><p><span>rest </span><span><!-- note --></span></p>"
+        );
+        assert_eq!(
+            metadata.comment_directives,
+            vec![CommentDirective {
+                name: "more".into(),
+                args: String::new(),
+                range: 7..20,
+            }]
+        );
+    }
+
+    #[test]
+    fn table_alignment() {
+        let source = "| a | b | c |\n|:--|:-:|--:|\n| 1 | 2 | 3 |";
+        assert_eq!(
+            render(source),
+            "<table>\
+            <thead>\
+            <th style=\"text-align: left\"><span>a</span></th>\
+            <th style=\"text-align: center\"><span>b</span></th>\
+            <th style=\"text-align: right\"><span>c</span></th>\
+            </thead>\
+            <tr>\
+            <td style=\"text-align: left\"><span>1</span></td>\
+            <td style=\"text-align: center\"><span>2</span></td>\
+            <td style=\"text-align: right\"><span>3</span></td>\
+            </tr>\
+            </table>"
+        )
+    }
+
+    #[test]
+    fn table_without_alignment() {
+        // unaligned columns have no style, and the head cells are still `th`
+        assert_eq!(
+            render("| a | b |\n|---|:-:|\n| 1 | 2 |"),
+            "<table>\
+            <thead>\
+            <th><span>a</span></th>\
+            <th style=\"text-align: center\"><span>b</span></th>\
+            </thead>\
+            <tr>\
+            <td><span>1</span></td>\
+            <td style=\"text-align: center\"><span>2</span></td>\
+            </tr>\
+            </table>"
+        )
+    }
+
+    #[test]
+    fn code_language_class() {
+        assert_eq!(
+            render("```unknown-lang\nlet x;\n```"),
+            "<pre><code class=\"language-unknown-lang\">let x;\n</code></pre>"
+        );
+        assert_eq!(render("    indented"), "<pre><code>indented</code></pre>");
+        // highlighted code keeps its language class
+        assert!(render("```python\nx = 1\n```").starts_with(
+            "<pre style=\"background-color:#eff1f5;\"><code class=\"language-python\"><span>"
+        ));
+    }
+
+    lazy_static::lazy_static! {
+        /// no syntaxes, so that the code blocks are not highlighted
+        static ref NO_SYNTAXES: syntect::parsing::SyntaxSet = Default::default();
+    }
+
+    #[test]
+    fn code_block_title() {
+        // without the rust syntax, the code is not highlighted and keeps its language class
+        let props = MarkdownProps {
+            syntax_highlight: Some(crate::SyntectConfig {
+                syntax_set: Some(&NO_SYNTAXES),
+                ..crate::SyntectConfig::theme("InspiredGitHub")
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string("```rust title=\"main.rs\"\nfn main() {}\n```", &props),
+            "<div class=\"md-code-title\">main.rs</div>\
+            <pre><code class=\"language-rust\">fn main() {}\n</code></pre>"
+        );
+        assert!(render("```rust filename=main.rs\nfn main() {}\n```")
+            .starts_with("<div class=\"md-code-title\">main.rs</div><pre"));
+    }
+
+    #[test]
+    fn language_aliases() {
+        // without syntaxes, the code is not highlighted and keeps its language class
+        let render = |source, language_aliases| {
+            let props = MarkdownProps {
+                syntax_highlight: Some(crate::SyntectConfig {
+                    syntax_set: Some(&NO_SYNTAXES),
+                    ..crate::SyntectConfig::theme("InspiredGitHub")
+                }),
+                language_aliases,
+                ..Default::default()
+            };
+            render_markdown_to_string(source, &props)
+        };
+        assert_eq!(
+            render("```js\nx\n```", None),
+            "<pre><code class=\"language-javascript\">x\n</code></pre>"
+        );
+        assert_eq!(
+            render("```unknown-lang\nx\n```", None),
+            "<pre><code class=\"language-unknown-lang\">x\n</code></pre>"
+        );
+
+        lazy_static::lazy_static! {
+            static ref ALIASES: HashMap<&'static str, &'static str> =
+                HashMap::from([("node", "javascript"), ("py", "python3")]);
+        }
+        assert_eq!(
+            render("```node\nx\n```", Some(&ALIASES)),
+            "<pre><code class=\"language-javascript\">x\n</code></pre>"
+        );
+        // the default aliases are kept, unless they are overridden
+        assert_eq!(
+            render("```js\nx\n```", Some(&ALIASES)),
+            "<pre><code class=\"language-javascript\">x\n</code></pre>"
+        );
+        assert_eq!(
+            render("```py\nx\n```", Some(&ALIASES)),
+            "<pre><code class=\"language-python3\">x\n</code></pre>"
+        );
+    }
+
+    #[test]
+    fn code_block_normalization() {
+        let source = "```\n    if x {\n        y\n    }\n\n\n```";
+        let props = MarkdownProps {
+            code_block_dedent: true,
+            code_block_trim: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string(source, &props),
+            "<pre><code>if x {\n    y\n}\n</code></pre>"
+        );
+        assert_eq!(
+            render(source),
+            "<pre><code>    if x {\n        y\n    }\n\n\n</code></pre>"
+        );
+    }
+
+    #[test]
+    fn code_block_whitespace() {
+        assert_eq!(
+            render("```\nfirst  \n\n  indented\n\tlast\n```"),
+            "<pre><code>first  \n\n  indented\n\tlast\n</code></pre>"
+        );
+        assert_eq!(
+            render("> ```\n> a  \n>\n>   b\n> ```"),
+            "<blockquote><pre><code>a  \n\n  b\n</code></pre></blockquote>"
+        );
+    }
+
+    #[test]
+    fn footnotes() {
+        assert_eq!(
+            render("text[^a]\n\n[^a]: note"),
+            "<p><span>text</span><sup id=\"fnref-1\" class=\"footnote-reference\"><a href=\"#fn-1\">1</a></sup></p>\
+            <div class=\"footnotes\"><ol start=\"1\">\
+            <li id=\"fn-1\"><p><span>note</span></p><a href=\"#fnref-1\">↩</a></li>\
+            </ol></div>"
+        )
+    }
+
+    #[test]
+    fn footnote_numbers() {
+        // the footnotes are numbered by their first reference,
+        // and only the first reference is the target of the back link
+        assert_eq!(
+            render("one[^b] two[^a] three[^b]\n\n[^a]: first\n\n[^b]: second"),
+            "<p><span>one</span><sup id=\"fnref-1\" class=\"footnote-reference\"><a href=\"#fn-1\">1</a></sup>\
+            <span> two</span><sup id=\"fnref-2\" class=\"footnote-reference\"><a href=\"#fn-2\">2</a></sup>\
+            <span> three</span><sup class=\"footnote-reference\"><a href=\"#fn-1\">1</a></sup></p>\
+            <div class=\"footnotes\"><ol start=\"1\">\
+            <li id=\"fn-1\"><p><span>second</span></p><a href=\"#fnref-1\">↩</a></li>\
+            <li id=\"fn-2\"><p><span>first</span></p><a href=\"#fnref-2\">↩</a></li>\
+            </ol></div>"
+        )
+    }
+
+    #[test]
+    fn footnote_popovers() {
+        let props = MarkdownProps {
+            footnote_popovers: true,
+            ..Default::default()
+        };
+        let html = render_markdown_to_string("text[^a]\n\n[^a]: the *first*\nnote", &props);
+        assert!(html.starts_with(
+            "<p><span>text</span>\
+            <sup id=\"fnref-1\" class=\"footnote-reference\" title=\"the first note\">\
+            <a href=\"#fn-1\">1</a></sup></p>"
+        ));
+    }
+
+    #[test]
+    fn frontmatter() {
+        let (html, metadata) = render_markdown_to_string_with_metadata(
+            "---\ntitle: x\n---\ntext",
+            &MarkdownProps::default(),
+        );
+        assert_eq!(html, "<p><span>text</span></p>");
+        assert_eq!(metadata.frontmatter.as_deref(), Some("title: x\n"));
+    }
+
+    #[test]
+    fn frontmatter_renders_nothing() {
+        let source = "---\ntitle: x\n---\n";
+        let (html, metadata) =
+            render_markdown_to_string_with_metadata(source, &MarkdownProps::default());
+        assert_eq!(html, "");
+        assert_eq!(metadata.frontmatter.as_deref(), Some("title: x\n"));
+
+        // the frontmatter is reported even when the blocks are reused
+        let props = MarkdownProps::default();
+        let metadata = RefCell::new(StringMetadata::default());
+        let cx = StringContext::new(&props, &metadata);
+        let mut cache = CachedRender::new();
+        let source = "---\ntitle: x\n---\ntext";
+        markdown_component_incremental(cx, source, &mut cache);
+        metadata.borrow_mut().frontmatter = None;
+        assert_eq!(
+            markdown_component_incremental(cx, source, &mut cache),
+            "<p><span>text</span></p>"
+        );
+        assert_eq!(metadata.borrow().frontmatter.as_deref(), Some("title: x\n"));
+    }
+
+    #[test]
+    fn math_stylesheet() {
+        let props = MarkdownProps {
+            math: Some(Default::default()),
+            ..Default::default()
+        };
+        let (_, metadata) = render_markdown_to_string_with_metadata("no maths", &props);
+        assert_eq!(metadata.links, vec![]);
+
+        let (_, metadata) = render_markdown_to_string_with_metadata("$x$ and $y$", &props);
+        assert_eq!(
+            metadata.links,
+            vec![crate::MathConfig::default().style_link()]
+        );
+
+        let (_, metadata) =
+            render_markdown_to_string_with_metadata("$x$", &MarkdownProps::default());
+        assert_eq!(metadata.links, vec![]);
+    }
+
+    #[test]
+    fn math_stylesheet_mounted_once() {
+        let props = MarkdownProps {
+            math: Some(Default::default()),
+            ..Default::default()
+        };
+        let metadata = RefCell::new(StringMetadata::default());
+        markdown_component(StringContext::new(&props, &metadata), "$x$");
+        markdown_component(StringContext::new(&props, &metadata), "$y$");
+        assert_eq!(
+            metadata.into_inner().links,
+            vec![crate::MathConfig::default().style_link()]
+        );
+    }
+
+    fn render_with_math_renderer(source: &str) -> String {
+        let props = MarkdownProps::default();
+        let metadata = RefCell::new(StringMetadata::default());
+        let renderer = |math: MathDescription| {
+            let mode = if math.display_mode {
+                "display"
+            } else {
+                "inline"
+            };
+            format!("<math {mode} {:?}>{}</math>", math.range, math.content)
+        };
+        let cx = StringContext::new(&props, &metadata).with_math_renderer(&renderer);
+        markdown_component(cx, source)
+    }
+
+    #[test]
+    fn math_renderer() {
+        assert_eq!(
+            render_with_math_renderer("$x^2$"),
+            "<p><math inline 0..5>x^2</math></p>"
+        );
+        assert_eq!(
+            render_with_math_renderer("$$\\int$$"),
+            "<p><math display 0..8>\\int</math></p>"
+        );
+    }
+
+    #[cfg(feature = "maths")]
+    #[test]
+    fn default_math_renderer() {
+        let html = render("$x^2$");
+        assert!(html.starts_with("<p><span class=\"math-inline\"><span class=\"katex\">"));
+    }
+
+    #[cfg(not(feature = "maths"))]
+    #[test]
+    fn default_math_renderer() {
+        assert_eq!(
+            render("$x^2$ $$\\int$$"),
+            "<p><span class=\"math math-inline\">x^2</span><span> </span>\
+            <span class=\"math math-display\">\\int</span></p>"
+        );
+    }
+
+    #[test]
+    fn callouts() {
+        assert_eq!(
+            render("> [!WARNING]\n> be careful"),
+            "<div class=\"md-callout md-callout-warning\">\
+            <p class=\"md-callout-title\">Warning</p>\
+            <p><span>be careful</span></p>\
+            </div>"
+        );
+        assert!(render("> [!TIP]\n> x").starts_with("<div class=\"md-callout md-callout-tip\">"));
+        assert!(!render("> [!NOTE]\n> x").contains("[!NOTE]"));
+    }
+
+    #[test]
+    fn unknown_callout() {
+        let html = render("> [!UNKNOWN]\n> x");
+        assert!(html.starts_with("<blockquote>"));
+        assert!(html.contains("!UNKNOWN"));
+    }
+
+    #[test]
+    fn definition_list() {
+        let props = MarkdownProps {
+            parse_options: Some(Options::ENABLE_DEFINITION_LIST),
+            ..Default::default()
+        };
+        let source =
+            "**term**\n: first [definition](https://a.b)\n\nother term\n: second definition";
+        assert_eq!(
+            render_markdown_to_string(source, &props),
+            "<dl>\
+            <dt><b><span>term</span></b></dt>\
+            <dd><span>first </span><a href=\"https://a.b\"><span>definition</span></a></dd>\
+            <dt><span>other term</span></dt>\
+            <dd><span>second definition</span></dd>\
+            </dl>"
+        )
+    }
+
+    fn render_with_options(source: &str, options: Options) -> String {
+        let props = MarkdownProps {
+            parse_options: Some(options),
+            ..Default::default()
+        };
+        render_markdown_to_string(source, &props)
+    }
+
+    #[test]
+    fn subscript() {
+        assert_eq!(
+            render_with_options("H~2~O", Options::ENABLE_SUBSCRIPT),
+            "<p><span>H</span><sub><span>2</span></sub><span>O</span></p>"
+        );
+        let options = Options::ENABLE_SUBSCRIPT | Options::ENABLE_STRIKETHROUGH;
+        assert_eq!(
+            render_with_options("~~x~~", options),
+            "<p><s><span>x</span></s></p>"
+        );
+    }
+
+    #[test]
+    fn superscript() {
+        assert_eq!(
+            render_with_options("E=mc^2^", Options::ENABLE_SUPERSCRIPT),
+            "<p><span>E=mc</span><sup><span>2</span></sup></p>"
+        );
+    }
+
+    #[test]
+    fn code_block_renderer() {
+        let props = MarkdownProps::default();
+        let metadata = RefCell::new(StringMetadata::default());
+        let descriptions = RefCell::new(Vec::new());
+        let renderer = |code_block: CodeBlockDescription| {
+            descriptions.borrow_mut().push(code_block);
+            "<diagram/>".to_string()
+        };
+        let cx = StringContext::new(&props, &metadata).with_code_block_renderer(&renderer);
+        let html = markdown_component(cx, "```mermaid\ngraph TD;\n  A-->B;\n```");
+        assert_eq!(html, "<diagram/>");
+        assert_eq!(
+            descriptions.into_inner(),
+            vec![CodeBlockDescription {
+                language: Some("mermaid".into()),
+                title: None,
+                content: "graph TD;\n  A-->B;\n".into(),
+                range: 0..33,
+            }]
+        );
+    }
+
+    #[test]
+    fn mermaid() {
+        let source = "```mermaid\ngraph TD;\n  A-->B;\n```";
+        let (html, metadata) =
+            render_markdown_to_string_with_metadata(source, &MarkdownProps::default());
+        assert_eq!(
+            html,
+            "<div class=\"mermaid\">graph TD;\n  A--&gt;B;\n</div>"
+        );
+        assert_eq!(metadata.scripts, Vec::<String>::new());
+
+        let props = MarkdownProps {
+            mermaid: Some(Default::default()),
+            ..Default::default()
+        };
+        let (_, metadata) = render_markdown_to_string_with_metadata(source, &props);
+        assert_eq!(
+            metadata.scripts,
+            vec!["https://cdn.jsdelivr.net/npm/mermaid@10/dist/mermaid.min.js"]
+        );
+    }
+
+    /// renders images without loading hints
+    fn render_image(source: &str) -> String {
+        let props = MarkdownProps {
+            image_loading: crate::ImageLoading::Auto,
+            ..Default::default()
+        };
+        render_markdown_to_string(source, &props)
+    }
+
+    #[test]
+    fn image_title() {
+        assert_eq!(
+            render_image("![a *cat*](cat.png \"my cat\")"),
+            "<p><img src=\"cat.png\" alt=\"a cat\" title=\"my cat\"/></p>"
+        );
+    }
+
+    #[test]
+    fn image_size() {
+        assert_eq!(
+            render_image("![cat](cat.png \"=100x200\")"),
+            "<p><img src=\"cat.png\" alt=\"cat\" width=\"100\" height=\"200\"/></p>"
+        );
+        assert_eq!(
+            render_image("![cat](cat.png){width=100} text"),
+            "<p><img src=\"cat.png\" alt=\"cat\" width=\"100\"/><span> text</span></p>"
+        );
+    }
+
+    #[test]
+    fn image_title_and_size() {
+        assert_eq!(
+            render_image("![cat](cat.png \"my cat =100x\")"),
+            "<p><img src=\"cat.png\" alt=\"cat\" title=\"my cat\" width=\"100\"/></p>"
+        );
+        assert_eq!(
+            render_image("![cat](cat.png \"my cat\"){height=20}"),
+            "<p><img src=\"cat.png\" alt=\"cat\" title=\"my cat\" height=\"20\"/></p>"
+        );
+    }
+
+    #[test]
+    fn decorative_images() {
+        let props = MarkdownProps {
+            decorative_empty_alt: true,
+            image_loading: crate::ImageLoading::Auto,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string("![](x.png)", &props),
+            "<p><img src=\"x.png\" alt=\"\" role=\"presentation\"/></p>"
+        );
+        assert_eq!(
+            render_markdown_to_string("![logo](x.png)", &props),
+            "<p><img src=\"x.png\" alt=\"logo\"/></p>"
+        );
+        assert_eq!(
+            render_image("![](x.png)"),
+            "<p><img src=\"x.png\" alt=\"\"/></p>"
+        );
+    }
+
+    #[test]
+    fn figure_images() {
+        let props = MarkdownProps {
+            figure_images: true,
+            image_loading: crate::ImageLoading::Auto,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string("![cat](cat.png \"my cat =100x\")", &props),
+            "<figure><img src=\"cat.png\" alt=\"cat\" title=\"my cat\" width=\"100\"/>\
+            <figcaption>my cat</figcaption></figure>"
+        );
+        // inline images, and images without a title, are not figures
+        assert_eq!(
+            render_markdown_to_string("see ![cat](cat.png \"my cat\")", &props),
+            "<p><span>see </span><img src=\"cat.png\" alt=\"cat\" title=\"my cat\"/></p>"
+        );
+        assert_eq!(
+            render_markdown_to_string("![cat](cat.png)", &props),
+            "<p><img src=\"cat.png\" alt=\"cat\"/></p>"
+        );
+    }
+
+    #[test]
+    fn image_loading() {
+        let props = MarkdownProps {
+            eager_image_count: 3,
+            ..Default::default()
+        };
+        let source = "![](a.png)".repeat(10);
+        let html = render_markdown_to_string(&source, &props);
+        let images: Vec<_> = html.split("<img").skip(1).collect();
+        assert_eq!(images.len(), 10);
+        assert_eq!(images[0], " src=\"a.png\" alt=\"\" loading=\"eager\"/>");
+        assert_eq!(
+            images[9],
+            " src=\"a.png\" alt=\"\" loading=\"lazy\" decoding=\"async\"/></p>"
+        );
+    }
+
+    #[test]
+    fn smart_punctuation() {
+        let source = "\"quoted\" -- 'text'";
+        assert_eq!(
+            render(source),
+            "<p><span>&quot;quoted&quot; -- &#39;text&#39;</span></p>"
+        );
+
+        let props = MarkdownProps {
+            options: crate::MarkdownOptions {
+                smart_punctuation: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let html = render_markdown_to_string(source, &props);
+        assert!(html.contains("“") && html.contains("–") && html.contains("’"));
+        assert!(!html.contains("&quot;") && !html.contains("&#39;"));
+    }
+
+    #[test]
+    fn default_options() {
+        assert_eq!(
+            crate::MarkdownOptions::default().parse_options(),
+            Options::ENABLE_GFM
+                | Options::ENABLE_MATH
+                | Options::ENABLE_TABLES
+                | Options::ENABLE_TASKLISTS
+                | Options::ENABLE_WIKILINKS
+                | Options::ENABLE_STRIKETHROUGH
+                | Options::ENABLE_FOOTNOTES
+                | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+                | Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS
+        )
+    }
+
+    #[test]
+    fn escaped_text() {
+        assert_eq!(
+            render("[x](https://a.b/?a=1&b=\"2\")"),
+            "<p><a href=\"https://a.b/?a=1&amp;b=&quot;2&quot;\"><span>x</span></a></p>"
+        )
+    }
+
+    fn render_wikilinks(source: &str) -> String {
+        let props = MarkdownProps::default();
+        let metadata = RefCell::new(StringMetadata::default());
+        let resolver = |target: &str| match target {
+            "Home" => Some(WikilinkResolution {
+                url: "/pages/home".into(),
+                exists: true,
+                title: Some("Home page".into()),
+            }),
+            "Draft" => Some(WikilinkResolution {
+                url: "/pages/draft".into(),
+                exists: false,
+                title: None,
+            }),
+            _ => None,
+        };
+        let cx = StringContext::new(&props, &metadata).with_wikilink_resolver(&resolver);
+        markdown_component(cx, source)
+    }
+
+    #[test]
+    fn mentions() {
+        let props = MarkdownProps {
+            mentions: Some(Default::default()),
+            ..Default::default()
+        };
+        let metadata = RefCell::new(StringMetadata::default());
+        let mentions = |name: &str| (name == "alice").then(|| format!("/users/{name}"));
+        let hashtags = |tag: &str| Some(format!("/tags/{tag}"));
+        let cx = StringContext::new(&props, &metadata)
+            .with_mention_resolver(&mentions)
+            .with_hashtag_resolver(&hashtags);
+        assert_eq!(
+            markdown_component(cx, "@alice and @bob, not me@x.com or `#tag`, but #rust"),
+            "<p><a href=\"/users/alice\" class=\"md-mention\"><span>@alice</span></a>\
+            <span> and </span><span class=\"md-mention-broken\"><span>@bob</span></span>\
+            <span>, not me@x.com or </span><code>#tag</code><span>, but </span>\
+            <a href=\"/tags/rust\" class=\"md-hashtag\"><span>#rust</span></a></p>"
+        );
+        assert_eq!(
+            markdown_component(cx, "[@alice](/a)"),
+            "<p><a href=\"/a\"><span>@alice</span></a></p>"
+        );
+    }
+
+    #[test]
+    fn resolved_wikilink() {
+        assert_eq!(
+            render_wikilinks("[[Home]]"),
+            "<p><a href=\"/pages/home\" title=\"Home page\" class=\"md-wikilink\"><span>Home</span></a></p>"
+        );
+        assert_eq!(
+            render_wikilinks("[[Draft]]"),
+            "<p><a href=\"/pages/draft\" class=\"md-wikilink-broken\"><span>Draft</span></a></p>"
+        );
+    }
+
+    #[test]
+    fn wikilinks_resolved_once() {
+        let props = MarkdownProps::default();
+        let metadata = RefCell::new(StringMetadata::default());
+        let calls = Rc::new(Cell::new(0));
+        let counter = calls.clone();
+        let resolver = move |target: &str| {
+            counter.set(counter.get() + 1);
+            Some(WikilinkResolution::identity(target))
+        };
+        let cx = StringContext::new(&props, &metadata).with_wikilink_resolver(&resolver);
+        markdown_component(cx, "[[Page]] and [[Page|again]]\n\n- [[Page]]\n- [[Other]]");
+        assert_eq!(calls.get(), 2);
+
+        let mut blocks = BlockRenderer::new(cx, "[[Page]]\n\n[[Page]]\n\n[[Page]]");
+        calls.set(0);
+        blocks.next();
+        blocks.next();
+        assert_eq!(calls.get(), 1);
+        blocks.clear_resolutions();
+        blocks.next();
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn aliased_wikilink() {
+        assert_eq!(
+            render_wikilinks("[[Home|back home]]"),
+            "<p><a href=\"/pages/home\" title=\"Home page\" class=\"md-wikilink\"><span>back home</span></a></p>"
+        );
+    }
+
+    #[test]
+    fn broken_wikilink() {
+        assert_eq!(
+            render_wikilinks("[[Nowhere]]"),
+            "<p><a href=\"Nowhere\" class=\"md-wikilink-broken\"><span>Nowhere</span></a></p>"
+        );
+        assert_eq!(
+            render("[[Nowhere]]"),
+            "<p><a href=\"Nowhere\" class=\"md-wikilink\"><span>Nowhere</span></a></p>"
+        );
+    }
+    #[test]
+    fn base_url() {
+        let props = MarkdownProps {
+            base_url: Some("https://example.com/docs/guide/"),
+            image_loading: crate::ImageLoading::Auto,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string("[a](../a.md#top) ![b](./b.png) [c](#c)", &props),
+            "<p><a href=\"https://example.com/docs/a.md#top\"><span>a</span></a><span> </span>\
+            <img src=\"https://example.com/docs/guide/b.png\" alt=\"b\"/><span> </span>\
+            <a href=\"#c\"><span>c</span></a></p>"
+        );
+    }
+    #[test]
+    fn transform_stream() {
+        fn emojis(stream: &mut Vec<(crate::Event<'_>, Range<usize>)>) {
+            for (event, _) in stream {
+                if let crate::Event::Text(text) = event {
+                    if text.contains(":smile:") {
+                        *text = text.replace(":smile:", "😄").into();
+                    }
+                }
+            }
+        }
+        let props = MarkdownProps {
+            transform_stream: Some(crate::StreamTransform(emojis)),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string("hi :smile:", &props),
+            "<p><span>hi 😄</span></p>"
+        );
+        assert_eq!(render("hi :smile:"), "<p><span>hi :smile:</span></p>");
+    }
+
+    #[test]
+    fn text_transform() {
+        fn shout(text: &str) -> String {
+            text.to_uppercase()
+        }
+        let props = MarkdownProps {
+            typography: &[
+                crate::TypographyPreset::Symbols,
+                crate::TypographyPreset::German,
+            ],
+            text_transform: Some(crate::TextTransform(shout)),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string("(c) \"hi\" -> `(c) x`", &props),
+            "<p><span>© „HI“ → </span><code>(c) x</code></p>"
+        );
+    }
+
+    #[test]
+    fn soft_breaks() {
+        let render_breaks = |soft_break, hard_line_breaks| {
+            let props = MarkdownProps {
+                soft_break,
+                hard_line_breaks,
+                ..Default::default()
+            };
+            render_markdown_to_string("a\nb", &props)
+        };
+        assert_eq!(
+            render_breaks(crate::SoftBreakMode::Space, false),
+            "<p><span>a</span> <span>b</span></p>"
+        );
+        assert_eq!(
+            render_breaks(crate::SoftBreakMode::Newline, false),
+            "<p><span>a</span>\n<span>b</span></p>"
+        );
+        assert_eq!(
+            render_breaks(crate::SoftBreakMode::HardBreak, false),
+            "<p><span>a</span><br/><span>b</span></p>"
+        );
+        assert_eq!(
+            render_breaks(crate::SoftBreakMode::Newline, true),
+            "<p><span>a</span><br/><span>b</span></p>"
+        );
+    }
+    #[test]
+    fn clickable_elements() {
+        // the elements with click handlers render the same,
+        // without events to handle
+        assert_eq!(
+            render("- [x] done\n- [ ] todo\n\n---\n\n[link](https://example.com)"),
+            "<ul>\
+            <li id=\"task-2\"><input type=\"checkbox\" checked aria-labelledby=\"task-2\"/><span>done</span></li>\
+            <li id=\"task-13\"><input type=\"checkbox\" aria-labelledby=\"task-13\"/><span>todo</span></li>\
+            </ul>\
+            <hr role=\"separator\" tabindex=\"0\"/>\
+            <p><a href=\"https://example.com\"><span>link</span></a></p>"
+        )
+    }
+
+    #[test]
+    fn rules() {
+        let render_rules = |collapse_rules| {
+            let props = MarkdownProps {
+                rule_class: Some("fancy wide"),
+                collapse_rules,
+                ..Default::default()
+            };
+            render_markdown_to_string("a\n\n---\n\n***\n\nb", &props)
+        };
+        let rule = "<hr class=\"fancy wide\" role=\"separator\" tabindex=\"0\"/>";
+        assert_eq!(
+            render_rules(false),
+            format!("<p><span>a</span></p>{rule}{rule}<p><span>b</span></p>")
+        );
+        assert_eq!(
+            render_rules(true),
+            format!("<p><span>a</span></p>{rule}<p><span>b</span></p>")
+        );
+    }
+    #[cfg(feature = "frontmatter")]
+    #[test]
+    fn parsed_frontmatter() {
+        let (html, metadata) = render_markdown_to_string_with_metadata(
+            "+++\ntitle = \"Hello\"\n+++\ntext",
+            &MarkdownProps::default(),
+        );
+        assert_eq!(html, "<p><span>text</span></p>");
+        assert_eq!(metadata.frontmatter.as_deref(), Some("title = \"Hello\"\n"));
+        assert_eq!(
+            metadata.parsed_frontmatter.unwrap()["title"],
+            crate::FrontmatterValue::String("Hello".into())
+        );
+
+        let (_, metadata) = render_markdown_to_string_with_metadata(
+            "---\ntitle: [unclosed\n---\ntext",
+            &MarkdownProps::default(),
+        );
+        assert_eq!(metadata.frontmatter.as_deref(), Some("title: [unclosed\n"));
+        assert_eq!(metadata.parsed_frontmatter, None);
+    }
+
+    #[cfg(feature = "frontmatter")]
+    #[test]
+    fn frontmatter_options() {
+        let source = "---\nhard_breaks: true\nlang: fr\n---\na\nb";
+        assert_eq!(
+            render(source),
+            "<div lang=\"fr\"><p><span>a</span><br/><span>b</span></p></div>"
+        );
+
+        let props = MarkdownProps {
+            soft_break: crate::SoftBreakMode::Newline,
+            lang: Some("en"),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string(source, &props),
+            "<div lang=\"en\"><p><span>a</span>\n<span>b</span></p></div>"
+        );
+
+        let props = MarkdownProps {
+            frontmatter_overrides: true,
+            ..props
+        };
+        assert_eq!(
+            render_markdown_to_string(source, &props),
+            "<div lang=\"fr\"><p><span>a</span><br/><span>b</span></p></div>"
+        );
+    }
+    #[test]
+    fn element_attributes() {
+        let props = MarkdownProps::default();
+        let metadata = RefCell::new(StringMetadata::default());
+        let cx = StringContext::new(&props, &metadata);
+        let attributes = ElementAttributes {
+            classes: vec!["a".into(), "b".into()],
+            id: Some("x".into()),
+            title: Some("a \"tip\"".into()),
+            data: vec![("data-line", "3".into()), ("data-lang", "rust".into())],
+            ..Default::default()
+        };
+        assert_eq!(
+            cx.el_with_attributes(HtmlElement::Span, "text".into(), attributes),
+            "<span id=\"x\" class=\"a b\" title=\"a &quot;tip&quot;\" data-line=\"3\" data-lang=\"rust\">text</span>"
+        );
+    }
+    #[test]
+    fn heading_aria_level() {
+        for level in 1..=6 {
+            let source = format!("{} Title", "#".repeat(level));
+            let html = render(&source);
+            assert!(html.starts_with(&format!("<h{level} id=\"title\" aria-level=\"{level}\">")));
+        }
+    }
+
+    #[test]
+    fn accessibility_attributes() {
+        assert_eq!(render("---"), "<hr role=\"separator\" tabindex=\"0\"/>");
+        assert_eq!(
+            render("- [x] done"),
+            "<ul><li id=\"task-2\"><input type=\"checkbox\" checked aria-labelledby=\"task-2\"/>\
+            <span>done</span></li></ul>"
+        );
+        // every task is labelled by its own item
+        assert_eq!(
+            render("- [ ] a\n  - [ ] b\n- c"),
+            "<ul><li id=\"task-2\"><input type=\"checkbox\" aria-labelledby=\"task-2\"/><span>a</span>\
+            <ul><li id=\"task-12\"><input type=\"checkbox\" aria-labelledby=\"task-12\"/><span>b</span></li></ul>\
+            </li><li><span>c</span></li></ul>"
+        );
+    }
+
+    #[test]
+    fn read_only_task_lists() {
+        let props = MarkdownProps {
+            read_only_task_lists: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string("- [ ] todo", &props),
+            "<ul><li id=\"task-2\"><input type=\"checkbox\" aria-labelledby=\"task-2\" disabled/>\
+            <span>todo</span></li></ul>"
+        );
+    }
+
+    #[test]
+    fn task_list_states() {
+        let states = Box::leak(Box::new(std::collections::HashMap::from([(
+            '/',
+            crate::TaskState {
+                class: "md-task-partial",
+            },
+        )])));
+        let props = MarkdownProps {
+            task_list_states: Some(states),
+            ..Default::default()
+        };
+        let render = |source| render_markdown_to_string(source, &props);
+        assert_eq!(
+            render("- [ ] todo"),
+            "<ul><li id=\"task-2\"><input type=\"checkbox\" aria-labelledby=\"task-2\"/>\
+            <span>todo</span></li></ul>"
+        );
+        assert_eq!(
+            render("- [x] done"),
+            "<ul><li id=\"task-2\"><input type=\"checkbox\" checked aria-labelledby=\"task-2\"/>\
+            <span>done</span></li></ul>"
+        );
+        assert_eq!(
+            render("- [/] partial"),
+            "<ul><li id=\"task-2\"><input type=\"checkbox\" aria-checked=\"mixed\" class=\"md-task-partial\" \
+            aria-labelledby=\"task-2\"/><span>partial</span></li></ul>"
+        );
+        // unknown markers are plain list items
+        assert_eq!(
+            render("- [?] unknown"),
+            "<ul><li><span>[?] unknown</span></li></ul>"
+        );
+    }
+
+    #[test]
+    fn link_rel_policy() {
+        let props = MarkdownProps {
+            link_target: crate::LinkTarget::NewTabExternalOnly,
+            link_rel_policy: crate::LinkRelPolicy::NoFollowUgc,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string("[a](https://example.com) [b](/b)", &props),
+            "<p><a href=\"https://example.com\" target=\"_blank\" \
+            rel=\"nofollow ugc noopener noreferrer\"><span>a</span></a>\
+            <span> </span><a href=\"/b\"><span>b</span></a></p>"
+        );
+
+        // the mentions are links too
+        let props = MarkdownProps {
+            link_rel_policy: crate::LinkRelPolicy::NoFollowUgc,
+            base_url: Some("https://example.com/"),
+            mentions: Some(Default::default()),
+            ..Default::default()
+        };
+        let metadata = RefCell::new(StringMetadata::default());
+        let mentions = |name: &str| Some(format!("users/{name}"));
+        let cx = StringContext::new(&props, &metadata).with_mention_resolver(&mentions);
+        assert_eq!(
+            markdown_component(cx, "@alice"),
+            "<p><a href=\"https://example.com/users/alice\" rel=\"nofollow ugc\" \
+            class=\"md-mention\"><span>@alice</span></a></p>"
+        );
+    }
+
+    #[test]
+    fn html_tables() {
+        let source = "<table>\n<tr><th align=\"right\">a</th></tr>\n\
+            <tr><td>b &amp; <b>c</b></td></tr>\n\
+            <tr><td style=\"text-align: center\">d&nbsp;e</td></tr>\n</table>";
+        let props = MarkdownProps {
+            normalize_html_tables: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string(source, &props),
+            "<table><thead><th style=\"text-align: right\"><span>a</span></th></thead>\
+            <tr><td><span>b &amp; </span>\
+            <span><b></span><span>c</span><span></b></span></td></tr>\
+            <tr><td style=\"text-align: center\"><span>d&nbsp;e</span></td></tr></table>"
+        );
+        // the tables are not rendered when the raw html is removed
+        let props = MarkdownProps {
+            normalize_html_tables: true,
+            html_policy: crate::HtmlPolicy::Strip,
+            ..Default::default()
+        };
+        assert_eq!(render_markdown_to_string(source, &props), "");
+        assert_eq!(
+            render(source),
+            "<span><table>\n</span><span><tr><th align=\"right\">a</th></tr>\n</span>\
+            <span><tr><td>b &amp; <b>c</b></td></tr>\n</span>\
+            <span><tr><td style=\"text-align: center\">d&nbsp;e</td></tr>\n</span>\
+            <span></table></span>"
+        );
+    }
+
+    #[test]
+    fn table_caption() {
+        let props = MarkdownProps {
+            table_caption_prefix: Some("Table:"),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string("| a |\n|---|\n| b |\n\nTable: Letters", &props),
+            "<table><caption><span>Letters</span></caption>\
+            <thead><th><span>a</span></th></thead>\
+            <tr><td><span>b</span></td></tr></table>"
+        );
+        assert!(render("Table: Letters\n\n| a |\n|---|\n").starts_with("<p>"));
+        // an empty caption is left as a paragraph
+        assert_eq!(
+            render_markdown_to_string("Table:\n\n| a |\n|---|\n", &props),
+            "<p><span>Table:</span></p><table><thead><th><span>a</span></th></thead></table>"
+        );
+        // a caption between two tables belongs to the second one
+        assert_eq!(
+            render_markdown_to_string("| a |\n|---|\n\nTable: C\n\n| b |\n|---|\n", &props),
+            "<table><thead><th><span>a</span></th></thead></table>\
+            <table><caption><span>C</span></caption>\
+            <thead><th><span>b</span></th></thead></table>"
+        );
+    }
+
+    #[test]
+    fn code_block_copy_button() {
+        let source = "```\nlet x = \"<a>\";\n```";
+        let props = MarkdownProps {
+            code_block_copy_button: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string(source, &props),
+            "<div class=\"md-codeblock\" data-code=\"let x = &quot;&lt;a&gt;&quot;;\n\">\
+            <pre><code>let x = &quot;&lt;a&gt;&quot;;\n</code></pre>\
+            <button class=\"md-copy\">Copy</button></div>"
+        );
+        let default = render(source);
+        assert!(!default.contains("md-copy"));
+        assert!(!default.contains("data-code"));
+    }
+
+    #[test]
+    fn code_block_highlighted_lines() {
+        assert_eq!(
+            render("```{2}\na\nb\n```"),
+            "<pre><code>\
+            <span class=\"md-line\" data-line=\"1\">a\n</span>\
+            <span class=\"md-line md-line-highlight\" data-line=\"2\">b\n</span>\
+            </code></pre>"
+        );
+    }
+
+    #[test]
+    fn code_block_line_numbers() {
+        let props = MarkdownProps {
+            code_block_line_numbers: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string("```unknown-lang {1}\na\nb\n```", &props),
+            "<pre class=\"md-line-numbers\"><code class=\"language-unknown-lang\">\
+            <span class=\"md-line md-line-highlight\" data-line=\"1\">\
+            <span class=\"md-line-number\">1</span>a\n</span>\
+            <span class=\"md-line\" data-line=\"2\">\
+            <span class=\"md-line-number\">2</span>b\n</span>\
+            </code></pre>"
+        );
+    }
+
+    #[test]
+    fn syntax_highlight_theme() {
+        let props = MarkdownProps {
+            syntax_highlight: Some(crate::SyntectConfig::theme("InspiredGitHub")),
+            ..Default::default()
+        };
+        let source = "```rust\nfn main() {}\n```";
+        let html = render_markdown_to_string(source, &props);
+        assert!(html.starts_with("<pre style=\"background-color:#ffffff;\">"));
+        assert!(html.contains("<span style=\"color:"));
+        assert!(html.contains(">main</span>"));
+        assert_ne!(html, render(source));
+
+        let unknown_theme = MarkdownProps {
+            syntax_highlight: Some(crate::SyntectConfig::theme("unknown")),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string(source, &unknown_theme),
+            render(source)
+        );
+        assert_eq!(
+            render_markdown_to_string("```unknown-lang\na\n```", &props),
+            "<pre><code class=\"language-unknown-lang\">a\n</code></pre>"
+        );
+    }
+
+    fn render_with_components(source: &str, props: &MarkdownProps) -> String {
+        let mut components = StringComponents::new();
+        components.insert(
+            "Counter".into(),
+            Box::new(|props| {
+                let initial = props.get_number::<i32>("initial").unwrap_or(0);
+                Ok(format!("<counter>{initial}</counter>"))
+            }),
+        );
+        components.insert(
+            "Box".into(),
+            Box::new(|props| Ok(format!("<div class=\"box\">{}</div>", props.children))),
+        );
+        components.insert(
+            "Raw".into(),
+            Box::new(|props| Ok(format!("<pre>{}</pre>{}", props.source, props.children))),
+        );
+        let metadata = RefCell::new(StringMetadata::default());
+        let cx = StringContext::new(props, &metadata).with_components(&components);
+        markdown_component(cx, source)
+    }
+
+    #[test]
+    fn self_closing_component() {
+        let props = MarkdownProps::default();
+        assert_eq!(
+            render_with_components("<Counter initial=\"5\"/>", &props),
+            "<counter>5</counter>"
+        );
+        assert_eq!(
+            render_with_components("a <Counter/> b", &props),
+            "<p><span>a </span><counter>0</counter><span> b</span></p>"
+        );
+    }
+
+    #[test]
+    fn component_source() {
+        let props = MarkdownProps::default();
+        assert_eq!(
+            render_with_components("x <Raw>**a** <Box>b</Box></Raw>", &props),
+            "<p><span>x </span><pre>**a** <Box>b</Box></pre>\
+            <b><span>a</span></b><span> </span><div class=\"box\"><span>b</span></div></p>"
+        );
+        assert_eq!(
+            render_with_components("<Raw>\n\n# hi\n\n</Raw>", &props),
+            "<pre>\n# hi\n\n</pre><h1 id=\"hi\" aria-level=\"1\"><span>hi</span></h1>"
+        );
+    }
+
+    #[test]
+    fn nested_components() {
+        let props = MarkdownProps::default();
+        assert_eq!(
+            render_with_components(
+                "<Box>\n\n**hey**\n\n<Box>\n\n<Counter/>\n\n</Box>\n\n</Box>\n\nafter",
+                &props
+            ),
+            "<div class=\"box\"><p><b><span>hey</span></b></p>\
+            <div class=\"box\"><counter>0</counter></div></div>\
+            <p><span>after</span></p>"
+        );
+        assert_eq!(
+            render_with_components("a <Box>*b* <Box>c</Box></Box> d", &props),
+            "<p><span>a </span>\
+            <div class=\"box\"><i><span>b</span></i><span> </span><div class=\"box\"><span>c</span></div></div>\
+            <span> d</span></p>"
+        );
+    }
+
+    #[test]
+    fn unclosed_component() {
+        assert_eq!(
+            render_with_components("a <Box>b\n\nc", &MarkdownProps::default()),
+            "<p><span>a </span><div class=\"box\"><span>b</span></div></p><p><span>c</span></p>"
+        );
+    }
+
+    #[test]
+    fn unknown_component() {
+        let source = "a <Unknown>b</Unknown> <span>c</span>";
+        assert_eq!(
+            render_with_components(source, &MarkdownProps::default()),
+            "<p><span>a </span><span><Unknown></span><span>b</span><span></Unknown></span>\
+            <span> </span><span><span></span><span>c</span><span></span></span></p>"
+        );
+        let props = MarkdownProps {
+            unknown_components: crate::UnknownComponentPolicy::Drop,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_with_components(source, &props),
+            "<p><span>a </span><span>b</span>\
+            <span> </span><span><span></span><span>c</span><span></span></span></p>"
+        );
+    }
+
+    fn render_with_html_policy(html_policy: crate::HtmlPolicy) -> String {
+        let source = "<script>onerror</script>\n\na <img src=x onerror=\"alert(1)\"> b";
+        let props = MarkdownProps {
+            html_policy,
+            ..Default::default()
+        };
+        render_markdown_to_string(source, &props)
+    }
+
+    #[test]
+    fn html_passthrough() {
+        assert_eq!(
+            render_with_html_policy(crate::HtmlPolicy::Passthrough),
+            "<span><script>onerror</script>\n</span>\
+            <p><span>a </span><span><img src=x onerror=\"alert(1)\"></span><span> b</span></p>"
+        );
+    }
+
+    #[test]
+    fn html_escape() {
+        assert_eq!(
+            render_with_html_policy(crate::HtmlPolicy::Escape),
+            "&lt;script&gt;onerror&lt;/script&gt;\n\
+            <p><span>a </span>&lt;img src=x onerror=&quot;alert(1)&quot;&gt;<span> b</span></p>"
+        );
+    }
+
+    #[test]
+    fn html_strip() {
+        assert_eq!(
+            render_with_html_policy(crate::HtmlPolicy::Strip),
+            "<p><span>a </span><span> b</span></p>"
+        );
+    }
+
+    #[test]
+    fn html_sanitize() {
+        assert_eq!(
+            render_with_html_policy(crate::HtmlPolicy::Sanitize(Default::default())),
+            "<p><span>a </span><span><img src=\"x\"></span><span> b</span></p>"
+        );
+    }
+
+    fn render_with_heading_offset(source: &str, heading_offset: u8) -> String {
+        let props = MarkdownProps {
+            heading_offset,
+            ..Default::default()
+        };
+        render_markdown_to_string(source, &props)
+    }
+
+    #[test]
+    fn heading_offset() {
+        assert_eq!(
+            render_with_heading_offset("# Title", 0),
+            "<h1 id=\"title\" aria-level=\"1\"><span>Title</span></h1>"
+        );
+        assert_eq!(
+            render_with_heading_offset("# Title", 1),
+            "<h2 id=\"title\" aria-level=\"2\"><span>Title</span></h2>"
+        );
+    }
+
+    #[test]
+    fn heading_offset_clamped() {
+        assert_eq!(
+            render_with_heading_offset("###### Title", 2),
+            "<h6 id=\"title\" aria-level=\"6\"><span>Title</span></h6>"
+        );
+    }
+
+    fn render_autolinks(source: &str) -> String {
+        let props = MarkdownProps {
+            autolink: true,
+            ..Default::default()
+        };
+        render_markdown_to_string(source, &props)
+    }
+
+    #[test]
+    fn autolink_urls() {
+        assert_eq!(
+            render_autolinks("see https://example.com/a."),
+            "<p><span>see </span>\
+            <a href=\"https://example.com/a\"><span>https://example.com/a</span></a>\
+            <span>.</span></p>"
+        );
+        assert_eq!(
+            render_autolinks("go to www.example.com"),
+            "<p><span>go to </span>\
+            <a href=\"http://www.example.com\"><span>www.example.com</span></a></p>"
+        );
+        assert_eq!(
+            render("see https://example.com"),
+            "<p><span>see https://example.com</span></p>"
+        );
+    }
+
+    #[test]
+    fn autolink_gfm_literals() {
+        assert_eq!(
+            render_autolinks("www.x.com. or a@b.com"),
+            "<p><a href=\"http://www.x.com\"><span>www.x.com</span></a><span>. or </span>\
+            <a href=\"mailto:a@b.com\"><span>a@b.com</span></a></p>"
+        );
+        assert_eq!(
+            render_autolinks("www.x_y.com"),
+            "<p><span>www.x_y.com</span></p>"
+        );
+    }
+
+    #[test]
+    fn autolink_email() {
+        assert_eq!(
+            render_autolinks("mail me@example.com"),
+            "<p><span>mail </span>\
+            <a href=\"mailto:me@example.com\"><span>me@example.com</span></a></p>"
+        );
+    }
+
+    #[test]
+    fn autolink_ignores_code_and_links() {
+        assert_eq!(
+            render_autolinks("`https://example.com`"),
+            "<p><code>https://example.com</code></p>"
+        );
+        assert_eq!(
+            render_autolinks("[www.a.com](https://b.com)"),
+            "<p><a href=\"https://b.com\"><span>www.a.com</span></a></p>"
+        );
+        // nor inside the links written in html
+        assert_eq!(
+            render_autolinks("<a href=\"https://b.com\">see www.a.com</a> or www.c.com"),
+            "<p><span><a href=\"https://b.com\"></span><span>see www.a.com</span>\
+            <span></a></span><span> or </span>\
+            <a href=\"http://www.c.com\"><span>www.c.com</span></a></p>"
+        );
+    }
+
+    #[test]
+    fn class_prefix() {
+        let source = "```unknown-lang\nx\n```\n\n> [!NOTE]\n> text {.mine}";
+        assert_eq!(
+            render(source),
+            "<pre><code class=\"language-unknown-lang\">x\n</code></pre>\
+            <div class=\"md-callout md-callout-note\"><p class=\"md-callout-title\">Note</p>\
+            <p><span>text {.mine}</span></p></div>"
+        );
+        let props = MarkdownProps {
+            class_prefix: Some("prose-"),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string(source, &props),
+            "<pre><code class=\"prose-language-unknown-lang\">x\n</code></pre>\
+            <div class=\"prose-md-callout prose-md-callout-note\">\
+            <p class=\"prose-md-callout-title\">Note</p>\
+            <p><span>text {.mine}</span></p></div>"
+        );
+    }
+
+    #[test]
+    fn heading_attributes() {
+        let props = MarkdownProps {
+            options: crate::MarkdownOptions {
+                heading_attributes: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let render = |source| render_markdown_to_string(source, &props);
+        assert_eq!(
+            render("# Title {#x}"),
+            "<h1 id=\"x\" aria-level=\"1\"><span>Title</span></h1>"
+        );
+        assert_eq!(
+            render("# Title {.c}"),
+            "<h1 id=\"title\" class=\"c\" aria-level=\"1\"><span>Title</span></h1>"
+        );
+        assert_eq!(
+            render("# Title {#x .c key=val onclick=alert(1)}"),
+            "<h1 id=\"x\" class=\"c\" aria-level=\"1\" key=\"val\"><span>Title</span></h1>"
+        );
+    }
+
+    #[test]
+    fn class_prefix_keeps_source_classes() {
+        let props = MarkdownProps {
+            class_prefix: Some("prose-"),
+            options: crate::MarkdownOptions {
+                heading_attributes: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string("# Title {.mine}", &props),
+            "<h1 id=\"title\" class=\"mine\" aria-level=\"1\"><span>Title</span></h1>"
+        );
+    }
+
+    #[test]
+    fn details_directive() {
+        assert_eq!(
+            render(":::details Read more\nhidden *text*\n:::\n\nafter"),
+            "<details><summary>Read more</summary><p><span>hidden </span><i><span>text</span></i></p></details>\
+            <p><span>after</span></p>"
+        );
+        assert_eq!(
+            render(":::details\n\n- a\n\n:::"),
+            "<details><summary>Details</summary><ul><li><span>a</span></li></ul></details>"
+        );
+    }
+
+    #[test]
+    fn nested_details_directive() {
+        assert_eq!(
+            render(":::details A\n:::details B\nb\n:::\na\n:::"),
+            "<details><summary>A</summary>\
+            <details><summary>B</summary><p><span>b</span></p></details>\
+            <p><span>a</span></p></details>"
+        );
+        assert_eq!(
+            render(":::details A\nnever closed"),
+            "<details><summary>A</summary><p><span>never closed</span></p></details>"
+        );
+    }
+
+    #[test]
+    fn colons_outside_directives() {
+        assert_eq!(render(":::"), "<p><span>:::</span></p>");
+        assert_eq!(
+            render("a ::: b\n:::details\\\n:::note that"),
+            "<p><span>a ::: b</span> <span>:::details</span><br/><span>:::note that</span></p>"
+        );
+        assert_eq!(
+            render("time: 10:30:00"),
+            "<p><span>time: 10:30:00</span></p>"
+        );
+    }
+
+    #[test]
+    fn directives_disabled() {
+        let options = crate::MarkdownOptions {
+            directives: false,
+            ..Default::default()
+        };
+        let props = MarkdownProps {
+            options,
+            ..Default::default()
+        };
+        let source = ":::details Read more\nhidden\n:::";
+        assert_eq!(
+            render_markdown_to_string(source, &props),
+            "<p><span>:::details Read more</span> <span>hidden</span> <span>:::</span></p>"
+        );
+        // the parsed events are the ones of the parser
+        let html = |config| {
+            crate::parse_markdown(source, &config)
+                .into_iter()
+                .filter(|(e, _)| matches!(e, crate::Event::Html(_)))
+                .count()
+        };
+        assert_eq!(html(props.parse_config()), 0);
+        assert_eq!(html(MarkdownProps::default().parse_config()), 2);
+    }
+
+    #[test]
+    fn blockquote_renderer() {
+        let renderer = |quote: BlockquoteDescription<String>| {
+            let kind = quote.kind?;
+            Some(format!(
+                "<aside data-kind=\"{kind:?}\" data-start=\"{}\">{}</aside>",
+                quote.range.start, quote.children
+            ))
+        };
+        let render = |source: &str| {
+            let props = MarkdownProps::default();
+            let metadata = RefCell::new(StringMetadata::default());
+            let cx = StringContext::new(&props, &metadata).with_blockquote_renderer(&renderer);
+            markdown_component(cx, source)
+        };
+
+        assert_eq!(
+            render("> [!NOTE]\n> be careful"),
+            "<aside data-kind=\"Note\" data-start=\"0\"><p><span>be careful</span></p></aside>"
+        );
+        // the plain blockquotes are rendered as usual when the renderer returns None
+        assert_eq!(
+            render("> quote"),
+            "<blockquote><p><span>quote</span></p></blockquote>"
+        );
+    }
+
+    #[test]
+    fn element_overrides() {
+        let mut overrides = ElementOverrides::new();
+        overrides.insert(
+            HtmlElementKind::Table,
+            Box::new(|table: ElementOverride<String, ()>| {
+                format!("<virtual-table>{}</virtual-table>", table.children)
+            }),
+        );
+        let props = MarkdownProps::default();
+        let metadata = RefCell::new(StringMetadata::default());
+        let cx = StringContext::new(&props, &metadata).with_element_overrides(&overrides);
+
+        assert_eq!(
+            markdown_component(cx, "| a |\n|---|\n| b |\n\ntext"),
+            "<virtual-table>\
+            <thead><th><span>a</span></th></thead>\
+            <tr><td><span>b</span></td></tr>\
+            </virtual-table>\
+            <p><span>text</span></p>"
+        );
+        // the images are wrapped, for example in a lightbox
+        overrides.insert(
+            HtmlElementKind::Img,
+            Box::new(|image: ElementOverride<String, ()>| {
+                let src = image.src.unwrap_or_default();
+                format!(
+                    "<a href=\"{src}\" class=\"lightbox\">{}</a>",
+                    image.children
+                )
+            }),
+        );
+        let cx = StringContext::new(&props, &metadata).with_element_overrides(&overrides);
+        assert_eq!(
+            markdown_component(cx, "![cat](cat.png)"),
+            "<p><a href=\"cat.png\" class=\"lightbox\">\
+            <img src=\"cat.png\" alt=\"cat\" loading=\"lazy\" decoding=\"async\"/></a></p>"
+        );
+    }
+
+    #[test]
+    fn directive_without_renderer() {
+        assert_eq!(
+            render(":::warning{.big}\nbe *careful*\n:::\n\nafter"),
+            "<div><p><span>be </span><i><span>careful</span></i></p></div><p><span>after</span></p>"
+        );
+    }
+
+    #[test]
+    fn directive_renderer() {
+        let renderer = |directive: DirectiveDescription<String>| {
+            let attributes: Vec<_> = directive
+                .attributes
+                .iter()
+                .map(|(k, v)| format!("{k}={v}"))
+                .collect();
+            let html = format!(
+                "<aside class=\"{}\" data-attributes=\"{}\" data-range=\"{:?}\">{}</aside>",
+                directive.name,
+                attributes.join(" "),
+                directive.range,
+                directive.children
+            );
+            (directive.name == "warning").then_some(html)
+        };
+        let render = |source: &str| {
+            let props = MarkdownProps::default();
+            let metadata = RefCell::new(StringMetadata::default());
+            let cx = StringContext::new(&props, &metadata).with_directive_renderer(&renderer);
+            markdown_component(cx, source)
+        };
+
+        assert_eq!(
+            render(":::warning{#w level=2}\nbe careful\n:::"),
+            "<aside class=\"warning\" data-attributes=\"id=w level=2\" data-range=\"0..37\">\
+            <p><span>be careful</span></p></aside>"
+        );
+        assert_eq!(
+            render(":::warning\n:::note\ninner\n:::\n:::"),
+            "<aside class=\"warning\" data-attributes=\"\" data-range=\"0..32\">\
+            <div><p><span>inner</span></p></div></aside>"
+        );
+    }
+
+    #[test]
+    fn config_builder() {
+        let props = crate::MarkdownConfig::new()
+            .hard_line_breaks(true)
+            .list_tightness_classes(true)
+            .class_prefix("x-")
+            .build();
+        assert_eq!(
+            render_markdown_to_string("- a\n  b", &props),
+            "<ul class=\"x-md-list-tight\"><li><span>a</span><br/><span>b</span></li></ul>"
+        );
+    }
+
+    #[test]
+    fn source_positions() {
+        use pulldown_cmark::{Event, Parser, Tag};
+        let props = MarkdownProps {
+            source_positions: true,
+            ..Default::default()
+        };
+        let source = "first\n\nsecond *paragraph*\n\n> quote";
+        let html = render_markdown_to_string(source, &props);
+        let paragraphs: Vec<_> = Parser::new(source)
+            .into_offset_iter()
+            .filter(|(e, _)| *e == Event::Start(Tag::Paragraph))
+            .map(|(_, range)| range)
+            .collect();
+        for range in &paragraphs[..2] {
+            let attributes = format!(
+                "<p data-source-start=\"{}\" data-source-end=\"{}\">",
+                range.start, range.end
+            );
+            assert!(html.contains(&attributes), "{html}");
+        }
+        // only the top-level blocks get them, not the paragraph in the quote
+        assert!(html.contains("<blockquote data-source-start=\"27\""));
+        assert_eq!(html.matches("data-source-start").count(), 3);
+        assert_eq!(crate::line_number(source, paragraphs[1].start), 3);
+        assert_eq!(crate::line_number(source, source.len() + 10), 5);
+
+        assert!(!render(source).contains("data-source-start"));
+    }
+
+    #[test]
+    fn inline_attributes() {
+        let props = MarkdownProps {
+            inline_attributes: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string("Intro {.lead}", &props),
+            "<p class=\"lead\"><span>Intro</span></p>"
+        );
+        assert_eq!(
+            render_markdown_to_string("> quote\n> {#note data-kind=aside}", &props),
+            "<blockquote id=\"note\" data-kind=\"aside\"><p><span>quote</span></p></blockquote>"
+        );
+        // only attributes are read, and only when enabled
+        assert_eq!(
+            render_markdown_to_string("a {b}", &props),
+            "<p><span>a {b}</span></p>"
+        );
+        assert_eq!(render("Intro {.lead}"), "<p><span>Intro {.lead}</span></p>");
+    }
+
+    #[test]
+    fn list_tightness() {
+        let props = MarkdownProps {
+            list_tightness_classes: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string("- a\n- b", &props),
+            "<ul class=\"md-list-tight\"><li><span>a</span></li><li><span>b</span></li></ul>"
+        );
+        assert_eq!(
+            render_markdown_to_string("1. a\n\n2. b", &props),
+            "<ol start=\"1\" class=\"md-list-loose\"><li><p><span>a</span></p></li>\
+            <li><p><span>b</span></p></li></ol>"
+        );
+        // the tightness of a nested list doesn't depend on the outer list
+        assert_eq!(
+            render_markdown_to_string("- a\n\n  - b\n  - c", &props),
+            "<ul class=\"md-list-loose\"><li><p><span>a</span></p>\
+            <ul class=\"md-list-tight\"><li><span>b</span></li><li><span>c</span></li></ul></li></ul>"
+        );
+        assert_eq!(
+            render("- a\n\n- b"),
+            "<ul><li><p><span>a</span></p></li><li><p><span>b</span></p></li></ul>"
+        );
+    }
+
+    #[test]
+    fn ordered_list_start() {
+        assert_eq!(
+            render("5. a\n6. b"),
+            "<ol start=\"5\"><li><span>a</span></li><li><span>b</span></li></ol>"
+        );
+    }
+
+    #[test]
+    fn ordered_list_style() {
+        let props = MarkdownProps {
+            ordered_list_style: Some(crate::ListStyle::UpperRoman),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string("3. a\n4. b\n\n- c", &props),
+            "<ol start=\"3\" type=\"I\"><li><span>a</span></li><li><span>b</span></li></ol>\
+            <ul><li><span>c</span></li></ul>"
+        );
+    }
+
+    #[test]
+    fn max_nesting_depth() {
+        let props = MarkdownProps {
+            max_nesting_depth: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string("> > a\n\n> b", &props),
+            "<blockquote><blockquote><p><span class=\"md-depth-limit\"></span></p></blockquote>\
+            </blockquote><blockquote><p><span>b</span></p></blockquote>"
+        );
+    }
+
+    #[test]
+    fn deeply_nested_blockquotes() {
+        let props = MarkdownProps {
+            max_nesting_depth: Some(20),
+            ..Default::default()
+        };
+        let source = format!("{}deep", "> ".repeat(500));
+        let html = render_markdown_to_string(&source, &props);
+        assert_eq!(html.matches("<blockquote>").count(), 21);
+        assert!(html.contains("<div class=\"md-depth-limit\"></div>"));
+        assert!(!html.contains("deep"));
+    }
+
+    #[test]
+    fn deeply_nested_directives() {
+        let props = MarkdownProps {
+            max_nesting_depth: Some(20),
+            ..Default::default()
+        };
+        let source = format!(
+            "{}deep\n{}\nafter",
+            ":::note\n".repeat(500),
+            ":::\n".repeat(500)
+        );
+        let html = render_markdown_to_string(&source, &props);
+        assert_eq!(html.matches("<div>").count(), 20);
+        assert!(html.contains("<div class=\"md-depth-limit\"></div>"));
+        assert!(!html.contains("deep"));
+        assert!(html.ends_with("<p><span>after</span></p>"));
+    }
+
+    #[test]
+    fn deeply_nested_components() {
+        let props = MarkdownProps {
+            max_nesting_depth: Some(20),
+            ..Default::default()
+        };
+        let source = format!("{}deep{} after", "<Box>".repeat(500), "</Box>".repeat(500));
+        let html = render_with_components(&source, &props);
+        assert_eq!(html.matches("<div class=\"box\">").count(), 19);
+        // the placeholder is inline, inside the paragraph
+        assert!(html.contains("<span class=\"md-depth-limit\"></span>"));
+        assert!(!html.contains("deep"));
+        assert!(html.ends_with("<span> after</span></p>"));
+
+        let source = format!(
+            "{}deep\n\n{}after",
+            "<Box>\n\n".repeat(500),
+            "</Box>\n\n".repeat(500)
+        );
+        let html = render_with_components(&source, &props);
+        assert_eq!(html.matches("<div class=\"box\">").count(), 20);
+        assert!(html.contains("<div class=\"md-depth-limit\"></div>"));
+        assert!(html.ends_with("<p><span>after</span></p>"));
+    }
+
+    #[test]
+    fn rtl_root() {
+        let props = MarkdownProps {
+            dir: Some(crate::Direction::Rtl),
+            lang: Some("ar"),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string("مرحبا", &props),
+            "<div dir=\"rtl\" lang=\"ar\"><p><span>مرحبا</span></p></div>"
+        );
+    }
+
+    #[test]
+    fn root_wrapper() {
+        let mut props = MarkdownProps {
+            root_wrapper: Some(crate::RootWrapper {
+                element: HtmlElement::Div,
+                classes: &["prose", "md-root"],
+                id: Some("doc"),
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string("hi", &props),
+            "<div id=\"doc\" class=\"prose md-root\"><p><span>hi</span></p></div>"
+        );
+        props.lang = Some("en");
+        assert_eq!(
+            render_markdown_to_string("hi", &props),
+            "<div id=\"doc\" class=\"prose md-root\" lang=\"en\"><p><span>hi</span></p></div>"
+        );
+    }
+
+    #[test]
+    fn auto_block_direction() {
+        let props = MarkdownProps {
+            auto_block_direction: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string("# שלום\n\nhello\n\n- a", &props),
+            "<h1 id=\"שלום\" aria-level=\"1\" dir=\"auto\"><span>שלום</span></h1>\
+            <p dir=\"auto\"><span>hello</span></p>\
+            <ul><li dir=\"auto\"><span>a</span></li></ul>"
+        );
+    }
+
+    #[test]
+    fn strict_mode() {
+        let props = MarkdownProps {
+            max_nesting_depth: Some(2),
+            ..Default::default()
+        };
+        let metadata = RefCell::new(StringMetadata::default());
+        let cx = StringContext::new(&props, &metadata);
+        assert_eq!(
+            try_markdown_component(cx, "a\n\n<Foo/>\n\n> > > b"),
+            Err(RenderError {
+                issues: vec![
+                    RenderIssue::UnknownComponent("Foo".into()),
+                    RenderIssue::DepthLimitExceeded
+                ]
+            })
+        );
+        assert_eq!(
+            try_markdown_component(cx, "a"),
+            Ok("<p><span>a</span></p>".to_string())
+        );
+        // the nesting of directives is limited too
+        assert_eq!(
+            try_markdown_component(cx, ":::note\n:::note\n:::note\n:::\n:::\n:::"),
+            Err(RenderError {
+                issues: vec![RenderIssue::DepthLimitExceeded]
+            })
+        );
+    }
+
+    #[test]
+    fn max_image_count() {
+        let props = MarkdownProps {
+            max_image_count: Some(2),
+            image_loading: crate::ImageLoading::Auto,
+            ..Default::default()
+        };
+        let source = "![a](a.png)\n\n![b](b.png)\n\n![c](c.png)\n\nafter";
+        assert_eq!(
+            render_markdown_to_string(source, &props),
+            "<p><img src=\"a.png\" alt=\"a\"/></p><p><img src=\"b.png\" alt=\"b\"/></p>\
+            <p></p><div class=\"md-truncated\"></div>"
+        );
+    }
+
+    #[test]
+    fn max_elements() {
+        let props = MarkdownProps {
+            max_elements: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string("a\n\nb\n\nc", &props),
+            "<p><span>a</span></p><div class=\"md-truncated\"></div>"
+        );
+    }
+
+    #[test]
+    fn block_renderer() {
+        let props = MarkdownProps::default();
+        let metadata = RefCell::new(StringMetadata::default());
+        let cx = StringContext::new(&props, &metadata);
+        let source = "# Title\n\nfirst\n\nsecond\n\n- a\n- b";
+        let blocks: Vec<_> = BlockRenderer::new(cx, source).collect();
+        assert_eq!(
+            blocks,
+            vec![
+                "<h1 id=\"title\" aria-level=\"1\"><span>Title</span></h1>",
+                "<p><span>first</span></p>",
+                "<p><span>second</span></p>",
+                "<ul><li><span>a</span></li><li><span>b</span></li></ul>",
+            ]
+        );
+        assert_eq!(blocks.concat(), render(source));
+        assert_eq!(metadata.borrow().headings.len(), 1);
+    }
+
+    #[test]
+    fn block_renderer_footnotes() {
+        let props = MarkdownProps::default();
+        let metadata = RefCell::new(StringMetadata::default());
+        let cx = StringContext::new(&props, &metadata);
+        let mut blocks = BlockRenderer::new(cx, "a[^1]\n\n[^1]: note\n\nb");
+        assert!(blocks.next().unwrap().starts_with("<p><span>a</span>"));
+        // the definition is rendered with the other footnotes, at the end
+        assert_eq!(blocks.next().unwrap(), "");
+        assert_eq!(blocks.next().unwrap(), "<p><span>b</span></p>");
+        assert!(blocks
+            .next()
+            .unwrap()
+            .starts_with("<div class=\"footnotes\">"));
+        assert_eq!(blocks.next(), None);
+    }
+
+    #[cfg(feature = "debug")]
+    #[test]
+    fn debug_events() {
+        let props = MarkdownProps {
+            max_nesting_depth: Some(2),
+            html_policy: crate::HtmlPolicy::Strip,
+            ..Default::default()
+        };
+        let (_, metadata) =
+            render_markdown_to_string_with_metadata("> > > a\n\n<Chart/>\n\n:::note\nb", &props);
+        let events = metadata.debug_events;
+
+        let unhandled: Vec<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                DebugEvent::UnhandledEvent { event, .. } => Some(event.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(unhandled.len(), 3);
+        assert!(unhandled[0].starts_with("Start(Paragraph"));
+        assert!(unhandled[1].starts_with("Text("));
+        assert!(unhandled[2].contains("<Chart/>"));
+
+        assert!(events.contains(&DebugEvent::ComponentNotFound {
+            name: "Chart".into()
+        }));
+        assert!(events.contains(&DebugEvent::ParseWarning {
+            message: "the directive is never closed by a `:::` line".into(),
+            range: 19..26,
+        }));
+        let phases: Vec<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                DebugEvent::Timing { phase, .. } => Some(*phase),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(phases, ["parse", "render"]);
+
+        let props = MarkdownProps {
+            max_image_count: Some(2),
+            ..Default::default()
+        };
+        let source = "![a](a.png)\n\n![b](b.png)\n\n![c](c.png)\n\nafter";
+        let (_, metadata) = render_markdown_to_string_with_metadata(source, &props);
+        assert!(metadata.debug_events.contains(&DebugEvent::Truncated {
+            limit: "max_image_count",
+            position: 26
+        }));
+    }
+
+    #[test]
+    fn abbreviations() {
+        let source = "HTML is not XHTML.\n\n*[HTML]: HyperText Markup Language";
+        let props = MarkdownProps {
+            options: crate::MarkdownOptions {
+                abbreviations: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string(source, &props),
+            "<p><abbr title=\"HyperText Markup Language\"><span>HTML</span></abbr>\
+            <span> is not XHTML.</span></p>"
+        );
+        // the definitions are plain paragraphs by default
+        assert_eq!(
+            render(source),
+            "<p><span>HTML is not XHTML.</span></p>\
+            <p><span>*</span><span>[</span><span>HTML</span><span>]</span>\
+            <span>: HyperText Markup Language</span></p>"
+        );
+    }
+
+    #[test]
+    fn abbreviation_glossary() {
+        lazy_static::lazy_static! {
+            static ref GLOSSARY: HashMap<&'static str, &'static str> = HashMap::from([
+                ("CSS", "Cascading Style Sheets"),
+                ("HTML", "HyperText Markup Language"),
+            ]);
+        }
+        let props = MarkdownProps {
+            abbreviations: Some(&GLOSSARY),
+            options: crate::MarkdownOptions {
+                abbreviations: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string("*[HTML]: Hypertext\n\n# HTML and `CSS`, CSS", &props),
+            "<h1 id=\"html-and-css-css\" aria-level=\"1\">\
+            <abbr title=\"Hypertext\"><span>HTML</span></abbr><span> and </span>\
+            <code>CSS</code><span>, </span>\
+            <abbr title=\"Cascading Style Sheets\"><span>CSS</span></abbr></h1>"
+        );
+    }
+
+    #[test]
+    fn heading_anchor_links() {
+        let props = MarkdownProps {
+            heading_anchor_links: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string("# Hello *world*", &props),
+            "<h1 id=\"hello-world\" aria-level=\"1\"><span>Hello </span><i><span>world</span></i>\
+            <a href=\"#hello-world\" class=\"md-heading-anchor\">#</a></h1>"
+        );
+        let (_, metadata) = render_markdown_to_string_with_metadata("# Hello", &props);
+        assert_eq!(metadata.headings[0].text, "Hello");
+    }
+
+    #[test]
+    fn heading_numbering() {
+        let props = MarkdownProps {
+            heading_numbering: true,
+            ..Default::default()
+        };
+        let html = render_markdown_to_string("# A\n## B\n## C\n### D", &props);
+        for number in ["1 ", "1.1 ", "1.2 ", "1.2.1 "] {
+            assert!(html.contains(&format!(
+                "<span class=\"md-heading-number\">{number}</span>"
+            )));
+        }
+        assert_eq!(
+            render_markdown_to_string("## Usage", &props),
+            "<h2 id=\"usage\" aria-level=\"2\"><span class=\"md-heading-number\">1 </span>\
+            <span>Usage</span></h2>"
+        );
+
+        // the numbers follow the rendered levels, and are left out of the table of content
+        let props = MarkdownProps {
+            heading_numbering: true,
+            heading_offset: 1,
+            ..Default::default()
+        };
+        let (html, metadata) =
+            render_markdown_to_string_with_metadata("# A\n## B\n###### C", &props);
+        assert!(html
+            .contains("<h2 id=\"a\" aria-level=\"2\"><span class=\"md-heading-number\">1 </span>"));
+        assert!(html.contains(
+            "<h6 id=\"c\" aria-level=\"6\"><span class=\"md-heading-number\">1.1.0.0.1 </span>"
+        ));
+        assert_eq!(metadata.headings[1].text, "B");
+    }
+
+    #[test]
+    fn empty_placeholder() {
+        let props = MarkdownProps {
+            empty_placeholder: Some("Nothing here"),
+            ..Default::default()
+        };
+        let render = |source| render_markdown_to_string(source, &props);
+        assert_eq!(render(""), "<div class=\"md-empty\">Nothing here</div>");
+        assert_eq!(
+            render(" \n\n  "),
+            "<div class=\"md-blank\">Nothing here</div>"
+        );
+        assert_eq!(
+            render("<!-- a comment -->\n"),
+            "<div class=\"md-blank\">Nothing here</div>"
+        );
+        assert_eq!(render("a"), "<p><span>a</span></p>");
+        assert_eq!(render_markdown_to_string("", &MarkdownProps::default()), "");
+    }
+
+    #[test]
+    fn unwrap_single_paragraph() {
+        let props = MarkdownProps {
+            unwrap_single_paragraph: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string("Hello *world*", &props),
+            "<span>Hello </span><i><span>world</span></i>"
+        );
+        assert_eq!(
+            render_markdown_to_string("a\n\nb", &props),
+            "<p><span>a</span></p><p><span>b</span></p>"
+        );
+        assert_eq!(
+            render_markdown_to_string("a\n- b", &props),
+            "<p><span>a</span></p><ul><li><span>b</span></li></ul>"
+        );
+    }
+
+    #[test]
+    fn highlight_marks() {
+        let props = MarkdownProps {
+            highlight_marks: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string("a ==b== c `==d==`", &props),
+            "<p><span>a </span><mark><span>b</span></mark><span> c </span>\
+            <code>==d==</code></p>"
+        );
+        assert_eq!(
+            render_markdown_to_string("a ==b", &props),
+            "<p><span>a ==b</span></p>"
+        );
+        assert_eq!(render("a ==b=="), "<p><span>a ==b==</span></p>");
+    }
+
+    #[test]
+    fn kbd() {
+        let props = MarkdownProps {
+            kbd: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_markdown_to_string("press ++Ctrl+C++, not `++x++`", &props),
+            "<p><span>press </span><kbd><span>Ctrl+C</span></kbd><span>, not </span>\
+            <code>++x++</code></p>"
+        );
+        assert_eq!(render("++Ctrl+C++"), "<p><span>++Ctrl+C++</span></p>");
+    }
+
+    #[test]
+    fn render_range() {
+        let props = MarkdownProps::default();
+        let metadata = RefCell::new(StringMetadata::default());
+        let cx = StringContext::new(&props, &metadata);
+
+        let source = "first\n\nsecond\n\nthird";
+        assert_eq!(
+            markdown_component_range(cx, source, 7..13),
+            "<p><span>second</span></p>"
+        );
+        // the blocks that are partly in the range are rendered entirely
+        let source = "a\n\n```\nx\ny\n```";
+        assert_eq!(
+            markdown_component_range(cx, source, 9..10),
+            "<pre><code>x\ny\n</code></pre>"
+        );
+    }
+
+    #[test]
+    fn incremental_render() {
+        let props = MarkdownProps::default();
+        let metadata = RefCell::new(StringMetadata::default());
+        let cx = StringContext::new(&props, &metadata);
+        let mut cache = CachedRender::new();
+
+        let first = "# Title\n\nsome text\n\n- a\n- b";
+        assert_eq!(
+            markdown_component_incremental(cx, first, &mut cache),
+            render(first)
+        );
+        assert_eq!(cache.hits(), 0);
+        assert_eq!(cache.len(), 3);
+
+        let second = "# Title\n\nsome TEXT\n\n- a\n- b";
+        assert_eq!(
+            markdown_component_incremental(cx, second, &mut cache),
+            render(second)
+        );
+        assert_eq!(cache.hits(), 2);
+
+        // the blocks after an edit that changes the length of the source move
+        let third = "# Title\n\nsome more text\n\n- a\n- b";
+        assert_eq!(
+            markdown_component_incremental(cx, third, &mut cache),
+            render(third)
+        );
+        assert_eq!(cache.hits(), 1);
+    }
+
+    #[test]
+    fn incremental_render_with_references() {
+        let props = MarkdownProps::default();
+        let metadata = RefCell::new(StringMetadata::default());
+        let cx = StringContext::new(&props, &metadata);
+        let mut cache = CachedRender::new();
+
+        let first = "see [x][ref]\n\ntext\n\n[ref]: /a";
+        markdown_component_incremental(cx, first, &mut cache);
+        // the block with the link is the same, but its reference is not
+        let second = "see [x][ref]\n\ntext\n\n[ref]: /b";
+        assert_eq!(
+            markdown_component_incremental(cx, second, &mut cache),
+            render(second)
+        );
+        assert_eq!(cache.hits(), 1);
+    }
+
+    #[test]
+    fn incremental_render_with_footnotes_and_html() {
+        let props = MarkdownProps::default();
+        let metadata = RefCell::new(StringMetadata::default());
+        let cx = StringContext::new(&props, &metadata);
+        let mut cache = CachedRender::new();
+
+        let source = "a[^1]\n\n[^1]: note\n\nb\n\n<div>\n\nc\n\n</div>\n\nd";
+        for _ in 0..2 {
+            assert_eq!(
+                markdown_component_incremental(cx, source, &mut cache),
+                render(source)
+            );
+        }
+        assert_eq!(cache.hits(), 1);
+    }
+}
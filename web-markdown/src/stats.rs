@@ -0,0 +1,146 @@
+use core::ops::Range;
+use core::time::Duration;
+
+use pulldown_cmark::{Event, Tag, TagEnd};
+
+/// Statistics about the text of a document,
+/// for example to display the reading time of a blog post
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DocumentStats {
+    /// the number of words
+    pub word_count: usize,
+    /// the estimated time to read the document, rounded up
+    pub reading_time_minutes: u32,
+    /// the number of characters, without whitespace
+    pub char_count: usize,
+}
+
+/// Information about the rendering of a document,
+/// passed to the callback of [`crate::Context::on_render_complete`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    /// the number of elements produced by the renderer,
+    /// including text nodes
+    pub element_count: usize,
+    /// the time it took to parse and render the document.
+    /// None on targets without a clock, like `wasm32`
+    pub duration: Option<Duration>,
+}
+
+/// How the [`DocumentStats`] of a document are computed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StatsConfig {
+    /// the reading speed used to compute the reading time
+    pub words_per_minute: u32,
+    /// wether the content of code blocks is counted.
+    /// Inline code is always counted
+    pub count_code_blocks: bool,
+}
+
+impl Default for StatsConfig {
+    fn default() -> Self {
+        Self {
+            words_per_minute: 200,
+            count_code_blocks: false,
+        }
+    }
+}
+
+/// wether the content of `tag` is separated from the text around it
+fn is_block(tag: TagEnd) -> bool {
+    matches!(
+        tag,
+        TagEnd::Paragraph
+            | TagEnd::Heading(_)
+            | TagEnd::BlockQuote(_)
+            | TagEnd::CodeBlock
+            | TagEnd::Item
+            | TagEnd::TableCell
+            | TagEnd::FootnoteDefinition
+            | TagEnd::DefinitionListTitle
+            | TagEnd::DefinitionListDefinition
+    )
+}
+
+/// `collect_stats(stream, config)` counts the words of the text of `stream`.
+/// The alt text of images and the frontmatter are not counted
+pub fn collect_stats(stream: &[(Event<'_>, Range<usize>)], config: StatsConfig) -> DocumentStats {
+    // the text read by the user, where the words of different blocks
+    // are separated by whitespace
+    let mut text = String::new();
+    let mut skipped_depth = 0;
+    for (event, _) in stream {
+        match event {
+            Event::Start(tag) => {
+                let skipped = match tag {
+                    Tag::CodeBlock(_) => !config.count_code_blocks,
+                    Tag::Image { .. } | Tag::MetadataBlock(_) => true,
+                    _ => false,
+                };
+                if skipped || skipped_depth > 0 {
+                    skipped_depth += 1
+                }
+                if is_block(tag.to_end()) {
+                    text.push(' ')
+                }
+            }
+            Event::End(tag) => {
+                if skipped_depth > 0 {
+                    skipped_depth -= 1
+                }
+                if is_block(*tag) {
+                    text.push(' ')
+                }
+            }
+            Event::Text(s) | Event::Code(s) if skipped_depth == 0 => text.push_str(s),
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            _ => (),
+        }
+    }
+
+    let word_count = text.split_whitespace().count();
+    let words_per_minute = config.words_per_minute.max(1) as usize;
+    DocumentStats {
+        word_count,
+        reading_time_minutes: word_count.div_ceil(words_per_minute) as u32,
+        char_count: text.chars().filter(|c| !c.is_whitespace()).count(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pulldown_cmark::Parser;
+
+    fn stats(source: &str, config: StatsConfig) -> DocumentStats {
+        let stream: Vec<_> = Parser::new(source).into_offset_iter().collect();
+        collect_stats(&stream, config)
+    }
+
+    #[test]
+    fn known_document() {
+        let source = "# A title\n\nSome **bold**ly `formatted`\ntext.\n\n- one\n- two\n\n\
+                      ![a cat](cat.png)\n\n```\nlet x = 1;\n```\n";
+        assert_eq!(
+            stats(source, StatsConfig::default()),
+            DocumentStats {
+                word_count: 8,
+                reading_time_minutes: 1,
+                char_count: 36,
+            }
+        );
+        let with_code = StatsConfig {
+            count_code_blocks: true,
+            ..Default::default()
+        };
+        assert_eq!(stats(source, with_code).word_count, 12);
+    }
+
+    #[test]
+    fn reading_time() {
+        let source = "word ".repeat(401);
+        let config = StatsConfig::default();
+        assert_eq!(stats(&source, config).reading_time_minutes, 3);
+        assert_eq!(stats("", config), DocumentStats::default());
+    }
+}
@@ -0,0 +1,481 @@
+use core::ops::Range;
+use std::collections::HashMap;
+
+use pulldown_cmark::{Alignment, CowStr, Event, Tag, TagEnd};
+
+use crate::html::{decode_entities, parse_tag, tag_end, ParsedTag};
+
+/// `caption_end(stream, start, prefix)` returns the index of the end of the paragraph
+/// starting at index `start`, if it is a caption starting with `prefix`.
+/// A paragraph with nothing after the prefix is not a caption
+fn caption_end(stream: &[(Event<'_>, Range<usize>)], start: usize, prefix: &str) -> Option<usize> {
+    if !matches!(stream.get(start), Some((Event::Start(Tag::Paragraph), _))) {
+        return None;
+    }
+    let empty = match stream.get(start + 1) {
+        Some((Event::Text(text), _)) if text.starts_with(prefix) => {
+            text[prefix.len()..].trim().is_empty()
+        }
+        _ => return None,
+    };
+    let len = stream[start..]
+        .iter()
+        .position(|(e, _)| matches!(e, Event::End(TagEnd::Paragraph)))?;
+    (!empty || len > 2).then_some(start + len)
+}
+
+/// `fold_captions(stream, prefix)` moves the paragraphs starting with `prefix`
+/// that are right before or right after a table inside the table,
+/// before its head, and removes the prefix.
+/// A caption between two tables belongs to the second one.
+/// Like pandoc, the prefix is usually `Table:`.
+/// The paragraph is then rendered as the caption of the table
+pub fn fold_captions<'a>(
+    mut stream: Vec<(Event<'a>, Range<usize>)>,
+    prefix: &str,
+) -> Vec<(Event<'a>, Range<usize>)> {
+    let mut i = 0;
+    while i < stream.len() {
+        let Event::Start(Tag::Table(_)) = stream[i].0 else {
+            i += 1;
+            continue;
+        };
+        let Some(table_len) = stream[i..]
+            .iter()
+            .position(|(e, _)| matches!(e, Event::End(TagEnd::Table)))
+        else {
+            break;
+        };
+        let table_end = i + table_len;
+
+        // the caption before the table is preferred
+        let before = stream[..i]
+            .iter()
+            .rposition(|(e, _)| matches!(e, Event::Start(Tag::Paragraph)))
+            .filter(|&start| caption_end(&stream, start, prefix) == Some(i - 1));
+        let (start, end) = if let Some(start) = before {
+            (start, i - 1)
+        } else if let Some(end) = caption_end(&stream, table_end + 1, prefix)
+            .filter(|&end| !matches!(stream.get(end + 1), Some((Event::Start(Tag::Table(_)), _))))
+        {
+            (table_end + 1, end)
+        } else {
+            i = table_end + 1;
+            continue;
+        };
+
+        let mut caption: Vec<_> = stream.drain(start..=end).collect();
+        if let (Event::Text(text), range) = &mut caption[1] {
+            let rest = text[prefix.len()..].trim_start().to_string();
+            range.start = range.end - rest.len().min(range.len());
+            *text = CowStr::from(rest);
+        }
+        if matches!(&caption[1].0, Event::Text(text) if text.is_empty()) {
+            caption.remove(1);
+        }
+
+        // the position of the start of the table, after removing the caption
+        let table_start = if start < i { start } else { i };
+        stream.splice(table_start + 1..table_start + 1, caption);
+        i = table_end + 1;
+    }
+    stream
+}
+
+/// a cell of a raw html table
+struct HtmlCell<'a> {
+    /// wether it is a `<th>`
+    header: bool,
+    /// the position of the cell in the source, from its start tag to its end tag
+    range: Range<usize>,
+    content: Vec<(Event<'a>, Range<usize>)>,
+}
+
+/// a row of a raw html table, and wether it is inside its `<thead>`
+type HtmlRow<'a> = (bool, Vec<HtmlCell<'a>>);
+
+/// the alignment of the html cell `tag`, from its `align` attribute or its style
+fn cell_alignment(tag: &ParsedTag) -> Alignment {
+    let value = |name| {
+        tag.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .and_then(|(_, v)| v.as_deref())
+    };
+    let align = value("align").or_else(|| {
+        let style = value("style")?;
+        let (_, rest) = style.split_once("text-align")?;
+        Some(
+            rest.trim_start_matches([' ', ':'])
+                .split(';')
+                .next()?
+                .trim(),
+        )
+    });
+    match align.map(str::to_ascii_lowercase).as_deref() {
+        Some("left") => Alignment::Left,
+        Some("center") => Alignment::Center,
+        Some("right") => Alignment::Right,
+        _ => Alignment::None,
+    }
+}
+
+/// wether `text` has a character reference that [`decode_entities`] leaves unchanged
+fn has_unknown_entity(text: &str) -> bool {
+    text.match_indices('&').any(|(start, _)| {
+        let rest = &text[start + 1..];
+        let Some(end) = rest.find(';') else {
+            return false;
+        };
+        let name = &rest[..end];
+        let entity = &text[start..start + end + 2];
+        !name.is_empty()
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '#')
+            && decode_entities(entity) == entity
+    })
+}
+
+/// removes the whitespace around the content of a cell, like markdown does
+fn trim_content(content: &mut Vec<(Event<'_>, Range<usize>)>) {
+    if let Some((Event::Text(text), _)) = content.first_mut() {
+        *text = CowStr::from(text.trim_start().to_string());
+    }
+    if let Some((Event::Text(text), _)) = content.last_mut() {
+        *text = CowStr::from(text.trim_end().to_string());
+    }
+    content.retain(|(e, _)| !matches!(e, Event::Text(text) if text.is_empty()));
+}
+
+/// `html_table(html, position)` returns the events of a markdown table
+/// for the raw html table `html`, or None if it is not a single simple table.
+/// `position(range)` returns the position in the source of `range` in `html`
+fn html_table<'a>(
+    html: &str,
+    position: impl Fn(Range<usize>) -> Range<usize>,
+) -> Option<Vec<(Event<'a>, Range<usize>)>> {
+    let mut rows: Vec<HtmlRow> = Vec::new();
+    let mut row: Option<HtmlRow> = None;
+    let mut cell: Option<HtmlCell> = None;
+    let mut caption: Option<Vec<_>> = None;
+    // wether the caption was closed, the table was opened or closed, or we are in the head
+    let (mut captioned, mut opened, mut closed, mut in_head) = (false, false, false, false);
+
+    let mut i = 0;
+    while i < html.len() {
+        let rest = &html[i..];
+        let end = if rest.starts_with("<!--") {
+            rest.find("-->")? + 3
+        } else if rest.starts_with('<') {
+            tag_end(rest)? + 1
+        } else {
+            rest.find('<').unwrap_or(rest.len())
+        };
+        let (raw, range) = (&rest[..end], position(i..i + end));
+        i += end;
+        let content = match (&mut cell, &mut caption) {
+            (Some(cell), _) => Some(&mut cell.content),
+            (None, Some(caption)) if !captioned => Some(caption),
+            _ => None,
+        };
+
+        if !raw.starts_with('<') {
+            match content {
+                // the browser decodes the entities that we don't know, like `&nbsp;`
+                Some(content) if has_unknown_entity(raw) => {
+                    content.push((Event::InlineHtml(raw.to_string().into()), range))
+                }
+                Some(content) => content.push((Event::Text(decode_entities(raw).into()), range)),
+                None if raw.trim().is_empty() => (),
+                None => return None,
+            }
+            continue;
+        }
+        let tag = match raw.strip_prefix("<!--") {
+            Some(_) => None,
+            None => parse_tag(&raw[1..raw.len() - 1]),
+        };
+        let Some(tag) = tag.filter(|t| !closed && is_table_tag(&t.name)) else {
+            // the other tags are only allowed in the cells and the caption
+            match content {
+                Some(content) => content.push((Event::InlineHtml(raw.to_string().into()), range)),
+                None if raw.starts_with("<!--") => (),
+                None => return None,
+            }
+            continue;
+        };
+        match (tag.name.as_str(), tag.end) {
+            ("table", false) if !opened => opened = true,
+            _ if !opened => return None,
+            ("table", true) if cell.is_none() => {
+                rows.extend(row.take());
+                closed = true
+            }
+            ("caption", false) if caption.is_none() && rows.is_empty() && row.is_none() => {
+                caption = Some(vec![])
+            }
+            ("caption", true) if caption.is_some() => captioned = true,
+            ("thead", closing) => in_head = !closing,
+            ("tbody" | "tfoot" | "colgroup" | "col", _) => (),
+            ("tr", false) if cell.is_none() => rows.extend(row.replace((in_head, vec![]))),
+            ("tr", true) if cell.is_none() => rows.extend(row.take()),
+            ("th" | "td", false) if cell.is_none() => {
+                // merged cells can't be represented in a markdown table
+                if tag
+                    .attributes
+                    .iter()
+                    .any(|(n, _)| n == "colspan" || n == "rowspan")
+                {
+                    return None;
+                }
+                cell = Some(HtmlCell {
+                    header: tag.name == "th",
+                    range,
+                    content: vec![],
+                })
+            }
+            ("th" | "td", true) if cell.is_some() => {
+                let mut cell = cell.take()?;
+                cell.range.end = range.end;
+                trim_content(&mut cell.content);
+                row.get_or_insert((in_head, vec![])).1.push(cell)
+            }
+            _ => return None,
+        }
+    }
+    if !closed || (caption.is_some() && !captioned) {
+        return None;
+    }
+
+    // without a `<thead>`, a first row of `<th>` is the head
+    let head_rows = rows.iter().filter(|(head, _)| *head).count();
+    let first_is_head = match rows.first() {
+        Some((true, _)) => true,
+        Some((false, cells)) => head_rows == 0 && cells.iter().all(|c| c.header),
+        None => false,
+    };
+    if head_rows > 1 || (head_rows == 1 && !first_is_head) {
+        return None;
+    }
+    let head = first_is_head.then(|| rows.remove(0).1);
+    // the cells are aligned by their own attributes, see `cell_alignments`
+    let columns = head
+        .iter()
+        .chain(rows.iter().map(|(_, cells)| cells))
+        .map(Vec::len)
+        .max()
+        .unwrap_or(0);
+
+    let whole = position(0..html.len());
+    let wrap = |tag: Tag<'a>, inside: Vec<(Event<'a>, Range<usize>)>| {
+        let end = tag.to_end();
+        let mut events = vec![(Event::Start(tag), whole.clone())];
+        events.extend(inside);
+        events.push((Event::End(end), whole.clone()));
+        events
+    };
+    let cells = |cells: Vec<HtmlCell<'a>>| -> Vec<_> {
+        cells
+            .into_iter()
+            .flat_map(|cell| {
+                let mut events = wrap(Tag::TableCell, cell.content);
+                let last = events.len() - 1;
+                (events[0].1, events[last].1) = (cell.range.clone(), cell.range);
+                events
+            })
+            .collect()
+    };
+    let mut inside = Vec::new();
+    if let Some(caption) = caption {
+        inside.extend(wrap(Tag::Paragraph, caption));
+    }
+    if let Some(head) = head {
+        inside.extend(wrap(Tag::TableHead, cells(head)));
+    }
+    for (_, row) in rows {
+        inside.extend(wrap(Tag::TableRow, cells(row)));
+    }
+    Some(wrap(Tag::Table(vec![Alignment::None; columns]), inside))
+}
+
+/// `cell_alignments(source, stream)` returns the alignment of the cells
+/// of the raw html tables turned into markdown tables by [`normalize_html_tables`],
+/// from the `align` attribute or the style of each cell, by position of the cell.
+/// Unlike the columns of markdown tables, the cells of a column can be aligned differently
+pub fn cell_alignments(
+    source: &str,
+    stream: &[(Event<'_>, Range<usize>)],
+) -> HashMap<usize, Alignment> {
+    let mut alignments = HashMap::new();
+    let mut in_html_table = false;
+    for (event, range) in stream {
+        let raw = source.get(range.clone()).unwrap_or_default();
+        match event {
+            Event::Start(Tag::Table(_)) => {
+                in_html_table = raw
+                    .get(..6)
+                    .is_some_and(|t| t.eq_ignore_ascii_case("<table"))
+            }
+            Event::Start(Tag::TableCell) if in_html_table => {
+                let tag = raw
+                    .strip_prefix('<')
+                    .and_then(|tag| parse_tag(&tag[..tag_end(tag)?]))
+                    .filter(|tag| tag.name == "td" || tag.name == "th");
+                if let Some(tag) = tag {
+                    alignments.insert(range.start, cell_alignment(&tag));
+                }
+            }
+            _ => (),
+        }
+    }
+    alignments
+}
+
+/// wether `name` is the name of a tag that structures a table
+fn is_table_tag(name: &str) -> bool {
+    matches!(
+        name,
+        "table" | "caption" | "thead" | "tbody" | "tfoot" | "colgroup" | "col" | "tr" | "th" | "td"
+    )
+}
+
+/// `normalize_html_tables(stream)` replaces the html blocks made of a single raw `<table>`
+/// by the events of a markdown table, so that they are rendered like one.
+/// The text of the cells is not parsed as markdown,
+/// and the other tags inside them are kept as inline html.
+/// The tables that a markdown table can't represent,
+/// like the ones with merged cells, are left unchanged
+pub fn normalize_html_tables(
+    mut stream: Vec<(Event<'_>, Range<usize>)>,
+) -> Vec<(Event<'_>, Range<usize>)> {
+    let mut i = 0;
+    while i < stream.len() {
+        if stream[i].0 != Event::Start(Tag::HtmlBlock) {
+            i += 1;
+            continue;
+        }
+        let Some(len) = stream[i..]
+            .iter()
+            .position(|(e, _)| *e == Event::End(TagEnd::HtmlBlock))
+        else {
+            break;
+        };
+        let end = i + len;
+        let lines = &stream[i + 1..end];
+        let html: String = lines
+            .iter()
+            .filter_map(|(e, _)| match e {
+                Event::Html(line) => Some(line.as_ref()),
+                _ => None,
+            })
+            .collect();
+
+        // the lines map to the source if they are contiguous, unlike in a blockquote
+        let start = lines.first().map_or(0, |(_, r)| r.start);
+        let contiguous = lines.last().map(|(_, r)| r.end - start) == Some(html.len());
+        let block = stream[i].1.clone();
+        let position = |r: Range<usize>| match contiguous {
+            true => start + r.start..start + r.end,
+            false => block.clone(),
+        };
+        let trimmed = html.trim_start();
+        let offset = html.len() - trimmed.len();
+        let table = trimmed
+            .get(..6)
+            .filter(|t| t.eq_ignore_ascii_case("<table"))
+            .and_then(|_| html_table(trimmed, |r| position(offset + r.start..offset + r.end)));
+        match table {
+            Some(table) => {
+                let len = table.len();
+                stream.splice(i..=end, table);
+                i += len;
+            }
+            None => i = end + 1,
+        }
+    }
+    stream
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pulldown_cmark::{Options, Parser};
+
+    fn events(source: &str) -> Vec<Event<'_>> {
+        let stream = Parser::new_ext(source, Options::ENABLE_TABLES)
+            .into_offset_iter()
+            .collect();
+        fold_captions(stream, "Table:")
+            .into_iter()
+            .map(|(e, _)| e)
+            .collect()
+    }
+
+    #[test]
+    fn caption_before() {
+        let events = events("Table: *Fruits*\n\n| a |\n|---|\n| b |\n");
+        assert!(matches!(events[0], Event::Start(Tag::Table(_))));
+        assert_eq!(events[1], Event::Start(Tag::Paragraph));
+        assert_eq!(events[2], Event::Start(Tag::Emphasis));
+        assert_eq!(events[3], Event::Text("Fruits".into()));
+    }
+
+    #[test]
+    fn caption_after() {
+        let events = events("| a |\n|---|\n| b |\n\nTable: Fruits\n\nafter");
+        assert!(matches!(events[0], Event::Start(Tag::Table(_))));
+        assert_eq!(events[2], Event::Text("Fruits".into()));
+        assert_eq!(events[events.len() - 2], Event::Text("after".into()));
+    }
+
+    #[test]
+    fn html_tables() {
+        let source = "<table>\n<caption>Fruits</caption>\n\
+            <thead><tr><th align=\"right\">a</th></tr></thead>\n\
+            <tr><td> b &amp; <i>c</i> </td></tr>\n</table>\n";
+        let stream = Parser::new(source).into_offset_iter().collect();
+        let stream = normalize_html_tables(stream);
+        let alignments = cell_alignments(source, &stream);
+        let events: Vec<_> = stream.into_iter().map(|(e, _)| e).collect();
+        assert_eq!(events[0], Event::Start(Tag::Table(vec![Alignment::None])));
+        // each cell has its own alignment
+        let th = source.find("<th align").unwrap();
+        let td = source.find("<td").unwrap();
+        assert_eq!(
+            alignments,
+            HashMap::from([(th, Alignment::Right), (td, Alignment::None)])
+        );
+        assert_eq!(events[2], Event::Text("Fruits".into()));
+        assert_eq!(events[4], Event::Start(Tag::TableHead));
+        assert_eq!(
+            events[11..15],
+            [
+                Event::Text("b & ".into()),
+                Event::InlineHtml("<i>".into()),
+                Event::Text("c".into()),
+                Event::InlineHtml("</i>".into()),
+            ]
+        );
+        assert_eq!(events.last(), Some(&Event::End(TagEnd::Table)));
+
+        // the entities that can't be decoded are left to the browser
+        let source = "<table><tr><td>a&nbsp;b &amp; &copy;</td></tr></table>";
+        let stream = Parser::new(source).into_offset_iter().collect();
+        let events: Vec<_> = normalize_html_tables(stream)
+            .into_iter()
+            .map(|(e, _)| e)
+            .collect();
+        assert_eq!(events[3], Event::InlineHtml("a&nbsp;b &amp; &copy;".into()));
+
+        // merged cells are left as raw html
+        let source = "<table><tr><td colspan=\"2\">a</td></tr></table>";
+        let stream: Vec<_> = Parser::new(source).into_offset_iter().collect();
+        assert_eq!(normalize_html_tables(stream.clone()), stream);
+    }
+
+    #[test]
+    fn no_caption() {
+        let source = "Tables: are nice\n\n| a |\n|---|\n";
+        let stream: Vec<_> = Parser::new_ext(source, Options::ENABLE_TABLES).collect();
+        assert_eq!(events(source), stream);
+    }
+}
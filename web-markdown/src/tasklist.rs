@@ -0,0 +1,154 @@
+use core::ops::Range;
+use std::collections::HashMap;
+
+use pulldown_cmark::{CowStr, Event, Tag};
+
+use crate::scripts::merge_text;
+
+/// the change of state of a task list checkbox, when the user clicks on it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TaskToggle {
+    /// the position in the markdown source of the marker, like `[ ]` or `[x]`
+    pub range: Range<usize>,
+    /// wether the task is now checked
+    pub checked: bool,
+}
+
+/// an extra state of the tasks of task lists, besides `[ ]` and `[x]`,
+/// like `[/]` for a partially complete task, see [`crate::MarkdownProps::task_list_states`].
+/// These tasks are rendered as indeterminate checkboxes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TaskState {
+    /// the css class of the checkbox, like `md-task-partial`,
+    /// to tell the states apart
+    pub class: &'static str,
+}
+
+/// `marker(text)` returns the character between the brackets
+/// of the task marker that starts `text`, like `/` for `[/] partial`,
+/// and the length of the marker
+fn marker(text: &str) -> Option<(char, usize)> {
+    let mut chars = text.strip_prefix('[')?.chars();
+    let c = chars.next()?;
+    chars
+        .as_str()
+        .starts_with(']')
+        .then(|| (c, c.len_utf8() + 2))
+}
+
+/// `task_state(source, range, states)` returns the extra state
+/// of the task list marker at `range` in `source`, if it is one of `states`
+pub(crate) fn task_state(
+    source: &str,
+    range: &Range<usize>,
+    states: Option<&HashMap<char, TaskState>>,
+) -> Option<TaskState> {
+    let (c, len) = marker(source.get(range.clone())?)?;
+    if len != range.len() {
+        return None;
+    }
+    states?.get(&c).copied()
+}
+
+/// turns the texts that start a list item with a marker of `states`, like `[/] partial`,
+/// into a task list marker followed by the rest of the text.
+/// The marker keeps its position in the source, that tells its state apart from `[ ]`.
+/// Unknown markers are left as text
+pub fn fold_task_states<'a>(
+    stream: Vec<(Event<'a>, Range<usize>)>,
+    states: &HashMap<char, TaskState>,
+) -> Vec<(Event<'a>, Range<usize>)> {
+    let mut result = Vec::with_capacity(stream.len());
+    // wether the next text starts a list item
+    let mut item_start = false;
+    for (event, range) in merge_text(stream) {
+        let starts_item = item_start;
+        item_start = matches!(event, Event::Start(Tag::Item))
+            || (starts_item && matches!(event, Event::Start(Tag::Paragraph)));
+        let Event::Text(text) = &event else {
+            result.push((event, range));
+            continue;
+        };
+        let marker = marker(text)
+            .filter(|(c, len)| states.contains_key(c) && text[*len..].starts_with([' ', '\t']));
+        match marker {
+            // the text maps exactly to the source, so that the marker can be found in it
+            Some((_, len)) if starts_item && text.len() == range.len() => {
+                let rest = text[len..].trim_start();
+                let rest_start = range.end - rest.len();
+                result.push((Event::TaskListMarker(false), range.start..range.start + len));
+                if !rest.is_empty() {
+                    result.push((
+                        Event::Text(CowStr::from(rest.to_string())),
+                        rest_start..range.end,
+                    ))
+                }
+            }
+            _ => result.push((event, range)),
+        }
+    }
+    result
+}
+
+/// `apply_task_toggle(source, toggle)` returns the markdown `source`
+/// with the marker of the task toggled by `toggle` updated.
+/// If `toggle` doesn't point to a marker in `source`, `source` is returned unchanged
+pub fn apply_task_toggle(source: &str, toggle: &TaskToggle) -> String {
+    let is_marker = source
+        .get(toggle.range.clone())
+        .is_some_and(|m| marker(m).is_some_and(|(_, len)| len == m.len()));
+
+    if !is_marker {
+        return source.to_string();
+    }
+
+    let marker = if toggle.checked { "[x]" } else { "[ ]" };
+    let mut result = source.to_string();
+    result.replace_range(toggle.range.clone(), marker);
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pulldown_cmark::{Event, Options, Parser};
+
+    /// the toggle of the first task of `source`
+    fn first_toggle(source: &str, checked: bool) -> TaskToggle {
+        let range = Parser::new_ext(source, Options::ENABLE_TASKLISTS)
+            .into_offset_iter()
+            .find_map(|(e, range)| matches!(e, Event::TaskListMarker(_)).then_some(range))
+            .unwrap();
+        TaskToggle { range, checked }
+    }
+
+    #[test]
+    fn round_trip() {
+        let source = "- [ ] write\n- [x] test\n";
+        let toggle = first_toggle(source, true);
+        let checked = apply_task_toggle(source, &toggle);
+        assert_eq!(checked, "- [x] write\n- [x] test\n");
+
+        let toggle = first_toggle(&checked, false);
+        assert_eq!(apply_task_toggle(&checked, &toggle), source);
+    }
+
+    #[test]
+    fn extra_state_toggle() {
+        let toggle = TaskToggle {
+            range: 2..5,
+            checked: true,
+        };
+        assert_eq!(apply_task_toggle("- [/] half", &toggle), "- [x] half");
+    }
+
+    #[test]
+    fn outdated_toggle() {
+        let toggle = TaskToggle {
+            range: 0..3,
+            checked: true,
+        };
+        assert_eq!(apply_task_toggle("text", &toggle), "text");
+        assert_eq!(apply_task_toggle("", &toggle), "");
+    }
+}
@@ -0,0 +1,184 @@
+use core::ops::Range;
+
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+
+use crate::scripts::merge_text;
+use crate::TextTransform;
+
+/// Built-in typographic replacements of the text of the document,
+/// see [`crate::MarkdownProps::typography`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TypographyPreset {
+    /// common symbols: `(c)` becomes ©, `(r)` ®, `(tm)` ™,
+    /// `->` →, `<-` ←, `<->` ↔ and `=>` ⇒
+    Symbols,
+    /// german quotes: "this" becomes „this“, and 'this' becomes ‚this‘
+    German,
+    /// french quotes: "this" becomes « this », with non-breaking spaces
+    French,
+}
+
+/// the replacements of [`TypographyPreset::Symbols`], longest first
+const SYMBOLS: &[(&str, &str)] = &[
+    ("(tm)", "™"),
+    ("(TM)", "™"),
+    ("<->", "↔"),
+    ("(c)", "©"),
+    ("(C)", "©"),
+    ("(r)", "®"),
+    ("(R)", "®"),
+    ("->", "→"),
+    ("<-", "←"),
+    ("=>", "⇒"),
+];
+
+impl TypographyPreset {
+    /// `apply(text, previous)` applies the replacements of the preset to `text`,
+    /// preceded by the character `previous` in the document
+    fn apply(self, text: &str, previous: Option<char>) -> String {
+        match self {
+            TypographyPreset::Symbols => SYMBOLS
+                .iter()
+                .fold(text.to_string(), |text, (from, to)| text.replace(from, to)),
+            TypographyPreset::German => replace_quotes(text, previous, ["„", "“", "‚", "‘"]),
+            TypographyPreset::French => {
+                replace_quotes(text, previous, ["«\u{a0}", "\u{a0}»", "‹\u{a0}", "\u{a0}›"])
+            }
+        }
+    }
+}
+
+/// `replace_quotes(text, previous, quotes)` replaces the straight and curly quotes of `text`,
+/// preceded by the character `previous`, by `quotes`:
+/// the opening and closing double quotes, then the opening and closing single quotes.
+/// A straight quote opens a quotation at the start of a block,
+/// or after a space or an opening bracket.
+/// Single quotes between two letters are apostrophes, and are left as is
+fn replace_quotes(text: &str, mut previous: Option<char>, quotes: [&str; 4]) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let opening = previous.is_none_or(|p| p.is_whitespace() || "([{".contains(p));
+        let apostrophe = previous.is_some_and(char::is_alphanumeric)
+            && chars.peek().is_some_and(|next| next.is_alphanumeric());
+        let replacement = match c {
+            '"' if opening => Some(quotes[0]),
+            '“' => Some(quotes[0]),
+            '"' | '”' => Some(quotes[1]),
+            '\'' | '’' if apostrophe => None,
+            '\'' if opening => Some(quotes[2]),
+            '‘' => Some(quotes[2]),
+            '\'' | '’' => Some(quotes[3]),
+            _ => None,
+        };
+        match replacement {
+            Some(quote) => result.push_str(quote),
+            None => result.push(c),
+        }
+        previous = Some(c);
+    }
+    result
+}
+
+/// wether `tag` is inside a paragraph, so that a quote right after it
+/// continues the text before it
+fn is_inline(tag: &Tag) -> bool {
+    matches!(
+        tag,
+        Tag::Emphasis
+            | Tag::Strong
+            | Tag::Strikethrough
+            | Tag::Superscript
+            | Tag::Subscript
+            | Tag::Link { .. }
+            | Tag::Image { .. }
+    )
+}
+
+/// `transform_text(stream, presets, transform)` applies the typographic `presets`,
+/// then `transform`, to the text of the document.
+/// Code, raw html and the frontmatter are never rewritten.
+/// The rewritten texts keep their position in the source
+pub fn transform_text<'a>(
+    stream: Vec<(Event<'a>, Range<usize>)>,
+    presets: &[TypographyPreset],
+    transform: Option<TextTransform>,
+) -> Vec<(Event<'a>, Range<usize>)> {
+    let mut verbatim_depth = 0;
+    // the last character of the text of the current block
+    let mut previous = None;
+    let mut result = merge_text(stream);
+    for (event, _) in &mut result {
+        match event {
+            Event::Start(Tag::CodeBlock(_) | Tag::MetadataBlock(_)) => verbatim_depth += 1,
+            Event::End(TagEnd::CodeBlock | TagEnd::MetadataBlock(_)) => verbatim_depth -= 1,
+            Event::Start(tag) if !is_inline(tag) => previous = None,
+            Event::SoftBreak | Event::HardBreak => previous = Some('\n'),
+            Event::Code(code) => previous = code.chars().next_back(),
+            Event::Text(text) if verbatim_depth == 0 => {
+                let mut rewritten = presets
+                    .iter()
+                    .fold(text.to_string(), |t, preset| preset.apply(&t, previous));
+                if let Some(transform) = transform {
+                    rewritten = (transform.0)(&rewritten)
+                }
+                previous = text.chars().next_back();
+                if rewritten != **text {
+                    *text = CowStr::from(rewritten)
+                }
+            }
+            _ => (),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn symbols() {
+        assert_eq!(
+            TypographyPreset::Symbols.apply("(c) 2024 -> (tm) <-> x", None),
+            "© 2024 → ™ ↔ x"
+        );
+    }
+
+    #[test]
+    fn german_quotes() {
+        let german = |text| TypographyPreset::German.apply(text, None);
+        assert_eq!(german("er sagte \"hallo\""), "er sagte „hallo“");
+        assert_eq!(german("'gut' und geht's"), "‚gut‘ und geht's");
+        assert_eq!(german("“smart”"), "„smart“");
+    }
+
+    #[test]
+    fn french_quotes() {
+        assert_eq!(
+            TypographyPreset::French.apply("il dit \"bonjour\"", None),
+            "il dit «\u{a0}bonjour\u{a0}»"
+        );
+    }
+
+    #[test]
+    fn quotes_across_formatting() {
+        let stream = vec![
+            (Event::Start(Tag::Paragraph), 0..7),
+            (Event::Text("\"".into()), 0..1),
+            (Event::Start(Tag::Emphasis), 1..6),
+            (Event::Text("ja".into()), 2..4),
+            (Event::End(TagEnd::Emphasis), 1..6),
+            (Event::Text("\"".into()), 6..7),
+            (Event::End(TagEnd::Paragraph), 0..7),
+        ];
+        let texts: Vec<_> = transform_text(stream, &[TypographyPreset::German], None)
+            .into_iter()
+            .filter_map(|(e, _)| match e {
+                Event::Text(t) => Some(t.to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(texts, vec!["„", "ja", "“"]);
+    }
+}
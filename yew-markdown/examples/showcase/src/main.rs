@@ -1,5 +1,5 @@
 use yew::prelude::*;
-use yew_markdown::Markdown;
+use yew_markdown::{Markdown, MathConfig};
 
 static MARKDOWN_SOURCE: &str = r#"
 ## Code
@@ -37,7 +37,7 @@ for markdown documentation, see https://github.com/wooorm/markdown or [here](htt
 #[function_component(App)]
 fn app() -> Html {
     html! {
-        <Markdown src={MARKDOWN_SOURCE}/>
+        <Markdown src={MARKDOWN_SOURCE} math={MathConfig::default()}/>
     }
 }
 
@@ -1,33 +1,82 @@
 use web_framework_markdown::{
-    markdown_component, Context, CowStr, ElementAttributes, HtmlElement, MarkdownProps, StyleLink,
-    MATH_STYLE_SHEET_LINK,
+    is_activation_key, markdown_component, slugify, Context, CowStr, ElementAttributes,
+    HtmlElement, MarkdownProps, StyleLink,
 };
 
 use core::ops::Range;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 
-pub use web_framework_markdown::{ComponentCreationError, LinkDescription, Options};
+pub use web_framework_markdown::{
+    apply_task_toggle, line_number, parse_markdown, BlockQuoteKind, BlockquoteDescription,
+    ClickedElement, CodeBlockDescription, CodeClickEvent, CommentDirective, ComponentCreationError,
+    DebugEvent, Direction, DirectiveDescription, DocumentStats, ElementOverride, Frontmatter,
+    FrontmatterOptions, FrontmatterValue, Heading, HtmlAllowlist, HtmlElementKind, HtmlPolicy,
+    ImageAttributes, ImageErrorEvent, ImageLoading, LinkAttributes, LinkClickEvent,
+    LinkDescription, LinkInfo, LinkRelPolicy, LinkTarget, ListStyle, MarkdownClickEvent,
+    MarkdownConfig, MarkdownHoverEvent, MarkdownOptions, MathConfig, MathDescription,
+    MentionConfig, MermaidConfig, Options, ParseConfig, RenderStats, RootWrapper, SoftBreakMode,
+    StatsConfig, StreamTransform, SyntectConfig, TaskState, TaskToggle, TextTransform,
+    TypographyPreset, UnknownComponentPolicy, WikilinkResolution,
+};
+
+#[cfg(feature = "cache")]
+pub use web_framework_markdown::{clear_markdown_cache, set_markdown_cache_size};
 
 use yew::prelude::{
-    function_component, html, AttrValue, Callback, Html, Properties, UseStateHandle,
+    function_component, html, AttrValue, Callback, Html, Properties, TargetCast, UseStateHandle,
 };
+use yew::virtual_dom::ApplyAttributeAs;
 
 pub type MdComponentProps = web_framework_markdown::MdComponentProps<Html>;
 
-use web_sys::{window, MouseEvent};
+/// adds the attributes `attributes` to the element `html`,
+/// for the attributes that are not set one by one in `html!`
+fn add_attributes(html: &mut Html, attributes: Vec<(&'static str, String)>) {
+    if let Html::VTag(tag) = html {
+        for (name, value) in attributes {
+            tag.add_attribute(name, value)
+        }
+    }
+}
+
+/// adds the attributes written in the markdown source to the element `html`.
+/// Their names are not `'static`, so they can't be added with `add_attribute`
+fn add_source_attributes(html: &mut Html, attributes: Vec<(String, String)>) {
+    if let Html::VTag(tag) = html {
+        let map = tag.attributes.get_mut_index_map();
+        for (name, value) in attributes {
+            map.insert(name.into(), (value.into(), ApplyAttributeAs::Attribute));
+        }
+    }
+}
 
-#[derive(Clone, Debug)]
-pub struct MarkdownMouseEvent {
-    /// the original mouse event triggered when a text element was clicked on
-    pub mouse_event: MouseEvent,
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{window, Element, Event, KeyboardEvent, MouseEvent};
 
-    /// the corresponding range in the markdown source, as a slice of [`u8`][u8]
-    pub position: Range<usize>,
-    // TODO: add a clonable tag for the type of the element
-    // pub tag: pulldown_cmark::Tag<'a>,
+/// clicks the element of the keyboard event `e`, if its key activates it,
+/// so that the same handler runs as for a click, see [`is_activation_key`]
+fn click_on_activation_key(e: KeyboardEvent) {
+    if is_activation_key(&e.key()) {
+        e.prevent_default();
+        if let Some(target) = e.target_dyn_into::<web_sys::HtmlElement>() {
+            target.click()
+        }
+    }
 }
 
+/// the event reported when a markdown element is clicked on
+pub type MarkdownMouseEvent = MarkdownClickEvent<MouseEvent>;
+
+/// the event reported when the mouse enters or leaves a markdown element
+pub type MarkdownHoverMouseEvent = MarkdownHoverEvent<MouseEvent>;
+
+/// the event reported when a link is clicked on
+pub type LinkClickMouseEvent = LinkClickEvent<MouseEvent>;
+
+/// the event reported when inline code is clicked on
+pub type CodeClickMouseEvent = CodeClickEvent<MouseEvent>;
+
 /// component store.
 /// It is called when therer is a `<CustomComponent>` inside the markdown source.
 /// It is basically a hashmap but more efficient for a small number of items
@@ -63,22 +112,145 @@ impl<'a> Context<'a, 'static> for &'a Props {
 
     type Handler<T: 'static> = Callback<T>;
 
-    type MouseEvent = MouseEvent;
+    type Event = MouseEvent;
 
     fn props(self) -> MarkdownProps {
+        if let Some(config) = &self.config {
+            return config.clone().build();
+        }
         let Props {
             theme,
+            syntax_highlight,
             wikilinks,
             hard_line_breaks,
             parse_options,
+            link_target,
+            link_rel_policy,
+            allowed_url_schemes,
+            code_block_copy_button,
+            code_block_line_numbers,
+            unknown_components,
+            html_policy,
+            heading_offset,
+            heading_anchor_links,
+            heading_numbering,
+            toc_min_level,
+            toc_max_level,
+            inline_attributes,
+            source_positions,
+            ordered_list_style,
+            list_tightness_classes,
+            max_nesting_depth,
+            max_elements,
+            max_image_count,
+            dir,
+            lang,
+            auto_block_direction,
+            abbreviations,
+            highlight_marks,
+            kbd,
+            empty_placeholder,
+            unwrap_single_paragraph,
+            read_only_task_lists,
+            task_list_states,
+            rule_class,
+            collapse_rules,
+            comment_directive_names,
+            scroll_to_fragment,
+            language_aliases,
+            footnote_popovers,
+            code_block_dedent,
+            code_block_trim,
+            root_wrapper,
+            frontmatter_overrides,
+            figure_images,
+            class_prefix,
+            autolink,
+            mentions,
+            table_caption_prefix,
+            normalize_html_tables,
+            stats_config,
+            soft_break,
+            emoji,
+            transform_stream,
+            typography,
+            text_transform,
+            base_url,
+            options,
+            broken_image_placeholder,
+            eager_image_count,
+            decorative_empty_alt,
+            image_loading,
+            math,
+            mermaid,
             ..
         } = self;
 
         MarkdownProps {
             theme: *theme,
+            syntax_highlight: *syntax_highlight,
             wikilinks: *wikilinks,
             hard_line_breaks: *hard_line_breaks,
             parse_options: *parse_options,
+            math: *math,
+            mermaid: *mermaid,
+            image_loading: *image_loading,
+            eager_image_count: *eager_image_count,
+            decorative_empty_alt: *decorative_empty_alt,
+            broken_image_placeholder: *broken_image_placeholder,
+            options: *options,
+            base_url: *base_url,
+            transform_stream: *transform_stream,
+            typography: *typography,
+            text_transform: *text_transform,
+            emoji: *emoji,
+            soft_break: *soft_break,
+            stats_config: *stats_config,
+            table_caption_prefix: *table_caption_prefix,
+            normalize_html_tables: *normalize_html_tables,
+            code_block_copy_button: *code_block_copy_button,
+            code_block_line_numbers: *code_block_line_numbers,
+            unknown_components: *unknown_components,
+            html_policy: *html_policy,
+            heading_offset: *heading_offset,
+            heading_anchor_links: *heading_anchor_links,
+            heading_numbering: *heading_numbering,
+            toc_min_level: *toc_min_level,
+            toc_max_level: *toc_max_level,
+            inline_attributes: *inline_attributes,
+            source_positions: *source_positions,
+            ordered_list_style: *ordered_list_style,
+            list_tightness_classes: *list_tightness_classes,
+            max_nesting_depth: *max_nesting_depth,
+            max_elements: *max_elements,
+            max_image_count: *max_image_count,
+            dir: *dir,
+            lang: *lang,
+            auto_block_direction: *auto_block_direction,
+            abbreviations: *abbreviations,
+            highlight_marks: *highlight_marks,
+            kbd: *kbd,
+            empty_placeholder: *empty_placeholder,
+            unwrap_single_paragraph: *unwrap_single_paragraph,
+            read_only_task_lists: *read_only_task_lists,
+            task_list_states: *task_list_states,
+            rule_class: *rule_class,
+            collapse_rules: *collapse_rules,
+            comment_directive_names: *comment_directive_names,
+            scroll_to_fragment: *scroll_to_fragment,
+            language_aliases: *language_aliases,
+            footnote_popovers: *footnote_popovers,
+            code_block_dedent: *code_block_dedent,
+            code_block_trim: *code_block_trim,
+            root_wrapper: *root_wrapper,
+            frontmatter_overrides: *frontmatter_overrides,
+            figure_images: *figure_images,
+            class_prefix: *class_prefix,
+            autolink: *autolink,
+            mentions: *mentions,
+            link_target: *link_target,
+            link_rel_policy: *link_rel_policy,
+            allowed_url_schemes: *allowed_url_schemes,
         }
     }
 
@@ -95,79 +267,141 @@ impl<'a> Context<'a, 'static> for &'a Props {
         inside: Self::View,
         attributes: ElementAttributes<Callback<MouseEvent>>,
     ) -> Self::View {
+        if let Some(f) = self.element_overrides.and_then(|o| o.get(&e.kind())) {
+            return f.emit(ElementOverride {
+                element: Some(e),
+                children: inside,
+                attributes,
+                src: None,
+            });
+        }
+        let extra = attributes.extra_attributes();
         let style = attributes.style.map(|x| x.to_string());
         let classes: Vec<_> = attributes.classes.iter().map(|x| x.to_string()).collect();
+        let id = attributes.id;
         let on_click = attributes.on_click;
+        let on_mouseenter = attributes.on_mouseenter;
+        let on_mouseleave = attributes.on_mouseleave;
+        let source_attributes = attributes.source_attributes;
 
-        match e {
+        let mut html = match e {
             HtmlElement::Div => {
-                html! {<div style={style} onclick={on_click} class={classes}>{inside}</div>}
+                html! {<div id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</div>}
             }
             HtmlElement::Span => {
-                html! {<span style={style} onclick={on_click} class={classes}>{inside}</span>}
+                html! {<span id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</span>}
             }
             HtmlElement::Paragraph => {
-                html! {<p  style={style} onclick={on_click} class={classes}>{inside}</p>}
+                html! {<p  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</p>}
             }
             HtmlElement::Ul => {
-                html! {<ul  style={style} onclick={on_click} class={classes}>{inside}</ul>}
+                html! {<ul  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</ul>}
             }
             HtmlElement::Ol(start) => {
-                html! {<ol start={start.to_string()}  style={style} onclick={on_click} class={classes}>{inside}</ol>}
+                html! {<ol start={start.to_string()}  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</ol>}
             }
             HtmlElement::Li => {
-                html! {<li  style={style} onclick={on_click} class={classes}>{inside}</li>}
+                html! {<li  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</li>}
             }
             HtmlElement::BlockQuote => {
-                html! {<blockquote  style={style} onclick={on_click} class={classes}>{inside}</blockquote>}
+                html! {<blockquote  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</blockquote>}
             }
             HtmlElement::Heading(1) => {
-                html! {<h1  style={style} onclick={on_click} class={classes}>{inside}</h1>}
+                html! {<h1  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</h1>}
             }
             HtmlElement::Heading(2) => {
-                html! {<h2  style={style} onclick={on_click} class={classes}>{inside}</h2>}
+                html! {<h2  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</h2>}
             }
             HtmlElement::Heading(3) => {
-                html! {<h3  style={style} onclick={on_click} class={classes}>{inside}</h3>}
+                html! {<h3  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</h3>}
             }
             HtmlElement::Heading(4) => {
-                html! {<h4  style={style} onclick={on_click} class={classes}>{inside}</h4>}
+                html! {<h4  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</h4>}
             }
             HtmlElement::Heading(5) => {
-                html! {<h5  style={style} onclick={on_click} class={classes}>{inside}</h5>}
+                html! {<h5  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</h5>}
             }
             HtmlElement::Heading(6) => {
-                html! {<h6  style={style} onclick={on_click} class={classes}>{inside}</h6>}
+                html! {<h6  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</h6>}
             }
             HtmlElement::Heading(_) => panic!(),
             HtmlElement::Table => {
-                html! {<table  style={style} onclick={on_click} class={classes}>{inside}</table>}
+                html! {<table  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</table>}
             }
             HtmlElement::Thead => {
-                html! {<thead  style={style} onclick={on_click} class={classes}>{inside}</thead>}
+                html! {<thead  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</thead>}
             }
             HtmlElement::Trow => {
-                html! {<tr  style={style} onclick={on_click} class={classes}>{inside}</tr>}
+                html! {<tr  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</tr>}
             }
             HtmlElement::Tcell => {
-                html! {<td  style={style} onclick={on_click} class={classes}>{inside}</td>}
+                html! {<td  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</td>}
+            }
+            HtmlElement::TheadCell => {
+                html! {<th  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</th>}
+            }
+            HtmlElement::Caption => {
+                html! {<caption  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</caption>}
+            }
+            HtmlElement::Button => {
+                html! {<button  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</button>}
             }
             HtmlElement::Italics => {
-                html! {<i  style={style} onclick={on_click} class={classes}>{inside}</i>}
+                html! {<i  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</i>}
             }
             HtmlElement::Bold => {
-                html! {<b  style={style} onclick={on_click} class={classes}>{inside}</b>}
+                html! {<b  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</b>}
             }
             HtmlElement::StrikeThrough => {
-                html! {<s  style={style} onclick={on_click} class={classes}>{inside}</s>}
+                html! {<s  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</s>}
+            }
+            HtmlElement::Sup => {
+                html! {<sup  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</sup>}
+            }
+            HtmlElement::Sub => {
+                html! {<sub  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</sub>}
             }
             HtmlElement::Pre => {
-                html! {<pre  style={style} onclick={on_click} class={classes}>{inside}</pre>}
+                html! {<pre  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</pre>}
             }
             HtmlElement::Code => {
-                html! {<code  style={style} onclick={on_click} class={classes}>{inside}</code>}
+                html! {<code  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</code>}
             }
-        }
+            HtmlElement::Dl => {
+                html! {<dl  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</dl>}
+            }
+            HtmlElement::Dt => {
+                html! {<dt  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</dt>}
+            }
+            HtmlElement::Dd => {
+                html! {<dd  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</dd>}
+            }
+            HtmlElement::Details => {
+                html! {<details  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</details>}
+            }
+            HtmlElement::Summary => {
+                html! {<summary  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</summary>}
+            }
+            HtmlElement::Abbr => {
+                html! {<abbr  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</abbr>}
+            }
+            HtmlElement::Mark => {
+                html! {<mark  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</mark>}
+            }
+            HtmlElement::Kbd => {
+                html! {<kbd  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</kbd>}
+            }
+            HtmlElement::Figure => {
+                html! {<figure  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</figure>}
+            }
+            HtmlElement::Figcaption => {
+                html! {<figcaption  id={id} style={style} onclick={on_click} onmouseenter={on_mouseenter} onmouseleave={on_mouseleave} class={classes}>{inside}</figcaption>}
+            }
+        };
+
+        add_attributes(&mut html, extra);
+        add_source_attributes(&mut html, source_attributes);
+        html
     }
 
     fn el_span_with_inner_html(
@@ -187,10 +421,18 @@ impl<'a> Context<'a, 'static> for &'a Props {
     }
 
     fn el_hr(self, attributes: ElementAttributes<Callback<MouseEvent>>) -> Self::View {
+        let extra = attributes.extra_attributes();
         let style = attributes.style.map(|x| x.to_string());
         let classes: Vec<_> = attributes.classes.iter().map(|x| x.to_string()).collect();
         let on_click = attributes.on_click;
-        html! {<hr  style={style} onclick={on_click} class={classes}/>}
+        let on_keydown = attributes
+            .keyboard_activation
+            .then(|| Callback::from(click_on_activation_key));
+        let mut html = html! {
+            <hr style={style} onclick={on_click} onkeydown={on_keydown} class={classes}/>
+        };
+        add_attributes(&mut html, extra);
+        html
     }
 
     fn el_br(self) -> Self::View {
@@ -201,12 +443,61 @@ impl<'a> Context<'a, 'static> for &'a Props {
         children.into_iter().collect()
     }
 
-    fn el_a(self, children: Self::View, href: String) -> Self::View {
-        html! {<a href={href.to_string()}>{children}</a>}
+    fn el_a(
+        self,
+        children: Self::View,
+        href: String,
+        attributes: LinkAttributes,
+        on_click: Option<Callback<MouseEvent>>,
+    ) -> Self::View {
+        html! {
+            <a
+                onclick={on_click}
+                href={href}
+                target={attributes.target}
+                rel={attributes.rel}
+                title={attributes.title}
+                class={attributes.classes}
+            >
+                {children}
+            </a>
+        }
     }
 
-    fn el_img(self, src: String, alt: String) -> Self::View {
-        html! {<img src={src} alt={alt}/>}
+    fn el_img(self, src: String, alt: String, attributes: ImageAttributes) -> Self::View {
+        let on_image_error = self.on_image_error.clone();
+        let ImageAttributes {
+            title,
+            width,
+            height,
+            loading,
+            decoding,
+            role,
+            ..
+        } = attributes.clone();
+        let onerror = Callback::from(move |e: Event| {
+            let img: Element = e.target_unchecked_into();
+            let failed_src = img.get_attribute("src").unwrap_or_default();
+            if let Some(f) = &on_image_error {
+                f.emit(attributes.error_event(&failed_src))
+            }
+            if let Some(fallback) = attributes.fallback_src(&failed_src) {
+                img.set_attribute("src", fallback).unwrap()
+            }
+        });
+        let img = html! {<img src={src.clone()} alt={alt} title={title} width={width} height={height} loading={loading} decoding={decoding} role={role} onerror={onerror}/>};
+        match self
+            .element_overrides
+            .and_then(|o| o.get(&HtmlElementKind::Img))
+        {
+            Some(f) => f.emit(ElementOverride {
+                element: None,
+                children: img,
+                attributes: Default::default(),
+                src: Some(src),
+            }),
+            None => img,
+        }
     }
 
     fn el_text(self, text: CowStr<'a>) -> Self::View {
@@ -216,20 +507,83 @@ impl<'a> Context<'a, 'static> for &'a Props {
     fn el_input_checkbox(
         self,
         checked: bool,
+        indeterminate: bool,
         attributes: ElementAttributes<Callback<MouseEvent>>,
     ) -> Self::View {
+        let mut extra = attributes.extra_attributes();
+        // yew can't set the `indeterminate` property of an element it creates
+        if indeterminate {
+            extra.push(("aria-checked", "mixed".into()))
+        }
         let style = attributes.style.map(|x| x.to_string());
         let classes: Vec<_> = attributes.classes.iter().map(|x| x.to_string()).collect();
         let on_click = attributes.on_click;
-        html! {
+        let mut html = html! {
             <input type="checkbox" checked={checked}
                 onclick={on_click}
                 class={classes}
                 style={style}
             />
+        };
+        add_attributes(&mut html, extra);
+        html
+    }
+
+    fn mount_dynamic_link(self, link: StyleLink) {
+        let document = window().unwrap().document().unwrap();
+
+        let element = document.create_element("link").unwrap();
+
+        let StyleLink {
+            rel,
+            href,
+            integrity,
+            crossorigin,
+        } = link;
+        element.set_attribute("rel", rel).unwrap();
+        element.set_attribute("href", href).unwrap();
+        element.set_attribute("integrity", integrity).unwrap();
+        element.set_attribute("crossorigin", crossorigin).unwrap();
+
+        document.head().unwrap().append_child(&element).unwrap();
+    }
+
+    fn mount_dynamic_script(self, src: String) {
+        let document = window().unwrap().document().unwrap();
+        let selector = format!("script[src=\"{src}\"]");
+        if let Ok(Some(_)) = document.query_selector(&selector) {
+            return;
+        }
+        let element = document.create_element("script").unwrap();
+        element.set_attribute("src", &src).unwrap();
+        document.head().unwrap().append_child(&element).unwrap();
+    }
+
+    fn location_fragment(self) -> Option<String> {
+        window()?.location().hash().ok()
+    }
+
+    fn scroll_into_view(self, id: String) {
+        // the document is not mounted yet, so the scroll waits for the next frame
+        let scroll = Closure::once_into_js(move || {
+            let element = window()
+                .and_then(|w| w.document())
+                .and_then(|d| d.get_element_by_id(&id));
+            if let Some(element) = element {
+                element.scroll_into_view()
+            }
+        });
+        if let Some(window) = window() {
+            let _ = window.request_animation_frame(scroll.unchecked_ref());
         }
     }
 
+    fn is_link_mounted(self, link: &StyleLink) -> bool {
+        let document = window().unwrap().document().unwrap();
+        let selector = format!("link[href=\"{}\"]", link.href);
+        matches!(document.query_selector(&selector), Ok(Some(_)))
+    }
+
     fn call_handler<T: 'static>(callback: &Self::Handler<T>, input: T) {
         callback.emit(input)
     }
@@ -237,6 +591,7 @@ impl<'a> Context<'a, 'static> for &'a Props {
     fn make_md_handler(
         self,
         position: Range<usize>,
+        element: ClickedElement,
         stop_propagation: bool,
     ) -> Self::Handler<MouseEvent> {
         match &self.onclick {
@@ -247,8 +602,9 @@ impl<'a> Context<'a, 'static> for &'a Props {
                         e.stop_propagation()
                     }
                     let report = MarkdownMouseEvent {
-                        mouse_event: e,
+                        event: e,
                         position: position.clone(),
+                        element: element.clone(),
                     };
                     f.emit(report)
                 })
@@ -257,21 +613,185 @@ impl<'a> Context<'a, 'static> for &'a Props {
         }
     }
 
+    fn make_md_hover_handler(
+        self,
+        position: Range<usize>,
+        entering: bool,
+    ) -> Option<Self::Handler<MouseEvent>> {
+        let f = self.on_hover.clone()?;
+        Some(Callback::from(move |e: MouseEvent| {
+            f.emit(MarkdownHoverMouseEvent {
+                event: e,
+                position: position.clone(),
+                entering,
+            })
+        }))
+    }
+
+    fn make_link_click_handler(
+        self,
+        url: String,
+        position: Range<usize>,
+    ) -> Option<Self::Handler<MouseEvent>> {
+        let f = self.on_link_click.clone()?;
+        Some(Callback::from(move |e: MouseEvent| {
+            f.emit(LinkClickMouseEvent {
+                url: url.clone(),
+                mouse_event: e,
+                range: position.clone(),
+            })
+        }))
+    }
+
+    fn make_inline_code_click_handler(
+        self,
+        code: String,
+        position: Range<usize>,
+    ) -> Option<Self::Handler<MouseEvent>> {
+        let f = self.on_inline_code_click.clone()?;
+        Some(Callback::from(move |e: MouseEvent| {
+            f.emit(CodeClickMouseEvent {
+                code: code.clone(),
+                mouse_event: e,
+                range: position.clone(),
+            })
+        }))
+    }
+
     fn has_custom_links(self) -> bool {
         self.render_links.is_some()
     }
 
+    fn heading_slug(self, text: &str) -> String {
+        match &self.heading_slugger {
+            Some(f) => f.emit(text.to_string()),
+            None => slugify(text),
+        }
+    }
+
+    fn render_code_block(self, code_block: CodeBlockDescription) -> Option<Html> {
+        self.render_code_block.as_ref().map(|f| f.emit(code_block))
+    }
+
+    fn render_directive(self, directive: DirectiveDescription<Html>) -> Option<Html> {
+        self.render_directive.as_ref().map(|f| f.emit(directive))
+    }
+
+    fn render_blockquote(self, blockquote: BlockquoteDescription<Html>) -> Option<Html> {
+        self.render_blockquote.as_ref().map(|f| f.emit(blockquote))
+    }
+
+    fn render_math(self, math: MathDescription) -> Option<Html> {
+        self.render_math.as_ref().map(|f| f.emit(math))
+    }
+
+    fn resolve_wikilink(self, target: &str) -> Option<WikilinkResolution> {
+        match &self.resolve_wikilink {
+            Some(f) => f.emit(target.to_string()),
+            None => Some(WikilinkResolution::identity(target)),
+        }
+    }
+
+    fn resolve_mention(self, name: &str) -> Option<String> {
+        self.resolve_mention
+            .as_ref()
+            .and_then(|f| f.emit(name.to_string()))
+    }
+
+    fn resolve_hashtag(self, tag: &str) -> Option<String> {
+        self.resolve_hashtag
+            .as_ref()
+            .and_then(|f| f.emit(tag.to_string()))
+    }
+
     fn render_links(self, link: LinkDescription<Html>) -> Result<Html, String> {
         let f = self.render_links.clone().unwrap();
         Ok(f.emit(link))
     }
 
+    fn make_task_toggle_handler(
+        self,
+        position: Range<usize>,
+        toggle: TaskToggle,
+    ) -> Self::Handler<MouseEvent> {
+        let on_click = self.make_md_handler(position, ClickedElement::TaskItem, true);
+        let task_toggle = self.task_toggle.clone();
+        Callback::from(move |e: MouseEvent| {
+            on_click.emit(e);
+            if let Some(setter) = &task_toggle {
+                setter.set(toggle.clone())
+            }
+        })
+    }
+
+    fn make_copy_handler(self, code: String) -> Option<Self::Handler<MouseEvent>> {
+        Some(Callback::from(move |_| {
+            if let Some(window) = window() {
+                let _ = window.navigator().clipboard().write_text(&code);
+            }
+        }))
+    }
+
+    fn make_permalink_copy_handler(self, id: String) -> Option<Self::Handler<MouseEvent>> {
+        Some(Callback::from(move |_| {
+            if let Some(window) = window() {
+                let href = window.location().href().unwrap_or_default();
+                let page = href.split('#').next().unwrap_or_default();
+                let _ = window
+                    .navigator()
+                    .clipboard()
+                    .write_text(&format!("{page}#{id}"));
+            }
+        }))
+    }
+
     fn set_frontmatter(&mut self, frontmatter: String) {
         if let Some(setter) = &self.frontmatter {
             setter.set(frontmatter)
         }
     }
 
+    fn set_parsed_frontmatter(&mut self, frontmatter: Frontmatter) {
+        if let Some(setter) = &self.parsed_frontmatter {
+            setter.set(frontmatter)
+        }
+    }
+
+    fn set_headings(&mut self, headings: Vec<Heading>) {
+        if let Some(setter) = &self.headings {
+            setter.set(headings)
+        }
+    }
+
+    fn set_document_links(&mut self, links: Vec<LinkInfo>) {
+        if let Some(setter) = &self.document_links {
+            setter.set(links)
+        }
+    }
+
+    fn set_comment_directives(&mut self, directives: Vec<CommentDirective>) {
+        if let Some(setter) = &self.comment_directives {
+            setter.set(directives)
+        }
+    }
+
+    fn set_stats(&mut self, stats: DocumentStats) {
+        if let Some(setter) = &self.stats {
+            setter.set(stats)
+        }
+    }
+
+    fn on_render_complete(self) -> Option<Self::Handler<RenderStats>> {
+        self.on_render_complete.clone()
+    }
+
+    #[cfg(feature = "debug")]
+    fn send_debug(self, events: Vec<DebugEvent>) {
+        if let Some(f) = &self.send_debug {
+            f.emit(events)
+        }
+    }
+
     fn has_custom_component(self, name: &str) -> bool {
         self.components.0.get(name).is_some()
     }
@@ -290,15 +810,389 @@ impl<'a> Context<'a, 'static> for &'a Props {
 pub struct Props {
     pub src: AttrValue,
 
+    /// the options set with a [`MarkdownConfig`].
+    /// When set, they replace the options passed as separate props
+    #[prop_or_default]
+    pub config: Option<MarkdownConfig>,
+
     #[prop_or_default]
     pub onclick: Option<Callback<MarkdownMouseEvent, ()>>,
 
+    /// the callback called when the mouse enters or leaves a text element
+    #[prop_or_default]
+    pub on_hover: Option<Callback<MarkdownHoverMouseEvent>>,
+
+    /// the callback called when an image fails to load
+    #[prop_or_default]
+    pub on_image_error: Option<Callback<ImageErrorEvent>>,
+
+    /// the callback called when a link is clicked on, before the navigation.
+    /// Call `prevent_default` on the mouse event to route the link inside the application
+    #[prop_or_default]
+    pub on_link_click: Option<Callback<LinkClickMouseEvent>>,
+
+    /// the callback called when inline code is clicked on, with the text of the code.
+    /// Clicks on code blocks are not reported
+    #[prop_or_default]
+    pub on_inline_code_click: Option<Callback<CodeClickMouseEvent>>,
+
+    /// the callback called once the document is rendered,
+    /// with the number of rendered elements and the time it took
+    #[prop_or_default]
+    pub on_render_complete: Option<Callback<RenderStats>>,
+
     #[prop_or_default]
     pub render_links: Option<Callback<LinkDescription<Html>, Html>>,
 
+    /// renders inline and display maths, for example to produce MathML.
+    /// By default, maths are compiled with katex
+    #[prop_or_default]
+    pub render_math: Option<Callback<MathDescription, Html>>,
+
+    /// renders code blocks, for example to draw diagrams
+    /// depending on the language of the block.
+    /// By default, code blocks are highlighted with syntect
+    #[prop_or_default]
+    pub render_code_block: Option<Callback<CodeBlockDescription, Html>>,
+
+    /// renders the container directives like `:::warning{key=value} ... :::`,
+    /// for example to create admonitions.
+    /// By default, the content of a directive is rendered in a `<div>`
+    #[prop_or_default]
+    pub render_directive: Option<Callback<DirectiveDescription<Html>, Html>>,
+
+    /// renders the blockquotes, and the callouts like `> [!NOTE]`,
+    /// for example to style quotes with their author.
+    /// By default, they are rendered as a `<blockquote>`, or as a callout
+    #[prop_or_default]
+    pub render_blockquote: Option<Callback<BlockquoteDescription<Html>, Html>>,
+
+    /// renders the elements of some kinds instead of the default tags,
+    /// for example to replace every table with a virtualized one.
+    /// The callback gets the rendered children and the attributes of the element
+    #[prop_or_default]
+    pub element_overrides: Option<
+        &'static HashMap<
+            HtmlElementKind,
+            Callback<ElementOverride<Html, Callback<MouseEvent>>, Html>,
+        >,
+    >,
+
+    /// finds the pages that `[[wikilinks]]` point to.
+    /// Links to missing pages get the `md-wikilink-broken` class.
+    /// By default, the target of a wikilink is used as its url
+    #[prop_or_default]
+    pub resolve_wikilink: Option<Callback<String, Option<WikilinkResolution>>>,
+
+    /// finds the urls of the users mentioned like `@alice`, when `mentions` is set.
+    /// Unresolved mentions get the `md-mention-broken` class
+    #[prop_or_default]
+    pub resolve_mention: Option<Callback<String, Option<String>>>,
+
+    /// finds the urls of the hashtags like `#topic`, when `mentions` is set.
+    /// Unresolved hashtags get the `md-hashtag-broken` class
+    #[prop_or_default]
+    pub resolve_hashtag: Option<Callback<String, Option<String>>>,
+
     #[prop_or_default]
     pub theme: Option<&'static str>,
 
+    /// how code blocks are highlighted.
+    /// If set, it replaces the theme
+    #[prop_or_default]
+    pub syntax_highlight: Option<SyntectConfig>,
+
+    /// the stylesheet used to display maths.
+    /// It is only loaded when the document contains maths.
+    /// If None, no stylesheet is loaded
+    #[prop_or_default]
+    pub math: Option<MathConfig>,
+
+    /// the mermaid script, that draws the ` ```mermaid ` diagrams.
+    /// It is only loaded when the document contains a diagram.
+    /// If None, no script is loaded
+    #[prop_or_default]
+    pub mermaid: Option<MermaidConfig>,
+
+    /// how images are loaded.
+    /// By default, they are loaded lazily
+    #[prop_or_default]
+    pub image_loading: ImageLoading,
+
+    /// the number of images at the start of the document
+    /// that are always loaded eagerly
+    #[prop_or_default]
+    pub eager_image_count: usize,
+
+    /// gives `role="presentation"` to the images with an empty alternative text,
+    /// like `![](divider.png)`, so that screen readers treat them as decorative
+    #[prop_or_default]
+    pub decorative_empty_alt: bool,
+
+    /// the url of the image displayed instead of images that fail to load
+    #[prop_or_default]
+    pub broken_image_placeholder: Option<&'static str>,
+
+    /// the markdown extensions to enable.
+    /// They are ignored if `parse_options` is set
+    #[prop_or_default]
+    pub options: MarkdownOptions,
+
+    /// the url that relative links and images are resolved against,
+    /// usually the url of the markdown document.
+    /// If None, relative urls are left unchanged
+    #[prop_or_default]
+    pub base_url: Option<&'static str>,
+
+    /// a function that rewrites the parsed events before they are rendered,
+    /// see [`StreamTransform`]
+    #[prop_or_default]
+    pub transform_stream: Option<StreamTransform>,
+
+    /// typographic replacements applied to the text, in order, like german quotes
+    #[prop_or_default]
+    pub typography: &'static [TypographyPreset],
+
+    /// a function that rewrites the text of the document, but not the code
+    #[prop_or_default]
+    pub text_transform: Option<TextTransform>,
+
+    /// replaces github shortcodes like `:tada:` by the corresponding emojis.
+    /// It only has an effect when the `emoji` feature is enabled
+    #[prop_or_default]
+    pub emoji: bool,
+
+    /// how line breaks inside paragraphs are rendered
+    #[prop_or_default]
+    pub soft_break: SoftBreakMode,
+
+    /// how the word count and reading time of the document are computed
+    #[prop_or_default]
+    pub stats_config: StatsConfig,
+
+    /// the prefix of the paragraphs that are used as the caption of a table,
+    /// when they are right before or right after it, like `Table:` in pandoc.
+    /// If None, tables have no caption
+    #[prop_or_default]
+    pub table_caption_prefix: Option<&'static str>,
+
+    /// wether the raw html tables are rendered like markdown tables,
+    /// with the same elements and alignment
+    #[prop_or_default]
+    pub normalize_html_tables: bool,
+
+    /// wether code blocks get a button to copy their content to the clipboard
+    #[prop_or_default]
+    pub code_block_copy_button: bool,
+
+    /// wether the lines of code blocks are numbered
+    #[prop_or_default]
+    pub code_block_line_numbers: bool,
+
+    /// what to do with the tags that look like custom components,
+    /// like `<MyComponent/>`, but that are not registered
+    #[prop_or_default]
+    pub unknown_components: UnknownComponentPolicy,
+
+    /// how the raw html of the source is rendered.
+    /// Use `HtmlPolicy::Sanitize` or `HtmlPolicy::Strip` for untrusted content
+    #[prop_or_default]
+    pub html_policy: HtmlPolicy,
+
+    /// the number of levels that every heading is shifted down by, up to `<h6>`.
+    /// For example, with an offset of 1, `# Title` is rendered as a `<h2>`
+    #[prop_or_default]
+    pub heading_offset: u8,
+
+    /// adds a `#` link to the anchor of every heading.
+    /// Clicking it copies the permalink of the heading
+    #[prop_or_default]
+    pub heading_anchor_links: bool,
+
+    /// prepends hierarchical numbers to the headings, like `1`, `1.1`, `1.2`, `1.2.1`,
+    /// following their levels once the heading offset is applied
+    #[prop_or_default]
+    pub heading_numbering: bool,
+
+    /// the lowest level of the headings in the table of content, like 2 to leave the `<h1>` out.
+    /// It doesn't change how the headings are rendered
+    #[prop_or_default]
+    pub toc_min_level: u8,
+
+    /// the highest level of the headings in the table of content, like 3 to leave the `<h4>` to `<h6>` out.
+    /// If 0, there is no limit
+    #[prop_or_default]
+    pub toc_max_level: u8,
+
+    /// reads a block like `{.lead #intro}` at the end of a paragraph
+    /// as its classes, id and attributes, and removes it from the text.
+    /// On its own line at the end of a blockquote, the block applies to the blockquote
+    #[prop_or_default]
+    pub inline_attributes: bool,
+
+    /// sets `data-source-start` and `data-source-end` on the top-level blocks,
+    /// to their byte offsets in the source, for example to synchronize the scrolling of an editor
+    #[prop_or_default]
+    pub source_positions: bool,
+
+    /// how the items of ordered lists are numbered, like `I.`, `II.` for [`ListStyle::UpperRoman`]
+    #[prop_or_default]
+    pub ordered_list_style: Option<ListStyle>,
+
+    /// wether lists get the `md-list-tight` class, or `md-list-loose`
+    /// if their items are separated by blank lines
+    #[prop_or_default]
+    pub list_tightness_classes: bool,
+
+    /// the number of elements that can be nested in each other, like blockquotes.
+    /// Deeper elements are replaced by a `<div class="md-depth-limit">`,
+    /// or a `<span>` inside a paragraph.
+    /// If None, 100 levels are allowed
+    #[prop_or_default]
+    pub max_nesting_depth: Option<usize>,
+
+    /// the number of elements that are rendered at most.
+    /// The rest of the document is replaced by a `<div class="md-truncated">`.
+    /// If None, there is no limit
+    #[prop_or_default]
+    pub max_elements: Option<usize>,
+
+    /// the number of images that are rendered at most.
+    /// The rest of the document is replaced by a `<div class="md-truncated">`.
+    /// If None, there is no limit
+    #[prop_or_default]
+    pub max_image_count: Option<usize>,
+
+    /// the direction of the text of the document, like [`Direction::Rtl`].
+    /// If it or `lang` is set, the document is wrapped in a `<div>`
+    #[prop_or_default]
+    pub dir: Option<Direction>,
+
+    /// the language of the document, like `ar` or `en-US`
+    #[prop_or_default]
+    pub lang: Option<&'static str>,
+
+    /// gives `dir="auto"` to the paragraphs, headings and list items,
+    /// for documents that mix left-to-right and right-to-left languages
+    #[prop_or_default]
+    pub auto_block_direction: bool,
+
+    /// abbreviations and their expansion, rendered as `<abbr>` wherever they appear.
+    /// The abbreviations defined in the document, like `*[HTML]: HyperText Markup Language`,
+    /// take precedence
+    #[prop_or_default]
+    pub abbreviations: Option<&'static HashMap<&'static str, &'static str>>,
+
+    /// renders the text between `==`, like `==this==`, in a `<mark>`
+    #[prop_or_default]
+    pub highlight_marks: bool,
+
+    /// renders the keys between `++`, like `++Ctrl+C++`, in a `<kbd>`
+    #[prop_or_default]
+    pub kbd: bool,
+
+    /// the text shown when the document has no content
+    #[prop_or_default]
+    pub empty_placeholder: Option<&'static str>,
+
+    /// if the document is a single paragraph,
+    /// its content is rendered without the `<p>` around it
+    #[prop_or_default]
+    pub unwrap_single_paragraph: bool,
+
+    /// renders the checkboxes of task lists disabled, so that they can't be toggled
+    #[prop_or_default]
+    pub read_only_task_lists: bool,
+
+    /// the extra states of the tasks of task lists, like `[/]`,
+    /// rendered as indeterminate checkboxes
+    #[prop_or_default]
+    pub task_list_states: Option<&'static HashMap<char, TaskState>>,
+
+    /// extra css classes of the horizontal rules, separated by spaces
+    #[prop_or_default]
+    pub rule_class: Option<&'static str>,
+
+    /// renders consecutive horizontal rules as a single one
+    #[prop_or_default]
+    pub collapse_rules: bool,
+
+    /// the names of the html comments that are directives, like `more` for `<!-- more -->`.
+    /// They are not rendered, and are written to `comment_directives`
+    #[prop_or_default]
+    pub comment_directive_names: Option<&'static [&'static str]>,
+
+    /// scrolls the heading that the fragment of the url points to, like `#usage`, into view
+    #[prop_or_default]
+    pub scroll_to_fragment: bool,
+
+    /// the names of the languages of code blocks, by alias, like `javascript` for `js`.
+    /// They are added to [`DEFAULT_LANGUAGE_ALIASES`][web_framework_markdown::DEFAULT_LANGUAGE_ALIASES],
+    /// and take precedence over them
+    #[prop_or_default]
+    pub language_aliases: Option<&'static HashMap<&'static str, &'static str>>,
+
+    /// gives the footnote references the text of their definition as a `title`, shown as a tooltip
+    #[prop_or_default]
+    pub footnote_popovers: bool,
+
+    /// removes the indentation that all the lines of a code block have in common
+    #[prop_or_default]
+    pub code_block_dedent: bool,
+
+    /// removes the blank lines at the end of code blocks
+    #[prop_or_default]
+    pub code_block_trim: bool,
+
+    /// the element around the whole document, like a `<div>` with an id.
+    /// If None, it is a `<div>` only if the direction or the language is set
+    #[prop_or_default]
+    pub root_wrapper: Option<RootWrapper>,
+
+    /// with the `frontmatter` feature, lets the options set by the frontmatter
+    /// of the document take precedence over the props
+    #[prop_or_default]
+    pub frontmatter_overrides: bool,
+
+    /// renders the images alone in their paragraph and with a title
+    /// as a `<figure>`, with the title as caption
+    #[prop_or_default]
+    pub figure_images: bool,
+
+    /// a prefix added to the css classes created by this crate,
+    /// like `Some("prose-")` to get `prose-language-rust` instead of `language-rust`
+    #[prop_or_default]
+    pub class_prefix: Option<&'static str>,
+
+    /// wether bare urls like `https://example.com` or `www.example.com`,
+    /// and email addresses are turned into links
+    #[prop_or_default]
+    pub autolink: bool,
+
+    /// turns the mentions like `@alice` and the hashtags like `#topic` into links,
+    /// with `resolve_mention` and `resolve_hashtag`
+    #[prop_or_default]
+    pub mentions: Option<MentionConfig>,
+
+    /// where links are opened when clicked.
+    /// By default, they are opened in the same tab
+    #[prop_or_default]
+    pub link_target: LinkTarget,
+
+    /// the `rel` keywords of the links to other websites, like `nofollow ugc`
+    #[prop_or_default]
+    pub link_rel_policy: LinkRelPolicy,
+
+    /// the url schemes allowed in links and images.
+    /// By default, [`DEFAULT_URL_SCHEMES`][web_framework_markdown::DEFAULT_URL_SCHEMES]
+    #[prop_or_default]
+    pub allowed_url_schemes: Option<&'static [&'static str]>,
+
+    /// the function used to compute the anchor id of headings from their text.
+    /// By default, github-style slugs are used
+    #[prop_or_default]
+    pub heading_slugger: Option<Callback<String, String>>,
+
     #[prop_or(false)]
     pub wikilinks: bool,
 
@@ -314,28 +1208,44 @@ pub struct Props {
     #[prop_or_default]
     pub frontmatter: Option<UseStateHandle<String>>,
 
+    /// the frontmatter of the document, parsed into key-value pairs.
+    /// It is only set with the `frontmatter` feature,
+    /// when the frontmatter is valid yaml or toml
     #[prop_or_default]
-    pub send_debug_info: Option<Callback<Vec<String>>>,
-}
+    pub parsed_frontmatter: Option<UseStateHandle<Frontmatter>>,
 
-#[function_component]
-pub fn Markdown(props: &Props) -> Html {
-    let document = window().unwrap().document().unwrap();
+    /// the headings of the document, to build a table of content
+    #[prop_or_default]
+    pub headings: Option<UseStateHandle<Vec<Heading>>>,
+
+    /// the links and images of the document, for example to check them
+    #[prop_or_default]
+    pub document_links: Option<UseStateHandle<Vec<LinkInfo>>>,
+
+    /// the html comments of the document that are directives, like `<!-- more -->`
+    #[prop_or_default]
+    pub comment_directives: Option<UseStateHandle<Vec<CommentDirective>>>,
 
-    let link = document.create_element("link").unwrap();
+    /// the word count and reading time of the document
+    #[prop_or_default]
+    pub stats: Option<UseStateHandle<DocumentStats>>,
 
-    let StyleLink {
-        rel,
-        href,
-        integrity,
-        crossorigin,
-    } = MATH_STYLE_SHEET_LINK;
-    link.set_attribute("rel", rel).unwrap();
-    link.set_attribute("href", href).unwrap();
-    link.set_attribute("integrity", integrity).unwrap();
-    link.set_attribute("crossorigin", crossorigin).unwrap();
+    /// the last task list checkbox toggled by the user.
+    /// Use [`apply_task_toggle`] to update the source accordingly
+    #[prop_or_default]
+    pub task_toggle: Option<UseStateHandle<TaskToggle>>,
 
-    document.head().unwrap().append_child(&link).unwrap();
+    #[prop_or_default]
+    pub send_debug_info: Option<Callback<Vec<String>>>,
 
+    /// the callback called once the document is rendered,
+    /// with what happened while rendering it, like the events that were dropped.
+    /// It is only called with the `debug` feature
+    #[prop_or_default]
+    pub send_debug: Option<Callback<Vec<DebugEvent>>>,
+}
+
+#[function_component]
+pub fn Markdown(props: &Props) -> Html {
     markdown_component(props, &props.src)
 }